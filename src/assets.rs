@@ -0,0 +1,103 @@
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use crate::obj::Obj;
+use crate::texture::{ColorSpace, Texture};
+use crate::vertex::Vertex;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AssetKind {
+    Mesh,
+    Texture(ColorSpace),
+}
+
+pub enum AssetData {
+    Mesh(Vec<Vertex>),
+    Texture(Texture),
+}
+
+pub struct LoadedAsset {
+    pub kind: AssetKind,
+    pub path: String,
+    pub data: AssetData,
+}
+
+enum LoadResult {
+    Loaded(LoadedAsset),
+    Failed(String),
+}
+
+// Loads every requested asset on its own thread and hands them back to the
+// main loop through a channel, so the window stays responsive during startup.
+pub struct AssetLoader {
+    receiver: Receiver<LoadResult>,
+    total: usize,
+    completed: usize,
+    errors: Vec<String>,
+}
+
+impl AssetLoader {
+    pub fn spawn(requests: Vec<(AssetKind, String)>) -> Self {
+        let (sender, receiver) = channel();
+        let total = requests.len();
+
+        for (kind, path) in requests {
+            let sender: Sender<LoadResult> = sender.clone();
+            thread::spawn(move || {
+                let result = match kind {
+                    AssetKind::Mesh => Obj::load(&path)
+                        .map(|obj| AssetData::Mesh(obj.get_vertex_array()))
+                        .map_err(|e| format!("Failed to load mesh {}: {}", path, e)),
+                    AssetKind::Texture(space) => image::open(&path)
+                        .map(|img| AssetData::Texture(Texture::new(&img.to_rgb8(), space)))
+                        .map_err(|e| format!("Failed to load texture {}: {}", path, e)),
+                };
+
+                let message = match result {
+                    Ok(data) => LoadResult::Loaded(LoadedAsset { kind, path, data }),
+                    Err(error) => LoadResult::Failed(error),
+                };
+                // The main loop may already be gone if the window was closed.
+                let _ = sender.send(message);
+            });
+        }
+
+        AssetLoader {
+            receiver,
+            total,
+            completed: 0,
+            errors: Vec::new(),
+        }
+    }
+
+    // Returns the assets that finished since the last call.
+    pub fn poll(&mut self) -> Vec<LoadedAsset> {
+        let mut loaded = Vec::new();
+        while let Ok(result) = self.receiver.try_recv() {
+            self.completed += 1;
+            match result {
+                LoadResult::Loaded(asset) => loaded.push(asset),
+                LoadResult::Failed(error) => {
+                    eprintln!("{}", error);
+                    self.errors.push(error);
+                }
+            }
+        }
+        loaded
+    }
+
+    pub fn progress(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.completed as f32 / self.total as f32
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.completed >= self.total
+    }
+
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+}
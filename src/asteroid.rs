@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use nalgebra_glm::{Vec2, Vec3};
+use crate::noise::{NoiseField, NoisePreset};
+use crate::rng::{noise_seed, Purpose, Rng};
+use crate::vertex::Vertex;
+
+// Subdivisions of the icosahedron: 1280 triangles, enough for craters to
+// read as round.
+const SUBDIVISIONS: usize = 3;
+// 80 triangles, for rocks that are only ever a few pixels across.
+pub const LOW_POLY_SUBDIVISIONS: usize = 1;
+// FastNoiseLite's default frequency is 0.01; this brings the lumps down to
+// a few per side.
+const LUMP_SCALE: f32 = 110.0;
+const LUMP_DEPTH: f32 = 0.22;
+
+// A unique lumpy, cratered rock for `body_id`. The same seed always makes
+// the same rock. Vertices sit around radius 1; the mesh cache resizes them.
+pub fn generate(seed: u64, body_id: u64) -> Vec<Vertex> {
+    generate_with_detail(seed, body_id, SUBDIVISIONS)
+}
+
+pub fn generate_with_detail(seed: u64, body_id: u64, subdivisions: usize) -> Vec<Vertex> {
+    let mut rng = Rng::keyed(seed, body_id, Purpose::Asteroid);
+    let lumps = NoiseField::new(noise_seed(seed, body_id), NoisePreset::Fbm);
+
+    // Stretched along random axes so no two rocks share a silhouette.
+    let stretch = Vec3::new(rng.range(0.8, 1.35), rng.range(0.7, 1.0), rng.range(0.75, 1.15));
+    let craters: Vec<(Vec3, f32, f32)> = (0..5 + rng.below(6))
+        .map(|_| {
+            let direction = random_direction(&mut rng);
+            let radius = rng.range(0.25, 0.6);
+            let depth = rng.range(0.06, 0.14) * radius;
+            (direction, radius, depth)
+        })
+        .collect();
+
+    let (directions, triangles) = icosphere(subdivisions);
+    let positions: Vec<Vec3> = directions
+        .iter()
+        .map(|direction| {
+            let p = direction * LUMP_SCALE;
+            let mut radius = 1.0 + lumps.get_noise_3d(p.x, p.y, p.z) * LUMP_DEPTH;
+            for (center, crater_radius, depth) in &craters {
+                radius -= crater(direction.metric_distance(center) / crater_radius) * depth;
+            }
+            direction.component_mul(&stretch) * radius
+        })
+        .collect();
+
+    // Smooth normals: each vertex averages the faces around it.
+    let mut normals = vec![Vec3::zeros(); positions.len()];
+    for [a, b, c] in &triangles {
+        let face = (positions[*b] - positions[*a]).cross(&(positions[*c] - positions[*a]));
+        for index in [a, b, c] {
+            normals[*index] += face;
+        }
+    }
+
+    let mut vertices = Vec::with_capacity(triangles.len() * 3);
+    for triangle in &triangles {
+        for &index in triangle {
+            let direction = directions[index];
+            let tex_coords = Vec2::new(0.5 + direction.z.atan2(direction.x) / (2.0 * PI), 0.5 + direction.y.asin() / PI);
+            vertices.push(Vertex::new(positions[index], normals[index].normalize(), tex_coords));
+        }
+    }
+    vertices
+}
+
+// Bowl with a raised rim, by distance from the center in crater radii.
+// Positive values push the surface in.
+fn crater(distance: f32) -> f32 {
+    if distance < 1.0 {
+        1.0 - distance * distance
+    } else if distance < 1.4 {
+        let rim = (distance - 1.0) / 0.4;
+        -0.3 * (rim * PI).sin()
+    } else {
+        0.0
+    }
+}
+
+fn random_direction(rng: &mut Rng) -> Vec3 {
+    let y = rng.range(-1.0, 1.0);
+    let angle = rng.range(0.0, 2.0 * PI);
+    let ring = (1.0 - y * y).sqrt();
+    Vec3::new(ring * angle.cos(), y, ring * angle.sin())
+}
+
+// Unit icosahedron with every face split in four `subdivisions` times.
+// Triangles are counter-clockwise seen from outside.
+fn icosphere(subdivisions: usize) -> (Vec<Vec3>, Vec<[usize; 3]>) {
+    let t = (1.0 + 5.0_f32.sqrt()) / 2.0;
+    let mut directions: Vec<Vec3> = [
+        (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+        (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+        (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0),
+    ]
+    .iter()
+    .map(|&(x, y, z)| Vec3::new(x, y, z).normalize())
+    .collect();
+
+    let mut triangles = vec![
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        // Edges are shared by two faces; split each one only once.
+        let mut midpoints: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut midpoint = |a: usize, b: usize, directions: &mut Vec<Vec3>| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                directions.push((directions[a] + directions[b]).normalize());
+                directions.len() - 1
+            })
+        };
+
+        let mut split = Vec::with_capacity(triangles.len() * 4);
+        for [a, b, c] in triangles {
+            let ab = midpoint(a, b, &mut directions);
+            let bc = midpoint(b, c, &mut directions);
+            let ca = midpoint(c, a, &mut directions);
+            split.extend([[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]);
+        }
+        triangles = split;
+    }
+    (directions, triangles)
+}
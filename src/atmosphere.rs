@@ -0,0 +1,35 @@
+use nalgebra_glm::Vec3;
+use crate::color::{Color, Rgba};
+use crate::fragment::Fragment;
+use crate::noise::smoothstep;
+
+// Radius of the glowing shell, in radii of the body it wraps.
+pub const SHELL_SCALE: f32 = 1.06;
+// Sky blue, used for `atmosphere = true`.
+pub const DEFAULT_COLOR: Color = Color::new(110, 165, 255);
+const OPACITY: f32 = 0.8;
+
+// Fresnel-like glow of the shell's front face: nothing where the air is
+// seen straight down, building up towards the planet's limb where the line
+// of sight grazes the most air, then thinning out to the shell's edge. Only
+// the day side lights up, with a soft twilight band, and it goes out in
+// the shadow of another body. `to_eye` and `to_sun`
+// are in the shell's own frame.
+pub fn shade(fragment: &Fragment, color: Color, to_eye: Vec3, to_sun: Vec3) -> Option<Rgba> {
+    let normal = fragment.vertex_position.normalize();
+    let facing = normal.dot(&to_eye).clamp(0.0, 1.0);
+    // Distance from the center across the screen, in shell radii.
+    let projected = (1.0 - facing * facing).sqrt();
+    let limb = 1.0 / SHELL_SCALE;
+    let glow = if projected < limb {
+        (projected / limb).powi(6)
+    } else {
+        ((1.0 - projected) / (1.0 - limb)).powf(1.5)
+    };
+    let daylight = smoothstep(-0.25, 0.35, normal.dot(&to_sun));
+    let alpha = glow * daylight * OPACITY * fragment.sunlight;
+    if alpha <= 0.0 {
+        return None;
+    }
+    Some(Rgba::new(color, alpha))
+}
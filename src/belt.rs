@@ -0,0 +1,100 @@
+use std::f32::consts::PI;
+use nalgebra_glm::{rotation, Mat4, Vec3};
+use crate::asteroid;
+use crate::orbit::Orbit;
+use crate::rng::{Purpose, Rng};
+use crate::scene::{Scene, SPHERE_RADIUS};
+use crate::sim::STEPS_PER_SECOND;
+use crate::vertex::Vertex;
+
+pub const DEFAULT_COUNT: usize = 400;
+pub const DEFAULT_SCALE: f32 = 0.05;
+// Shader id of `rocoso`.
+pub const ROCK_SHADER: u8 = 7;
+// Mean motion at unit distance when the scene has no planets to scale from.
+const FALLBACK_MEAN_MOTION: f32 = 0.52;
+
+// A `[asteroid_belt]` from the scene file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BeltDesc {
+    // Orbit radii the rocks are spread between, in scene units.
+    pub inner: f32,
+    pub outer: f32,
+    pub count: usize,
+    // Typical rock size, like a body's `scale`.
+    pub scale: f32,
+}
+
+struct Rock {
+    // Unlike a body's, its mean motion is in radians per second, as rocks
+    // are placed straight from the sim time instead of being stepped.
+    orbit: Orbit,
+    scale: f32,
+    spin_axis: Vec3,
+    // Radians per second.
+    spin_speed: f32,
+}
+
+// Many small rocks around the sun sharing one low-poly mesh, drawn as
+// instances of it. Each keeps its own orbit, size and spin, all picked from
+// the scene seed.
+pub struct AsteroidBelt {
+    rocks: Vec<Rock>,
+    pub mesh: Vec<Vertex>,
+}
+
+impl AsteroidBelt {
+    pub fn generate(desc: &BeltDesc, scene: &Scene) -> Self {
+        let mut rng = Rng::keyed(scene.seed, 0, Purpose::Belt);
+        let rocks = (0..desc.count)
+            .map(|_| {
+                let radius = rng.range(desc.inner, desc.outer);
+                let orbit = Orbit {
+                    semi_major_axis: radius,
+                    eccentricity: rng.range(0.0, 0.08),
+                    inclination: rng.range(-0.06, 0.06),
+                    periapsis: rng.range(0.0, 2.0 * PI),
+                    mean_motion: mean_motion(scene, radius) * STEPS_PER_SECOND,
+                    phase: rng.range(0.0, 2.0 * PI),
+                };
+                let axis = Vec3::new(rng.range(-1.0, 1.0), rng.range(-1.0, 1.0), rng.range(-1.0, 1.0));
+                Rock {
+                    orbit,
+                    // Mostly small, a few several times larger.
+                    scale: desc.scale * (0.4 + rng.next_f32().powi(3) * 2.2),
+                    spin_axis: axis.try_normalize(1.0e-6).unwrap_or(Vec3::y()),
+                    spin_speed: rng.range(-4.8, 4.8),
+                }
+            })
+            .collect();
+        // One rock shape for the whole belt, keyed past every body.
+        let mesh = asteroid::generate_with_detail(scene.seed, (scene.bodies.len() + scene.comets.len()) as u64, asteroid::LOW_POLY_SUBDIVISIONS);
+        AsteroidBelt { rocks, mesh }
+    }
+
+    // Model matrices of the rocks `time` seconds in that pass `visible`,
+    // which gets each rock's center and radius.
+    pub fn instances(&self, time: f32, visible: impl Fn(Vec3, f32) -> bool) -> Vec<Mat4> {
+        self.rocks
+            .iter()
+            .filter_map(|rock| {
+                let position = rock.orbit.position(rock.orbit.phase + rock.orbit.mean_motion * time);
+                let radius = rock.scale * SPHERE_RADIUS;
+                visible(position, radius).then(|| {
+                    Mat4::new_translation(&position) * rotation(rock.spin_speed * time, &rock.spin_axis) * Mat4::new_scaling(radius)
+                })
+            })
+            .collect()
+    }
+}
+
+// Kepler's third law from the nearest planet going round the sun, so the
+// belt keeps pace with its neighbours.
+pub fn mean_motion(scene: &Scene, radius: f32) -> f32 {
+    scene
+        .bodies
+        .iter()
+        .filter(|body| body.parent.is_none() && body.orbit.semi_major_axis > 0.0 && body.orbit.mean_motion != 0.0)
+        .min_by(|a, b| (a.orbit.semi_major_axis - radius).abs().total_cmp(&(b.orbit.semi_major_axis - radius).abs()))
+        .map_or(FALLBACK_MEAN_MOTION * radius.powf(-1.5), |body| body.orbit.mean_motion.abs() * (body.orbit.semi_major_axis / radius).powf(1.5))
+}
@@ -0,0 +1,59 @@
+use std::f32::consts::PI;
+use nalgebra_glm::Vec3;
+use crate::color::{Color, Rgba};
+use crate::fragment::Fragment;
+use crate::noise::{loop_phase, remap, smoothstep, NoiseField, LOOP_PERIOD};
+use crate::rings::annulus;
+use crate::scene::SPHERE_RADIUS;
+use crate::vertex::Vertex;
+
+// Edges of the accretion disk, in radii of the black hole.
+const DISK_INNER: f32 = 1.6;
+const DISK_OUTER: f32 = 5.0;
+const HOT_COLOR: Color = Color::new(255, 240, 205);
+const COOL_COLOR: Color = Color::new(255, 120, 40);
+// Gas moving towards the camera looks bluer, gas moving away redder.
+const APPROACHING_COLOR: Color = Color::new(190, 215, 255);
+const RECEDING_COLOR: Color = Color::new(255, 70, 25);
+// Turns the innermost gas makes per animation loop; further out it is
+// slower, as in a Keplerian disk.
+const INNER_TURNS_PER_LOOP: f32 = 6.0;
+const STREAK_ZOOM: f32 = 160.0;
+
+// The disk around a black hole, in its body's own frame.
+pub fn disk_mesh() -> Vec<Vertex> {
+    annulus(DISK_INNER * SPHERE_RADIUS, DISK_OUTER * SPHERE_RADIUS)
+}
+
+// Additive glow of the disk: white hot at the inner edge, cooling outwards,
+// torn into streaks that each circle at their own speed. The side rotating
+// towards the camera (`to_eye`, in the disk's frame) is brighter and
+// bluer, the other side dimmer and redder.
+pub fn shade(fragment: &Fragment, noise: &NoiseField, to_eye: Vec3, time: f32) -> Option<Rgba> {
+    let position = fragment.vertex_position;
+    let radius = position.xz().magnitude() / SPHERE_RADIUS;
+    let across = remap(radius, DISK_INNER, DISK_OUTER, 0.0, 1.0);
+
+    // Whole turns per loop at every radius, so recorded clips still repeat.
+    let turns = (INNER_TURNS_PER_LOOP * (DISK_INNER / radius).powf(1.5)).round().max(1.0);
+    let angle = position.z.atan2(position.x) - loop_phase(time, LOOP_PERIOD) * turns * 2.0 * PI;
+    let (sin, cos) = angle.sin_cos();
+    let streaks = noise.get_noise_3d(cos * STREAK_ZOOM, sin * STREAK_ZOOM, radius * STREAK_ZOOM * 0.6) * 0.5 + 0.5;
+
+    let heading = Vec3::new(-position.z, 0.0, position.x).try_normalize(1.0e-6).unwrap_or(Vec3::zeros());
+    let doppler = heading.dot(&to_eye.try_normalize(1.0e-6).unwrap_or(Vec3::y()));
+    let beaming = (1.0 + 0.5 * doppler).powi(3);
+    let tint = if doppler >= 0.0 {
+        APPROACHING_COLOR
+    } else {
+        RECEDING_COLOR
+    };
+    let color = HOT_COLOR.lerp(&COOL_COLOR, across).lerp(&tint, doppler.abs() * 0.6);
+
+    let profile = smoothstep(0.0, 0.06, across) * (1.0 - smoothstep(0.35, 1.0, across));
+    let alpha = profile * (0.35 + 0.65 * streaks) * beaming;
+    if alpha <= 0.0 {
+        return None;
+    }
+    Some(Rgba::new(color, alpha.clamp(0.0, 1.0)))
+}
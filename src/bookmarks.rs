@@ -0,0 +1,103 @@
+use std::fmt::Write as _;
+use std::fs;
+use nalgebra_glm::Vec3;
+use crate::camera::Pose;
+use crate::toml_lite::{self, Table, Value};
+
+pub const BOOKMARKS_PATH: &str = "bookmarks.toml";
+pub const SLOTS: usize = 9;
+
+// Camera poses saved in numbered slots (Ctrl+1..9) and flown back to
+// (Alt+1..9). Slots belong to the scene they were saved in, by name, and
+// the file keeps every scene's, so they survive restarts and scene changes.
+pub struct Bookmarks {
+    // (scene, slot, pose), slots counted from 1.
+    saved: Vec<(String, usize, Pose)>,
+}
+
+impl Bookmarks {
+    pub fn new() -> Self {
+        Bookmarks { saved: Vec::new() }
+    }
+
+    // A missing file means no bookmarks yet.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let mut bookmarks = Bookmarks::new();
+        let Ok(source) = fs::read_to_string(path) else {
+            return Ok(bookmarks);
+        };
+        let document = toml_lite::parse(&source).map_err(|errors| {
+            errors
+                .iter()
+                .map(|error| format!("{}:{}: {}", path, error.line, error.message))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })?;
+
+        for table in document.tables_named("bookmark") {
+            match read_bookmark(table) {
+                Some((scene, slot, pose)) => bookmarks.set(&scene, slot, pose),
+                None => eprintln!("{}:{}: incomplete bookmark, skipping", path, table.line),
+            }
+        }
+        Ok(bookmarks)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_toml()).map_err(|e| format!("cannot write {}: {}", path, e))
+    }
+
+    fn to_toml(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# Vistas de camara guardadas con Ctrl+1..9 en cada escena.");
+        for (scene, slot, pose) in &self.saved {
+            let _ = writeln!(out);
+            let _ = writeln!(out, "[[bookmark]]");
+            let _ = writeln!(out, "scene = {:?}", scene);
+            let _ = writeln!(out, "slot = {}", slot);
+            let _ = writeln!(out, "eye = {}", vector(pose.eye));
+            let _ = writeln!(out, "center = {}", vector(pose.center));
+            let _ = writeln!(out, "up = {}", vector(pose.up));
+            let _ = writeln!(out, "fov = {}", pose.fov);
+        }
+        out
+    }
+
+    pub fn set(&mut self, scene: &str, slot: usize, pose: Pose) {
+        match self.saved.iter_mut().find(|(saved_scene, saved_slot, _)| saved_scene == scene && *saved_slot == slot) {
+            Some((_, _, saved)) => *saved = pose,
+            None => self.saved.push((scene.to_string(), slot, pose)),
+        }
+    }
+
+    pub fn get(&self, scene: &str, slot: usize) -> Option<Pose> {
+        self.saved
+            .iter()
+            .find(|(saved_scene, saved_slot, _)| saved_scene == scene && *saved_slot == slot)
+            .map(|(_, _, pose)| *pose)
+    }
+}
+
+fn read_bookmark(table: &Table) -> Option<(String, usize, Pose)> {
+    let number = |key: &str| match table.get(key).map(|entry| &entry.value) {
+        Some(Value::Number(value)) => Some(*value),
+        _ => None,
+    };
+    let vector = |key: &str| match table.get(key).map(|entry| &entry.value) {
+        Some(Value::Array(items)) => match items.as_slice() {
+            [Value::Number(x), Value::Number(y), Value::Number(z)] => Some(Vec3::new(*x, *y, *z)),
+            _ => None,
+        },
+        _ => None,
+    };
+    let Some(Value::String(scene)) = table.get("scene").map(|entry| &entry.value) else {
+        return None;
+    };
+    let slot = number("slot").filter(|slot| (1.0..=SLOTS as f32).contains(slot))? as usize;
+    let pose = Pose { eye: vector("eye")?, center: vector("center")?, up: vector("up")?, fov: number("fov")? };
+    Some((scene.clone(), slot, pose))
+}
+
+fn vector(vector: Vec3) -> String {
+    format!("[{}, {}, {}]", vector.x, vector.y, vector.z)
+}
@@ -1,12 +1,135 @@
 
-use nalgebra_glm::{Vec3, rotate_vec3};
 use std::f32::consts::PI;
+use nalgebra_glm::{Vec3, quat_angle_axis, quat_rotate_vec3};
+use crate::timeline::{Easing, Tween};
 
+// Vertical field of view, in radians, unless a shot asks for another.
+pub const DEFAULT_FOV: f32 = 45.0 * PI / 180.0;
+// How near the aim, relative to the viewing distance, counts as there.
+const SETTLED: f32 = 1.0e-4;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CameraMode {
+  Free,
+  Follow,
+  Chase,
+}
+
+impl CameraMode {
+  pub fn next(self) -> Self {
+    match self {
+      CameraMode::Free => CameraMode::Follow,
+      CameraMode::Follow => CameraMode::Chase,
+      CameraMode::Chase => CameraMode::Free,
+    }
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      CameraMode::Free => "libre",
+      CameraMode::Follow => "seguir",
+      CameraMode::Chase => "persecución",
+    }
+  }
+}
+
+// Where the camera stands, what it looks at, which way is up and how wide
+// it sees.
+#[derive(Clone, Copy)]
+pub struct Pose {
+  pub eye: Vec3,
+  pub center: Vec3,
+  pub up: Vec3,
+  pub fov: f32,
+}
+
+// A flight from one pose to another, eased at both ends.
+struct Animation {
+  eye: Tween<Vec3>,
+  center: Tween<Vec3>,
+  up: Tween<Vec3>,
+  fov: Tween<f32>,
+  elapsed: f32,
+}
+
+// Where the camera is headed. The key and mouse controls move this rather
+// than the camera, and `settle` eases the camera after it.
+#[derive(Clone, Copy)]
+struct Aim {
+  eye: Vec3,
+  center: Vec3,
+  up: Vec3,
+}
+
+impl Aim {
+  // Forward, right and up, at right angles to each other. `up` drifts off
+  // square when something moves the eye or center directly, so it is
+  // straightened against forward here; if it ends up along forward, the
+  // world's up, or failing that Z, takes its place.
+  fn basis(&self) -> (Vec3, Vec3, Vec3) {
+    let forward = (self.center - self.eye).try_normalize(1.0e-6).unwrap_or(-Vec3::z());
+    let right = [self.up, Vec3::y(), Vec3::z()]
+      .iter()
+      .find_map(|up| forward.cross(up).try_normalize(1.0e-4))
+      .unwrap_or(Vec3::x());
+    (forward, right, right.cross(&forward))
+  }
+
+  fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
+    let (_, right, up) = self.basis();
+    let rotation = quat_angle_axis(-delta_yaw, &up) * quat_angle_axis(delta_pitch, &right);
+
+    self.eye = self.center + quat_rotate_vec3(&rotation, &(self.eye - self.center));
+    self.up = quat_rotate_vec3(&rotation, &up);
+  }
+
+  fn roll(&mut self, angle: f32) {
+    let (forward, _, up) = self.basis();
+    self.up = quat_rotate_vec3(&quat_angle_axis(angle, &forward), &up);
+  }
+
+  fn zoom(&mut self, delta: f32) {
+    let direction = (self.center - self.eye).normalize();
+    self.eye += direction * delta;
+  }
+
+  fn move_center(&mut self, direction: Vec3) {
+    let radius_vector = self.center - self.eye;
+    let (_, right, up) = self.basis();
+
+    let angle_x = direction.x * 0.05; // Adjust this factor to control rotation speed
+    let angle_y = direction.y * 0.05;
+
+    let rotation = quat_angle_axis(angle_y, &right) * quat_angle_axis(angle_x, &up);
+    self.center = self.eye + quat_rotate_vec3(&rotation, &radius_vector);
+    self.up = quat_rotate_vec3(&rotation, &up);
+  }
+
+  fn pan(&mut self, right_amount: f32, up_amount: f32) {
+    let offset = self.center - self.eye;
+    let (_, right, up) = self.basis();
+    let shift = (right * right_amount + up * up_amount) * offset.magnitude();
+
+    self.eye += shift;
+    self.center += shift;
+  }
+}
+
+// `up` is kept with the camera as it turns rather than recomputed from
+// angles, so orbiting over the poles keeps going instead of flipping or
+// stopping; the view ends up upside down until it is leveled.
 pub struct Camera {
   pub eye: Vec3,
   pub center: Vec3,
   pub up: Vec3,
-  pub has_changed: bool
+  pub fov: f32,
+  // Share of the way to the aim left behind every 1/60 of a second: 0
+  // moves at once, values towards 1 glide longer.
+  pub smoothing: f32,
+  animation: Option<Animation>,
+  aim: Option<Aim>,
+  // Pose saved to fly back to later, as after the overview.
+  bookmark: Option<Pose>,
 }
 
 impl Camera {
@@ -15,73 +138,233 @@ impl Camera {
       eye,
       center,
       up,
-      has_changed: true,
+      fov: DEFAULT_FOV,
+      smoothing: 0.0,
+      animation: None,
+      aim: None,
+      bookmark: None,
     }
   }
 
-  pub fn basis_change(&self, vector: &Vec3) -> Vec3 {
-    let forward = (self.center - self.eye).normalize();
-    let right = forward.cross(&self.up).normalize();
-    let up = right.cross(&forward).normalize();
+  pub fn basis(&self) -> (Vec3, Vec3, Vec3) {
+    self.current().basis()
+  }
 
-    let rotated = 
-    vector.x * right +
-    vector.y * up +
-    - vector.z * forward;
+  fn current(&self) -> Aim {
+    Aim { eye: self.eye, center: self.center, up: self.up }
+  }
 
-    rotated.normalize()
+  pub fn pose(&self) -> Pose {
+    Pose { eye: self.eye, center: self.center, up: self.up, fov: self.fov }
   }
 
+  // Applies a control to where the camera is headed, which is where it is
+  // unless it is still easing after an earlier one.
+  fn steer(&mut self, control: impl FnOnce(&mut Aim)) {
+    let mut aim = self.aim.unwrap_or(self.current());
+    control(&mut aim);
+    if self.smoothing > 0.0 {
+      self.aim = Some(aim);
+    } else {
+      (self.eye, self.center, self.up) = (aim.eye, aim.center, aim.up);
+    }
+  }
+
+  // Turns the eye around the center: yaw about the camera's own up, pitch
+  // about its right, with up turning along.
   pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
-    let radius_vector = self.eye - self.center;
-    let radius = radius_vector.magnitude();
+    self.steer(|aim| aim.orbit(delta_yaw, delta_pitch));
+  }
 
-    let current_yaw = radius_vector.z.atan2(radius_vector.x);
+  // Tilts the camera around the line of sight; positive turns it clockwise,
+  // so the scene seems to turn the other way.
+  pub fn roll(&mut self, angle: f32) {
+    self.steer(|aim| aim.roll(angle));
+  }
 
-    let radius_xz = (radius_vector.x * radius_vector.x + radius_vector.z * radius_vector.z).sqrt();
-    let current_pitch = (-radius_vector.y).atan2(radius_xz);
+  // Puts the world's up back at the top of the view.
+  pub fn level(&mut self) {
+    self.steer(|aim| aim.up = Vec3::y());
+  }
 
-    let new_yaw = (current_yaw + delta_yaw) % (2.0 * PI);
-    let new_pitch = (current_pitch + delta_pitch).clamp(-PI / 2.0 + 0.1, PI / 2.0 - 0.1);
+  pub fn zoom(&mut self, delta: f32) {
+    self.steer(|aim| aim.zoom(delta));
+  }
 
-    let new_eye = self.center + Vec3::new(
-      radius * new_yaw.cos() * new_pitch.cos(),
-      -radius * new_pitch.sin(),
-      radius * new_yaw.sin() * new_pitch.cos()
-    );
+  pub fn move_center(&mut self, direction: Vec3) {
+    self.steer(|aim| aim.move_center(direction));
+  }
 
-    self.eye = new_eye;
-    self.has_changed = true;
+  // Slides the camera and the point it looks at together, sideways and up,
+  // by fractions of the distance between them.
+  pub fn pan(&mut self, right_amount: f32, up_amount: f32) {
+    self.steer(|aim| aim.pan(right_amount, up_amount));
   }
 
-  pub fn zoom(&mut self, delta: f32) {
-    let direction = (self.center - self.eye).normalize();
-    self.eye += direction * delta;
-    self.has_changed = true;
+  // Eases the camera `delta` seconds closer to where the controls sent it,
+  // the same distance however often frames are drawn. The eye
+  // swings around the center (or the center around the eye, whichever of
+  // the two travels less) so orbiting keeps its distance on the way.
+  pub fn settle(&mut self, delta: f32) {
+    let Some(aim) = self.aim else {
+      return;
+    };
+    let step = 1.0 - self.smoothing.powf(delta * 60.0);
+    let offset = self.eye - self.center;
+    let aim_offset = aim.eye - aim.center;
+    if (aim.eye - self.eye).magnitude() <= (aim.center - self.center).magnitude() {
+      self.eye += (aim.eye - self.eye) * step;
+      self.center = self.eye - turn_toward(offset, aim_offset, step);
+    } else {
+      self.center += (aim.center - self.center) * step;
+      self.eye = self.center + turn_toward(offset, aim_offset, step);
+    }
+    self.up = turn_toward(self.up, aim.up, step);
+
+    let close = SETTLED * offset.magnitude().max(SETTLED);
+    if (aim.eye - self.eye).magnitude() < close && (aim.center - self.center).magnitude() < close && (aim.up - self.up).magnitude() < SETTLED {
+      (self.eye, self.center, self.up) = (aim.eye, aim.center, aim.up);
+      self.aim = None;
+    }
   }
 
-  pub fn move_center(&mut self, direction: Vec3) {
-    let radius_vector = self.center - self.eye;
-    let radius = radius_vector.magnitude();
+  // Puts the camera somewhere at once, dropping any easing under way.
+  pub fn jump_to(&mut self, pose: Pose) {
+    self.eye = pose.eye;
+    self.center = pose.center;
+    self.up = pose.up;
+    self.fov = pose.fov;
+    self.aim = None;
+  }
 
-    let angle_x = direction.x * 0.05; // Adjust this factor to control rotation speed
-    let angle_y = direction.y * 0.05;
+  // Moves where the camera is easing to along with it when something else
+  // carries it, so a glide started before keeps going from the new place.
+  fn carry_aim(&mut self, from: Vec3) {
+    let moved = self.center - from;
+    if let Some(aim) = &mut self.aim {
+      aim.eye += moved;
+      aim.center += moved;
+    }
+  }
+
+  // Moves towards tracking a moving target; `blend` goes from 0 (leave the
+  // camera as is) to 1 (fully locked on). Follow keeps the current viewing
+  // offset, Chase sits behind the target looking along its heading.
+  pub fn track(&mut self, mode: CameraMode, target: Vec3, heading: Vec3, blend: f32) {
+    let offset = self.eye - self.center;
+    let from = self.center;
+    let (eye, center) = match mode {
+      CameraMode::Free => return,
+      CameraMode::Chase if Vec3::new(heading.x, 0.0, heading.z).magnitude() > 0.0 => {
+        self.aim = None;
+        // Heading flattened onto the orbital plane, so climbing or diving
+        // never swings the camera underneath the target.
+        let along_track = Vec3::new(heading.x, 0.0, heading.z).normalize();
+        let behind = -along_track * 3.0 + Vec3::new(0.0, 1.5, 0.0);
+        (target + behind, target)
+      }
+      _ => (target + offset, target),
+    };
+
+    self.center += (center - self.center) * blend;
+    self.eye += (eye - self.eye) * blend;
+    self.carry_aim(from);
+  }
 
-    let rotated = rotate_vec3(&radius_vector, angle_x, &Vec3::new(0.0, 1.0, 0.0));
+  // Moves towards looking at `target` from `distance` away, keeping the
+  // current viewing direction; `blend` works as in `track`.
+  pub fn focus(&mut self, target: Vec3, distance: f32, blend: f32) {
+    let direction = (self.eye - self.center).try_normalize(1.0e-6).unwrap_or(Vec3::new(0.0, 0.0, 1.0));
+    let eye = target + direction * distance;
+    let from = self.center;
 
-    let right = rotated.cross(&self.up).normalize();
-    let final_rotated = rotate_vec3(&rotated, angle_y, &right);
+    self.center += (target - self.center) * blend;
+    self.eye += (eye - self.eye) * blend;
+    self.carry_aim(from);
+  }
 
-    self.center = self.eye + final_rotated.normalize() * radius;
-    self.has_changed = true;
+  // Moves towards standing at `from` looking at `target`; `blend` works as
+  // in `track`.
+  pub fn watch(&mut self, from: Vec3, target: Vec3, blend: f32) {
+    self.center += (target - self.center) * blend;
+    self.eye += (from - self.eye) * blend;
+    self.aim = None;
   }
 
-  pub fn check_if_changed(&mut self) -> bool {
-    if self.has_changed {
-      self.has_changed = false;
-      true
-    } else {
-      false
+  // Starts flying to `target` over `duration` seconds; `tick` moves it along.
+  pub fn animate_to(&mut self, target: Pose, duration: f32) {
+    self.aim = None;
+    self.animation = Some(Animation {
+      eye: Tween::new(self.eye, target.eye, duration, Easing::EaseInOut),
+      center: Tween::new(self.center, target.center, duration, Easing::EaseInOut),
+      up: Tween::new(self.up, target.up, duration, Easing::EaseInOut),
+      fov: Tween::new(self.fov, target.fov, duration, Easing::EaseInOut),
+      elapsed: 0.0,
+    });
+  }
+
+  // Moves where the current flight ends, for targets that keep moving.
+  pub fn retarget(&mut self, target: Pose) {
+    if let Some(animation) = &mut self.animation {
+      animation.eye.to = target.eye;
+      animation.center.to = target.center;
+      animation.up.to = target.up;
+      animation.fov.to = target.fov;
+    }
+  }
+
+  pub fn cancel_animation(&mut self) {
+    self.animation = None;
+  }
+
+  pub fn is_animating(&self) -> bool {
+    self.animation.is_some()
+  }
+
+  // Remembers where the camera is now, for `fly_to_bookmark`.
+  pub fn bookmark(&mut self) {
+    self.bookmark = Some(self.pose());
+  }
+
+  // Flies back to the bookmarked pose over `duration` seconds and forgets
+  // it. Returns false when there was none.
+  pub fn fly_to_bookmark(&mut self, duration: f32) -> bool {
+    let Some(pose) = self.bookmark.take() else {
+      return false;
+    };
+    self.animate_to(pose, duration);
+    true
+  }
+
+  // Advances the flight by `delta` seconds. Returns true on the frame it
+  // lands.
+  pub fn tick(&mut self, delta: f32) -> bool {
+    let Some(animation) = &mut self.animation else {
+      return false;
+    };
+    animation.elapsed += delta;
+    self.eye = animation.eye.value_at(animation.elapsed);
+    self.center = animation.center.value_at(animation.elapsed);
+    self.up = animation.up.value_at(animation.elapsed);
+    self.fov = animation.fov.value_at(animation.elapsed);
+    let landed = animation.elapsed >= animation.eye.duration;
+    if landed {
+      self.animation = None;
     }
+    landed
   }
-}
\ No newline at end of file
+}
+
+// `from` turned towards `to` by `amount` of the angle between them, with its
+// length eased the same way. Vectors in line are simply blended.
+fn turn_toward(from: Vec3, to: Vec3, amount: f32) -> Vec3 {
+  let length = from.magnitude() + (to.magnitude() - from.magnitude()) * amount;
+  let (Some(from_direction), Some(to_direction)) = (from.try_normalize(1.0e-6), to.try_normalize(1.0e-6)) else {
+    return from + (to - from) * amount;
+  };
+  let Some(axis) = from_direction.cross(&to_direction).try_normalize(1.0e-6) else {
+    return from + (to - from) * amount;
+  };
+  let angle = from_direction.dot(&to_direction).clamp(-1.0, 1.0).acos();
+  quat_rotate_vec3(&quat_angle_axis(angle * amount, &axis), &from_direction) * length
+}
@@ -0,0 +1,127 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufWriter;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, Rgba, RgbaImage};
+use crate::config::CaptureConfig;
+use crate::letterbox::Letterbox;
+
+// NeuQuant sampling factor: 1 is slowest and best, 30 fastest.
+const QUANTIZER_SPEED: i32 = 10;
+
+// Keeps the last few seconds of presented frames, downscaled, so a moment
+// can be saved after it happened. Frames are taken at a fixed rate no matter
+// how fast the window renders.
+pub struct ClipRecorder {
+    frames: VecDeque<(Instant, RgbaImage)>,
+    capacity: usize,
+    interval: Duration,
+    scale: f32,
+    last_capture: Option<Instant>,
+    exports: Vec<JoinHandle<()>>,
+}
+
+impl ClipRecorder {
+    pub fn new(config: &CaptureConfig) -> Self {
+        let capacity = (config.seconds * config.fps).ceil().max(1.0) as usize;
+        ClipRecorder {
+            frames: VecDeque::with_capacity(capacity),
+            capacity,
+            interval: Duration::from_secs_f32(1.0 / config.fps),
+            scale: config.scale,
+            last_capture: None,
+            exports: Vec::new(),
+        }
+    }
+
+    // Only `picture` is kept, leaving out letterbox bars; `width` is the
+    // buffer's.
+    pub fn record(&mut self, buffer: &[u32], width: usize, picture: &Letterbox) {
+        let now = Instant::now();
+        if self.last_capture.is_some_and(|last| now.duration_since(last) < self.interval) {
+            return;
+        }
+        self.last_capture = Some(now);
+
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back((now, downscale(buffer, width, picture, self.scale)));
+    }
+
+    // Encodes the buffered clip on a background thread and returns the file
+    // it will be written to, or None when nothing has been recorded yet.
+    pub fn export(&mut self) -> Option<String> {
+        if self.frames.is_empty() {
+            return None;
+        }
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+        let path = format!("clip_{}.gif", seconds);
+
+        // Each frame stays up until the next one was taken; the last one
+        // gets the nominal interval.
+        let mut frames = Vec::with_capacity(self.frames.len());
+        for (i, (taken, image)) in self.frames.iter().enumerate() {
+            let shown = self.frames.get(i + 1).map_or(self.interval, |(next, _)| next.duration_since(*taken));
+            frames.push(Frame::from_parts(image.clone(), 0, 0, Delay::from_saturating_duration(shown)));
+        }
+
+        let target = path.clone();
+        self.exports.push(thread::spawn(move || match write_gif(&target, frames) {
+            Ok(()) => println!("Clip saved to {}", target),
+            Err(error) => eprintln!("Could not save clip {}: {}", target, error),
+        }));
+        Some(path)
+    }
+
+    // Blocks until clips still being encoded are written, so quitting right
+    // after F6 doesn't lose them.
+    pub fn finish(self) {
+        for export in self.exports {
+            let _ = export.join();
+        }
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        self.frames.iter().map(|(_, image)| image.as_raw().len()).sum()
+    }
+}
+
+fn write_gif(path: &str, frames: Vec<Frame>) -> Result<(), String> {
+    let file = File::create(path).map_err(|error| error.to_string())?;
+    let mut encoder = GifEncoder::new_with_speed(BufWriter::new(file), QUANTIZER_SPEED);
+    encoder.set_repeat(Repeat::Infinite).map_err(|error| error.to_string())?;
+    encoder.encode_frames(frames).map_err(|error| error.to_string())
+}
+
+// Box filter: every output pixel averages the source pixels it covers.
+fn downscale(buffer: &[u32], stride: usize, picture: &Letterbox, scale: f32) -> RgbaImage {
+    let out_width = ((picture.width as f32 * scale).round() as usize).max(1);
+    let out_height = ((picture.height as f32 * scale).round() as usize).max(1);
+
+    RgbaImage::from_fn(out_width as u32, out_height as u32, |x, y| {
+        let (x0, x1) = span(x as usize, picture.width, out_width);
+        let (y0, y1) = span(y as usize, picture.height, out_height);
+
+        let mut sum = [0u32; 3];
+        for row in picture.y + y0..picture.y + y1 {
+            let start = row * stride + picture.x;
+            for &color in &buffer[start + x0..start + x1] {
+                sum[0] += (color >> 16) & 0xFF;
+                sum[1] += (color >> 8) & 0xFF;
+                sum[2] += color & 0xFF;
+            }
+        }
+        let count = ((x1 - x0) * (y1 - y0)) as u32;
+        Rgba([(sum[0] / count) as u8, (sum[1] / count) as u8, (sum[2] / count) as u8, 255])
+    })
+}
+
+// Source range covered by output index `i`, never empty.
+fn span(i: usize, source: usize, out: usize) -> (usize, usize) {
+    let start = i * source / out;
+    let end = ((i + 1) * source / out).max(start + 1);
+    (start, end)
+}
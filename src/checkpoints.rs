@@ -0,0 +1,157 @@
+use std::collections::VecDeque;
+use crate::draw2d;
+use crate::framebuffer::Framebuffer;
+use crate::input::InputFrame;
+use crate::scene::Scene;
+use crate::sim::Simulation;
+use crate::text::{draw_text, text_width};
+
+// Sim frames between checkpoints, and how many are kept; older ones are
+// dropped, so the timeline reaches this far back.
+pub const INTERVAL: u32 = 120;
+const CAPACITY: usize = 100;
+
+const BAR_WIDTH: usize = 360;
+const BAR_HEIGHT: usize = 8;
+const LABEL_GAP: usize = 4;
+const BAR_COLOR: u32 = 0x303848;
+const REACHED_COLOR: u32 = 0x5577AA;
+const TICK_COLOR: u32 = 0x8899BB;
+const NOW_COLOR: u32 = 0xFFFFFF;
+const LABEL_COLOR: u32 = 0xDDDDDD;
+
+// Copies of the whole simulation taken every INTERVAL frames. Stepping is
+// deterministic, so any frame between the oldest copy and the furthest one
+// reached is rebuilt by restoring the copy before it and stepping forward.
+pub struct Checkpoints {
+    // Oldest first.
+    saved: VecDeque<(u32, Simulation)>,
+    // Furthest frame the sim has been at since the copies were last valid.
+    horizon: u32,
+}
+
+impl Checkpoints {
+    pub fn new() -> Self {
+        Checkpoints { saved: VecDeque::new(), horizon: 0 }
+    }
+
+    // Call after every sim step. Frames already saved (played again after a
+    // jump back) replace their copy rather than adding another.
+    pub fn record(&mut self, time: u32, sim: &Simulation) {
+        self.horizon = self.horizon.max(time);
+        if !time.is_multiple_of(INTERVAL) {
+            return;
+        }
+        match self.saved.binary_search_by_key(&time, |(saved_time, _)| *saved_time) {
+            Ok(index) => self.saved[index].1 = sim.clone(),
+            Err(index) => self.saved.insert(index, (time, sim.clone())),
+        }
+        if self.saved.len() > CAPACITY {
+            self.saved.pop_front();
+        }
+    }
+
+    // For when the scene changed at `time`: what was saved past it no
+    // longer follows.
+    pub fn forget_after(&mut self, time: u32) {
+        self.saved.retain(|(saved_time, _)| *saved_time <= time);
+        self.horizon = time;
+    }
+
+    // For when bodies were added or removed, or a save was loaded.
+    pub fn clear(&mut self) {
+        self.saved.clear();
+        self.horizon = 0;
+    }
+
+    // Oldest and furthest frame a jump can reach.
+    pub fn range(&self) -> Option<(u32, u32)> {
+        self.saved.front().map(|(oldest, _)| (*oldest, self.horizon))
+    }
+
+    // Moves the sim to `target`, clamped to `range`, from the nearest
+    // checkpoint before it, or from where it is when that is closer. The
+    // selection stays. Returns false when there is nothing to jump to.
+    pub fn jump(&self, target: u32, scene: &Scene, sim: &mut Simulation, time: &mut u32) -> bool {
+        let Some((oldest, furthest)) = self.range() else {
+            return false;
+        };
+        let target = target.clamp(oldest, furthest);
+        let index = self.saved.partition_point(|(saved_time, _)| *saved_time <= target) - 1;
+        let (saved_time, saved) = &self.saved[index];
+        if *time < *saved_time || *time > target {
+            let selected = sim.selected;
+            *sim = saved.clone();
+            sim.selected = selected;
+            *time = *saved_time;
+        }
+        while *time < target {
+            sim.step(scene);
+            *time += 1;
+        }
+        true
+    }
+}
+
+// Bar spanning what the checkpoints reach, shown while the sim is paused.
+// Clicking or dragging along it jumps there.
+pub struct Scrubber {
+    // Where the bar was last drawn, as (x, y, width, height).
+    rect: Option<(usize, usize, usize, usize)>,
+    dragging: bool,
+    was_down: bool,
+}
+
+impl Scrubber {
+    pub fn new() -> Self {
+        Scrubber { rect: None, dragging: false, was_down: false }
+    }
+
+    pub fn contains(&self, cursor: (f32, f32)) -> bool {
+        self.rect.is_some_and(|(x, y, width, height)| {
+            let (cursor_x, cursor_y) = cursor;
+            cursor_x >= x as f32 && cursor_x < (x + width) as f32 && cursor_y >= y as f32 && cursor_y < (y + height) as f32
+        })
+    }
+
+    pub fn size() -> (usize, usize) {
+        (BAR_WIDTH, BAR_HEIGHT + LABEL_GAP + 8)
+    }
+
+    // The frame to jump to while the bar is pressed or dragged.
+    pub fn update(&mut self, input: &InputFrame, range: Option<(u32, u32)>) -> Option<u32> {
+        let down = input.mouse_down;
+        if down && !self.was_down {
+            self.dragging = input.mouse.is_some_and(|cursor| self.contains(cursor));
+        }
+        self.was_down = down;
+        self.dragging &= down;
+        let (true, Some(cursor), Some((x, _, width, _)), Some((oldest, furthest))) = (self.dragging, input.mouse, self.rect, range) else {
+            return None;
+        };
+        let along = ((cursor.0 - x as f32) / width as f32).clamp(0.0, 1.0);
+        Some(oldest + ((furthest - oldest) as f32 * along).round() as u32)
+    }
+
+    // `None` hides the bar, and it can't be grabbed until drawn again.
+    pub fn draw(&mut self, framebuffer: &mut Framebuffer, at: Option<(usize, usize)>, checkpoints: &Checkpoints, time: u32, label: &str) {
+        self.rect = None;
+        let (Some((x, y)), Some((oldest, furthest))) = (at, checkpoints.range()) else {
+            return;
+        };
+        self.rect = Some((x, y, BAR_WIDTH, BAR_HEIGHT));
+        let span = (furthest - oldest).max(1) as f32;
+        let offset = |frame: u32| ((frame.saturating_sub(oldest)) as f32 / span * (BAR_WIDTH - 1) as f32) as usize;
+
+        draw2d::fill_rect(framebuffer, x, y, BAR_WIDTH, BAR_HEIGHT, BAR_COLOR);
+        draw2d::fill_rect(framebuffer, x, y, offset(time.min(furthest)) + 1, BAR_HEIGHT, REACHED_COLOR);
+        for (saved_time, _) in &checkpoints.saved {
+            draw2d::fill_rect(framebuffer, x + offset(*saved_time), y + BAR_HEIGHT / 2, 1, BAR_HEIGHT / 2, TICK_COLOR);
+        }
+        let now = x + offset(time.min(furthest));
+        draw2d::fill_rect(framebuffer, now.saturating_sub(1), y.saturating_sub(2), 3, BAR_HEIGHT + 4, NOW_COLOR);
+
+        let label_x = x + BAR_WIDTH.saturating_sub(text_width(label, 1)) / 2;
+        draw_text(framebuffer, label_x, y + BAR_HEIGHT + LABEL_GAP, label, LABEL_COLOR, 1);
+    }
+}
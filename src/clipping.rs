@@ -0,0 +1,76 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::vertex::Vertex;
+
+// The six frustum planes in homogeneous clip space, as the signed distance
+// of a point to each one (inside when >= 0): -w <= x, y, z <= w.
+const PLANES: [fn(&Vec4) -> f32; 6] = [
+    |p| p.w + p.z, // near
+    |p| p.w - p.z, // far
+    |p| p.w + p.x, // left
+    |p| p.w - p.x, // right
+    |p| p.w + p.y, // bottom
+    |p| p.w - p.y, // top
+];
+
+// Clips a triangle against the view frustum before the perspective divide,
+// so corners behind the camera never get divided by a negative or tiny w.
+// Returns the visible part as a triangle fan with screen positions filled
+// in; empty when the triangle is entirely outside.
+pub fn clip_triangle(triangle: [&Vertex; 3], viewport_matrix: &Mat4) -> Vec<Vertex> {
+    let inside = |vertex: &Vertex, plane: &fn(&Vec4) -> f32| plane(&vertex.clip_position) >= 0.0;
+
+    // Nearly every triangle is either fully in or fully out of some plane.
+    if PLANES.iter().all(|plane| triangle.iter().all(|vertex| inside(vertex, plane))) {
+        return triangle.into_iter().cloned().collect();
+    }
+    if PLANES.iter().any(|plane| triangle.iter().all(|vertex| !inside(vertex, plane))) {
+        return Vec::new();
+    }
+
+    // Sutherland-Hodgman, one plane at a time.
+    let mut polygon: Vec<Vertex> = triangle.into_iter().cloned().collect();
+    for plane in &PLANES {
+        if polygon.is_empty() {
+            break;
+        }
+        let mut clipped = Vec::with_capacity(polygon.len() + 1);
+        for (i, current) in polygon.iter().enumerate() {
+            let next = &polygon[(i + 1) % polygon.len()];
+            let (d_current, d_next) = (plane(&current.clip_position), plane(&next.clip_position));
+            if d_current >= 0.0 {
+                clipped.push(current.clone());
+            }
+            if (d_current >= 0.0) != (d_next >= 0.0) {
+                clipped.push(lerp_vertex(current, next, d_current / (d_current - d_next)));
+            }
+        }
+        polygon = clipped;
+    }
+
+    for vertex in &mut polygon {
+        vertex.transformed_position = to_screen(&vertex.clip_position, viewport_matrix);
+    }
+    polygon
+}
+
+// Perspective divide and viewport transform.
+pub fn to_screen(clip_position: &Vec4, viewport_matrix: &Mat4) -> Vec3 {
+    let w = clip_position.w;
+    let ndc = Vec4::new(clip_position.x / w, clip_position.y / w, clip_position.z / w, 1.0);
+    let screen = viewport_matrix * ndc;
+    Vec3::new(screen.x, screen.y, screen.z)
+}
+
+// Attributes are interpolated linearly in clip space, where that is exact.
+fn lerp_vertex(a: &Vertex, b: &Vertex, t: f32) -> Vertex {
+    Vertex {
+        position: a.position.lerp(&b.position, t),
+        normal: a.normal.lerp(&b.normal, t),
+        tex_coords: a.tex_coords.lerp(&b.tex_coords, t),
+        color: a.color.lerp(&b.color, t),
+        clip_position: a.clip_position.lerp(&b.clip_position, t),
+        transformed_position: Vec3::zeros(),
+        transformed_normal: a.transformed_normal.lerp(&b.transformed_normal, t),
+        world_position: a.world_position.lerp(&b.world_position, t),
+    }
+}
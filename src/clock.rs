@@ -0,0 +1,35 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Frames are drawn at most this often; the sim's pace doesn't depend on it.
+const FRAME_TIME: Duration = Duration::from_micros(16_667);
+// A longer gap (the window dragged, a breakpoint) counts as only this much,
+// so the sim doesn't rush to catch up afterwards.
+const MAX_DELTA: f32 = 0.1;
+
+// Real time between frames. While input is recorded or replayed every frame
+// counts as exactly FRAME_TIME instead, so a replay steps the sim just like
+// the recording did however fast either machine draws.
+pub struct Clock {
+    frame_start: Instant,
+    fixed: bool,
+}
+
+impl Clock {
+    pub fn new(fixed: bool) -> Self {
+        Clock { frame_start: Instant::now(), fixed }
+    }
+
+    // Seconds since the last call, and the start of a new frame.
+    pub fn tick(&mut self) -> f32 {
+        let now = Instant::now();
+        let delta = now.duration_since(self.frame_start).as_secs_f32();
+        self.frame_start = now;
+        if self.fixed { FRAME_TIME.as_secs_f32() } else { delta.min(MAX_DELTA) }
+    }
+
+    // Sleeps out whatever is left of this frame's FRAME_TIME.
+    pub fn wait(&self) {
+        thread::sleep(FRAME_TIME.saturating_sub(self.frame_start.elapsed()));
+    }
+}
@@ -0,0 +1,60 @@
+use std::f32::consts::PI;
+use nalgebra_glm::Vec3;
+use crate::color::{Color, Rgba};
+use crate::fragment::Fragment;
+use crate::noise::{loop_phase, smoothstep, NoiseField, LOOP_PERIOD};
+
+// Height of the cloud deck above the surface, as a fraction of the radius.
+pub const CLOUD_HEIGHT: f32 = 0.04;
+// Clouds drift eastward over the ground, a whole turn per animation loop so
+// recorded clips still repeat.
+const DRIFT_TURNS: f32 = 1.0;
+const CLOUD_ZOOM: f32 = 260.0;
+// Moves the samples away from the ones the surface shaders take from the
+// same field, so clouds don't trace the terrain.
+const CLOUD_OFFSET: Vec3 = Vec3::new(71.3, -19.7, 43.1);
+const SHADOW_STRENGTH: f32 = 0.55;
+const CLOUD_COLOR: Color = Color::new(248, 250, 255);
+const CLOUD_OPACITY: f32 = 0.85;
+
+// How cloudy the sky is above `direction` (in the body's own frame, any
+// length), from 0 (clear) to 1 (overcast).
+pub fn coverage(noise: &NoiseField, direction: Vec3, time: f32) -> f32 {
+    let drift = loop_phase(time, LOOP_PERIOD) * DRIFT_TURNS * 2.0 * PI;
+    let (sin, cos) = drift.sin_cos();
+    let direction = direction.normalize();
+    let turned = Vec3::new(direction.x * cos + direction.z * sin, direction.y, -direction.x * sin + direction.z * cos);
+
+    let point = turned * CLOUD_ZOOM + CLOUD_OFFSET;
+    let detail = point * 2.3;
+    let value = noise.get_noise_3d(point.x, point.y, point.z) + 0.5 * noise.get_noise_3d(detail.x, detail.y, detail.z);
+    smoothstep(0.1, 0.55, value)
+}
+
+// The cloud deck itself, drawn on a shell CLOUD_HEIGHT above the surface
+// with the body's own model matrix, so the clouds line up with the shadows
+// they cast and drift over the ground as it turns beneath them.
+pub fn shade(fragment: &Fragment, noise: &NoiseField, time: f32) -> Option<Rgba> {
+    let cover = coverage(noise, fragment.vertex_position, time);
+    if cover <= 0.0 {
+        return None;
+    }
+    Some(Rgba::new(CLOUD_COLOR * fragment.intensity, cover * CLOUD_OPACITY))
+}
+
+// Darkens a surface color where the cloud deck sits between it and the sun.
+// `position` is the point on the surface and `to_sun` the direction to the
+// sun, both in the body's own frame. The cloud above the point is offset
+// towards the sun, so shadows slide away from their clouds near the
+// terminator.
+pub fn shadowed(color: Color, noise: &NoiseField, position: Vec3, to_sun: Vec3, time: f32) -> Color {
+    let normal = position.normalize();
+    let sun_height = normal.dot(&to_sun);
+    if sun_height <= 0.0 {
+        return color;
+    }
+    // Distance along the sun ray up to the deck, in radii.
+    let reach = CLOUD_HEIGHT / sun_height.max(0.2);
+    let shadow = coverage(noise, normal + to_sun * reach, time);
+    color * (1.0 - SHADOW_STRENGTH * shadow)
+}
@@ -1,72 +1,209 @@
 use std::fmt;
+use nalgebra_glm::Vec3;
 
+// Channels hold linear light from 0 to 1, so mixing and shading behave
+// like light does. `new` and `from_hex` take the usual sRGB bytes of
+// textures and color pickers, and `to_hex` encodes back to them.
 #[derive(Debug, Clone, Copy)]
 pub struct Color {
-    r: u8,
-    g: u8,
-    b: u8,
+    r: f32,
+    g: f32,
+    b: f32,
 }
 
 impl Color {
     pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Color { r: SRGB_TO_LINEAR[r as usize], g: SRGB_TO_LINEAR[g as usize], b: SRGB_TO_LINEAR[b as usize] }
+    }
+
+    // Straight from linear channels, for colors that are computed rather
+    // than picked.
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Self {
         Color { r, g, b }
     }
 
+    // Hue in degrees, saturation and value from 0 to 1, on the sRGB values
+    // the way color pickers show them.
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let value = value.clamp(0.0, 1.0);
+        let channel = |n: f32| {
+            let k = (n + hue.rem_euclid(360.0) / 60.0) % 6.0;
+            value - value * saturation * k.min(4.0 - k).clamp(0.0, 1.0)
+        };
+        Color::from_srgb(channel(5.0), channel(3.0), channel(1.0))
+    }
+
+    pub fn to_hsv(self) -> (f32, f32, f32) {
+        let (hue, max, min) = self.hue_range();
+        let saturation = if max > 0.0 { (max - min) / max } else { 0.0 };
+        (hue, saturation, max)
+    }
+
+    // Saturation scaled by `factor`: 0 gives gray, above 1 more vivid.
+    pub fn saturate(self, factor: f32) -> Self {
+        let (hue, saturation, value) = self.to_hsv();
+        Color::from_hsv(hue, saturation * factor, value)
+    }
+
+    // Value scaled by `factor`, keeping hue and saturation.
+    pub fn brighten(self, factor: f32) -> Self {
+        let (hue, saturation, value) = self.to_hsv();
+        Color::from_hsv(hue, saturation, value * factor)
+    }
+
     pub const fn from_hex(hex: u32) -> Self {
         let r = ((hex >> 16) & 0xFF) as u8;
         let g = ((hex >> 8) & 0xFF) as u8;
         let b = (hex & 0xFF) as u8;
-        Color { r, g, b }
+        Color::new(r, g, b)
     }
 
     pub const fn black() -> Self {
-        Color { r: 0, g: 0, b: 0 }
+        Color { r: 0.0, g: 0.0, b: 0.0 }
+    }
+
+    pub fn to_hex(self) -> u32 {
+        ((encode(self.r) as u32) << 16) | ((encode(self.g) as u32) << 8) | (encode(self.b) as u32)
     }
 
-    pub fn to_hex(&self) -> u32 {
-        ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
+    // The linear channels, the scale of the framebuffer's HDR buffer.
+    pub fn radiance(self) -> Vec3 {
+        Vec3::new(self.r, self.g, self.b)
     }
 
     // Linear interpolation between two colors
     pub fn lerp(&self, other: &Color, t: f32) -> Self {
         let t = t.clamp(0.0, 1.0);
         Color {
-            r: (self.r as f32 + (other.r as f32 - self.r as f32) * t).round() as u8,
-            g: (self.g as f32 + (other.g as f32 - self.g as f32) * t).round() as u8,
-            b: (self.b as f32 + (other.b as f32 - self.b as f32) * t).round() as u8,
+            r: self.r + (other.r - self.r) * t,
+            g: self.g + (other.g - self.g) * t,
+            b: self.b + (other.b - self.b) * t,
         }
     }
 
-    pub fn is_black(&self) -> bool {
-        self.r == 0 && self.g == 0 && self.b == 0 
+    fn from_srgb(r: f32, g: f32, b: f32) -> Self {
+        Color { r: decode(r), g: decode(g), b: decode(b) }
+    }
+
+    // Hue in degrees plus the largest and smallest sRGB channel.
+    fn hue_range(self) -> (f32, f32, f32) {
+        let (r, g, b) = (encode_f32(self.r), encode_f32(self.g), encode_f32(self.b));
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+        let hue = if delta <= 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        (hue, max, min)
+    }
+}
+
+// Colors placed along a line of values, to look up by where a value falls.
+// Stops go from the lowest position to the highest.
+#[derive(Debug, Clone, Copy)]
+pub struct Gradient {
+    stops: &'static [(f32, Color)],
+}
+
+impl Gradient {
+    pub const fn new(stops: &'static [(f32, Color)]) -> Self {
+        Gradient { stops }
     }
 
-    pub fn blend_normal(&self, blend: &Color) -> Color {
-        if blend.is_black() { *self } else { *blend }
-      }
-    
-    pub fn blend_multiply(&self, blend: &Color) -> Color {
-        Color::new(
-            ((self.r as f32 * blend.r as f32) / 255.0) as u8,
-            ((self.g as f32 * blend.g as f32) / 255.0) as u8,
-            ((self.b as f32 * blend.b as f32) / 255.0) as u8
-        )
+    // Blends smoothly between the two stops around `value`, holding the end
+    // colors beyond the first and last stop.
+    pub fn at(&self, value: f32) -> Color {
+        let next = self.stops.partition_point(|&(position, _)| position <= value);
+        if next == 0 {
+            return self.stops[0].1;
+        }
+        if next == self.stops.len() {
+            return self.stops[next - 1].1;
+        }
+        let (from, from_color) = self.stops[next - 1];
+        let (to, to_color) = self.stops[next];
+        from_color.lerp(&to_color, (value - from) / (to - from))
     }
-    
-    pub fn blend_add(&self, blend: &Color) -> Color {
-        Color::new(
-            (self.r as u16 + blend.r as u16).min(255) as u8,
-            (self.g as u16 + blend.g as u16).min(255) as u8,
-            (self.b as u16 + blend.b as u16).min(255) as u8
-        )
+
+    // Hard bands instead: each stop's color starts at its position and runs
+    // up to the next one.
+    pub fn band(&self, value: f32) -> Color {
+        let next = self.stops.partition_point(|&(position, _)| position <= value);
+        self.stops[next.saturating_sub(1)].1
     }
-    
-    pub fn blend_subtract(&self, blend: &Color) -> Color {
-        let r = (self.r as i16 - blend.r as i16).max(0).min(255) as u8;
-        let g = (self.g as i16 - blend.g as i16).max(0).min(255) as u8;
-        let b = (self.b as i16 - blend.b as i16).max(0).min(255) as u8;
+}
 
-        Color::new(r, g, b)
+// Linear light to an 8-bit sRGB channel.
+pub fn encode(linear: f32) -> u8 {
+    (encode_f32(linear) * 255.0).round() as u8
+}
+
+fn encode_f32(linear: f32) -> f32 {
+    let linear = linear.clamp(0.0, 1.0);
+    if linear <= 0.0031308 { linear * 12.92 } else { 1.055 * linear.powf(1.0 / 2.4) - 0.055 }
+}
+
+fn decode(encoded: f32) -> f32 {
+    let encoded = encoded.clamp(0.0, 1.0);
+    if encoded <= 0.04045 { encoded / 12.92 } else { ((encoded + 0.055) / 1.055).powf(2.4) }
+}
+
+// Every 8-bit sRGB value decoded to linear light. A table rather than the
+// formula so that colors can still be built in constants.
+#[allow(clippy::excessive_precision)]
+const SRGB_TO_LINEAR: [f32; 256] = [
+    0.0, 0.00030352698, 0.00060705397, 0.00091058095, 0.0012141079, 0.0015176349, 0.0018211619, 0.0021246889,
+    0.0024282159, 0.0027317429, 0.0030352698, 0.0033465358, 0.0036765073, 0.004024717, 0.004391442, 0.0047769535,
+    0.0051815167, 0.0056053916, 0.006048833, 0.0065120908, 0.0069954102, 0.007499032, 0.008023193, 0.0085681256,
+    0.0091340587, 0.0097212173, 0.010329823, 0.010960094, 0.011612245, 0.012286488, 0.012983032, 0.013702083,
+    0.014443844, 0.015208514, 0.015996293, 0.016807376, 0.017641954, 0.01850022, 0.019382361, 0.020288563,
+    0.02121901, 0.022173885, 0.023153366, 0.024157632, 0.02518686, 0.026241222, 0.027320892, 0.02842604,
+    0.029556834, 0.030713444, 0.031896033, 0.033104767, 0.034339807, 0.035601315, 0.03688945, 0.038204372,
+    0.039546235, 0.040915197, 0.042311411, 0.043735029, 0.045186204, 0.046665086, 0.048171824, 0.049706566,
+    0.051269458, 0.052860647, 0.054480276, 0.05612849, 0.05780543, 0.059511238, 0.061246054, 0.063010018,
+    0.064803267, 0.066625939, 0.06847817, 0.070360096, 0.072271851, 0.074213568, 0.076185381, 0.078187422,
+    0.08021982, 0.082282707, 0.084376212, 0.086500462, 0.088655586, 0.090841711, 0.093058963, 0.095307467,
+    0.097587347, 0.099898728, 0.10224173, 0.10461648, 0.1070231, 0.10946171, 0.11193243, 0.11443537,
+    0.11697067, 0.11953843, 0.12213877, 0.12477182, 0.12743768, 0.13013648, 0.13286832, 0.13563333,
+    0.13843162, 0.14126329, 0.14412847, 0.14702727, 0.14995979, 0.15292615, 0.15592646, 0.15896084,
+    0.16202938, 0.16513219, 0.1682694, 0.1714411, 0.1746474, 0.17788842, 0.18116424, 0.18447499,
+    0.18782077, 0.19120168, 0.19461783, 0.19806932, 0.20155625, 0.20507874, 0.20863687, 0.21223076,
+    0.2158605, 0.2195262, 0.22322796, 0.22696587, 0.23074005, 0.23455058, 0.23839757, 0.24228112,
+    0.24620133, 0.25015828, 0.25415209, 0.25818285, 0.26225066, 0.2663556, 0.27049779, 0.27467731,
+    0.27889426, 0.28314874, 0.28744084, 0.29177065, 0.29613827, 0.30054379, 0.30498731, 0.30946892,
+    0.31398871, 0.31854678, 0.32314321, 0.3277781, 0.33245154, 0.33716362, 0.34191442, 0.34670406,
+    0.3515326, 0.35640014, 0.36130678, 0.3662526, 0.37123768, 0.37626212, 0.38132601, 0.38642943,
+    0.39157248, 0.39675523, 0.40197778, 0.40724021, 0.41254261, 0.41788507, 0.42326767, 0.4286905,
+    0.43415364, 0.43965717, 0.44520119, 0.45078578, 0.45641102, 0.462077, 0.4677838, 0.4735315,
+    0.47932018, 0.48514994, 0.49102085, 0.496933, 0.50288646, 0.50888132, 0.51491767, 0.52099557,
+    0.52711513, 0.5332764, 0.53947949, 0.54572446, 0.5520114, 0.55834039, 0.56471151, 0.57112483,
+    0.57758044, 0.58407842, 0.59061884, 0.59720179, 0.60382734, 0.61049557, 0.61720656, 0.62396039,
+    0.63075714, 0.63759687, 0.64447968, 0.65140564, 0.65837482, 0.6653873, 0.67244316, 0.67954247,
+    0.68668531, 0.69387176, 0.70110189, 0.70837578, 0.7156935, 0.72305513, 0.73046074, 0.73791041,
+    0.74540421, 0.75294222, 0.7605245, 0.76815115, 0.77582222, 0.78353779, 0.79129794, 0.79910274,
+    0.80695226, 0.81484657, 0.82278575, 0.83076988, 0.83879901, 0.84687323, 0.85499261, 0.86315721,
+    0.87136712, 0.8796224, 0.88792312, 0.89626935, 0.90466117, 0.91309865, 0.92158186, 0.93011086,
+    0.93868573, 0.94730654, 0.95597335, 0.96468625, 0.97344529, 0.98225055, 0.9911021, 1.0,
+];
+
+// A color with an opacity, for geometry that is composited over what is
+// already drawn (rings, atmospheres, glows).
+#[derive(Debug, Clone, Copy)]
+pub struct Rgba {
+    pub color: Color,
+    pub alpha: f32,
+}
+
+impl Rgba {
+    pub const fn new(color: Color, alpha: f32) -> Self {
+        Rgba { color, alpha }
     }
 }
 
@@ -77,9 +214,9 @@ impl Add for Color {
 
     fn add(self, other: Color) -> Color {
         Color {
-            r: self.r.saturating_add(other.r),
-            g: self.g.saturating_add(other.g),
-            b: self.b.saturating_add(other.b),
+            r: (self.r + other.r).min(1.0),
+            g: (self.g + other.g).min(1.0),
+            b: (self.b + other.b).min(1.0),
         }
     }
 }
@@ -91,15 +228,15 @@ impl Mul<f32> for Color {
 
     fn mul(self, scalar: f32) -> Color {
         Color {
-            r: (self.r as f32 * scalar).clamp(0.0, 255.0) as u8,
-            g: (self.g as f32 * scalar).clamp(0.0, 255.0) as u8,
-            b: (self.b as f32 * scalar).clamp(0.0, 255.0) as u8,
+            r: (self.r * scalar).clamp(0.0, 1.0),
+            g: (self.g * scalar).clamp(0.0, 1.0),
+            b: (self.b * scalar).clamp(0.0, 1.0),
         }
     }
 }
 
 impl fmt::Display for Color {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Color(r: {}, g: {}, b: {})", self.r, self.g, self.b)
+        write!(f, "Color(r: {}, g: {}, b: {})", encode(self.r), encode(self.g), encode(self.b))
     }
 }
\ No newline at end of file
@@ -0,0 +1,101 @@
+use std::f32::consts::PI;
+use nalgebra_glm::Vec3;
+use crate::color::{Color, Gradient};
+use crate::particles::{EmitterParams, ParticleSystem};
+use crate::rng::{Purpose, Rng};
+use crate::scene::CometDesc;
+
+// "arcilla": a dark, rocky surface for the nucleus.
+pub const NUCLEUS_SHADER: u8 = 9;
+
+// Distance at which the comet is fully active; activity falls off with the
+// inverse square of the distance to the sun, like the sunlight heating it.
+const ACTIVITY_RADIUS: f32 = 6.0;
+const MAX_ACTIVITY: f32 = 2.0;
+
+// Gas is blown straight away from the sun by the solar wind, much faster
+// than the comet moves, so the ion tail stays a straight anti-solar line.
+const ION_RATE: f32 = 20.0;
+const ION_SPEED: f32 = 0.4;
+const ION_LIFETIME: f32 = 25.0;
+// Whiter near the nucleus.
+const ION_COLORS: Gradient = Gradient::new(&[(0.0, Color::new(150, 200, 255)), (0.5, Color::new(90, 150, 255))]);
+
+// Dust keeps the comet's orbital velocity and is only gently pushed by
+// radiation pressure, so it lags behind along the orbit and curves.
+const DUST_RATE: f32 = 4.0;
+const DUST_PUSH: f32 = 0.012;
+const DUST_PRESSURE: f32 = 0.0004;
+const DUST_LIFETIME: f32 = 120.0;
+// Reddens as it thins out.
+const DUST_COLORS: Gradient = Gradient::new(&[(0.0, Color::new(255, 230, 180)), (1.0, Color::new(240, 180, 120))]);
+
+#[derive(Clone)]
+pub struct Comet {
+    pub mean_anomaly: f32,
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub activity: f32,
+    pub ion_tail: ParticleSystem,
+    pub dust_tail: ParticleSystem,
+}
+
+impl Comet {
+    pub fn new(desc: &CometDesc, seed: u64, comet_id: u64) -> Self {
+        let ion_params = EmitterParams {
+            lifetime: ION_LIFETIME,
+            spread: 0.04,
+            color: ION_COLORS,
+            brightness: 0.0,
+            ..EmitterParams::default()
+        };
+        let dust_params = EmitterParams {
+            lifetime: DUST_LIFETIME,
+            spread: 0.15,
+            color: DUST_COLORS,
+            brightness: 0.0,
+            ..EmitterParams::default()
+        };
+
+        let position = desc.orbit.position(desc.orbit.phase);
+        Comet {
+            mean_anomaly: desc.orbit.phase,
+            position,
+            velocity: Vec3::zeros(),
+            activity: 0.0,
+            ion_tail: ParticleSystem::new(ion_params, Rng::keyed(seed, comet_id, Purpose::IonTail)),
+            dust_tail: ParticleSystem::new(dust_params, Rng::keyed(seed, comet_id, Purpose::DustTail)),
+        }
+    }
+
+    // Moves the comet without a velocity spike and drops the old tails.
+    pub fn jump_to(&mut self, desc: &CometDesc, mean_anomaly: f32) {
+        self.mean_anomaly = mean_anomaly;
+        self.position = desc.orbit.position(mean_anomaly);
+        self.ion_tail.particles.clear();
+        self.dust_tail.particles.clear();
+    }
+
+    pub fn step(&mut self, desc: &CometDesc) {
+        self.mean_anomaly = (self.mean_anomaly + desc.orbit.mean_motion).rem_euclid(2.0 * PI);
+        let position = desc.orbit.position(self.mean_anomaly);
+        self.velocity = position - self.position;
+        self.position = position;
+
+        // The sun sits at the origin.
+        let distance = self.position.magnitude().max(0.001);
+        let anti_solar = self.position / distance;
+        self.activity = (ACTIVITY_RADIUS / distance).powi(2).min(MAX_ACTIVITY);
+
+        self.ion_tail.params.rate = ION_RATE * self.activity;
+        self.ion_tail.params.brightness = self.activity.min(1.0);
+        self.ion_tail.emit(self.position, anti_solar * ION_SPEED * self.activity);
+        self.ion_tail.update();
+
+        self.dust_tail.params.rate = DUST_RATE * self.activity;
+        self.dust_tail.params.brightness = self.activity.min(1.0) * 0.8;
+        self.dust_tail.params.acceleration = anti_solar * DUST_PRESSURE * self.activity;
+        self.dust_tail.emit(self.position, self.velocity + anti_solar * DUST_PUSH * self.activity);
+        self.dust_tail.update();
+    }
+}
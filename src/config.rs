@@ -0,0 +1,283 @@
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use crate::scene::Diagnostic;
+use crate::title;
+use crate::toml_lite::{self, Table, Value};
+
+pub const CONFIG_PATH: &str = "config.toml";
+
+const SHADING_KEYS: [&str; 2] = ["half_resolution_below", "checkerboard"];
+const LOD_KEYS: [&str; 2] = ["medium_below", "low_below"];
+const CAPTURE_KEYS: [&str; 3] = ["seconds", "fps", "scale"];
+const WINDOW_KEYS: [&str; 2] = ["title", "aspect"];
+const HUD_KEYS: [&str; 1] = ["font"];
+const MOUSE_KEYS: [&str; 3] = ["orbit", "pan", "zoom"];
+const CAMERA_KEYS: [&str; 1] = ["smoothing"];
+// GIF frame delays are in hundredths of a second, and most viewers slow
+// down anything faster than 50 fps.
+const MAX_CAPTURE_FPS: f32 = 50.0;
+
+// Quality/performance knobs. Thresholds are in pixels of on-screen body
+// radius.
+#[derive(Clone, Debug)]
+pub struct ShadingConfig {
+    // Bodies smaller than this run the fragment shader once per 2x2 block.
+    pub half_resolution_below: f32,
+    // Start in the interlaced mode that shades half the pixels per frame.
+    pub checkerboard: bool,
+    pub lod_medium_below: f32,
+    pub lod_low_below: f32,
+}
+
+impl Default for ShadingConfig {
+    fn default() -> Self {
+        ShadingConfig {
+            half_resolution_below: 24.0,
+            checkerboard: false,
+            lod_medium_below: 48.0,
+            lod_low_below: 16.0,
+        }
+    }
+}
+
+// Clip recording for GIF export (F6).
+#[derive(Clone, Debug)]
+pub struct CaptureConfig {
+    // Length of the clip kept in memory; older frames are dropped.
+    pub seconds: f32,
+    pub fps: f32,
+    // Size of the GIF relative to the window.
+    pub scale: f32,
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        CaptureConfig {
+            seconds: 5.0,
+            fps: 15.0,
+            scale: 0.5,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WindowConfig {
+    // Title template; fields like {fps} are filled in about once a second.
+    pub title: String,
+    // Width over height to draw the scene at, letterboxed inside the window;
+    // the window's own when unset.
+    pub aspect: Option<f32>,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig { title: "{scene}".to_string(), aspect: None }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct HudConfig {
+    // TrueType font for HUD text; the built-in bitmap font when unset.
+    pub font: Option<String>,
+}
+
+// Multipliers on how far the camera moves per pixel dragged or wheel notch.
+#[derive(Clone, Debug)]
+pub struct MouseConfig {
+    pub orbit: f32,
+    pub pan: f32,
+    pub zoom: f32,
+}
+
+impl Default for MouseConfig {
+    fn default() -> Self {
+        MouseConfig { orbit: 1.0, pan: 1.0, zoom: 1.0 }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CameraConfig {
+    // Share of the way left behind each frame as the camera eases after the
+    // controls; 0 moves it at once.
+    pub smoothing: f32,
+}
+
+impl Default for CameraConfig {
+    fn default() -> Self {
+        CameraConfig { smoothing: 0.75 }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    pub shading: ShadingConfig,
+    pub capture: CaptureConfig,
+    pub window: WindowConfig,
+    pub hud: HudConfig,
+    pub mouse: MouseConfig,
+    pub camera: CameraConfig,
+}
+
+impl Config {
+    // A missing file means defaults; a broken one is reported as a whole
+    // rather than half applied.
+    pub fn load(path: &str) -> Result<Self, Vec<Diagnostic>> {
+        let mut config = Config::default();
+        let Ok(source) = fs::read_to_string(path) else {
+            return Ok(config);
+        };
+
+        let mut diagnostics = Vec::new();
+        let mut error = |line: usize, message: String| diagnostics.push(Diagnostic { file: path.to_string(), line, message });
+
+        let document = match toml_lite::parse(&source) {
+            Ok(document) => document,
+            Err(errors) => {
+                for parse_error in errors {
+                    error(parse_error.line, parse_error.message);
+                }
+                return Err(diagnostics);
+            }
+        };
+
+        for entry in &document.root.entries {
+            error(entry.line, format!("unknown key `{}` outside of a table", entry.key));
+        }
+        for table in &document.tables {
+            match table.name.as_str() {
+                "shading" => {
+                    check_keys(table, &SHADING_KEYS, &mut error);
+                    read_pixels(table, "half_resolution_below", &mut config.shading.half_resolution_below, &mut error);
+                    read_flag(table, "checkerboard", &mut config.shading.checkerboard, &mut error);
+                }
+                "lod" => {
+                    check_keys(table, &LOD_KEYS, &mut error);
+                    read_pixels(table, "medium_below", &mut config.shading.lod_medium_below, &mut error);
+                    read_pixels(table, "low_below", &mut config.shading.lod_low_below, &mut error);
+                    if config.shading.lod_low_below > config.shading.lod_medium_below {
+                        error(table.line, "`low_below` must not be larger than `medium_below`".to_string());
+                    }
+                }
+                "capture" => {
+                    check_keys(table, &CAPTURE_KEYS, &mut error);
+                    read_in_range(table, "seconds", 0.5..=60.0, &mut config.capture.seconds, &mut error);
+                    read_in_range(table, "fps", 1.0..=MAX_CAPTURE_FPS, &mut config.capture.fps, &mut error);
+                    read_in_range(table, "scale", 0.1..=1.0, &mut config.capture.scale, &mut error);
+                }
+                "window" => {
+                    check_keys(table, &WINDOW_KEYS, &mut error);
+                    read_title(table, "title", &mut config.window.title, &mut error);
+                    read_aspect(table, "aspect", &mut config.window.aspect, &mut error);
+                }
+                "hud" => {
+                    check_keys(table, &HUD_KEYS, &mut error);
+                    read_font(table, "font", &mut config.hud.font, &mut error);
+                }
+                "mouse" => {
+                    check_keys(table, &MOUSE_KEYS, &mut error);
+                    read_in_range(table, "orbit", 0.1..=10.0, &mut config.mouse.orbit, &mut error);
+                    read_in_range(table, "pan", 0.1..=10.0, &mut config.mouse.pan, &mut error);
+                    read_in_range(table, "zoom", 0.1..=10.0, &mut config.mouse.zoom, &mut error);
+                }
+                "camera" => {
+                    check_keys(table, &CAMERA_KEYS, &mut error);
+                    read_in_range(table, "smoothing", 0.0..=0.95, &mut config.camera.smoothing, &mut error);
+                }
+                _ => error(table.line, format!("unknown table `[{}]`", table.name)),
+            }
+        }
+
+        if diagnostics.is_empty() {
+            Ok(config)
+        } else {
+            diagnostics.sort_by_key(|diagnostic| diagnostic.line);
+            Err(diagnostics)
+        }
+    }
+}
+
+fn check_keys(table: &Table, allowed: &[&str], error: &mut impl FnMut(usize, String)) {
+    for entry in &table.entries {
+        if !allowed.contains(&entry.key.as_str()) {
+            error(entry.line, format!("unknown key `{}` (expected one of: {})", entry.key, allowed.join(", ")));
+        }
+    }
+}
+
+fn read_pixels(table: &Table, key: &str, target: &mut f32, error: &mut impl FnMut(usize, String)) {
+    let Some(entry) = table.get(key) else {
+        return;
+    };
+    match entry.value {
+        Value::Number(value) if value >= 0.0 => *target = value,
+        Value::Number(_) => error(entry.line, format!("`{}` must not be negative", key)),
+        ref other => error(entry.line, format!("`{}` must be a number of pixels, found {}", key, other.type_name())),
+    }
+}
+
+fn read_in_range(table: &Table, key: &str, range: RangeInclusive<f32>, target: &mut f32, error: &mut impl FnMut(usize, String)) {
+    let Some(entry) = table.get(key) else {
+        return;
+    };
+    match entry.value {
+        Value::Number(value) if range.contains(&value) => *target = value,
+        Value::Number(value) => error(entry.line, format!("`{}` must be between {} and {}, found {}", key, range.start(), range.end(), value)),
+        ref other => error(entry.line, format!("`{}` must be a number, found {}", key, other.type_name())),
+    }
+}
+
+fn read_flag(table: &Table, key: &str, target: &mut bool, error: &mut impl FnMut(usize, String)) {
+    let Some(entry) = table.get(key) else {
+        return;
+    };
+    match entry.value {
+        Value::Bool(value) => *target = value,
+        ref other => error(entry.line, format!("`{}` must be true or false, found {}", key, other.type_name())),
+    }
+}
+
+fn read_title(table: &Table, key: &str, target: &mut String, error: &mut impl FnMut(usize, String)) {
+    let Some(entry) = table.get(key) else {
+        return;
+    };
+    match &entry.value {
+        Value::String(template) => match title::check(template) {
+            Ok(()) => *target = template.clone(),
+            Err(message) => error(entry.line, message),
+        },
+        other => error(entry.line, format!("`{}` must be a string, found {}", key, other.type_name())),
+    }
+}
+
+// Either a ratio like "16:9" or the number it comes to.
+fn read_aspect(table: &Table, key: &str, target: &mut Option<f32>, error: &mut impl FnMut(usize, String)) {
+    let Some(entry) = table.get(key) else {
+        return;
+    };
+    let aspect = match &entry.value {
+        Value::Number(value) => Some(*value),
+        Value::String(ratio) => ratio
+            .split_once(':')
+            .and_then(|(width, height)| Some(width.trim().parse::<f32>().ok()? / height.trim().parse::<f32>().ok()?)),
+        other => {
+            error(entry.line, format!("`{}` must be a ratio like \"16:9\" or a number, found {}", key, other.type_name()));
+            return;
+        }
+    };
+    match aspect {
+        Some(aspect) if (0.25..=4.0).contains(&aspect) => *target = Some(aspect),
+        _ => error(entry.line, format!("`{}` must be a ratio like \"16:9\" between 1:4 and 4:1", key)),
+    }
+}
+
+fn read_font(table: &Table, key: &str, target: &mut Option<String>, error: &mut impl FnMut(usize, String)) {
+    let Some(entry) = table.get(key) else {
+        return;
+    };
+    match &entry.value {
+        Value::String(path) if Path::new(path).is_file() => *target = Some(path.clone()),
+        Value::String(path) => error(entry.line, format!("font `{}` does not exist", path)),
+        other => error(entry.line, format!("`{}` must be a path to a .ttf file, found {}", key, other.type_name())),
+    }
+}
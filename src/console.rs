@@ -0,0 +1,129 @@
+use minifb::{Key, KeyRepeat};
+use crate::framebuffer::Framebuffer;
+use crate::hud::draw_anchored_panel;
+use crate::input::InputFrame;
+use crate::layout::Anchor;
+use crate::shaders::{shader_id, SHADER_NAMES};
+use crate::spawn::Template;
+
+// Keys that type into the console, and what they type.
+const TYPED: [(Key, char); 40] = [
+    (Key::A, 'a'), (Key::B, 'b'), (Key::C, 'c'), (Key::D, 'd'), (Key::E, 'e'), (Key::F, 'f'), (Key::G, 'g'),
+    (Key::H, 'h'), (Key::I, 'i'), (Key::J, 'j'), (Key::K, 'k'), (Key::L, 'l'), (Key::M, 'm'), (Key::N, 'n'),
+    (Key::O, 'o'), (Key::P, 'p'), (Key::Q, 'q'), (Key::R, 'r'), (Key::S, 's'), (Key::T, 't'), (Key::U, 'u'),
+    (Key::V, 'v'), (Key::W, 'w'), (Key::X, 'x'), (Key::Y, 'y'), (Key::Z, 'z'),
+    (Key::Key0, '0'), (Key::Key1, '1'), (Key::Key2, '2'), (Key::Key3, '3'), (Key::Key4, '4'),
+    (Key::Key5, '5'), (Key::Key6, '6'), (Key::Key7, '7'), (Key::Key8, '8'), (Key::Key9, '9'),
+    (Key::Space, ' '), (Key::Period, '.'), (Key::Minus, '-'), (Key::Comma, ','),
+];
+const MAX_LINE: usize = 60;
+// File name, without extension, of `exportar` with no name given.
+const EXPORT_NAME: &str = "escena_exportada";
+const HELP: &str = "shader <nombre> | escala <factor> | velocidad <factor> | ocultar | mostrar | crear <plantilla> | borrar | guardar | exportar [nombre] | toma [nombre]";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Edit(Edit),
+    // A new body where the camera is looking.
+    Spawn(Template),
+    // Deletes the selection.
+    Delete,
+    // Writes the selection's orbits back to the scene file.
+    WriteOrbits,
+    // Writes the whole scene as it is now to a new scene file.
+    Export(String),
+    // Flies to the scene's shot of that name, or lists them.
+    Shot(Option<String>),
+}
+
+// Edits that apply to every body in the selection at once.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Edit {
+    Shader(u8),
+    // Multiplies the size.
+    Scale(f32),
+    // Multiplies the orbital speed.
+    Speed(f32),
+    Hide,
+    Show,
+}
+
+pub fn parse(line: &str) -> Result<Command, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let factor = |word: Option<&&str>| -> Result<f32, String> {
+        let text = word.ok_or("falta el factor")?;
+        match text.replace(',', ".").parse::<f32>() {
+            Ok(factor) if factor.is_finite() && factor > 0.0 => Ok(factor),
+            _ => Err(format!("`{}` no es un factor positivo", text)),
+        }
+    };
+    let templates = || Template::ALL.map(Template::name).join(", ");
+    match words.first().copied() {
+        Some("shader") => {
+            let name = words.get(1).ok_or_else(|| format!("falta el shader ({})", SHADER_NAMES.join(", ")))?;
+            shader_id(name).map(|shader| Command::Edit(Edit::Shader(shader))).ok_or_else(|| format!("shader desconocido `{}`", name))
+        }
+        Some("escala") => factor(words.get(1)).map(|factor| Command::Edit(Edit::Scale(factor))),
+        Some("velocidad") => factor(words.get(1)).map(|factor| Command::Edit(Edit::Speed(factor))),
+        Some("ocultar") => Ok(Command::Edit(Edit::Hide)),
+        Some("mostrar") => Ok(Command::Edit(Edit::Show)),
+        Some("crear") => {
+            let name = words.get(1).ok_or_else(|| format!("falta la plantilla ({})", templates()))?;
+            Template::parse(name).map(Command::Spawn).ok_or_else(|| format!("plantilla desconocida `{}` ({})", name, templates()))
+        }
+        Some("borrar") => Ok(Command::Delete),
+        Some("guardar") => Ok(Command::WriteOrbits),
+        Some("exportar") => Ok(Command::Export(format!("{}.toml", words.get(1).copied().unwrap_or(EXPORT_NAME)))),
+        Some("toma") => Ok(Command::Shot((words.len() > 1).then(|| words[1..].join(" ")))),
+        Some(other) => Err(format!("comando desconocido `{}`: {}", other, HELP)),
+        None => Err(HELP.to_string()),
+    }
+}
+
+// One-line command prompt, opened and closed with the ` key. While it is
+// open every key types into it instead of controlling the viewer.
+pub struct Console {
+    pub open: bool,
+    line: String,
+    // Result of the last command.
+    message: String,
+}
+
+impl Console {
+    pub fn new() -> Self {
+        Console { open: false, line: String::new(), message: HELP.to_string() }
+    }
+
+    // Returns the line when Enter is pressed.
+    pub fn handle_input(&mut self, input: &InputFrame) -> Option<String> {
+        if input.is_key_pressed(Key::Backquote, KeyRepeat::No) {
+            self.open = !self.open;
+            return None;
+        }
+        if !self.open {
+            return None;
+        }
+        if input.is_key_pressed(Key::Enter, KeyRepeat::No) {
+            return Some(std::mem::take(&mut self.line));
+        }
+        if input.is_key_pressed(Key::Backspace, KeyRepeat::Yes) {
+            self.line.pop();
+        }
+        for (key, c) in TYPED {
+            if input.is_key_pressed(key, KeyRepeat::Yes) && self.line.len() < MAX_LINE {
+                self.line.push(c);
+            }
+        }
+        None
+    }
+
+    pub fn report(&mut self, message: String) {
+        self.message = message;
+    }
+
+    pub fn draw(&self, framebuffer: &mut Framebuffer) {
+        if self.open {
+            draw_anchored_panel(framebuffer, Anchor::TopLeft, &[format!("> {}_", self.line), self.message.clone()]);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use nalgebra_glm::Vec3;
+use crate::color::{Color, Rgba};
+use crate::fragment::Fragment;
+use crate::noise::{loop_phase, looping_noise_3d, smoothstep, NoiseField, LOOP_PERIOD};
+
+const SHELLS: usize = 4;
+// Shells are born just above the surface and fade out at the outer radius,
+// both in sun radii.
+const INNER_RADIUS: f32 = 1.05;
+const OUTER_RADIUS: f32 = 2.2;
+// Seconds for a shell to swell from the inner to the outer radius; divides
+// the animation loop so recorded clips still repeat.
+const EXPANSION_SECONDS: f32 = LOOP_PERIOD / 8.0;
+const CORONA_COLOR: Color = Color::new(255, 196, 120);
+const STREAMER_ZOOM: f32 = 300.0;
+const STREAMER_DRIFT: Vec3 = Vec3::new(0.0, 120.0, 0.0);
+
+// One glowing layer: its radius in sun radii and how bright it is right now.
+pub struct Shell {
+    pub radius: f32,
+    pub strength: f32,
+}
+
+// The layers at `time`, evenly staggered so one is always being born as
+// another fades out.
+pub fn shells(time: f32) -> impl Iterator<Item = Shell> {
+    (0..SHELLS).map(move |layer| {
+        let age = loop_phase(time + layer as f32 * EXPANSION_SECONDS / SHELLS as f32, EXPANSION_SECONDS);
+        Shell {
+            radius: INNER_RADIUS + (OUTER_RADIUS - INNER_RADIUS) * age,
+            // Fades in quickly and out slowly, and dims as it spreads.
+            strength: smoothstep(0.0, 0.1, age) * (1.0 - age) / SHELLS as f32,
+        }
+    })
+}
+
+// Additive glow of a shell's front face. `to_eye` points from the fragment
+// to the camera in the shell's own frame. Each shell lights up the sky from
+// the sun's limb out to its own silhouette, brightest near the sun, broken
+// into streamers by noise; over the sun's disk it stays out of the way.
+pub fn shade(fragment: &Fragment, noise: &NoiseField, shell: &Shell, to_eye: Vec3, time: f32) -> Option<Rgba> {
+    let normal = fragment.vertex_position.normalize();
+    let facing = normal.dot(&to_eye).clamp(0.0, 1.0);
+    // Distance from the sun's center across the screen, in sun radii.
+    let projected = (1.0 - facing * facing).sqrt() * shell.radius;
+    if projected < 0.9 {
+        return None;
+    }
+
+    let edge = 1.0 - projected / shell.radius;
+    let falloff = edge / (projected * projected);
+    let streamers = looping_noise_3d(noise, normal * STREAMER_ZOOM, STREAMER_DRIFT, time, LOOP_PERIOD);
+    let glow = falloff * smoothstep(0.9, 1.05, projected) * (1.0 + 0.6 * streamers) * shell.strength;
+    Some(Rgba::new(CORONA_COLOR, (glow * 3.0).clamp(0.0, 1.0)))
+}
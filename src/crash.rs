@@ -0,0 +1,127 @@
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use nalgebra_glm::Vec3;
+use crate::camera::Camera;
+use crate::scene::Scene;
+use crate::sim::Simulation;
+use crate::state::State;
+
+const LOG_LINES: usize = 40;
+
+// What the main loop last reported, kept where the panic hook can reach it.
+// The scene state is a copy, formatted only when a report is written, so
+// the hook doesn't have to touch anything that might be half updated.
+struct CrashContext {
+    state: State,
+    camera: [Vec3; 3],
+    frame: u64,
+    log: VecDeque<String>,
+}
+
+static CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext {
+    state: State::new(),
+    camera: [Vec3::new(0.0, 0.0, 0.0); 3],
+    frame: 0,
+    log: VecDeque::new(),
+});
+
+// Replaces the default panic output with a report file plus a short summary
+// on the console.
+pub fn install() {
+    panic::set_hook(Box::new(|info| {
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+        let path = format!("crash_{}.txt", seconds);
+        let report = report(info);
+
+        eprintln!();
+        eprintln!("Lab4_G stopped with an error: {}", message(info));
+        if let Some(location) = info.location() {
+            eprintln!("  at {}:{}", location.file(), location.line());
+        }
+        match fs::write(&path, report) {
+            Ok(()) => eprintln!("Report saved to {}, please attach it when reporting the error.", path),
+            Err(error) => eprintln!("Could not write {}: {}", path, error),
+        }
+    }));
+}
+
+// Called once per frame with what a report should describe.
+pub fn update(camera: &Camera, frame: u64, scene: &Scene, sim: &Simulation, time: u32) {
+    let mut context = lock();
+    context.camera = [camera.eye, camera.center, camera.up];
+    context.frame = frame;
+    context.state.capture(scene, sim, time);
+}
+
+// Prints a message and keeps it for the next crash report.
+pub fn log(message: String) {
+    println!("{}", message);
+    remember(message);
+}
+
+pub fn log_error(message: String) {
+    eprintln!("{}", message);
+    remember(message);
+}
+
+fn remember(message: String) {
+    let mut context = lock();
+    if context.log.len() == LOG_LINES {
+        context.log.pop_front();
+    }
+    context.log.push_back(message);
+}
+
+// A panic while the lock was held must not stop the report from being written.
+fn lock() -> std::sync::MutexGuard<'static, CrashContext> {
+    CONTEXT.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn message(info: &PanicHookInfo) -> String {
+    let payload = info.payload();
+    payload
+        .downcast_ref::<&str>()
+        .map(|text| text.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "panic without a message".to_string())
+}
+
+fn report(info: &PanicHookInfo) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "Lab4_G {} crash report", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(out, "panic: {}", message(info));
+    if let Some(location) = info.location() {
+        let _ = writeln!(out, "at: {}:{}:{}", location.file(), location.line(), location.column());
+    }
+    let _ = writeln!(out, "thread: {}", std::thread::current().name().unwrap_or("<unnamed>"));
+
+    // try_lock: if the panicking thread holds the lock, report without it
+    // rather than deadlock.
+    match CONTEXT.try_lock() {
+        Ok(context) => write_context(&mut out, &context),
+        Err(std::sync::TryLockError::Poisoned(poisoned)) => write_context(&mut out, &poisoned.into_inner()),
+        Err(std::sync::TryLockError::WouldBlock) => {
+            let _ = writeln!(out, "\n(state unavailable: lock held by the panicking thread)");
+        }
+    }
+
+    let _ = writeln!(out, "\n== backtrace ==\n{}", Backtrace::force_capture());
+    out
+}
+
+fn write_context(out: &mut String, context: &CrashContext) {
+    let _ = writeln!(out, "frame: {}", context.frame);
+    let vector = |v: &Vec3| format!("[{}, {}, {}]", v.x, v.y, v.z);
+    let [eye, center, up] = &context.camera;
+    let _ = writeln!(out, "\n== camera ==\neye = {}\ncenter = {}\nup = {}", vector(eye), vector(center), vector(up));
+    let _ = writeln!(out, "\n== recent log ==");
+    for line in &context.log {
+        let _ = writeln!(out, "{}", line);
+    }
+    let _ = writeln!(out, "\n== state ==\n{}", context.state.to_toml());
+}
@@ -0,0 +1,123 @@
+use crate::framebuffer::Framebuffer;
+
+// Flat shapes drawn straight onto the shown pixels, after tone mapping and
+// ignoring depth: the building blocks of the HUD. Anything outside the
+// screen is clipped.
+
+pub fn fill_rect(framebuffer: &mut Framebuffer, x: usize, y: usize, width: usize, height: usize, color: u32) {
+    let x_end = (x + width).min(framebuffer.width);
+    let y_end = (y + height).min(framebuffer.height);
+    for row in y.min(y_end)..y_end {
+        framebuffer.buffer[row * framebuffer.width + x.min(x_end)..row * framebuffer.width + x_end].fill(color);
+    }
+}
+
+// A one pixel border just inside the rectangle.
+pub fn stroke_rect(framebuffer: &mut Framebuffer, x: usize, y: usize, width: usize, height: usize, color: u32) {
+    if width == 0 || height == 0 {
+        return;
+    }
+    fill_rect(framebuffer, x, y, width, 1, color);
+    fill_rect(framebuffer, x, y + height - 1, width, 1, color);
+    fill_rect(framebuffer, x, y, 1, height, color);
+    fill_rect(framebuffer, x + width - 1, y, 1, height, color);
+}
+
+// Mixes `color` over the rectangle by `alpha`, for see-through panels.
+pub fn blend_rect(framebuffer: &mut Framebuffer, x: usize, y: usize, width: usize, height: usize, color: u32, alpha: f32) {
+    for row in y..(y + height).min(framebuffer.height) {
+        for column in x..(x + width).min(framebuffer.width) {
+            blend_pixel(framebuffer, column, row, color, alpha);
+        }
+    }
+}
+
+// A box that stretches to any size without distorting its corners: the
+// corner pixels are drawn as they are (mirrored for the other three), the
+// last row and column of the corner repeat along the edges and the middle
+// is filled.
+#[derive(Clone, Copy, Debug)]
+pub struct NinePatch {
+    // The top-left corner: 0 leaves the pixel alone, 1 is border, 2 fill.
+    pub corner: [[u8; 4]; 4],
+    pub fill: u32,
+    pub border: u32,
+    // Opacity of the whole box over what is behind it.
+    pub alpha: f32,
+}
+
+// The translucent, slightly rounded box behind HUD panels and graphs.
+pub const PANEL: NinePatch = NinePatch {
+    corner: [[0, 0, 1, 1], [0, 1, 2, 2], [1, 2, 2, 2], [1, 2, 2, 2]],
+    fill: 0x101018,
+    border: 0x34344A,
+    alpha: 0.85,
+};
+
+pub fn nine_patch(framebuffer: &mut Framebuffer, x: usize, y: usize, width: usize, height: usize, patch: &NinePatch) {
+    let size = patch.corner.len();
+    // Where a pixel falls in the corner, counting from the nearest end.
+    let patch_index = |offset: usize, length: usize| offset.min(length - 1 - offset).min(size - 1);
+    for row in y..(y + height).min(framebuffer.height) {
+        let patch_row = patch_index(row - y, height);
+        for column in x..(x + width).min(framebuffer.width) {
+            let color = match patch.corner[patch_row][patch_index(column - x, width)] {
+                1 => patch.border,
+                2 => patch.fill,
+                _ => continue,
+            };
+            blend_pixel(framebuffer, column, row, color, patch.alpha);
+        }
+    }
+}
+
+// A one pixel line between two points, stepping along the longer axis.
+pub fn line(framebuffer: &mut Framebuffer, from: (f32, f32), to: (f32, f32), color: u32) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let steps = dx.abs().max(dy.abs()).round().max(1.0) as usize;
+    for step in 0..=steps {
+        let t = step as f32 / steps as f32;
+        plot(framebuffer, from.0 + dx * t, from.1 + dy * t, color);
+    }
+}
+
+pub fn fill_circle(framebuffer: &mut Framebuffer, center: (f32, f32), radius: f32, color: u32) {
+    let top = (center.1 - radius).floor().max(0.0) as usize;
+    let bottom = (center.1 + radius).ceil().max(0.0) as usize;
+    for row in top..=bottom {
+        let dy = row as f32 + 0.5 - center.1;
+        let half_width = (radius * radius - dy * dy).max(0.0).sqrt();
+        if half_width <= 0.0 {
+            continue;
+        }
+        let left = (center.0 - half_width).round().max(0.0) as usize;
+        let right = (center.0 + half_width).round().max(0.0) as usize;
+        fill_rect(framebuffer, left, row, right.saturating_sub(left), 1, color);
+    }
+}
+
+// A one pixel ring, with enough points that it has no gaps.
+pub fn stroke_circle(framebuffer: &mut Framebuffer, center: (f32, f32), radius: f32, color: u32) {
+    let points = (radius * std::f32::consts::TAU).ceil().max(8.0) as usize;
+    for point in 0..points {
+        let (sin, cos) = (point as f32 / points as f32 * std::f32::consts::TAU).sin_cos();
+        plot(framebuffer, center.0 + cos * radius, center.1 + sin * radius, color);
+    }
+}
+
+fn plot(framebuffer: &mut Framebuffer, x: f32, y: f32, color: u32) {
+    if x >= 0.0 && y >= 0.0 {
+        fill_rect(framebuffer, x as usize, y as usize, 1, 1, color);
+    }
+}
+
+fn blend_pixel(framebuffer: &mut Framebuffer, x: usize, y: usize, color: u32, alpha: f32) {
+    let index = y * framebuffer.width + x;
+    let existing = framebuffer.buffer[index];
+    let channel = |shift: u32| {
+        let below = ((existing >> shift) & 0xFF) as f32;
+        let above = ((color >> shift) & 0xFF) as f32;
+        ((below + (above - below) * alpha) as u32) << shift
+    };
+    framebuffer.buffer[index] = channel(16) | channel(8) | channel(0);
+}
@@ -0,0 +1,213 @@
+use std::f32::consts::PI;
+use std::fmt::Write as _;
+use std::fs;
+use nalgebra_glm::Vec3;
+use crate::camera::{Camera, DEFAULT_FOV};
+use crate::color::Color;
+use crate::material::Material;
+use crate::noise::NoisePreset;
+use crate::orbit::Orbit;
+use crate::sampling::Filter;
+use crate::texture::{ColorSpace, PLANET_WRAP};
+use crate::scene::{BodyDesc, Scene};
+use crate::shaders::SHADER_NAMES;
+use crate::sim::{Simulation, STEPS_PER_SECOND};
+use crate::variability::LightCurve;
+
+// Seeds above this don't survive being read back as f32 and are written as
+// strings instead.
+const LARGEST_EXACT_SEED: u64 = 1 << 24;
+
+// Writes the scene as it is now as a scene file `Scene::load` reads back:
+// generated systems, runtime edits, added and deleted bodies, and the
+// camera. Orbits start where the bodies are at the moment of export.
+pub fn save(path: &str, scene: &Scene, sim: &Simulation, camera: &Camera) -> Result<(), String> {
+    fs::write(path, to_toml(scene, sim, camera)).map_err(|e| format!("cannot write {}: {}", path, e))
+}
+
+pub fn to_toml(scene: &Scene, sim: &Simulation, camera: &Camera) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# Exportado desde el visor.");
+    let _ = writeln!(out, "name = {:?}", scene.name);
+    let _ = writeln!(out, "seed = {}", seed(scene.seed));
+    let _ = writeln!(out, "heat_palette = {:?}", scene.heat_palette.name());
+
+    // Everything below is in scene units and seconds; these only set how
+    // the HUD converts them back.
+    let _ = writeln!(out);
+    let _ = writeln!(out, "[units]");
+    let _ = writeln!(out, "distance_scale = {}", scene.au);
+    let _ = writeln!(out, "day_length = {}", scene.day_length);
+
+    if let Some(zone) = &scene.habitable_zone {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[habitable_zone]");
+        let _ = writeln!(out, "inner = {}", zone.inner);
+        let _ = writeln!(out, "outer = {}", zone.outer);
+    }
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "[camera]");
+    let _ = writeln!(out, "eye = {}", vector(camera.eye));
+    let _ = writeln!(out, "center = {}", vector(camera.center));
+
+    if let Some(starfield) = &scene.starfield {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[starfield]");
+        let _ = writeln!(out, "density = {}", starfield.density);
+        let _ = writeln!(out, "seed = {}", seed(starfield.seed));
+        let _ = writeln!(out, "twinkle = {}", starfield.twinkle);
+    }
+
+    if let Some(belt) = &scene.asteroid_belt {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[asteroid_belt]");
+        let _ = writeln!(out, "inner = {}", belt.inner);
+        let _ = writeln!(out, "outer = {}", belt.outer);
+        let _ = writeln!(out, "count = {}", belt.count);
+        let _ = writeln!(out, "scale = {}", belt.scale);
+    }
+
+    for (body, state) in scene.bodies.iter().zip(&sim.bodies) {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[[body]]");
+        write_body(&mut out, scene, body, state.orbit_angle);
+    }
+
+    for (desc, comet) in scene.comets.iter().zip(&sim.comets) {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[[comet]]");
+        let _ = writeln!(out, "name = {:?}", desc.name);
+        let _ = writeln!(out, "scale = {}", desc.scale);
+        let _ = writeln!(out, "semi_major_axis = {}", desc.orbit.semi_major_axis);
+        write_orbit_shape(&mut out, &desc.orbit);
+        let _ = writeln!(out, "mean_anomaly = {}", comet.mean_anomaly.rem_euclid(2.0 * PI));
+        let _ = writeln!(out, "orbital_speed = {}", desc.orbit.mean_motion * STEPS_PER_SECOND);
+    }
+
+    for caption in &scene.captions.captions {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[[caption]]");
+        let _ = writeln!(out, "text = {:?}", caption.text);
+        let _ = writeln!(out, "start = {}", caption.start);
+        let _ = writeln!(out, "duration = {}", caption.duration);
+        if let Some(shot) = &caption.shot {
+            let _ = writeln!(out, "shot = {:?}", shot);
+        }
+    }
+
+    for shot in &scene.shots {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[[shot]]");
+        let _ = writeln!(out, "name = {:?}", shot.name);
+        if let Some(focus) = shot.focus {
+            let _ = writeln!(out, "focus = {:?}", scene.bodies[focus].name);
+        }
+        let _ = writeln!(out, "eye = {}", vector(shot.eye));
+        let _ = writeln!(out, "center = {}", vector(shot.center));
+        if shot.fov != DEFAULT_FOV {
+            let _ = writeln!(out, "fov = \"{} deg\"", shot.fov.to_degrees());
+        }
+    }
+
+    out
+}
+
+// Keys left at their defaults are skipped.
+fn write_body(out: &mut String, scene: &Scene, body: &BodyDesc, orbit_angle: f32) {
+    let _ = writeln!(out, "name = {:?}", body.name);
+    let _ = writeln!(out, "shader = {:?}", SHADER_NAMES[body.shader as usize]);
+    if body.noise != NoisePreset::Plain {
+        let _ = writeln!(out, "noise = {:?}", body.noise.name());
+    }
+    if body.material != Material::for_shader(body.shader) {
+        if let Some(name) = body.material.name() {
+            let _ = writeln!(out, "material = {:?}", name);
+        }
+    }
+    let _ = writeln!(out, "scale = {}", body.scale);
+    if let Some(parent) = body.parent {
+        let _ = writeln!(out, "parent = {:?}", scene.bodies[parent].name);
+    }
+    if body.orbit.semi_major_axis > 0.0 {
+        let _ = writeln!(out, "orbit_radius = {}", body.orbit.semi_major_axis);
+        let _ = writeln!(out, "orbit_phase = {}", orbit_angle.rem_euclid(2.0 * PI));
+        write_orbit_shape(out, &body.orbit);
+        let _ = writeln!(out, "orbital_speed = {}", body.orbit.mean_motion * STEPS_PER_SECOND);
+    }
+    let _ = writeln!(out, "rotation_speed = {}", body.rotation_speed * STEPS_PER_SECOND);
+    if body.axial_tilt != 0.0 {
+        let _ = writeln!(out, "axial_tilt = {}", body.axial_tilt);
+    }
+    if body.magnetosphere {
+        let _ = writeln!(out, "magnetosphere = true");
+    }
+    if body.clouds {
+        let _ = writeln!(out, "clouds = true");
+    }
+    if let Some(color) = body.atmosphere {
+        let _ = writeln!(out, "atmosphere = {}", rgb(color));
+    }
+    if let Some(radius) = body.lensing {
+        let _ = writeln!(out, "lensing = {}", radius);
+    }
+    if let Some(variability) = &body.variability {
+        let _ = writeln!(out, "variability_period = {}", variability.period);
+        match &variability.curve {
+            LightCurve::Sinusoid { amplitude } => {
+                let _ = writeln!(out, "variability_amplitude = {}", amplitude);
+            }
+            LightCurve::Table(samples) => {
+                let samples: Vec<String> = samples.iter().map(|sample| sample.to_string()).collect();
+                let _ = writeln!(out, "light_curve = [{}]", samples.join(", "));
+            }
+        }
+    }
+    if let Some(texture) = &body.texture {
+        let _ = writeln!(out, "texture = {:?}", texture);
+        if body.texture_filter != Filter::Bilinear {
+            let _ = writeln!(out, "texture_filter = {:?}", body.texture_filter.name());
+        }
+        if body.texture_space != ColorSpace::Srgb {
+            let _ = writeln!(out, "texture_space = {:?}", body.texture_space.name());
+        }
+        match body.texture_wrap {
+            wrap if wrap == PLANET_WRAP => {}
+            (wrap_u, wrap_v) if wrap_u == wrap_v => {
+                let _ = writeln!(out, "texture_wrap = {:?}", wrap_u.name());
+            }
+            (wrap_u, wrap_v) => {
+                let _ = writeln!(out, "texture_wrap = [{:?}, {:?}]", wrap_u.name(), wrap_v.name());
+            }
+        }
+    }
+    if let Some(mesh) = &body.mesh {
+        let _ = writeln!(out, "mesh = {:?}", mesh);
+    }
+    if let Some(rings) = &body.rings {
+        let _ = writeln!(out, "ring_inner = {}", rings.inner);
+        let _ = writeln!(out, "ring_outer = {}", rings.outer);
+        let _ = writeln!(out, "ring_gaps = {}", rings.gaps);
+    }
+}
+
+fn write_orbit_shape(out: &mut String, orbit: &Orbit) {
+    for (key, value) in [("eccentricity", orbit.eccentricity), ("inclination", orbit.inclination), ("periapsis", orbit.periapsis)] {
+        if value != 0.0 {
+            let _ = writeln!(out, "{} = {}", key, value);
+        }
+    }
+}
+
+fn seed(seed: u64) -> String {
+    if seed <= LARGEST_EXACT_SEED { seed.to_string() } else { format!("\"{}\"", seed) }
+}
+
+fn vector(vector: Vec3) -> String {
+    format!("[{}, {}, {}]", vector.x, vector.y, vector.z)
+}
+
+fn rgb(color: Color) -> String {
+    let hex = color.to_hex();
+    format!("[{}, {}, {}]", hex >> 16 & 0xFF, hex >> 8 & 0xFF, hex & 0xFF)
+}
@@ -0,0 +1,86 @@
+use nalgebra_glm::{Mat4, Vec3};
+use crate::color::{Color, Gradient};
+use crate::framebuffer::Framebuffer;
+use crate::particles::{Curve, EmitterParams, ParticleSystem, Shape};
+use crate::rng::{Purpose, Rng};
+use crate::scene::{Scene, SPHERE_RADIUS};
+use crate::shaders::is_black_hole;
+
+// Chance per frame that a star throws off a flare.
+const FLARE_CHANCE: f32 = 1.0 / 150.0;
+const PARTICLES_PER_FLARE: usize = 40;
+const FLARE_FRAMES: f32 = 90.0;
+// Launch speed and the pull back down, per frame, in star radii; together
+// they make the plasma loop back to the surface about when it fades.
+const LAUNCH_SPEED: f32 = 0.045;
+const SURFACE_GRAVITY: f32 = 0.0011;
+const COLORS: Gradient = Gradient::new(&[(0.0, Color::new(255, 250, 220)), (0.25, Color::new(255, 190, 80)), (1.0, Color::new(220, 70, 30))]);
+const SIZE: Curve = Curve::new(&[(0.0, 2.5), (1.0, 1.0)]);
+const FADE: Curve = Curve::new(&[(0.0, 0.0), (0.1, 1.0), (1.0, 0.0)]);
+
+// One eruption: a fan of plasma from a point on a star's surface, arcing
+// up and falling back. Positions are relative to the star's center.
+#[derive(Clone)]
+pub struct Flare {
+    pub star: usize,
+    pub plasma: ParticleSystem,
+}
+
+// Flares bursting at random from every star in the scene.
+#[derive(Clone)]
+pub struct SolarFlares {
+    pub flares: Vec<Flare>,
+    rng: Rng,
+}
+
+impl SolarFlares {
+    pub fn new(seed: u64) -> Self {
+        SolarFlares { flares: Vec::new(), rng: Rng::keyed(seed, 0, Purpose::Flares) }
+    }
+
+    pub fn step(&mut self, scene: &Scene) {
+        for (star, body) in scene.bodies.iter().enumerate() {
+            if body.orbit.semi_major_axis != 0.0 || is_black_hole(body.shader) || self.rng.next_f32() >= FLARE_CHANCE {
+                continue;
+            }
+            let flare = self.erupt(star, body.scale * SPHERE_RADIUS);
+            self.flares.push(flare);
+        }
+        for flare in &mut self.flares {
+            flare.plasma.update();
+        }
+        self.flares.retain(|flare| !flare.plasma.particles.is_empty());
+    }
+
+    fn erupt(&mut self, star: usize, radius: f32) -> Flare {
+        let normal = Vec3::new(self.rng.range(-1.0, 1.0), self.rng.range(-1.0, 1.0), self.rng.range(-1.0, 1.0))
+            .try_normalize(1.0e-6)
+            .unwrap_or(Vec3::y());
+        let params = EmitterParams {
+            lifetime: FLARE_FRAMES,
+            acceleration: -normal * SURFACE_GRAVITY * radius,
+            color: COLORS,
+            fade: FADE,
+            size: SIZE,
+            shape: Shape::Quad,
+            ..EmitterParams::default()
+        };
+        let mut plasma = ParticleSystem::new(params, Rng::new(self.rng.next_u64()));
+        // Leaning off the normal in one direction, so the fan arcs over.
+        let lean = normal.cross(&Vec3::y()).try_normalize(1.0e-6).unwrap_or(Vec3::x());
+        for _ in 0..PARTICLES_PER_FLARE {
+            let jitter = Vec3::new(self.rng.range(-1.0, 1.0), self.rng.range(-1.0, 1.0), self.rng.range(-1.0, 1.0)) * 0.25;
+            let direction = (normal + lean * self.rng.range(0.0, 0.6) + jitter).normalize();
+            plasma.spawn(normal * radius, direction * LAUNCH_SPEED * radius * self.rng.range(0.6, 1.0));
+        }
+        Flare { star, plasma }
+    }
+
+    pub fn render(&self, framebuffer: &mut Framebuffer, transform: &Mat4, star_position: impl Fn(usize) -> Option<Vec3>) {
+        for flare in &self.flares {
+            if let Some(position) = star_position(flare.star) {
+                flare.plasma.render(framebuffer, &(transform * Mat4::new_translation(&position)));
+            }
+        }
+    }
+}
@@ -10,6 +10,10 @@ pub struct Fragment {
     pub normal: Vec3,
     pub intensity: f32,
     pub vertex_position: Vec3,
+    pub world_position: Vec3,
+    // Share of the sun not hidden by other bodies.
+    pub sunlight: f32,
+    pub tex_coords: Vec2,
 }
 
 impl Fragment {
@@ -20,7 +24,10 @@ impl Fragment {
             depth,
             normal,
             intensity,
-            vertex_position
+            vertex_position,
+            world_position: vertex_position,
+            sunlight: 1.0,
+            tex_coords: Vec2::zeros(),
         }
     }
 }
@@ -1,11 +1,37 @@
 
+use nalgebra_glm::Vec3;
+use rayon::prelude::*;
+use crate::fragment::Fragment;
+use crate::color::{Color, Rgba};
+use crate::tonemap::ToneMapping;
+
+// Rows per band in `write_fragments`.
+const BAND_ROWS: usize = 16;
+
+// How a translucent color combines with the pixel below it.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BlendMode {
+    // Covers the pixel by the color's opacity, like ring particles of dust.
+    Alpha,
+    // Only ever brightens, like glowing gas; order doesn't matter.
+    Additive,
+}
+
+// The scene is drawn into `hdr`, where 1 is the white of a fully lit
+// surface and anything brighter, like the sun, is kept rather than clipped.
+// `tone_map` turns it into `buffer`, the pixels shown on screen, and the HUD
+// is drawn straight onto those.
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
     pub buffer: Vec<u32>,
+    pub hdr: Vec<Vec3>,
     pub zbuffer: Vec<f32>,
+    // Pixels whose depth is final but whose color still has to be filled in
+    // by `resolve_deferred`.
+    pub deferred: Vec<bool>,
     background_color: u32,
-    current_color: u32,
+    current_color: Vec3,
 }
 
 impl Framebuffer {
@@ -14,9 +40,11 @@ impl Framebuffer {
             width,
             height,
             buffer: vec![0; width * height],
+            hdr: vec![Vec3::zeros(); width * height],
             zbuffer: vec![f32::INFINITY; width * height],
+            deferred: vec![false; width * height],
             background_color: 0x000000,
-            current_color: 0xFFFFFF
+            current_color: Vec3::repeat(1.0),
         }
     }
 
@@ -24,9 +52,11 @@ impl Framebuffer {
         for pixel in self.buffer.iter_mut() {
             *pixel = self.background_color;
         }
+        self.hdr.fill(Color::from_hex(self.background_color).radiance());
         for depth in self.zbuffer.iter_mut() {
             *depth = f32::INFINITY;
         }
+        self.deferred.fill(false);
     }
 
     pub fn point(&mut self, x: usize, y: usize, depth: f32) {
@@ -34,17 +64,160 @@ impl Framebuffer {
             let index = y * self.width + x;
 
             if self.zbuffer[index] > depth {
-                self.buffer[index] = self.current_color;
+                self.hdr[index] = self.current_color;
                 self.zbuffer[index] = depth;
+                self.deferred[index] = false;
+            }
+        }
+    }
+
+    // Depth-tests and writes fragments on horizontal bands of the screen in
+    // parallel. Each band owns its rows of the color, depth and deferred
+    // buffers, so no two threads ever touch the same pixel, and fragments
+    // keep their order within a band, so the result matches a serial pass.
+    // `shade` runs only for fragments that pass the depth test and returns
+    // their radiance; None leaves the pixel's color for `resolve_deferred`.
+    pub fn write_fragments<F>(&mut self, fragments: &[Fragment], shade: F)
+    where
+        F: Fn(&Fragment) -> Option<Vec3> + Sync,
+    {
+        let width = self.width;
+        let mut bands: Vec<Vec<&Fragment>> = vec![Vec::new(); self.height.div_ceil(BAND_ROWS)];
+        for fragment in fragments {
+            let (x, y) = (fragment.position.x as usize, fragment.position.y as usize);
+            if x < width && y < self.height {
+                bands[y / BAND_ROWS].push(fragment);
+            }
+        }
+
+        let band_pixels = BAND_ROWS * width;
+        self.hdr
+            .par_chunks_mut(band_pixels)
+            .zip(self.zbuffer.par_chunks_mut(band_pixels))
+            .zip(self.deferred.par_chunks_mut(band_pixels))
+            .zip(bands.par_iter())
+            .enumerate()
+            .for_each(|(band, (((colors, depths), deferred), band_fragments))| {
+                let first_row = band * BAND_ROWS;
+                for fragment in band_fragments {
+                    let index = (fragment.position.y as usize - first_row) * width + fragment.position.x as usize;
+                    if depths[index] <= fragment.depth {
+                        continue;
+                    }
+                    depths[index] = fragment.depth;
+                    match shade(fragment) {
+                        Some(color) => {
+                            colors[index] = color;
+                            deferred[index] = false;
+                        }
+                        None => deferred[index] = true,
+                    }
+                }
+            });
+    }
+
+    // Mixes translucent fragments over what is already drawn. They are depth
+    // tested but leave the depth buffer alone, so they should come after
+    // everything opaque, and alpha blended ones back to front.
+    pub fn blend_fragments<F>(&mut self, fragments: &[Fragment], mode: BlendMode, shade: F)
+    where
+        F: Fn(&Fragment) -> Option<Rgba>,
+    {
+        for fragment in fragments {
+            let (x, y) = (fragment.position.x as usize, fragment.position.y as usize);
+            if x >= self.width || y >= self.height {
+                continue;
+            }
+            let index = y * self.width + x;
+            if self.zbuffer[index] <= fragment.depth {
+                continue;
+            }
+            if let Some(rgba) = shade(fragment) {
+                self.blend_rgba(index, rgba, mode);
+            }
+        }
+    }
+
+    // Fills deferred pixels from the previous frame, or from the pixel to
+    // their left when there is no previous frame yet.
+    pub fn resolve_deferred(&mut self, previous: &[Vec3]) {
+        let has_previous = previous.len() == self.hdr.len();
+        for (index, deferred) in self.deferred.iter_mut().enumerate() {
+            if !*deferred {
+                continue;
+            }
+            self.hdr[index] = if has_previous {
+                previous[index]
+            } else if index % self.width > 0 {
+                self.hdr[index - 1]
+            } else {
+                self.hdr[index + 1]
+            };
+            *deferred = false;
+        }
+    }
+
+    // Adds a color on top of what is already there (saturating per channel)
+    // if the point is in front of the stored depth. Depth is left untouched
+    // so overlapping glows accumulate.
+    pub fn add_point(&mut self, x: usize, y: usize, depth: f32, color: u32) {
+        self.blend_point(x, y, depth, Rgba::new(Color::from_hex(color), 1.0), BlendMode::Additive);
+    }
+
+    // Like `point`, but blends the color in instead of replacing the pixel,
+    // and leaves the depth buffer alone.
+    pub fn blend_point(&mut self, x: usize, y: usize, depth: f32, rgba: Rgba, mode: BlendMode) {
+        if x < self.width && y < self.height {
+            let index = y * self.width + x;
+
+            if self.zbuffer[index] > depth {
+                self.blend_rgba(index, rgba, mode);
             }
         }
     }
 
+    // Composites onto the scene. Additive colors pile up past white instead
+    // of saturating, so overlapping glows stay distinct until tone mapping.
+    pub fn blend_rgba(&mut self, index: usize, rgba: Rgba, mode: BlendMode) {
+        let (below, color) = (self.hdr[index], rgba.color.radiance());
+        self.hdr[index] = match mode {
+            BlendMode::Alpha => below.lerp(&color, rgba.alpha.clamp(0.0, 1.0)),
+            BlendMode::Additive => below + color * rgba.alpha.clamp(0.0, 1.0),
+        };
+    }
+
+    // Mixes a whole previous scene over this one, e.g. for motion blur.
+    pub fn blend_frame(&mut self, frame: &[Vec3], alpha: f32) {
+        for (pixel, color) in self.hdr.iter_mut().zip(frame) {
+            *pixel = pixel.lerp(color, alpha);
+        }
+    }
+
+    // Like `draw2d::fill_rect`, but into the scene under the HUD.
+    pub fn fill_scene_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: u32) {
+        let radiance = Color::from_hex(color).radiance();
+        for row in y.min(self.height)..(y + height).min(self.height) {
+            let start = row * self.width;
+            self.hdr[start + x.min(self.width)..start + (x + width).min(self.width)].fill(radiance);
+        }
+    }
+
+    // Brings the scene down to what the screen can show, replacing
+    // everything in `buffer`.
+    pub fn tone_map(&mut self, tone_mapping: &ToneMapping) {
+        self.buffer.par_iter_mut().zip(self.hdr.par_iter()).for_each(|(pixel, &radiance)| *pixel = tone_mapping.map(radiance));
+    }
+
     pub fn set_background_color(&mut self, color: u32) {
         self.background_color = color;
     }
 
     pub fn set_current_color(&mut self, color: u32) {
-        self.current_color = color;
+        self.current_color = Color::from_hex(color).radiance();
+    }
+
+    // For colors brighter than white.
+    pub fn set_current_radiance(&mut self, radiance: Vec3) {
+        self.current_color = radiance;
     }
 }
\ No newline at end of file
@@ -0,0 +1,122 @@
+use nalgebra_glm::Vec3;
+use crate::camera::Camera;
+use crate::draw2d;
+use crate::framebuffer::Framebuffer;
+use crate::input::InputFrame;
+use crate::light::Light;
+use crate::render_target::RenderTarget;
+
+const MAX_THUMBNAIL_SIZE: usize = 56;
+const GAP: usize = 6;
+const MARGIN: usize = 6;
+// Share of the screen width the strip may take; the rest of the bottom edge
+// is left to the selection panel.
+const MAX_STRIP_WIDTH: f32 = 0.62;
+const BACKGROUND_COLOR: u32 = 0x101018;
+const BORDER_COLOR: u32 = 0x404050;
+const SELECTED_COLOR: u32 = 0x66FF66;
+// Extra radians per frame each body turns in its thumbnail.
+const SPIN_RATE: f32 = 0.03;
+// Camera distance from each body's center, in body radii.
+const VIEW_DISTANCE: f32 = 3.0;
+
+pub struct Thumbnail {
+    pub target: RenderTarget,
+    pub camera: Camera,
+}
+
+// Strip of small offscreen renders of every body along the bottom of the
+// screen. Each thumbnail has its own camera on the sunlit side of its body,
+// and the body is turned a little further every frame. Clicking one reports
+// which body it shows.
+pub struct Gallery {
+    pub visible: bool,
+    pub thumbnails: Vec<Thumbnail>,
+    pub spin: f32,
+    mouse_was_down: bool,
+}
+
+impl Gallery {
+    pub fn new() -> Self {
+        Gallery { visible: true, thumbnails: Vec::new(), spin: 0.0, mouse_was_down: false }
+    }
+
+    // Points one camera at each (center, radius), adding or resizing
+    // thumbnails when the number of bodies changes.
+    pub fn aim(&mut self, bodies: &[(Vec3, f32)], light: &Light, screen_width: usize) {
+        let size = thumbnail_size(bodies.len(), screen_width);
+        if self.thumbnails.len() != bodies.len() || self.thumbnails.first().is_some_and(|thumbnail| thumbnail.target.width() != size) {
+            self.thumbnails = bodies
+                .iter()
+                .map(|_| {
+                    let mut target = RenderTarget::new(size, size);
+                    target.framebuffer.set_background_color(BACKGROUND_COLOR);
+                    Thumbnail { target, camera: Camera::new(Vec3::new(0.0, 0.0, 1.0), Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0)) }
+                })
+                .collect();
+        }
+
+        self.spin += SPIN_RATE;
+        for (thumbnail, &(center, radius)) in self.thumbnails.iter_mut().zip(bodies) {
+            thumbnail.camera.center = center;
+            thumbnail.camera.eye = center + light.lit_side(center) * radius * VIEW_DISTANCE;
+        }
+    }
+
+    pub fn draw(&self, framebuffer: &mut Framebuffer, selected: usize) {
+        for (index, thumbnail) in self.thumbnails.iter().enumerate() {
+            let (x, y, size) = self.slot(index, framebuffer.width, framebuffer.height);
+            let color = if index == selected { SELECTED_COLOR } else { BORDER_COLOR };
+            draw2d::fill_rect(framebuffer, x - 1, y - 1, size + 2, size + 2, color);
+            thumbnail.target.blit(framebuffer, x, y);
+        }
+    }
+
+    // Space the strip takes up from the bottom edge of the screen.
+    pub fn height(&self) -> usize {
+        match self.thumbnails.first() {
+            Some(thumbnail) if self.visible => thumbnail.target.height() + MARGIN + 1,
+            _ => 0,
+        }
+    }
+
+    // The body whose thumbnail was clicked this frame, if any.
+    pub fn clicked(&mut self, input: &InputFrame, screen_width: usize, screen_height: usize) -> Option<usize> {
+        let down = input.mouse_down;
+        let pressed = down && !self.mouse_was_down;
+        self.mouse_was_down = down;
+        if !pressed || !self.visible {
+            return None;
+        }
+
+        let cursor = input.mouse?;
+        self.thumbnail_at(cursor, screen_width, screen_height)
+    }
+
+    pub fn thumbnail_at(&self, (mouse_x, mouse_y): (f32, f32), screen_width: usize, screen_height: usize) -> Option<usize> {
+        if !self.visible {
+            return None;
+        }
+        (0..self.thumbnails.len()).find(|&index| {
+            let (x, y, size) = self.slot(index, screen_width, screen_height);
+            (x as f32..(x + size) as f32).contains(&mouse_x) && (y as f32..(y + size) as f32).contains(&mouse_y)
+        })
+    }
+
+    // Top-left corner and size of a thumbnail; the strip is anchored to the
+    // bottom-right corner.
+    fn slot(&self, index: usize, screen_width: usize, screen_height: usize) -> (usize, usize, usize) {
+        let size = self.thumbnails.first().map_or(0, |thumbnail| thumbnail.target.width());
+        let count = self.thumbnails.len();
+        let strip_width = count * (size + GAP) - GAP;
+        let left = screen_width.saturating_sub(strip_width + MARGIN);
+        let top = screen_height.saturating_sub(size + MARGIN);
+        (left + index * (size + GAP), top, size)
+    }
+}
+
+fn thumbnail_size(count: usize, screen_width: usize) -> usize {
+    let available = screen_width as f32 * MAX_STRIP_WIDTH;
+    let fitting = (available / count.max(1) as f32) as usize;
+    fitting.saturating_sub(GAP).clamp(8, MAX_THUMBNAIL_SIZE)
+}
@@ -0,0 +1,28 @@
+use nalgebra_glm::Vec3;
+use crate::color::Color;
+
+const GLINT_COLOR: Color = Color::new(255, 246, 225);
+// Angular spread of the reflection, along the sun's direction and across
+// it. Ripples smear the sun's image towards the viewer, so the lobe is
+// longer than it is wide.
+const SPREAD_ALONG: f32 = 0.16;
+const SPREAD_ACROSS: f32 = 0.06;
+
+// Brightness of the sun's reflection on water at a point with surface
+// normal `normal`, from 0 to 1. All directions are unit vectors in the
+// same frame.
+pub fn strength(normal: Vec3, to_sun: Vec3, to_eye: Vec3) -> f32 {
+    if normal.dot(&to_sun) <= 0.0 || normal.dot(&to_eye) <= 0.0 {
+        return 0.0;
+    }
+    // Facets tilted towards the half vector mirror the sun at the eye.
+    let half = (to_sun + to_eye).normalize();
+    let along = (to_sun - normal * normal.dot(&to_sun)).try_normalize(1.0e-6).unwrap_or(Vec3::x());
+    let across = normal.cross(&along);
+    let (tilt_along, tilt_across) = (half.dot(&along) / SPREAD_ALONG, half.dot(&across) / SPREAD_ACROSS);
+    (-(tilt_along * tilt_along + tilt_across * tilt_across)).exp() * half.dot(&normal).max(0.0)
+}
+
+pub fn apply(color: Color, strength: f32) -> Color {
+    color.lerp(&GLINT_COLOR, strength)
+}
@@ -0,0 +1,80 @@
+use std::collections::VecDeque;
+use crate::draw2d;
+use crate::framebuffer::Framebuffer;
+use crate::text::{draw_text, LINE_HEIGHT};
+
+const PLOT_HEIGHT: usize = 48;
+const PADDING: usize = 6;
+const TEXT_COLOR: u32 = 0xE0E0E0;
+const REFERENCE_COLOR: u32 = 0x404050;
+
+// A line graph of the most recent samples of some value, one pixel column
+// per sample, newest on the right. The vertical range fits whatever is in
+// the buffer, so a steady value shows as a flat line however large it is.
+pub struct Graph {
+    samples: VecDeque<f32>,
+    capacity: usize,
+    color: u32,
+    // A value worth comparing against, like a frame budget, drawn as a dim
+    // line and always kept in range.
+    reference: Option<f32>,
+}
+
+impl Graph {
+    pub fn new(capacity: usize, color: u32) -> Self {
+        Graph { samples: VecDeque::with_capacity(capacity), capacity, color, reference: None }
+    }
+
+    pub fn with_reference(mut self, reference: f32) -> Self {
+        self.reference = Some(reference);
+        self
+    }
+
+    pub fn push(&mut self, sample: f32) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    pub fn latest(&self) -> Option<f32> {
+        self.samples.back().copied()
+    }
+
+    // Size on screen, label included.
+    pub fn size(&self) -> (usize, usize) {
+        (self.capacity + PADDING * 2, LINE_HEIGHT + PLOT_HEIGHT + PADDING * 2)
+    }
+
+    // Draws the graph on a panel with `label` above the plot; nothing if
+    // there are no samples yet.
+    pub fn draw(&self, framebuffer: &mut Framebuffer, x: usize, y: usize, label: &str) {
+        if self.samples.is_empty() {
+            return;
+        }
+        let (width, height) = self.size();
+        draw2d::nine_patch(framebuffer, x, y, width, height, &draw2d::PANEL);
+        draw_text(framebuffer, x + PADDING, y + PADDING, label, TEXT_COLOR, 1);
+
+        let bounds = self.reference.map_or((f32::INFINITY, f32::NEG_INFINITY), |reference| (reference, reference));
+        let (low, high) = self.samples.iter().fold(bounds, |(low, high), &sample| (low.min(sample), high.max(sample)));
+        let span = (high - low).max(high.abs() * 1.0e-3).max(1.0e-6);
+        let top = y + PADDING + LINE_HEIGHT;
+        let row = |value: f32| top as f32 + ((high - value) / span * (PLOT_HEIGHT - 1) as f32).round().clamp(0.0, (PLOT_HEIGHT - 1) as f32);
+
+        if let Some(reference) = self.reference {
+            draw2d::fill_rect(framebuffer, x + PADDING, row(reference) as usize, self.capacity, 1, REFERENCE_COLOR);
+        }
+        let first_column = (x + PADDING + self.capacity - self.samples.len()) as f32;
+        let points: Vec<(f32, f32)> = self.samples.iter().enumerate().map(|(column, &sample)| (first_column + column as f32, row(sample))).collect();
+        // A lone sample still shows, as a dot.
+        draw2d::line(framebuffer, points[0], points[0], self.color);
+        for pair in points.windows(2) {
+            draw2d::line(framebuffer, pair[0], pair[1], self.color);
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use nalgebra_glm::Vec3;
+use crate::scene::{BodyDesc, Scene, SPHERE_RADIUS};
+
+// Lines in each direction, and points along each line so wells bend them
+// smoothly.
+const GRID_LINES: usize = 33;
+const POINTS_PER_LINE: usize = 161;
+// Margin past the outermost orbit.
+const EXTENT_MARGIN: f32 = 1.15;
+// Depth of a well per unit of mass at unit distance.
+const STRENGTH: f32 = 1.2;
+// Planets are far lighter than stars of the same size; this keeps their
+// wells shallower without making them vanish. Not to scale.
+const PLANET_DENSITY: f32 = 0.4;
+// Wells are rounded off within this many body radii, instead of plunging
+// to infinity under the body's center.
+const SOFTENING_RADII: f32 = 2.0;
+
+// Rubber-sheet picture of gravity: a grid on the ecliptic plane pulled down
+// under every body in proportion to its mass, so planets sit in dimples on
+// the slope of the sun's well. `positions` are the bodies' current world
+// positions; the grid is rebuilt from them every frame.
+pub fn lines(scene: &Scene, positions: &[Vec3]) -> Vec<Vec<Vec3>> {
+    let half_extent = scene
+        .bodies
+        .iter()
+        .filter(|body| body.parent.is_none())
+        .map(|body| body.orbit.semi_major_axis * (1.0 + body.orbit.eccentricity))
+        .fold(1.0, f32::max)
+        * EXTENT_MARGIN;
+    let wells: Vec<(Vec3, f32, f32)> = scene
+        .bodies
+        .iter()
+        .zip(positions)
+        .map(|(body, &position)| (position, mass(body), body.scale * SPHERE_RADIUS * SOFTENING_RADII))
+        .collect();
+    let sheet = |x: f32, z: f32| {
+        let depth: f32 = wells
+            .iter()
+            .map(|&(center, mass, softening)| {
+                let distance_squared = (x - center.x).powi(2) + (z - center.z).powi(2);
+                mass / (distance_squared + softening * softening).sqrt()
+            })
+            .sum();
+        Vec3::new(x, -STRENGTH * depth, z)
+    };
+
+    let coordinate = |index: usize, count: usize| -half_extent + 2.0 * half_extent * index as f32 / (count - 1) as f32;
+    let mut lines = Vec::with_capacity(GRID_LINES * 2);
+    for line in 0..GRID_LINES {
+        let across = coordinate(line, GRID_LINES);
+        lines.push((0..POINTS_PER_LINE).map(|point| sheet(coordinate(point, POINTS_PER_LINE), across)).collect());
+        lines.push((0..POINTS_PER_LINE).map(|point| sheet(across, coordinate(point, POINTS_PER_LINE))).collect());
+    }
+    lines
+}
+
+// Relative mass from the body's size: its volume, lightened for anything
+// that isn't a star.
+fn mass(body: &BodyDesc) -> f32 {
+    let density = if body.orbit.semi_major_axis == 0.0 { 1.0 } else { PLANET_DENSITY };
+    body.scale.powi(3) * density
+}
@@ -0,0 +1,95 @@
+use std::f32::consts::PI;
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::color::{Color, Rgba};
+use crate::framebuffer::{BlendMode, Framebuffer};
+use crate::triangle::triangle;
+use crate::vertex::Vertex;
+
+pub const SUN_TEMPERATURE: f32 = 5772.0;
+
+const SEGMENTS: usize = 128;
+const ZONE_COLOR: Color = Color::new(0x30, 0xD0, 0x60);
+const ZONE_ALPHA: f32 = 0.22;
+// Fraction of the width over which the edges fade in.
+const EDGE_SOFTNESS: f32 = 0.15;
+
+// Distances from the star, in scene units, between which a planet could
+// keep liquid water on its surface.
+#[derive(Clone, Copy)]
+pub struct HabitableZone {
+    pub inner: f32,
+    pub outer: f32,
+}
+
+impl HabitableZone {
+    // Conservative limits from the effective stellar flux of the runaway
+    // and maximum greenhouse (Kopparapu et al. 2013), fitted for 2600-7200 K.
+    pub fn from_star(temperature: f32, luminosity: f32, au: f32) -> Self {
+        let t = temperature.clamp(2600.0, 7200.0) - SUN_TEMPERATURE;
+        let flux = |s: f32, a: f32, b: f32, c: f32, d: f32| s + a * t + b * t * t + c * t.powi(3) + d * t.powi(4);
+        let inner_flux = flux(1.0385, 1.2456e-4, 1.4612e-8, -7.6345e-12, -1.7511e-15);
+        let outer_flux = flux(0.3507, 5.9578e-5, 1.6707e-9, -3.0058e-12, -5.1925e-16);
+
+        HabitableZone {
+            inner: (luminosity / inner_flux).sqrt() * au,
+            outer: (luminosity / outer_flux).sqrt() * au,
+        }
+    }
+
+    pub fn contains(&self, distance: f32) -> bool {
+        (self.inner..=self.outer).contains(&distance)
+    }
+}
+
+// Rough main-sequence luminosity (in suns) for a star of this temperature,
+// used when a scene gives only the temperature.
+pub fn main_sequence_luminosity(temperature: f32) -> f32 {
+    (temperature / SUN_TEMPERATURE).powi(7)
+}
+
+// Translucent annulus on the ecliptic plane. Drawn before the planets and
+// without touching depth, so it always sits underneath them.
+pub fn render_zone(framebuffer: &mut Framebuffer, transform: &Mat4, zone: &HabitableZone) {
+    let mut covered = vec![false; framebuffer.width * framebuffer.height];
+    let ring_point = |radius: f32, i: usize| {
+        let angle = i as f32 / SEGMENTS as f32 * 2.0 * PI;
+        Vec3::new(radius * angle.cos(), 0.0, radius * angle.sin())
+    };
+
+    for i in 0..SEGMENTS {
+        let corners = [
+            ring_point(zone.inner, i),
+            ring_point(zone.outer, i),
+            ring_point(zone.outer, i + 1),
+            ring_point(zone.inner, i + 1),
+        ];
+        let Some(projected) = corners.iter().map(|corner| project(transform, *corner)).collect::<Option<Vec<_>>>() else {
+            continue;
+        };
+
+        for [a, b, c] in [[0, 1, 2], [0, 2, 3]] {
+            for fragment in triangle(&projected[a], &projected[b], &projected[c], framebuffer.width, framebuffer.height) {
+                let index = fragment.position.y as usize * framebuffer.width + fragment.position.x as usize;
+                if covered[index] {
+                    continue;
+                }
+                covered[index] = true;
+
+                let radius = fragment.vertex_position.magnitude();
+                let across = (radius - zone.inner) / (zone.outer - zone.inner);
+                let edge = (across.min(1.0 - across) / EDGE_SOFTNESS).clamp(0.0, 1.0);
+                framebuffer.blend_rgba(index, Rgba::new(ZONE_COLOR, ZONE_ALPHA * (0.4 + 0.6 * edge)), BlendMode::Alpha);
+            }
+        }
+    }
+}
+
+fn project(transform: &Mat4, point: Vec3) -> Option<Vertex> {
+    let clip = transform * Vec4::new(point.x, point.y, point.z, 1.0);
+    if clip.w <= 0.0 {
+        return None;
+    }
+    let mut vertex = Vertex::new(point, Vec3::new(0.0, 1.0, 0.0), Default::default());
+    vertex.set_transformed(Vec3::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w), vertex.normal);
+    Some(vertex)
+}
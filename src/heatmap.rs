@@ -0,0 +1,114 @@
+use nalgebra_glm::Vec3;
+use crate::color::Color;
+use crate::fragment::Fragment;
+use crate::draw2d;
+use crate::framebuffer::Framebuffer;
+use crate::layout::{self, Anchor};
+use crate::text::{draw_text, text_width, GLYPH_HEIGHT};
+
+// The legend spans this range of log2(flux), in multiples of the flux
+// received at 1 AU.
+const MIN_LOG_FLUX: f32 = -5.0;
+const MAX_LOG_FLUX: f32 = 3.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum HeatPalette {
+    Thermal,
+    IceFire,
+    Grayscale,
+}
+
+impl HeatPalette {
+    pub const NAMES: [&'static str; 3] = ["thermal", "ice_fire", "grayscale"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "thermal" => Some(HeatPalette::Thermal),
+            "ice_fire" => Some(HeatPalette::IceFire),
+            "grayscale" => Some(HeatPalette::Grayscale),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HeatPalette::Thermal => "thermal",
+            HeatPalette::IceFire => "ice_fire",
+            HeatPalette::Grayscale => "grayscale",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            HeatPalette::Thermal => HeatPalette::IceFire,
+            HeatPalette::IceFire => HeatPalette::Grayscale,
+            HeatPalette::Grayscale => HeatPalette::Thermal,
+        }
+    }
+
+    fn stops(self) -> &'static [u32] {
+        match self {
+            HeatPalette::Thermal => &[0x000004, 0x3B0F70, 0x8C2981, 0xDE4968, 0xFE9F6D, 0xFCFDBF],
+            HeatPalette::IceFire => &[0x0A1A4F, 0x1F6FD1, 0x8FE3F0, 0xFFFFFF, 0xFFD23F, 0xE8471C, 0x6B0A0A],
+            HeatPalette::Grayscale => &[0x000000, 0xFFFFFF],
+        }
+    }
+
+    // Evenly spaced stops, linearly blended; t in [0, 1].
+    pub fn sample(self, t: f32) -> Color {
+        let stops = self.stops();
+        let scaled = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+        let index = (scaled as usize).min(stops.len() - 2);
+        Color::from_hex(stops[index]).lerp(&Color::from_hex(stops[index + 1]), scaled - index as f32)
+    }
+}
+
+// Flux at a distance from the sun relative to the flux at 1 AU.
+pub fn relative_flux(distance: f32, au: f32) -> f32 {
+    let distance = distance.max(0.001);
+    (au / distance).powi(2)
+}
+
+fn flux_to_t(flux: f32) -> f32 {
+    if flux <= 0.0 {
+        return 0.0;
+    }
+    (flux.log2() - MIN_LOG_FLUX) / (MAX_LOG_FLUX - MIN_LOG_FLUX)
+}
+
+// Colors a fragment by the sunlight it receives: the body's flux weighted
+// by how squarely the surface faces the sun, so the night side is coldest.
+pub fn heat_shader(fragment: &Fragment, to_sun: Vec3, flux: f32, palette: HeatPalette) -> Color {
+    let facing = fragment.normal.dot(&to_sun).max(0.0);
+    palette.sample(flux_to_t(flux * facing))
+}
+
+// Gradient bar with flux labels in the bottom-right corner.
+pub fn draw_legend(framebuffer: &mut Framebuffer, palette: HeatPalette) {
+    let (width, height) = (160, 8);
+    // Room below the bar for the labels.
+    let (x, y) = layout::place(framebuffer, Anchor::BottomRight, (width, height + 12), 12);
+
+    for column in 0..width {
+        let color = palette.sample(column as f32 / (width - 1) as f32);
+        draw2d::fill_rect(framebuffer, x + column, y, 1, height, color.to_hex());
+    }
+
+    let label_y = y + height + 3;
+    let labels = [(MIN_LOG_FLUX, 0.0), (0.0, flux_to_t(1.0)), (MAX_LOG_FLUX, 1.0)];
+    for (log_flux, t) in labels {
+        let text = format!("{} S", format_flux(2f32.powf(log_flux)));
+        let center = x + (t * width as f32) as usize;
+        let label_x = center.saturating_sub(text_width(&text, 1) / 2).min(framebuffer.width.saturating_sub(text_width(&text, 1)));
+        draw_text(framebuffer, label_x, label_y, &text, 0xE0E0E0, 1);
+    }
+    draw_text(framebuffer, x, y.saturating_sub(GLYPH_HEIGHT + 3), &format!("Flujo solar ({})", palette.name()), 0xE0E0E0, 1);
+}
+
+fn format_flux(flux: f32) -> String {
+    if flux >= 1.0 {
+        format!("{:.0}", flux)
+    } else {
+        format!("{:.2}", flux)
+    }
+}
@@ -0,0 +1,117 @@
+use crate::scene::Scene;
+
+// Most edits kept; the oldest are dropped past this.
+const MAX_STEPS: usize = 100;
+
+// What can be changed on a body while the viewer runs.
+#[derive(Clone, Copy, PartialEq)]
+struct BodyEdits {
+    shader: u8,
+    scale: f32,
+    mean_motion: f32,
+    rotation_speed: f32,
+    semi_major_axis: f32,
+    eccentricity: f32,
+    inclination: f32,
+    hidden: bool,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Snapshot {
+    bodies: Vec<BodyEdits>,
+}
+
+impl Snapshot {
+    pub fn take(scene: &Scene, hidden: &[bool]) -> Self {
+        let bodies = scene
+            .bodies
+            .iter()
+            .zip(hidden)
+            .map(|(body, &hidden)| BodyEdits {
+                shader: body.shader,
+                scale: body.scale,
+                mean_motion: body.orbit.mean_motion,
+                rotation_speed: body.rotation_speed,
+                semi_major_axis: body.orbit.semi_major_axis,
+                eccentricity: body.orbit.eccentricity,
+                inclination: body.orbit.inclination,
+                hidden,
+            })
+            .collect();
+        Snapshot { bodies }
+    }
+
+    fn restore(&self, scene: &mut Scene, hidden: &mut [bool]) {
+        for ((body, hidden), edits) in scene.bodies.iter_mut().zip(hidden.iter_mut()).zip(&self.bodies) {
+            body.shader = edits.shader;
+            body.scale = edits.scale;
+            body.orbit.mean_motion = edits.mean_motion;
+            body.rotation_speed = edits.rotation_speed;
+            body.orbit.semi_major_axis = edits.semi_major_axis;
+            body.orbit.eccentricity = edits.eccentricity;
+            body.orbit.inclination = edits.inclination;
+            *hidden = edits.hidden;
+        }
+    }
+}
+
+// Undo and redo for runtime edits. Each frame is compared against a
+// snapshot from before its input was handled; anything that changed is
+// one step, and changes made while keys stay held (a speed key repeating)
+// are merged into the step that started them.
+pub struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    merging: bool,
+}
+
+impl History {
+    pub fn new() -> Self {
+        History { undo: Vec::new(), redo: Vec::new(), merging: false }
+    }
+
+    // Call after the frame's edits with the snapshot taken before them.
+    // Returns whether anything changed.
+    pub fn record(&mut self, before: Snapshot, scene: &Scene, hidden: &[bool], keys_held: bool) -> bool {
+        let changed = before != Snapshot::take(scene, hidden);
+        if changed {
+            if !self.merging {
+                self.undo.push(before);
+                if self.undo.len() > MAX_STEPS {
+                    self.undo.remove(0);
+                }
+            }
+            self.redo.clear();
+            self.merging = true;
+        }
+        self.merging &= keys_held;
+        changed
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.merging = false;
+    }
+
+    // Both return false when there is nothing to undo or redo.
+    pub fn undo(&mut self, scene: &mut Scene, hidden: &mut [bool]) -> bool {
+        self.merging = false;
+        Self::swap(&mut self.undo, &mut self.redo, scene, hidden)
+    }
+
+    pub fn redo(&mut self, scene: &mut Scene, hidden: &mut [bool]) -> bool {
+        self.merging = false;
+        Self::swap(&mut self.redo, &mut self.undo, scene, hidden)
+    }
+
+    // Restores the top of `from`, saving what it replaces on `to`.
+    fn swap(from: &mut Vec<Snapshot>, to: &mut Vec<Snapshot>, scene: &mut Scene, hidden: &mut [bool]) -> bool {
+        let Some(snapshot) = from.pop() else {
+            return false;
+        };
+        to.push(Snapshot::take(scene, hidden));
+        snapshot.restore(scene, hidden);
+        true
+    }
+}
@@ -0,0 +1,195 @@
+use std::time::{Duration, Instant};
+use crate::draw2d;
+use crate::framebuffer::Framebuffer;
+use crate::graph::Graph;
+use crate::layout::{self, Anchor, Stack};
+use crate::text::{draw_text, text_width, LINE_HEIGHT};
+
+const TEXT_COLOR: u32 = 0xE0E0E0;
+const PADDING: usize = 6;
+const FRAME_TIME_SAMPLES: usize = 240;
+// Milliseconds a frame can take at 60 fps.
+const FRAME_BUDGET: f32 = 1000.0 / 60.0;
+
+pub struct StatsOverlay {
+    pub visible: bool,
+    last_frame: Instant,
+    fps: f32,
+    frame_times: Graph,
+}
+
+impl StatsOverlay {
+    pub fn new() -> Self {
+        StatsOverlay {
+            visible: false,
+            last_frame: Instant::now(),
+            fps: 0.0,
+            frame_times: Graph::new(FRAME_TIME_SAMPLES, 0x66FF66).with_reference(FRAME_BUDGET),
+        }
+    }
+
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_frame).as_secs_f32();
+        self.last_frame = now;
+
+        self.frame_times.push(elapsed * 1000.0);
+        if elapsed > 0.0 {
+            // Smooth the reading so the number stays legible.
+            let instant_fps = 1.0 / elapsed;
+            self.fps = if self.fps == 0.0 { instant_fps } else { self.fps * 0.9 + instant_fps * 0.1 };
+        }
+    }
+
+    pub fn fps(&self) -> f32 {
+        self.fps
+    }
+
+    // The panel, then the frame time history and any other `graphs` with
+    // their labels stacked under it.
+    pub fn draw(&self, framebuffer: &mut Framebuffer, extra_lines: &[String], graphs: &[(&Graph, &str)]) {
+        if !self.visible {
+            return;
+        }
+
+        let mut lines = vec![format!("FPS    {:.1}", self.fps)];
+        lines.extend_from_slice(extra_lines);
+
+        let mut stack = Stack::new(Anchor::TopLeft, PADDING);
+        let (x, y) = stack.place(framebuffer, panel_size(&lines));
+        draw_panel(framebuffer, x, y, &lines);
+        let frame_label = format!("cuadro {:.1} ms", self.frame_times.latest().unwrap_or(0.0));
+        for (graph, label) in [(&self.frame_times, frame_label.as_str())].into_iter().chain(graphs.iter().copied()) {
+            let (x, y) = stack.place(framebuffer, graph.size());
+            graph.draw(framebuffer, x, y, label);
+        }
+    }
+}
+
+pub fn panel_size(lines: &[String]) -> (usize, usize) {
+    let width = lines.iter().map(|line| text_width(line, 1)).max().unwrap_or(0);
+    (width + PADDING * 2, lines.len() * LINE_HEIGHT + PADDING)
+}
+
+pub fn draw_panel(framebuffer: &mut Framebuffer, x: usize, y: usize, lines: &[String]) {
+    let (width, height) = panel_size(lines);
+    draw2d::nine_patch(framebuffer, x, y, width, height, &draw2d::PANEL);
+
+    for (i, line) in lines.iter().enumerate() {
+        draw_text(framebuffer, x + PADDING, y + PADDING + i * LINE_HEIGHT, line, TEXT_COLOR, 1);
+    }
+}
+
+// Panel pinned to `anchor`, a margin away from the screen edges.
+pub fn draw_anchored_panel(framebuffer: &mut Framebuffer, anchor: Anchor, lines: &[String]) {
+    let (x, y) = layout::place(framebuffer, anchor, panel_size(lines), PADDING);
+    draw_panel(framebuffer, x, y, lines);
+}
+
+// Next panel on `stack`, for several panels sharing a corner.
+pub fn draw_stacked_panel(framebuffer: &mut Framebuffer, stack: &mut Stack, lines: &[String]) {
+    let (x, y) = stack.place(framebuffer, panel_size(lines));
+    draw_panel(framebuffer, x, y, lines);
+}
+
+// Corner brackets around a point on screen, used to mark the selection.
+pub fn draw_marker(framebuffer: &mut Framebuffer, x: f32, y: f32, half_size: f32, color: u32) {
+    let length = (half_size * 0.5).max(3.0) as usize;
+    let left = (x - half_size).max(0.0) as usize;
+    let top = (y - half_size).max(0.0) as usize;
+    let right = (x + half_size).max(0.0) as usize;
+    let bottom = (y + half_size).max(0.0) as usize;
+
+    for (corner_x, corner_y, dx, dy) in [
+        (left, top, 0, 0),
+        (right.saturating_sub(length), top, length - 1, 0),
+        (left, bottom.saturating_sub(length), 0, length - 1),
+        (right.saturating_sub(length), bottom.saturating_sub(length), length - 1, length - 1),
+    ] {
+        draw2d::fill_rect(framebuffer, corner_x, corner_y + dy, length, 1, color);
+        draw2d::fill_rect(framebuffer, corner_x + dx, corner_y, 1, length, color);
+    }
+}
+
+// How long the cursor has to rest on something before its tooltip shows.
+const HOVER_DELAY: Duration = Duration::from_millis(500);
+
+// Tracks what the cursor rests on, so a tooltip appears only once it has
+// stayed on the same body for a moment.
+pub struct Hover {
+    body: Option<usize>,
+    since: Instant,
+}
+
+impl Hover {
+    pub fn new() -> Self {
+        Hover { body: None, since: Instant::now() }
+    }
+
+    // Takes the body under the cursor this frame and returns it once it has
+    // been there for `HOVER_DELAY`.
+    pub fn update(&mut self, body: Option<usize>) -> Option<usize> {
+        if body != self.body {
+            self.body = body;
+            self.since = Instant::now();
+        }
+        self.body.filter(|_| self.since.elapsed() >= HOVER_DELAY)
+    }
+}
+
+// A small panel next to the cursor.
+pub fn draw_tooltip(framebuffer: &mut Framebuffer, cursor: (f32, f32), lines: &[String]) {
+    let (x, y) = layout::beside_cursor(framebuffer, cursor, panel_size(lines));
+    draw_panel(framebuffer, x, y, lines);
+}
+
+const CAPTION_SCALE: usize = 2;
+
+// Subtitles centered above `bottom` pixels from the lower edge, each faded by
+// its opacity. Long captions are wrapped at word boundaries; later captions
+// stack above earlier ones.
+pub fn draw_captions(framebuffer: &mut Framebuffer, captions: &[(&str, f32)], bottom: usize) {
+    let max_width = framebuffer.width.saturating_sub(PADDING * 8);
+    let mut y = framebuffer.height.saturating_sub(bottom + PADDING);
+
+    for &(text, opacity) in captions {
+        let lines = wrap(text, max_width, CAPTION_SCALE);
+        let width = lines.iter().map(|line| text_width(line, CAPTION_SCALE)).max().unwrap_or(0) + PADDING * 2;
+        let height = lines.len() * LINE_HEIGHT * CAPTION_SCALE + PADDING;
+        y = y.saturating_sub(height);
+        let x = framebuffer.width.saturating_sub(width) / 2;
+
+        draw2d::nine_patch(framebuffer, x, y, width, height, &draw2d::NinePatch { alpha: opacity * draw2d::PANEL.alpha, ..draw2d::PANEL });
+        // Text sits on the panel, so fading it towards the panel color reads
+        // the same as blending it with what is behind.
+        let color = mix(draw2d::PANEL.fill, TEXT_COLOR, opacity);
+        for (i, line) in lines.iter().enumerate() {
+            let line_x = framebuffer.width.saturating_sub(text_width(line, CAPTION_SCALE)) / 2;
+            draw_text(framebuffer, line_x, y + PADDING + i * LINE_HEIGHT * CAPTION_SCALE, line, color, CAPTION_SCALE);
+        }
+        y = y.saturating_sub(PADDING);
+    }
+}
+
+fn wrap(text: &str, max_width: usize, scale: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if text_width(&format!("{} {}", line, word), scale) <= max_width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    lines
+}
+
+fn mix(from: u32, to: u32, t: f32) -> u32 {
+    let channel = |shift: u32| {
+        let a = ((from >> shift) & 0xFF) as f32;
+        let b = ((to >> shift) & 0xFF) as f32;
+        ((a + (b - a) * t) as u32) << shift
+    };
+    channel(16) | channel(8) | channel(0)
+}
@@ -0,0 +1,38 @@
+use nalgebra_glm::Vec3;
+use crate::color::Color;
+use crate::noise::{smoothstep, NoiseField};
+
+const ICE_COLOR: Color = Color::new(236, 242, 250);
+// Latitude of the cap edges at the equinoxes.
+const EQUINOX_EDGE_DEGREES: f32 = 68.0;
+// How far an edge moves per degree the sun stands north or south of the
+// equator. Tilted planets swing between a large winter cap and a small
+// summer one; without tilt the caps never change.
+const SEASONAL_SWING: f32 = 0.85;
+const EDGE_NOISE_DEGREES: f32 = 4.0;
+const EDGE_ZOOM: f32 = 180.0;
+
+// Latitudes (radians) where the northern and southern caps end, from the
+// direction to the sun in the body's own frame. The sun's latitude follows
+// the orbit, so the caps grow and shrink over the simulated year.
+pub fn cap_edges(to_sun: Vec3) -> (f32, f32) {
+    let sun_latitude = to_sun.y.clamp(-1.0, 1.0).asin();
+    let edge = EQUINOX_EDGE_DEGREES.to_radians();
+    (edge + SEASONAL_SWING * sun_latitude, edge - SEASONAL_SWING * sun_latitude)
+}
+
+// How much ice covers the point `position` (body frame), from 0 to 1.
+pub fn cover(noise: &NoiseField, position: Vec3, (north, south): (f32, f32)) -> f32 {
+    let direction = position.normalize();
+    let latitude = direction.y.clamp(-1.0, 1.0).asin();
+    let point = direction * EDGE_ZOOM;
+    let ragged = noise.get_noise_3d(point.x, point.y, point.z) * EDGE_NOISE_DEGREES.to_radians();
+    let edge = if latitude >= 0.0 { north } else { south };
+    smoothstep(edge - 0.02, edge + 0.02, latitude.abs() + ragged)
+}
+
+// Lays the ice over a lit surface color; `intensity` is the fragment's
+// lighting so caps on the night side stay dark.
+pub fn apply(color: Color, cover: f32, intensity: f32) -> Color {
+    color.lerp(&(ICE_COLOR * intensity), cover)
+}
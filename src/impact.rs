@@ -0,0 +1,208 @@
+use std::f32::consts::PI;
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::framebuffer::Framebuffer;
+use crate::color::{Color, Gradient};
+use crate::particles::{Curve, EmitterParams, ParticleSystem, Shape};
+use crate::rng::{Purpose, Rng};
+
+const FLASH_FRAMES: f32 = 40.0;
+const SPARK_COUNT: f32 = 120.0;
+// White-hot sparks that slow down, shrink and cool to a dull red.
+const SPARK_COLORS: Gradient = Gradient::new(&[(0.0, Color::new(255, 245, 220)), (0.3, Color::new(255, 190, 100)), (1.0, Color::new(200, 60, 30))]);
+const SPARK_SPEED: Curve = Curve::new(&[(0.0, 1.0), (1.0, 0.15)]);
+const SPARK_SIZE: Curve = Curve::new(&[(0.0, 3.0), (0.6, 1.0), (1.0, 0.5)]);
+
+const DEBRIS_COUNT: usize = 600;
+// Frames until the ring has cooled down to its final faint glow.
+const SETTLE_FRAMES: f32 = 900.0;
+// Loading a saved ring replays at most this many frames of its history.
+const MAX_REPLAY_FRAMES: u32 = 2000;
+
+// Short-lived bright burst where something hit a body. Offsets are relative
+// to the body center so the flash rides along with its orbit.
+#[derive(Clone)]
+pub struct Flash {
+    pub body: usize,
+    pub offset: Vec3,
+    pub age: f32,
+    pub sparks: ParticleSystem,
+}
+
+impl Flash {
+    pub fn new(body: usize, offset: Vec3, rng: Rng) -> Self {
+        let params = EmitterParams {
+            rate: SPARK_COUNT,
+            lifetime: FLASH_FRAMES,
+            spread: 1.0,
+            color: SPARK_COLORS,
+            speed: SPARK_SPEED,
+            size: SPARK_SIZE,
+            shape: Shape::Quad,
+            ..EmitterParams::default()
+        };
+        let mut sparks = ParticleSystem::new(params, rng);
+        sparks.emit(offset, offset.normalize() * 0.03);
+        sparks.params.rate = 0.0;
+        Flash { body, offset, age: 0.0, sparks }
+    }
+
+    pub fn step(&mut self) {
+        self.age += 1.0;
+        self.sparks.update();
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.age >= FLASH_FRAMES
+    }
+
+    pub fn render(&self, framebuffer: &mut Framebuffer, transform: &Mat4, body_position: Vec3) {
+        let t = self.age / FLASH_FRAMES;
+        let intensity = (1.0 - t) * (1.0 - t);
+        // Nudged off the surface so the body itself does not hide the glow.
+        let center = transform * to_point(body_position + self.offset * 1.15);
+        if center.w > 0.0 {
+            // Screen-space glow that swells while it fades.
+            let radius = 4.0 + 18.0 * t.sqrt();
+            let (cx, cy, depth) = (center.x / center.w, center.y / center.w, center.z / center.w);
+            let reach = radius.ceil() as i32;
+            for dy in -reach..=reach {
+                for dx in -reach..=reach {
+                    let falloff = 1.0 - ((dx * dx + dy * dy) as f32).sqrt() / radius;
+                    let (x, y) = (cx + dx as f32, cy + dy as f32);
+                    if falloff <= 0.0 || x < 0.0 || y < 0.0 {
+                        continue;
+                    }
+                    let glow = falloff * intensity;
+                    let color = ((255.0 * glow) as u32) << 16 | ((230.0 * glow) as u32) << 8 | (180.0 * glow) as u32;
+                    framebuffer.add_point(x as usize, y as usize, depth, color);
+                }
+            }
+        }
+
+        let sparks_transform = transform * Mat4::new_translation(&body_position);
+        self.sparks.render(framebuffer, &sparks_transform);
+    }
+}
+
+#[derive(Clone)]
+pub struct Debris {
+    angle: f32,
+    radius: f32,
+    settled_radius: f32,
+    radial_speed: f32,
+    height: f32,
+}
+
+// Ejecta thrown into orbit around the surviving body. It starts as a hot arc
+// leaving the impact point, spreads around the body as inner debris outpaces
+// outer debris, and settles into a thin, faint ring.
+#[derive(Clone)]
+pub struct DebrisRing {
+    pub body: usize,
+    pub normal: Vec3,
+    pub impact_angle: f32,
+    pub seed: u64,
+    pub age: f32,
+    pub debris: Vec<Debris>,
+    body_radius: f32,
+}
+
+impl DebrisRing {
+    pub fn new(body: usize, body_radius: f32, normal: Vec3, impact_angle: f32, seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        let debris = (0..DEBRIS_COUNT)
+            .map(|_| Debris {
+                angle: impact_angle + rng.range(-0.25, 0.25),
+                radius: body_radius,
+                settled_radius: body_radius * rng.range(1.6, 2.2),
+                radial_speed: body_radius * rng.range(0.02, 0.06),
+                height: body_radius * rng.range(-0.3, 0.3),
+            })
+            .collect();
+
+        DebrisRing { body, normal: normal.normalize(), impact_angle, seed, age: 0.0, body_radius, debris }
+    }
+
+    // Rebuilds a saved ring by replaying its history.
+    pub fn restore(body: usize, body_radius: f32, normal: Vec3, impact_angle: f32, seed: u64, age: f32) -> Self {
+        let mut ring = DebrisRing::new(body, body_radius, normal, impact_angle, seed);
+        for _ in 0..(age as u32).min(MAX_REPLAY_FRAMES) {
+            ring.step();
+        }
+        ring.age = age;
+        ring
+    }
+
+    pub fn step(&mut self) {
+        self.age += 1.0;
+        for piece in &mut self.debris {
+            // Overshoot outwards, then get pulled back and damped onto the
+            // settled radius.
+            piece.radial_speed = piece.radial_speed * 0.97 + (piece.settled_radius - piece.radius) * 0.002;
+            piece.radius += piece.radial_speed;
+            piece.height *= 0.995;
+            // Keplerian shear: inner debris laps outer debris.
+            piece.angle += 0.02 * (self.body_radius / piece.radius).powf(1.5);
+        }
+    }
+
+    pub fn render(&self, framebuffer: &mut Framebuffer, transform: &Mat4, body_position: Vec3) {
+        let (u, v) = plane_basis(self.normal);
+        let cooling = (self.age / SETTLE_FRAMES).min(1.0);
+        // Hot orange ejecta fading into a dim grey ring.
+        let hot = (255.0, 150.0, 60.0);
+        let cold = (30.0, 30.0, 36.0);
+        let color = |hot: f32, cold: f32| hot + (cold - hot) * cooling;
+        let (r, g, b) = (color(hot.0, cold.0), color(hot.1, cold.1), color(hot.2, cold.2));
+
+        for piece in &self.debris {
+            let (sin, cos) = piece.angle.sin_cos();
+            let local = (u * cos + v * sin) * piece.radius + self.normal * piece.height;
+            let clip = transform * to_point(body_position + local);
+            if clip.w <= 0.0 {
+                continue;
+            }
+            let (x, y) = (clip.x / clip.w, clip.y / clip.w);
+            if x < 0.0 || y < 0.0 {
+                continue;
+            }
+            let pixel = (r as u32) << 16 | (g as u32) << 8 | b as u32;
+            framebuffer.add_point(x as usize, y as usize, clip.z / clip.w, pixel);
+        }
+    }
+}
+
+// Random impact on a body of the given radius: where it hits (relative to
+// the center) and the debris ring it leaves behind.
+pub fn scripted_impact(body: usize, body_radius: f32, seed: u64, index: u64) -> (Flash, DebrisRing) {
+    let mut rng = Rng::keyed(seed, body as u64 * 1000 + index, Purpose::Impact);
+
+    // Mostly equatorial hits, so rings sit close to the orbital plane.
+    let tilt = rng.range(-0.4, 0.4);
+    let normal = Vec3::new(tilt.sin(), tilt.cos(), 0.0);
+    let normal = rotate_y(normal, rng.range(0.0, 2.0 * PI));
+    let impact_angle = rng.range(0.0, 2.0 * PI);
+
+    let (u, v) = plane_basis(normal);
+    let offset = (u * impact_angle.cos() + v * impact_angle.sin()) * body_radius;
+
+    let ring_seed = rng.next_u64();
+    let flash = Flash::new(body, offset, Rng::new(rng.next_u64()));
+    (flash, DebrisRing::new(body, body_radius, normal, impact_angle, ring_seed))
+}
+
+fn plane_basis(normal: Vec3) -> (Vec3, Vec3) {
+    let helper = if normal.x.abs() < 0.9 { Vec3::new(1.0, 0.0, 0.0) } else { Vec3::new(0.0, 0.0, 1.0) };
+    let u = normal.cross(&helper).normalize();
+    let v = normal.cross(&u);
+    (u, v)
+}
+
+fn rotate_y(vector: Vec3, angle: f32) -> Vec3 {
+    let (sin, cos) = angle.sin_cos();
+    Vec3::new(vector.x * cos + vector.z * sin, vector.y, -vector.x * sin + vector.z * cos)
+}
+
+fn to_point(position: Vec3) -> Vec4 {
+    Vec4::new(position.x, position.y, position.z, 1.0)
+}
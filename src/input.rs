@@ -0,0 +1,267 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window};
+
+const REPLAY_HEADER: &str = "# Lab4_G input replay";
+
+// Keys that can be written to and read back from a replay file: every key
+// the viewer binds, except Escape, which always quits for real. A test
+// collects the bindings from the sources and checks this list against them.
+const REPLAY_KEYS: [Key; 76] = [
+    Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9,
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M,
+    Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12,
+    Key::Up, Key::Down, Key::Left, Key::Right, Key::Tab, Key::Enter, Key::Space, Key::Backspace, Key::Delete,
+    Key::PageUp, Key::PageDown,
+    Key::LeftShift, Key::RightShift, Key::LeftCtrl, Key::RightCtrl, Key::LeftAlt, Key::RightAlt,
+    Key::Equal, Key::Minus, Key::NumPadPlus, Key::NumPadMinus,
+    Key::LeftBracket, Key::RightBracket, Key::Backslash, Key::Slash, Key::Period, Key::Comma, Key::Backquote,
+];
+
+// Everything the main loop reads from the keyboard and mouse in one frame.
+// Gameplay code asks this instead of the window, so a frame can come from a
+// replay file just as well.
+#[derive(Clone, Default)]
+pub struct InputFrame {
+    down: Vec<Key>,
+    pressed: Vec<Key>,
+    repeated: Vec<Key>,
+    pub mouse: Option<(f32, f32)>,
+    pub mouse_down: bool,
+    pub right_down: bool,
+    // Wheel movement this frame; positive away from the user.
+    pub scroll: f32,
+}
+
+impl InputFrame {
+    pub fn capture(window: &Window) -> Self {
+        InputFrame {
+            down: window.get_keys(),
+            pressed: window.get_keys_pressed(KeyRepeat::No),
+            repeated: window.get_keys_pressed(KeyRepeat::Yes),
+            mouse: window.get_mouse_pos(MouseMode::Discard),
+            mouse_down: window.get_mouse_down(MouseButton::Left),
+            right_down: window.get_mouse_down(MouseButton::Right),
+            scroll: window.get_scroll_wheel().map_or(0.0, |(_, y)| y),
+        }
+    }
+
+    pub fn is_key_down(&self, key: Key) -> bool {
+        self.down.contains(&key)
+    }
+
+    pub fn is_key_pressed(&self, key: Key, repeat: KeyRepeat) -> bool {
+        match repeat {
+            KeyRepeat::No => self.pressed.contains(&key),
+            KeyRepeat::Yes => self.repeated.contains(&key),
+        }
+    }
+
+    pub fn any_key_down(&self) -> bool {
+        !self.down.is_empty()
+    }
+
+    // The same frame with the keyboard left out, for when something else
+    // has taken it.
+    pub fn without_keys(&self) -> Self {
+        InputFrame { mouse: self.mouse, mouse_down: self.mouse_down, right_down: self.right_down, scroll: self.scroll, ..InputFrame::default() }
+    }
+}
+
+// Writes one line per frame that had any input, keyed by frame number. The
+// simulation advances a fixed step per frame, so frame numbers are all the
+// timing a replay needs.
+pub struct InputRecorder {
+    writer: BufWriter<File>,
+    last_mouse: Option<(f32, f32)>,
+}
+
+impl InputRecorder {
+    // `args` are the scene arguments the session was started with, so the
+    // replay can load the same scene.
+    pub fn create(path: &str, args: &[String]) -> Result<Self, String> {
+        let file = File::create(path).map_err(|error| format!("cannot create replay {}: {}", path, error))?;
+        let mut writer = BufWriter::new(file);
+        writeln!(writer, "{}", REPLAY_HEADER).map_err(|error| error.to_string())?;
+        writeln!(writer, "args {}", args.join(" ")).map_err(|error| error.to_string())?;
+        Ok(InputRecorder { writer, last_mouse: None })
+    }
+
+    pub fn record(&mut self, frame: u64, input: &InputFrame) {
+        let mut fields = Vec::new();
+        for (name, keys) in [("down", &input.down), ("pressed", &input.pressed), ("repeat", &input.repeated)] {
+            let names: Vec<String> = keys.iter().filter(|key| REPLAY_KEYS.contains(key)).map(|key| format!("{:?}", key)).collect();
+            if !names.is_empty() {
+                fields.push(format!("{}={}", name, names.join(",")));
+            }
+        }
+        if input.mouse != self.last_mouse || input.mouse_down || input.right_down {
+            if let Some((x, y)) = input.mouse {
+                // Buttons as bits: 1 left, 2 right.
+                fields.push(format!("mouse={},{},{}", x, y, input.mouse_down as u8 | (input.right_down as u8) << 1));
+            }
+            self.last_mouse = input.mouse;
+        }
+        if input.scroll != 0.0 {
+            fields.push(format!("scroll={}", input.scroll));
+        }
+
+        if !fields.is_empty() {
+            // A failed write only loses the replay, not the session.
+            let _ = writeln!(self.writer, "{} {}", frame, fields.join(" "));
+        }
+    }
+
+    // Marks where the session stopped, so playback lasts just as long.
+    pub fn finish(mut self, frame: u64) {
+        let _ = writeln!(self.writer, "end {}", frame);
+        let _ = self.writer.flush();
+    }
+}
+
+pub struct InputPlayback {
+    pub args: Vec<String>,
+    frames: Vec<(u64, InputFrame)>,
+    end: u64,
+    next: usize,
+    mouse: Option<(f32, f32)>,
+}
+
+impl InputPlayback {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let source = fs::read_to_string(path).map_err(|error| format!("cannot read replay {}: {}", path, error))?;
+        let mut lines = source.lines().enumerate();
+        if lines.next().map(|(_, line)| line) != Some(REPLAY_HEADER) {
+            return Err(format!("{} is not an input replay", path));
+        }
+
+        let mut args = Vec::new();
+        let mut frames = Vec::new();
+        let mut end = None;
+        for (index, line) in lines {
+            let error = |message: &str| format!("{}:{}: {}", path, index + 1, message);
+            let mut words = line.split_whitespace();
+            match words.next() {
+                None => {}
+                Some("args") => args = words.map(str::to_string).collect(),
+                Some("end") => end = Some(words.next().and_then(|frame| frame.parse().ok()).ok_or_else(|| error("invalid end frame"))?),
+                Some(frame) => {
+                    let frame: u64 = frame.parse().map_err(|_| error("expected a frame number"))?;
+                    frames.push((frame, parse_frame(words).map_err(|message| error(&message))?));
+                }
+            }
+        }
+
+        let end = end.unwrap_or_else(|| frames.last().map_or(0, |(frame, _)| frame + 1));
+        Ok(InputPlayback { args, frames, end, next: 0, mouse: None })
+    }
+
+    // Input for `frame`, or None once the recording is over. Frames without
+    // a line had no keys held; the mouse stays where it was last seen.
+    pub fn frame(&mut self, frame: u64) -> Option<InputFrame> {
+        if frame >= self.end {
+            return None;
+        }
+        let mut input = InputFrame { mouse: self.mouse, ..InputFrame::default() };
+        while let Some((recorded, recorded_input)) = self.frames.get(self.next) {
+            if *recorded > frame {
+                break;
+            }
+            if *recorded == frame {
+                input = InputFrame { mouse: recorded_input.mouse.or(self.mouse), ..recorded_input.clone() };
+            }
+            self.next += 1;
+        }
+        self.mouse = input.mouse;
+        Some(input)
+    }
+}
+
+fn parse_frame<'a>(fields: impl Iterator<Item = &'a str>) -> Result<InputFrame, String> {
+    let mut input = InputFrame::default();
+    for field in fields {
+        let (name, value) = field.split_once('=').ok_or_else(|| format!("malformed field `{}`", field))?;
+        match name {
+            "down" => input.down = parse_keys(value)?,
+            "pressed" => input.pressed = parse_keys(value)?,
+            "repeat" => input.repeated = parse_keys(value)?,
+            "mouse" => {
+                let parts: Vec<&str> = value.split(',').collect();
+                let [x, y, button] = parts[..] else {
+                    return Err(format!("malformed mouse `{}`", value));
+                };
+                let number = |text: &str| text.parse::<f32>().map_err(|_| format!("malformed mouse `{}`", value));
+                let buttons: u8 = button.parse().map_err(|_| format!("malformed mouse `{}`", value))?;
+                input.mouse = Some((number(x)?, number(y)?));
+                input.mouse_down = buttons & 1 != 0;
+                input.right_down = buttons & 2 != 0;
+            }
+            "scroll" => input.scroll = value.parse().map_err(|_| format!("malformed scroll `{}`", value))?,
+            _ => return Err(format!("unknown field `{}`", name)),
+        }
+    }
+    Ok(input)
+}
+
+fn parse_keys(names: &str) -> Result<Vec<Key>, String> {
+    names
+        .split(',')
+        .map(|name| {
+            REPLAY_KEYS
+                .iter()
+                .find(|key| format!("{:?}", key) == name)
+                .copied()
+                .ok_or_else(|| format!("unknown key `{}`", name))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+    use std::path::Path;
+
+    // Names of every `Key::` the viewer's sources read, outside this file.
+    fn bound_keys() -> BTreeSet<String> {
+        let sources = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let mut keys = BTreeSet::new();
+        for entry in fs::read_dir(sources).unwrap() {
+            let path = entry.unwrap().path();
+            if path.file_name().is_some_and(|name| name == "input.rs") {
+                continue;
+            }
+            let source = fs::read_to_string(&path).unwrap();
+            for (at, _) in source.match_indices("Key::") {
+                let name: String = source[at + 5..].chars().take_while(|c| c.is_ascii_alphanumeric()).collect();
+                keys.insert(name);
+            }
+        }
+        keys
+    }
+
+    #[test]
+    fn replay_keys_are_the_bound_keys() {
+        let mut bound = bound_keys();
+        assert!(bound.remove("Escape"));
+        let listed: BTreeSet<String> = REPLAY_KEYS.iter().map(|key| format!("{:?}", key)).collect();
+        assert_eq!(listed.len(), REPLAY_KEYS.len(), "REPLAY_KEYS lists a key twice");
+        assert_eq!(listed, bound);
+    }
+
+    #[test]
+    fn frames_read_back_as_written() {
+        let names: Vec<String> = REPLAY_KEYS.iter().map(|key| format!("{:?}", key)).collect();
+        assert_eq!(parse_keys(&names.join(",")).unwrap(), REPLAY_KEYS);
+
+        let fields = "down=LeftShift,W pressed=W mouse=12.5,40,3 scroll=-1";
+        let input = parse_frame(fields.split_whitespace()).unwrap();
+        assert!(input.is_key_down(Key::W) && input.is_key_down(Key::LeftShift));
+        assert!(input.is_key_pressed(Key::W, KeyRepeat::No) && !input.is_key_pressed(Key::W, KeyRepeat::Yes));
+        assert_eq!((input.mouse, input.mouse_down, input.right_down, input.scroll), (Some((12.5, 40.0)), true, true, -1.0));
+
+        assert_eq!(parse_keys("Escape").err().unwrap(), "unknown key `Escape`");
+        assert_eq!(parse_frame(["mouse=1,2"].into_iter()).err().unwrap(), "malformed mouse `1,2`");
+    }
+}
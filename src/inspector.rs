@@ -0,0 +1,47 @@
+use minifb::{Window, WindowOptions};
+use nalgebra_glm::Vec3;
+use crate::camera::{Camera, CameraMode, Pose};
+use crate::light::Light;
+use crate::render_target::RenderTarget;
+
+pub const INSPECTOR_WIDTH: usize = 360;
+pub const INSPECTOR_HEIGHT: usize = 360;
+// Starting distance from the body's center, in body radii.
+const VIEW_DISTANCE: f32 = 4.0;
+
+// Second window with a close-up of the selected body. It shares the scene
+// and simulation with the main view but has its own camera and framebuffer,
+// and reads its own keys (arrows/W/S orbit and zoom around the body). The
+// close-up is drawn into an offscreen target and then presented.
+pub struct Inspector {
+    pub window: Window,
+    pub target: RenderTarget,
+    pub camera: Camera,
+    body: Option<usize>,
+}
+
+impl Inspector {
+    pub fn open(x: isize, y: isize) -> Result<Self, String> {
+        let mut window = Window::new("Inspector", INSPECTOR_WIDTH, INSPECTOR_HEIGHT, WindowOptions::default())
+            .map_err(|error| error.to_string())?;
+        window.set_position(x, y);
+
+        Ok(Inspector {
+            window,
+            target: RenderTarget::new(INSPECTOR_WIDTH, INSPECTOR_HEIGHT),
+            camera: Camera::new(Vec3::new(0.0, 0.0, VIEW_DISTANCE), Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0)),
+            body: None,
+        })
+    }
+
+    // Keeps the camera centered on the body as it moves. A newly selected
+    // body gets a fresh framing on its day side, scaled to its size.
+    pub fn follow(&mut self, body: usize, center: Vec3, radius: f32, light: &Light) {
+        if self.body != Some(body) {
+            self.body = Some(body);
+            let offset = light.lit_side(center) * radius * VIEW_DISTANCE;
+            self.camera.jump_to(Pose { eye: center + offset, center, ..self.camera.pose() });
+        }
+        self.camera.track(CameraMode::Follow, center, Vec3::zeros(), 1.0);
+    }
+}
@@ -0,0 +1,73 @@
+use crate::framebuffer::Framebuffer;
+
+// Where on the screen something is pinned. Positions are worked out from
+// the framebuffer's size every frame, so the HUD follows the window and the
+// render scale.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Anchor {
+    TopLeft,
+    TopCenter,
+    TopRight,
+    Center,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Anchor {
+    fn is_bottom(self) -> bool {
+        matches!(self, Anchor::BottomLeft | Anchor::BottomRight)
+    }
+}
+
+// Top-left corner for a box of `size` at `anchor`, kept `margin` pixels from
+// the edges it is pinned to.
+pub fn place(framebuffer: &Framebuffer, anchor: Anchor, size: (usize, usize), margin: usize) -> (usize, usize) {
+    let (width, height) = size;
+    let left = margin;
+    let center_x = framebuffer.width.saturating_sub(width) / 2;
+    let right = framebuffer.width.saturating_sub(width + margin);
+    let top = margin;
+    let center_y = framebuffer.height.saturating_sub(height) / 2;
+    let bottom = framebuffer.height.saturating_sub(height + margin);
+    match anchor {
+        Anchor::TopLeft => (left, top),
+        Anchor::TopCenter => (center_x, top),
+        Anchor::TopRight => (right, top),
+        Anchor::Center => (center_x, center_y),
+        Anchor::BottomLeft => (left, bottom),
+        Anchor::BottomRight => (right, bottom),
+    }
+}
+
+// Top-left corner for a box of `size` just below and right of the cursor,
+// or on whichever side has room near the screen edges.
+pub fn beside_cursor(framebuffer: &Framebuffer, cursor: (f32, f32), size: (usize, usize)) -> (usize, usize) {
+    const OFFSET: (usize, usize) = (12, 16);
+    let (cursor_x, cursor_y) = (cursor.0.max(0.0) as usize, cursor.1.max(0.0) as usize);
+    let x = if cursor_x + OFFSET.0 + size.0 <= framebuffer.width { cursor_x + OFFSET.0 } else { cursor_x.saturating_sub(size.0 + OFFSET.0 / 2) };
+    let y = if cursor_y + OFFSET.1 + size.1 <= framebuffer.height { cursor_y + OFFSET.1 } else { cursor_y.saturating_sub(size.1 + OFFSET.1 / 2) };
+    (x, y)
+}
+
+// Boxes piled one after another from an anchor, downwards from the top of
+// the screen and upwards from the bottom, `margin` pixels apart.
+pub struct Stack {
+    anchor: Anchor,
+    margin: usize,
+    // How far the pile already reaches from the edge it starts at.
+    used: usize,
+}
+
+impl Stack {
+    pub fn new(anchor: Anchor, margin: usize) -> Self {
+        Stack { anchor, margin, used: 0 }
+    }
+
+    // Position for the next box of `size`.
+    pub fn place(&mut self, framebuffer: &Framebuffer, size: (usize, usize)) -> (usize, usize) {
+        let (x, y) = place(framebuffer, self.anchor, size, self.margin);
+        let y = if self.anchor.is_bottom() { y.saturating_sub(self.used) } else { y + self.used };
+        self.used += size.1 + self.margin;
+        (x, y)
+    }
+}
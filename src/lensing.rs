@@ -0,0 +1,61 @@
+use nalgebra_glm::{Vec2, Vec3};
+use crate::framebuffer::Framebuffer;
+use crate::noise::smoothstep;
+
+// The effect fades out by this many Einstein radii from the lens, so there
+// is no seam where it stops.
+const REACH: f32 = 6.0;
+
+// Bends the background around a lens at `center` (screen x, y and depth),
+// like a point mass: each pixel shows what lies closer to the lens, along
+// the same line, so stars are pushed out into arcs and a ring forms at the
+// Einstein radius. Only pixels deeper than the lens are touched, and only
+// from other such pixels, so planets in front of it stay sharp and never
+// get smeared into the ring.
+pub fn apply(framebuffer: &mut Framebuffer, center: Vec3, einstein_radius: f32) {
+    if einstein_radius < 1.0 {
+        return;
+    }
+    let reach = einstein_radius * REACH;
+    let (width, height) = (framebuffer.width as i64, framebuffer.height as i64);
+    let min_x = ((center.x - reach).floor() as i64).clamp(0, width);
+    let max_x = ((center.x + reach).ceil() as i64).clamp(0, width);
+    let min_y = ((center.y - reach).floor() as i64).clamp(0, height);
+    let max_y = ((center.y + reach).ceil() as i64).clamp(0, height);
+    if min_x >= max_x || min_y >= max_y {
+        return;
+    }
+
+    let source = framebuffer.hdr.clone();
+    let behind = |index: usize, zbuffer: &[f32]| zbuffer[index] > center.z;
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let index = (y * width + x) as usize;
+            if !behind(index, &framebuffer.zbuffer) {
+                continue;
+            }
+            let offset = Vec2::new(x as f32 + 0.5 - center.x, y as f32 + 0.5 - center.y);
+            let distance = offset.magnitude();
+            if distance >= reach || distance < 0.5 {
+                continue;
+            }
+            // Lens equation: the image at `distance` comes from a source at
+            // `distance - radius² / distance`, on the far side inside the ring.
+            let fade = 1.0 - smoothstep(reach * 0.5, reach, distance);
+            let source_distance = distance - einstein_radius * einstein_radius / distance * fade;
+            let point = Vec2::new(center.x, center.y) + offset * (source_distance / distance);
+            let (source_x, source_y) = (point.x.floor() as i64, point.y.floor() as i64);
+            let color = if (0..width).contains(&source_x) && (0..height).contains(&source_y) {
+                let source_index = (source_y * width + source_x) as usize;
+                if behind(source_index, &framebuffer.zbuffer) {
+                    source[source_index]
+                } else {
+                    Vec3::zeros()
+                }
+            } else {
+                Vec3::zeros()
+            };
+            framebuffer.hdr[index] = color;
+        }
+    }
+}
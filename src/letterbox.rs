@@ -0,0 +1,45 @@
+use crate::draw2d;
+use crate::framebuffer::Framebuffer;
+
+const BAR_COLOR: u32 = 0x000000;
+
+// The part of the screen the scene is drawn into when a target aspect ratio
+// is set (`aspect` under [window] in config.toml). It is as large as fits,
+// centered, and black bars cover the rest, above and below or at the sides.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Letterbox {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Letterbox {
+    // The whole screen when `aspect` is None or already matches it.
+    pub fn fit(width: usize, height: usize, aspect: Option<f32>) -> Self {
+        let full = Letterbox { x: 0, y: 0, width, height };
+        let Some(aspect) = aspect else {
+            return full;
+        };
+        let fitted_width = ((height as f32 * aspect).round() as usize).clamp(1, width);
+        let fitted_height = ((width as f32 / aspect).round() as usize).clamp(1, height);
+        if fitted_width < width {
+            Letterbox { x: (width - fitted_width) / 2, width: fitted_width, ..full }
+        } else {
+            Letterbox { y: (height - fitted_height) / 2, height: fitted_height, ..full }
+        }
+    }
+
+    pub fn aspect(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+
+    // Paints the bars over whatever was drawn outside the picture.
+    pub fn cover(&self, framebuffer: &mut Framebuffer) {
+        let (right, bottom) = (self.x + self.width, self.y + self.height);
+        draw2d::fill_rect(framebuffer, 0, 0, framebuffer.width, self.y, BAR_COLOR);
+        draw2d::fill_rect(framebuffer, 0, bottom, framebuffer.width, framebuffer.height.saturating_sub(bottom), BAR_COLOR);
+        draw2d::fill_rect(framebuffer, 0, self.y, self.x, self.height, BAR_COLOR);
+        draw2d::fill_rect(framebuffer, right, self.y, framebuffer.width.saturating_sub(right), self.height, BAR_COLOR);
+    }
+}
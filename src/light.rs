@@ -0,0 +1,132 @@
+use nalgebra_glm::Vec3;
+use crate::noise::smoothstep;
+use crate::scene::{Scene, SPHERE_RADIUS};
+use crate::sim::{self, world_position, BodyState};
+
+// Distance, in AU, at which the sun's light has dropped to half. Real light
+// falls off with the square of the distance from the start, which would
+// leave the outer planets of the compressed scenes almost black.
+const HALF_LIGHT_AU: f32 = 2.0;
+// A point closer than this to a sphere's center, in its radii, is on that
+// body (its faceted mesh dips under the sphere, its clouds and air float
+// just above it) and is not shadowed by it.
+const SURFACE_RADII: f32 = 1.1;
+
+// A body that can cast a shadow, as a sphere.
+#[derive(Clone, Copy)]
+pub struct Occluder {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+// Every body but the stars, at `positions`.
+pub fn occluders(scene: &Scene, positions: &[Vec3]) -> Vec<Occluder> {
+    scene
+        .bodies
+        .iter()
+        .zip(positions)
+        .filter(|(body, _)| body.orbit.semi_major_axis > 0.0)
+        .map(|(body, &center)| Occluder { center, radius: body.scale * SPHERE_RADIUS })
+        .collect()
+}
+
+// The first star of the scene: a body that sits still at the center of its
+// orbit.
+pub fn sun_id(scene: &Scene) -> Option<usize> {
+    scene.bodies.iter().position(|body| body.parent.is_none() && body.orbit.semi_major_axis == 0.0)
+}
+
+// Point light at the sun's center that every body shader is lit by.
+#[derive(Clone, Copy)]
+pub struct Light {
+    pub position: Vec3,
+    pub intensity: f32,
+    // Distance at which the light is at half strength, in scene units.
+    pub range: f32,
+    // Size of the sun's disk, which softens shadows into a penumbra.
+    pub radius: f32,
+}
+
+impl Light {
+    // Placed at the first star of the scene, or at the origin if there is
+    // none. A variable star shines as bright as its light curve says at
+    // `time`.
+    pub fn sun(scene: &Scene, bodies: &[BodyState], time: u32) -> Self {
+        let sun = sun_id(scene);
+        let variability = sun.and_then(|body_id| scene.bodies[body_id].variability.as_ref());
+        Light {
+            position: sun.map_or(Vec3::zeros(), |body_id| world_position(scene, bodies, body_id)),
+            intensity: variability.map_or(1.0, |variability| variability.luminosity(sim::seconds(time))),
+            range: HALF_LIGHT_AU * scene.au,
+            radius: sun.map_or(0.0, |body_id| scene.bodies[body_id].scale * SPHERE_RADIUS),
+        }
+    }
+
+    pub fn attenuation(&self, distance: f32) -> f32 {
+        1.0 / (1.0 + (distance / self.range).powi(2))
+    }
+
+    // Direct light reaching a surface at `world_position` facing along
+    // `normal`.
+    pub fn diffuse(&self, world_position: Vec3, normal: Vec3) -> f32 {
+        let to_light = self.position - world_position;
+        let distance = to_light.magnitude();
+        let facing = to_light.try_normalize(1.0e-6).map_or(0.0, |direction| normal.dot(&direction).max(0.0));
+        self.intensity * facing * self.attenuation(distance)
+    }
+
+    // Share of the sun's disk visible from `world_position` past the
+    // occluders, from 1 in full sunlight to 0 in the umbra. Each occluder
+    // dims the disk by how much of it its own disk overlaps, eased between
+    // first and full contact.
+    pub fn visibility(&self, world_position: Vec3, occluders: &[Occluder]) -> f32 {
+        let to_light = self.position - world_position;
+        let light_distance = to_light.magnitude();
+        let Some(to_light) = to_light.try_normalize(1.0e-6) else {
+            return 1.0;
+        };
+        let sun_size = (self.radius / light_distance).min(1.0).asin().max(1.0e-4);
+
+        let mut visible = 1.0;
+        for occluder in occluders {
+            let offset = occluder.center - world_position;
+            let distance = offset.magnitude();
+            if distance < occluder.radius * SURFACE_RADII || distance >= light_distance || offset.dot(&to_light) <= 0.0 {
+                continue;
+            }
+            let size = (occluder.radius / distance).asin();
+            let separation = (offset / distance).dot(&to_light).clamp(-1.0, 1.0).acos();
+            if separation >= sun_size + size {
+                continue;
+            }
+            let covered = ((size / sun_size).powi(2)).min(1.0);
+            visible *= 1.0 - covered * smoothstep(sun_size + size, (sun_size - size).abs(), separation);
+        }
+        visible
+    }
+
+    // Blinn-Phong highlight seen from `eye`: how closely the normal lines up
+    // with the halfway vector between the light and the camera. Surfaces
+    // turned away from the light get none.
+    pub fn specular(&self, world_position: Vec3, normal: Vec3, eye: Vec3, shininess: f32) -> f32 {
+        let to_light = self.position - world_position;
+        let distance = to_light.magnitude();
+        let (Some(to_light), Some(to_eye)) = (to_light.try_normalize(1.0e-6), (eye - world_position).try_normalize(1.0e-6)) else {
+            return 0.0;
+        };
+        if normal.dot(&to_light) <= 0.0 {
+            return 0.0;
+        }
+        let halfway = (to_light + to_eye).try_normalize(1.0e-6).unwrap_or(normal);
+        self.intensity * normal.dot(&halfway).max(0.0).powf(shininess) * self.attenuation(distance)
+    }
+
+    // Direction from `center` towards the light, turned a little aside and
+    // lifted out of the ecliptic; cameras framing a single body look from
+    // here so it shows its day side with a sliver of terminator.
+    pub fn lit_side(&self, center: Vec3) -> Vec3 {
+        let to_light = (self.position - center).try_normalize(1.0e-6).unwrap_or(Vec3::z());
+        let aside = to_light.cross(&Vec3::y()).try_normalize(1.0e-6).unwrap_or(Vec3::x());
+        (to_light + aside * 0.4 + Vec3::y() * 0.35).normalize()
+    }
+}
@@ -0,0 +1,84 @@
+use std::collections::VecDeque;
+use nalgebra_glm::Vec3;
+use crate::scene::Scene;
+use crate::sim::{world_position, BodyState, Simulation, STEPS_PER_SECOND};
+
+// Light crosses one AU in about 499 seconds.
+const LIGHT_AU_PER_DAY: f32 = 173.14;
+// At the real speed of light the delay is a fraction of a frame in any of
+// the scenes, so the mode can slow light down to make it visible. Faster
+// than this, inner planets would outrun their own light.
+const SLOWDOWNS: [f32; 4] = [1.0, 1.0e2, 1.0e3, 1.0e4];
+const FIXED_POINT_STEPS: usize = 6;
+// Brackets marking where bodies really are.
+pub const GHOST_COLOR: u32 = 0x7788AA;
+
+// Educational mode (T) that draws every body where it was when the light now
+// reaching the camera left it, instead of where it is.
+pub struct LightTime {
+    pub enabled: bool,
+    slowdown_index: usize,
+}
+
+impl LightTime {
+    pub fn new() -> Self {
+        LightTime { enabled: false, slowdown_index: SLOWDOWNS.len() - 1 }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    pub fn next_slowdown(&mut self) {
+        self.slowdown_index = (self.slowdown_index + 1) % SLOWDOWNS.len();
+    }
+
+    pub fn slowdown(&self) -> f32 {
+        SLOWDOWNS[self.slowdown_index]
+    }
+
+    // Scene units per sim step.
+    pub fn speed(&self, scene: &Scene) -> f32 {
+        LIGHT_AU_PER_DAY * scene.au / (scene.day_length * STEPS_PER_SECOND) / self.slowdown()
+    }
+
+    // Body states as seen from `eye`, each rewound by its own light delay,
+    // along with the delays in sim steps. A moon is drawn on its parent's
+    // rewound orbit, which is off by the small difference in their delays.
+    pub fn retarded(&self, scene: &Scene, sim: &Simulation, eye: Vec3) -> (Vec<BodyState>, Vec<f32>) {
+        let speed = self.speed(scene);
+        let rewind = |body_id: usize, delay: f32| {
+            let (state, body) = (&sim.bodies[body_id], &scene.bodies[body_id]);
+            BodyState {
+                orbit_angle: state.orbit_angle - body.orbit.mean_motion * delay,
+                spin_angle: state.spin_angle - body.rotation_speed * delay,
+                trail: VecDeque::new(),
+            }
+        };
+
+        // The delay depends on where the body was, which depends on the
+        // delay; a few rounds settle it while bodies move slower than light.
+        let delays: Vec<f32> = (0..scene.bodies.len())
+            .map(|body_id| {
+                let mut delay = 0.0;
+                for _ in 0..FIXED_POINT_STEPS {
+                    let states: Vec<BodyState> = (0..scene.bodies.len()).map(|other| rewind(other, delay)).collect();
+                    let position = world_position(scene, &states, body_id);
+                    delay = (position - eye).magnitude() / speed;
+                }
+                delay
+            })
+            .collect();
+
+        let states = delays.iter().enumerate().map(|(body_id, &delay)| rewind(body_id, delay)).collect();
+        (states, delays)
+    }
+
+    pub fn label(&self) -> String {
+        if self.slowdown() == 1.0 {
+            "luz a velocidad real (T, Shift+T)".to_string()
+        } else {
+            format!("luz {} veces mas lenta (T, Shift+T)", self.slowdown())
+        }
+    }
+}
@@ -1,7 +1,13 @@
+use nalgebra_glm::Vec3;
 use crate::fragment::Fragment;
 use crate::vertex::Vertex;
-use crate::color::Color;
 
+// Segments whose screen length goes past this are dropped rather than
+// walked pixel by pixel; they come from points right next to the camera.
+const MAX_SCREEN_LENGTH: i32 = 4096;
+
+// Rasterizes the segment between two transformed vertices, interpolating
+// depth and vertex color along it.
 pub fn line(a: &Vertex, b: &Vertex) -> Vec<Fragment> {
     let mut fragments = Vec::new();
 
@@ -15,28 +21,39 @@ pub fn line(a: &Vertex, b: &Vertex) -> Vec<Fragment> {
 
     let dx = (x1 - x0).abs();
     let dy = (y1 - y0).abs();
+    if dx.max(dy) > MAX_SCREEN_LENGTH {
+        return fragments;
+    }
 
     let sx = if x0 < x1 { 1 } else { -1 };
     let sy = if y0 < y1 { 1 } else { -1 };
 
-    let mut err = if dx > dy { dx / 2 } else { -dy / 2 };
+    let steps = dx.max(dy).max(1) as f32;
+    let mut step = 0.0;
+    let mut err = dx - dy;
 
     loop {
-        let z = start.z + (end.z - start.z) * (x0 - start.x as i32) as f32 / (end.x - start.x) as f32;
-        fragments.push(Fragment::new(x0 as f32, y0 as f32, Color::new(255, 255, 255), z));
-
-        if x0 == x1 && y0 == y1 { break; }
+        let t = step / steps;
+        let depth = start.z + (end.z - start.z) * t;
+        let color = a.color.lerp(&b.color, t);
+        let position = a.position.lerp(&b.position, t);
+        fragments.push(Fragment::new(x0 as f32, y0 as f32, color, depth, Vec3::zeros(), 1.0, position));
+
+        if x0 == x1 && y0 == y1 {
+            break;
+        }
 
-        let e2 = err;
-        if e2 > -dx {
+        let e2 = 2 * err;
+        if e2 > -dy {
             err -= dy;
             x0 += sx;
         }
-        if e2 < dy {
+        if e2 < dx {
             err += dx;
             y0 += sy;
         }
+        step += 1.0;
     }
 
     fragments
-}
\ No newline at end of file
+}
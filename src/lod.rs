@@ -0,0 +1,118 @@
+use std::f32::consts::PI;
+use nalgebra_glm::{Mat4, Vec2, Vec3};
+use crate::camera::Camera;
+use crate::config::ShadingConfig;
+use crate::scene::SPHERE_RADIUS;
+use crate::vertex::Vertex;
+
+// Stacks and slices of the generated lower detail spheres. The full level
+// is assets/models/sphere.obj.
+const MEDIUM_SPHERE: (usize, usize) = (10, 14);
+const LOW_SPHERE: (usize, usize) = (6, 8);
+// The model sphere's UVs are per face, not a map of the globe, so textured
+// bodies use a generated sphere at full detail too.
+const TEXTURED_SPHERE: (usize, usize) = (20, 40);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Detail {
+    Full,
+    Medium,
+    Low,
+}
+
+// How many fragments get their own shader call: every one, one per 2x2
+// block reused by the rest of the block, or every other pixel in a
+// checkerboard whose parity flips each frame (the rest is taken from the
+// previous frame).
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ShadingRate {
+    Full,
+    Half,
+    Checkerboard { odd: bool },
+}
+
+pub struct LodMeshes {
+    pub full: Vec<Vertex>,
+    pub medium: Vec<Vertex>,
+    pub low: Vec<Vertex>,
+    pub textured: Vec<Vertex>,
+}
+
+impl LodMeshes {
+    pub fn new(full: Vec<Vertex>) -> Self {
+        LodMeshes {
+            full,
+            medium: uv_sphere(MEDIUM_SPHERE.0, MEDIUM_SPHERE.1),
+            low: uv_sphere(LOW_SPHERE.0, LOW_SPHERE.1),
+            textured: uv_sphere(TEXTURED_SPHERE.0, TEXTURED_SPHERE.1),
+        }
+    }
+
+    // Every level but the model sphere has equirectangular UVs.
+    pub fn mesh(&self, detail: Detail, textured: bool) -> &[Vertex] {
+        match detail {
+            Detail::Full if textured => &self.textured,
+            Detail::Full => &self.full,
+            Detail::Medium => &self.medium,
+            Detail::Low => &self.low,
+        }
+    }
+}
+
+// Mesh detail and shading rate for a body covering `screen_radius` pixels.
+pub fn pick(screen_radius: f32, config: &ShadingConfig) -> (Detail, ShadingRate) {
+    let detail = if screen_radius < config.lod_low_below {
+        Detail::Low
+    } else if screen_radius < config.lod_medium_below {
+        Detail::Medium
+    } else {
+        Detail::Full
+    };
+    let rate = if screen_radius < config.half_resolution_below { ShadingRate::Half } else { ShadingRate::Full };
+    (detail, rate)
+}
+
+// Approximate radius in pixels of a sphere: the larger of its extents along
+// the camera's up and right vectors. None when the center is behind the
+// camera.
+pub fn screen_radius(transform: &Mat4, camera: &Camera, center: Vec3, radius: f32) -> Option<f32> {
+    let project = |point: Vec3| {
+        let clip = transform * point.push(1.0);
+        (clip.w > 0.0).then(|| Vec2::new(clip.x / clip.w, clip.y / clip.w))
+    };
+    let up = camera.up.normalize();
+    let right = (camera.center - camera.eye).cross(&up).try_normalize(1.0e-6).unwrap_or(up);
+
+    let middle = project(center)?;
+    let extent = |axis: Vec3| project(center + axis * radius).map_or(0.0, |edge| (edge - middle).magnitude());
+    Some(extent(up).max(extent(right)))
+}
+
+// Triangle list for a sphere of SPHERE_RADIUS, matching the model sphere.
+fn uv_sphere(stacks: usize, slices: usize) -> Vec<Vertex> {
+    let point = |stack: usize, slice: usize| {
+        let theta = stack as f32 / stacks as f32 * PI;
+        let phi = slice as f32 / slices as f32 * 2.0 * PI;
+        let normal = Vec3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+        let tex_coords = Vec2::new(slice as f32 / slices as f32, 1.0 - stack as f32 / stacks as f32);
+        Vertex::new(normal * SPHERE_RADIUS, normal, tex_coords)
+    };
+
+    let mut vertices = Vec::with_capacity(stacks * slices * 6);
+    for stack in 0..stacks {
+        for slice in 0..slices {
+            let (a, b) = (point(stack, slice), point(stack, slice + 1));
+            let (c, d) = (point(stack + 1, slice), point(stack + 1, slice + 1));
+            // The rows at the poles collapse to a single point; skip the
+            // degenerate half of their quads.
+            // Counter-clockwise seen from outside, like the model sphere.
+            if stack != 0 {
+                vertices.extend([a, b.clone(), c.clone()]);
+            }
+            if stack != stacks - 1 {
+                vertices.extend([b, d, c]);
+            }
+        }
+    }
+    vertices
+}
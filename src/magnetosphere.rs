@@ -0,0 +1,57 @@
+use std::f32::consts::PI;
+use nalgebra_glm::Vec3;
+use crate::color::Color;
+
+// Dipole shells drawn, in body radii at the equator.
+const SHELLS: [f32; 3] = [2.0, 3.0, 4.5];
+const MERIDIANS: usize = 8;
+const SEGMENTS: usize = 32;
+// How much the field is squeezed on the day side and stretched into a tail
+// on the night side.
+const DAYSIDE_COMPRESSION: f32 = 0.35;
+const NIGHTSIDE_STRETCH: f32 = 0.9;
+
+pub struct FieldLine {
+    pub points: Vec<Vec3>,
+    pub color: Color,
+}
+
+// Stylized dipole field lines around a body, in world space. Each line is
+// r = L sin^2(theta) in one meridian plane, from one footpoint on the surface
+// to the other, then pushed in or out depending on which side faces the sun.
+pub fn field_lines(center: Vec3, radius: f32, sun: Vec3) -> Vec<FieldLine> {
+    let to_sun = sun - center;
+    let to_sun = Vec3::new(to_sun.x, 0.0, to_sun.z).try_normalize(1.0e-6).unwrap_or(Vec3::new(1.0, 0.0, 0.0));
+
+    let mut lines = Vec::new();
+    for (shell_index, &shell) in SHELLS.iter().enumerate() {
+        // Inner shells are brighter.
+        let brightness = 1.0 - shell_index as f32 / SHELLS.len() as f32 * 0.6;
+        let color = Color::new(80, 200, 255) * brightness;
+        let footpoint = (1.0 / shell).sqrt().asin();
+
+        for meridian in 0..MERIDIANS {
+            let phi = meridian as f32 / MERIDIANS as f32 * 2.0 * PI;
+            let horizontal = Vec3::new(phi.cos(), 0.0, phi.sin());
+            let facing = horizontal.dot(&to_sun);
+            let factor = if facing > 0.0 {
+                1.0 - DAYSIDE_COMPRESSION * facing
+            } else {
+                1.0 - NIGHTSIDE_STRETCH * facing
+            };
+
+            let points = (0..=SEGMENTS)
+                .map(|i| {
+                    let theta = footpoint + (PI - 2.0 * footpoint) * i as f32 / SEGMENTS as f32;
+                    let sin = theta.sin();
+                    let r = shell * radius * sin * sin;
+                    // Footpoints stay on the surface; only the loop bulges.
+                    let r = radius + (r - radius) * factor;
+                    center + (horizontal * sin + Vec3::new(0.0, theta.cos(), 0.0)) * r
+                })
+                .collect();
+            lines.push(FieldLine { points, color });
+        }
+    }
+    lines
+}
@@ -1,43 +1,254 @@
-use nalgebra_glm::{Vec3, Mat4, look_at, perspective};
-use minifb::{Key, Window, WindowOptions};
-use std::time::Duration;
+
+use nalgebra_glm::{Vec2, Vec3, Vec4, Mat4, look_at, mat4_to_mat3, perspective, scaling};
+use minifb::{Key, KeyRepeat, Window, WindowOptions};
 use std::f32::consts::PI;
 
 mod framebuffer;
 mod triangle;
+mod clipping;
+mod line;
 mod vertex;
 mod obj;
 mod color;
 mod fragment;
 mod shaders;
 mod camera;
+mod assets;
+mod text;
+mod hud;
+mod memory;
+mod toml_lite;
+mod scene;
+mod units;
+mod rng;
+mod noise;
+mod particles;
+mod comet;
+mod timeline;
+mod probe;
+mod impact;
+mod solar_wind;
+mod magnetosphere;
+mod heatmap;
+mod habitable;
+mod resonance;
+mod sim;
+mod config;
+mod lod;
+mod inspector;
+mod gallery;
+mod capture;
+mod texture;
+mod sampling;
+mod input;
+mod render_target;
+mod state;
+mod crash;
+mod title;
+mod scene_graph;
+mod orbit;
+mod mesh;
+mod planet_data;
+mod asteroid;
+mod rings;
+mod clouds;
+mod gravity_grid;
+mod light_time;
+mod atmosphere;
+mod corona;
+mod glint;
+mod ice;
+mod light;
+mod material;
+mod transit;
+mod lensing;
+mod black_hole;
+mod bloom;
+mod variability;
+mod graph;
+mod draw2d;
+mod post;
+mod tonemap;
+mod sun_view;
+mod layout;
+mod skybox;
+mod starfield;
+mod belt;
+mod ttf;
+mod selection;
+mod flares;
+mod console;
+mod spawn;
+mod mouse_look;
+mod checkpoints;
+mod export;
+mod orbit_gizmo;
+mod history;
+mod letterbox;
+mod bookmarks;
+mod clock;
 
-use framebuffer::Framebuffer;
+use framebuffer::{BlendMode, Framebuffer};
 use vertex::Vertex;
-use obj::Obj;
-use camera::Camera;
-use triangle::triangle;
-use shaders::{vertex_shader, fragment_shader};
-use fastnoise_lite::{FastNoiseLite, NoiseType, FractalType};
-use image::{open, DynamicImage};
-
-pub struct Uniforms {
+use fragment::Fragment;
+use camera::{Camera, CameraMode, Pose, DEFAULT_FOV};
+use triangle::{is_front_facing, triangle};
+use clipping::clip_triangle;
+use line::line;
+use color::{Color, Rgba};
+use shaders::{vertex_shader, fragment_shader, VertexTransform, has_oceans, ocean, ring_shader, is_black_hole};
+use assets::{AssetLoader, AssetKind, AssetData};
+use hud::{draw_anchored_panel, draw_captions, draw_marker, draw_stacked_panel, draw_tooltip, Hover, StatsOverlay};
+use layout::{Anchor, Stack};
+use letterbox::Letterbox;
+use memory::{MemoryFootprint, MemoryReport};
+use scene::{Scene, DEFAULT_COMPRESSION, SPHERE_RADIUS};
+use sim::{BodyState, Simulation, TimeScale, STEPS_PER_SECOND};
+use light_time::LightTime;
+use config::{Config, CONFIG_PATH};
+use lod::{Detail, LodMeshes, ShadingRate};
+use inspector::Inspector;
+use sun_view::SunView;
+use light::{Light, Occluder};
+use material::Material;
+use transit::TransitWatch;
+use graph::Graph;
+use post::PostChain;
+use tonemap::ToneMapping;
+use starfield::Starfield;
+use belt::AsteroidBelt;
+use selection::Selection;
+use console::{Command, Console, Edit};
+use spawn::{Spawned, Template};
+use mouse_look::MouseLook;
+use orbit_gizmo::OrbitGizmo;
+use checkpoints::{Checkpoints, Scrubber};
+use bookmarks::{Bookmarks, BOOKMARKS_PATH};
+use clock::Clock;
+use history::{History, Snapshot};
+use gallery::Gallery;
+use capture::ClipRecorder;
+use texture::{ColorSpace, Texture};
+use input::{InputFrame, InputPlayback, InputRecorder};
+use title::{TitleValues, WindowTitle};
+use scene_graph::{MeshRef, SceneNode};
+use mesh::MeshCache;
+use rings::RingSystem;
+use render_target::RenderTarget;
+use probe::Probe;
+use solar_wind::SolarWind;
+use timeline::{Easing, Tween};
+use noise::{NoiseField, NoisePreset, VoronoiField};
+use rayon::prelude::*;
+
+const SPHERE_PATH: &str = "assets/models/sphere.obj";
+const SKY_TEXTURE_PATH: &str = "assets/textures/Sky.png";
+// How far the camera sits from a body focused from the gallery, in body radii.
+const FOCUS_DISTANCE: f32 = 6.0;
+// Riding along (F): how far behind a body the camera trails, and how high
+// over a planet it stands to watch a moon, in body radii.
+const RIDE_DISTANCE: f32 = 5.0;
+const RIDE_ALTITUDE: f32 = 1.3;
+// Seconds a warp (Shift+digit) takes to fly to a planet.
+const WARP_SECONDS: f32 = 1.0;
+// Seconds the camera takes to fly to a named shot.
+const SHOT_SECONDS: f32 = 1.5;
+// Seconds the camera takes to fly up to the overview (F10) and back.
+const OVERVIEW_SECONDS: f32 = 1.25;
+// The overview looks down through a narrow lens from far away, so the
+// system looks almost flat, leaving this much room around the orbits.
+const OVERVIEW_FOV: f32 = 20.0 * PI / 180.0;
+const OVERVIEW_MARGIN: f32 = 1.1;
+// Kept inside the far clipping plane.
+const OVERVIEW_MAX_DISTANCE: f32 = 800.0;
+// Seconds the camera takes to fly back to a saved view (Alt+1-9).
+const BOOKMARK_SECONDS: f32 = 1.0;
+const DIGIT_KEYS: [Key; 10] = [Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9];
+// Samples kept by the HUD graphs, one pixel column each.
+const HISTORY_SAMPLES: usize = 240;
+
+pub struct Uniforms<'a> {
     model_matrix: Mat4,
     view_matrix: Mat4,
     projection_matrix: Mat4,
     viewport_matrix: Mat4,
-    time: u32,
-    noise: FastNoiseLite
+    // Sim time in seconds, for surface animation.
+    time: f32,
+    // Sim seconds since the previous frame, 0 while paused.
+    delta: f32,
+    noise: &'a NoiseField,
+    cells: &'a VoronoiField,
+    // Skip triangles facing away from the camera; off for debugging.
+    cull_back_faces: bool,
+    // Surface map for the textured shader.
+    texture: Option<&'a Texture>,
+    light: Light,
+    material: Material,
+    // Camera position in world space, for highlights.
+    eye: Vec3,
+    // Bodies that may shadow what is drawn.
+    occluders: &'a [Occluder],
+}
+
+// What every draw through one camera shares: its matrices, the sim time,
+// the sun's light and the bodies that can cast shadows.
+struct View<'a> {
+    view_matrix: Mat4,
+    projection_matrix: Mat4,
+    viewport_matrix: Mat4,
+    time: f32,
+    delta: f32,
+    light: Light,
+    eye: Vec3,
+    cull_back_faces: bool,
+    occluders: &'a [Occluder],
 }
 
-fn create_noise() -> FastNoiseLite {
-    create_cloud_noise()
+impl<'a> View<'a> {
+    // One draw of a model with its noise fields; matte and untextured until
+    // told otherwise.
+    fn uniforms(&self, model_matrix: Mat4, (noise, cells): &'a (NoiseField, VoronoiField)) -> Uniforms<'a> {
+        Uniforms {
+            model_matrix,
+            view_matrix: self.view_matrix,
+            projection_matrix: self.projection_matrix,
+            viewport_matrix: self.viewport_matrix,
+            time: self.time,
+            delta: self.delta,
+            noise,
+            cells,
+            cull_back_faces: self.cull_back_faces,
+            texture: None,
+            light: self.light,
+            material: Material::MATTE,
+            eye: self.eye,
+            occluders: self.occluders,
+        }
+    }
 }
 
-fn create_cloud_noise() -> FastNoiseLite {
-    let mut noise = FastNoiseLite::with_seed(1337);
-    noise.set_noise_type(Some(NoiseType::OpenSimplex2));
-    noise
+impl<'a> Uniforms<'a> {
+    fn with_texture(mut self, texture: Option<&'a Texture>) -> Self {
+        self.texture = texture;
+        self
+    }
+
+    fn with_material(mut self, material: Material) -> Self {
+        self.material = material;
+        self
+    }
+
+    // See-through layers never drop their back faces.
+    fn with_culling(mut self, cull_back_faces: bool) -> Self {
+        self.cull_back_faces = cull_back_faces;
+        self
+    }
+
+    // Nothing casts shadows on it, as for lines and glows.
+    fn unshadowed(mut self) -> Self {
+        self.occluders = &[];
+        self
+    }
 }
 
 fn create_model_matrix(translation: Vec3, scale: f32, rotation: Vec3) -> Mat4 {
@@ -83,71 +294,276 @@ fn create_view_matrix(eye: Vec3, center: Vec3, up: Vec3) -> Mat4 {
     look_at(&eye, &center, &up)
 }
 
-fn create_perspective_matrix(window_width: f32, window_height: f32) -> Mat4 {
-    let fov = 45.0 * PI / 180.0;
+fn create_perspective_matrix(fov: f32, window_width: f32, window_height: f32) -> Mat4 {
     let aspect_ratio = window_width / window_height;
     let near = 0.1;
     let far = 1000.0;
 
-    perspective(fov, aspect_ratio, near, far)
+    perspective(aspect_ratio, fov, near, far)
 }
 
-fn create_viewport_matrix(width: f32, height: f32) -> Mat4 {
+// Maps clip space onto the `width` x `height` rectangle whose top-left
+// corner is at (`x`, `y`).
+fn create_viewport_matrix(x: f32, y: f32, width: f32, height: f32) -> Mat4 {
     Mat4::new(
-        width / 2.0, 0.0, 0.0, width / 2.0,
-        0.0, -height / 2.0, 0.0, height / 2.0,
+        width / 2.0, 0.0, 0.0, x + width / 2.0,
+        0.0, -height / 2.0, 0.0, y + height / 2.0,
         0.0, 0.0, 1.0, 0.0,
         0.0, 0.0, 0.0, 1.0
     )
 }
 
-fn render(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex], current_shader: u8) {
-    let mut transformed_vertices = Vec::with_capacity(vertex_array.len());
-    for vertex in vertex_array {
-        let transformed = vertex_shader(vertex, uniforms);
-        transformed_vertices.push(transformed);
+// Vertices, triangles and fragments are processed in parallel with rayon;
+// see `Framebuffer::write_fragments` for how depth writes are kept apart.
+fn render<F: Fn(&Fragment) -> Color + Sync>(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex], rate: ShadingRate, shade: F) {
+    let fragments = rasterize(framebuffer.width, framebuffer.height, uniforms, vertex_array);
+    shade_fragments(framebuffer, uniforms, &fragments, rate, shade);
+}
+
+// Draws one copy of the mesh per model matrix, all in a single batch; the
+// uniforms' own model matrix is ignored.
+fn render_instances<F: Fn(&Fragment) -> Color + Sync>(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex], model_matrices: &[Mat4], shade: F) {
+    let fragments = rasterize_instances(framebuffer.width, framebuffer.height, uniforms, vertex_array, model_matrices);
+    shade_fragments(framebuffer, uniforms, &fragments, ShadingRate::Full, shade);
+}
+
+fn shade_fragments<F: Fn(&Fragment) -> Color + Sync>(framebuffer: &mut Framebuffer, uniforms: &Uniforms, fragments: &[Fragment], rate: ShadingRate, shade: F) {
+    let brightness = uniforms.material.brightness;
+    let shade = |fragment: &Fragment| shade(fragment).radiance() * brightness;
+
+    if rate == ShadingRate::Half {
+        shade_half_rate(framebuffer, fragments, shade);
+        return;
     }
 
-    let mut triangles = Vec::new();
-    for i in (0..transformed_vertices.len()).step_by(3) {
-        if i + 2 < transformed_vertices.len() {
-            triangles.push([
-                transformed_vertices[i].clone(),
-                transformed_vertices[i + 1].clone(),
-                transformed_vertices[i + 2].clone(),
-            ]);
+    framebuffer.write_fragments(fragments, |fragment| {
+        if let ShadingRate::Checkerboard { odd } = rate {
+            if (fragment.position.x as usize + fragment.position.y as usize) % 2 != odd as usize {
+                return None;
+            }
+        }
+        Some(shade(fragment))
+    });
+}
+
+// Like `render`, for see-through geometry: `shade` also returns an opacity,
+// or None to leave the pixel alone.
+fn render_translucent<F: Fn(&Fragment) -> Option<Rgba>>(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex], mode: BlendMode, shade: F) {
+    let fragments = rasterize(framebuffer.width, framebuffer.height, uniforms, vertex_array);
+    framebuffer.blend_fragments(&fragments, mode, shade);
+}
+
+// Alpha blended geometry, collected during the scene walk and drawn once
+// everything opaque is in place.
+enum Layer<'a> {
+    // A body's cloud deck, on a shell around its own mesh.
+    Clouds { model_matrix: Mat4, body: usize, mesh: &'a [Vertex] },
+    Rings { model_matrix: Mat4, body: usize },
+    // Glowing gas around a black hole, added onto what is behind it.
+    AccretionDisk { model_matrix: Mat4, body: usize },
+}
+
+impl Layer<'_> {
+    fn model_matrix(&self) -> &Mat4 {
+        match self {
+            Layer::Clouds { model_matrix, .. } | Layer::Rings { model_matrix, .. } | Layer::AccretionDisk { model_matrix, .. } => model_matrix,
         }
     }
 
-    let mut fragments = Vec::new();
-    for tri in &triangles {
-        fragments.extend(triangle(&tri[0], &tri[1], &tri[2]));
+    fn body(&self) -> usize {
+        match self {
+            Layer::Clouds { body, .. } | Layer::Rings { body, .. } | Layer::AccretionDisk { body, .. } => *body,
+        }
+    }
+}
+
+// Farthest first, so alpha blended layers cover the ones behind them.
+fn sort_back_to_front<T>(items: &mut [T], eye: Vec3, position: impl Fn(&T) -> Vec3) {
+    items.sort_by(|a, b| (position(b) - eye).magnitude_squared().total_cmp(&(position(a) - eye).magnitude_squared()));
+}
+
+fn rasterize(width: usize, height: usize, uniforms: &Uniforms, vertex_array: &[Vertex]) -> Vec<Fragment> {
+    rasterize_instances(width, height, uniforms, vertex_array, std::slice::from_ref(&uniforms.model_matrix))
+}
+
+// Every copy of the mesh is transformed, clipped and rasterized together:
+// the matrices are set up once per copy, and the work is split over all the
+// copies' triangles at once rather than one small mesh at a time.
+fn rasterize_instances(width: usize, height: usize, uniforms: &Uniforms, vertex_array: &[Vertex], model_matrices: &[Mat4]) -> Vec<Fragment> {
+    let transforms: Vec<VertexTransform> = model_matrices.iter().map(|model_matrix| VertexTransform::new(model_matrix, uniforms)).collect();
+    let triangle_count = vertex_array.len() / 3;
+
+    let triangles: Vec<[Vertex; 3]> = (0..transforms.len() * triangle_count)
+        .into_par_iter()
+        .flat_map_iter(|index| {
+            let transform = &transforms[index / triangle_count];
+            let corners = &vertex_array[index % triangle_count * 3..][..3];
+            let corners = [transform.apply(&corners[0]), transform.apply(&corners[1]), transform.apply(&corners[2])];
+            let polygon = clip_triangle([&corners[0], &corners[1], &corners[2]], &uniforms.viewport_matrix);
+            (1..polygon.len().saturating_sub(1))
+                .map(|i| [polygon[0].clone(), polygon[i].clone(), polygon[i + 1].clone()])
+                .filter(|[a, b, c]| !uniforms.cull_back_faces || is_front_facing(a, b, c))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    triangles
+        .par_iter()
+        .flat_map_iter(|tri| triangle(&tri[0], &tri[1], &tri[2], width, height))
+        .map(|mut fragment| {
+            let material = &uniforms.material;
+            fragment.sunlight = uniforms.light.visibility(fragment.world_position, uniforms.occluders);
+            fragment.intensity = material.ambient + material.diffuse * uniforms.light.diffuse(fragment.world_position, fragment.normal) * fragment.sunlight;
+            fragment
+        })
+        .collect()
+}
+
+// Resolves visibility at full resolution within the fragments' bounding box,
+// then runs the shader once per 2x2 block and spreads the result over the
+// block's visible pixels, so edges and depth stay sharp.
+fn shade_half_rate<F: Fn(&Fragment) -> Vec3>(framebuffer: &mut Framebuffer, fragments: &[Fragment], shade: F) {
+    let on_screen = |fragment: &&Fragment| {
+        (fragment.position.x as usize) < framebuffer.width && (fragment.position.y as usize) < framebuffer.height
+    };
+    let Some((min_x, min_y, max_x, max_y)) = fragments.iter().filter(on_screen).fold(None, |bounds, fragment| {
+        let (x, y) = (fragment.position.x as usize, fragment.position.y as usize);
+        Some(match bounds {
+            None => (x, y, x, y),
+            Some((x0, y0, x1, y1)) => (x.min(x0), y.min(y0), x.max(x1), y.max(y1)),
+        })
+    }) else {
+        return;
+    };
+
+    // Nearest fragment per pixel that also passes the depth already stored.
+    let box_width = max_x - min_x + 1;
+    let mut nearest: Vec<Option<usize>> = vec![None; box_width * (max_y - min_y + 1)];
+    for (index, fragment) in fragments.iter().enumerate().filter(|(_, fragment)| on_screen(fragment)) {
+        let (x, y) = (fragment.position.x as usize, fragment.position.y as usize);
+        if framebuffer.zbuffer[y * framebuffer.width + x] <= fragment.depth {
+            continue;
+        }
+        let slot = &mut nearest[(y - min_y) * box_width + (x - min_x)];
+        if slot.is_none_or(|other| fragments[other].depth > fragment.depth) {
+            *slot = Some(index);
+        }
     }
 
-    for fragment in fragments {
-        let x = fragment.position.x as usize;
-        let y = fragment.position.y as usize;
+    for block_y in (min_y..=max_y).step_by(2) {
+        for block_x in (min_x..=max_x).step_by(2) {
+            let pixels: Vec<(usize, usize, usize)> = [(0, 0), (1, 0), (0, 1), (1, 1)]
+                .iter()
+                .map(|(dx, dy)| (block_x + dx, block_y + dy))
+                .filter(|&(x, y)| x <= max_x && y <= max_y)
+                .filter_map(|(x, y)| nearest[(y - min_y) * box_width + (x - min_x)].map(|index| (x, y, index)))
+                .collect();
+            let Some(&(_, _, sample)) = pixels.first() else {
+                continue;
+            };
 
-        if x < framebuffer.width && y < framebuffer.height {
-            let shaded_color = fragment_shader(&fragment, uniforms, current_shader);
-            let color = shaded_color.to_hex();
-            framebuffer.set_current_color(color);
-            framebuffer.point(x, y, fragment.depth);
+            framebuffer.set_current_radiance(shade(&fragments[sample]));
+            for (x, y, index) in pixels {
+                framebuffer.point(x, y, fragments[index].depth);
+            }
         }
     }
 }
 
+// Removes `flag` and the value after it from the arguments.
+fn take_flag(args: &mut Vec<String>, flag: &str) -> Option<String> {
+    let index = args.iter().position(|arg| arg == flag)?;
+    args.remove(index);
+    if index < args.len() {
+        Some(args.remove(index))
+    } else {
+        eprintln!("{} needs a file path", flag);
+        std::process::exit(1);
+    }
+}
 
 fn main() {
     let window_width = 800;
     let window_height = 600;
     let framebuffer_width = 800;
     let framebuffer_height = 600;
-    let frame_delay = Duration::from_millis(16);
+    crash::install();
+
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let record_path = take_flag(&mut args, "--record");
+    let mut playback = take_flag(&mut args, "--replay").map(|path| match InputPlayback::load(&path) {
+        Ok(playback) => playback,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    });
+    // A replay brings the scene it was recorded with unless one is given.
+    if let Some(playback) = &playback {
+        if args.is_empty() {
+            args = playback.args.clone();
+        }
+    }
+
+    // Where orbit edits are written back to, when the scene came from a file.
+    let scene_path = args.first().filter(|arg| !arg.starts_with("--")).cloned();
+    // The arguments that reproduce this scene, seed included.
+    let mut scene_args = args.clone();
+    let mut scene = match args.first() {
+        Some(flag) if flag == "--random" => {
+            let seed = args
+                .get(1)
+                .and_then(|seed| seed.parse().ok())
+                .unwrap_or_else(|| {
+                    std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map_or(0, |elapsed| elapsed.as_secs())
+                });
+            crash::log(format!("Random system seed: {}", seed));
+            scene_args = vec![flag.clone(), seed.to_string()];
+            Scene::random_system(seed)
+        }
+        Some(flag) if flag == "--real" => {
+            let compression = match args.get(1).map(|value| value.parse::<f32>()) {
+                Some(Ok(compression)) if (0.0..=1.0).contains(&compression) => compression,
+                Some(_) => {
+                    eprintln!("--real expects a compression between 0 and 1, found `{}`", args[1]);
+                    std::process::exit(1);
+                }
+                None => DEFAULT_COMPRESSION,
+            };
+            Scene::real_system(compression)
+        }
+        Some(path) => match Scene::load(path) {
+            Ok(scene) => scene,
+            Err(diagnostics) => {
+                for diagnostic in &diagnostics {
+                    eprintln!("{}", diagnostic);
+                }
+                eprintln!("{} problem(s) found in scene file", diagnostics.len());
+                std::process::exit(1);
+            }
+        },
+        None => Scene::default_system(),
+    };
+
+    let config = Config::load(CONFIG_PATH).unwrap_or_else(|diagnostics| {
+        for diagnostic in &diagnostics {
+            crash::log_error(diagnostic.to_string());
+        }
+        crash::log_error(format!("{} problem(s) found in config file, using defaults", diagnostics.len()));
+        Config::default()
+    });
+    // A font that can't be read leaves the bitmap one in place.
+    if let Some(font_path) = &config.hud.font {
+        if let Err(message) = text::load_font(font_path) {
+            crash::log_error(message);
+        }
+    }
 
     let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
     let mut window = Window::new(
-        "Sistema Solar",
+        &scene.name,
         window_width,
         window_height,
         WindowOptions::default(),
@@ -157,166 +573,1769 @@ fn main() {
     window.set_position(500, 500);
     window.update();
 
-    // Cargar la imagen del espacio
-    let space_texture = load_texture("assets/textures/Sky.png");
+    // Cargar los assets en segundo plano mientras se muestra la pantalla de carga
+    let mut requests = vec![(AssetKind::Mesh, SPHERE_PATH.to_string())];
+    // A generated starfield replaces the sky image, which then isn't needed.
+    let starfield = scene.starfield.as_ref().map(Starfield::new);
+    if starfield.is_none() {
+        requests.push((AssetKind::Texture(ColorSpace::Srgb), SKY_TEXTURE_PATH.to_string()));
+    }
+    let body_assets = scene.bodies.iter().flat_map(|body| {
+        let texture = body.texture.clone().map(|path| (AssetKind::Texture(body.texture_space), path));
+        let mesh = body.mesh.clone().filter(|_| MeshRef::of(body) == MeshRef::Model).map(|path| (AssetKind::Mesh, path));
+        texture.into_iter().chain(mesh)
+    });
+    // Bodies sharing a file, read the same way, share one load.
+    for request in body_assets {
+        if !requests.contains(&request) {
+            requests.push(request);
+        }
+    }
+    let mut loader = AssetLoader::spawn(requests);
+    let mut space_texture: Option<Texture> = None;
+    // Surface maps by body, filled in as they finish loading.
+    let mut body_textures: Vec<Option<Texture>> = scene.bodies.iter().map(|_| None).collect();
+    let mut lod_meshes: Option<LodMeshes> = None;
+    let mut mesh_cache = MeshCache::default();
+    for (body_id, body) in scene.bodies.iter().enumerate() {
+        if MeshRef::of(body) == MeshRef::Asteroid {
+            mesh_cache.insert_generated(body_id, asteroid::generate(scene.seed, body_id as u64));
+        }
+    }
 
     let mut camera = Camera::new(
-        Vec3::new(0.0, 0.0, 20.0),
-        Vec3::new(0.0, 0.0, 0.0),
+        scene.camera.eye,
+        scene.camera.center,
         Vec3::new(0.0, 1.0, 0.0),
     );
+    camera.smoothing = config.camera.smoothing;
+    let mut camera_mode = CameraMode::Free;
+    // Eases the camera into a new tracking mode instead of snapping to it.
+    let mut camera_blend = Tween::new(1.0, 1.0, 0.0, Easing::Linear);
+    let mut camera_blend_elapsed = 0.0;
+    // Body chosen from the gallery; the camera eases onto it and then
+    // follows it until C is pressed.
+    let mut focus: Option<usize> = scene.camera.focus;
+    if focus.is_some() {
+        camera_blend = Tween::new(0.0, 1.0, 0.75, Easing::EaseInOut);
+    }
+    // Bodies dragged over together, and whether the camera follows them.
+    let mut selection = Selection::new();
+    let mut follow_group = false;
+    // Body the camera rides along with (F), and where it was last frame to
+    // tell which way it is going.
+    let mut ride: Option<usize> = None;
+    let mut ride_last: Option<Vec3> = None;
+    // Body the camera is flying to; once there it stays focused on it.
+    let mut warp: Option<usize> = None;
+    // Named shot the camera is flying to, the one F8 picks next, and the
+    // clock up to which captions have cued theirs.
+    let mut shot: Option<usize> = None;
+    let mut next_shot = 0;
+    let mut cue_time = -1.0;
+    // While in the overview, what the camera followed before it, for F11 to
+    // take up again once back; `returning` holds it during that flight.
+    let mut overview: Option<Option<usize>> = None;
+    let mut returning: Option<Option<usize>> = None;
+    // Views saved with Ctrl+1-9, and whether the camera is flying back to one.
+    let mut bookmarks = Bookmarks::load(BOOKMARKS_PATH).unwrap_or_else(|error| {
+        crash::log_error(error);
+        Bookmarks::new()
+    });
+    let mut recalling = false;
+    let mut hidden = vec![false; scene.bodies.len()];
+    let mut console = Console::new();
+    let mut history = History::new();
+    let mut orbit_gizmo = OrbitGizmo::new();
 
-    let sphere = Obj::load("assets/models/sphere.obj").expect("Failed to load sphere.obj");
-    let vertex_arrays = sphere.get_vertex_array();
+    // Noise fields are measured for equalization when built, so build them
+    // once per body rather than every frame.
+    let mut surface_noise: Vec<(NoiseField, VoronoiField)> = scene
+        .bodies
+        .iter()
+        .enumerate()
+        .map(|(body_id, body)| {
+            let noise_seed = rng::noise_seed(scene.seed, body_id as u64);
+            (NoiseField::new(noise_seed, body.noise), VoronoiField::new(noise_seed, 1.0))
+        })
+        .collect();
+    let mut ring_systems: Vec<RingSystem> = scene
+        .bodies
+        .iter()
+        .enumerate()
+        .filter_map(|(body_id, body)| body.rings.as_ref().map(|desc| RingSystem::generate(desc, scene.seed, body_id)))
+        .collect();
+    let mut comet_noise: Vec<(NoiseField, VoronoiField)> = (0..scene.comets.len())
+        .map(|comet_id| {
+            let noise_seed = rng::noise_seed(scene.seed, (scene.bodies.len() + comet_id) as u64);
+            (NoiseField::new(noise_seed, NoisePreset::Ridged), VoronoiField::new(noise_seed, 1.0))
+        })
+        .collect();
 
-    let mut time = 0;
+    let asteroid_belt = scene.asteroid_belt.as_ref().map(|desc| {
+        let noise_seed = rng::noise_seed(scene.seed, (scene.bodies.len() + scene.comets.len()) as u64);
+        (AsteroidBelt::generate(desc, &scene), (NoiseField::new(noise_seed, NoisePreset::Ridged), VoronoiField::new(noise_seed, 1.0)))
+    });
 
-    let planet_data = vec![
-        (Vec3::new(0.0, 0.0, 0.0), 2.0, 6, 0.0, 0.0),
-        (Vec3::new(3.0, 0.0, 0.0), 0.5, 1, 0.05, 0.02),
-        (Vec3::new(6.0, 0.0, 0.0), 0.7, 2, 0.03, 0.015),
-        (Vec3::new(9.0, 0.0, 0.0), 0.9, 3, 0.02, 0.01),
-        (Vec3::new(12.0, 0.0, 0.0), 1.2, 4, 0.01, 0.007),
-        (Vec3::new(15.0, 0.0, 0.0), 1.5, 5, 0.04, 0.005),
-        (Vec3::new(18.0, 0.0, 0.0), 1.7, 7, 0.02, 0.003),
-        (Vec3::new(21.0, 0.0, 0.0), 1.8, 8, 0.03, 0.002),
-    ];
+    let mut time = 0;
+    let mut sim = Simulation::new(&scene);
+    if let Some(body_id) = focus {
+        sim.selected = body_id;
+    }
+    let mut checkpoints = Checkpoints::new();
+    checkpoints.record(time, &sim);
+    let mut scrubber = Scrubber::new();
+    let mut solar_wind = SolarWind::new(&scene);
+    let mut show_field_lines = false;
+    let mut show_orbits = false;
+    let mut show_gravity_grid = false;
+    let mut sun_view = SunView::new();
+    let mut transits = TransitWatch::new();
+    let accretion_disk = black_hole::disk_mesh();
+    let mut post_effects = PostChain::standard();
+    let mut tone_mapping = ToneMapping::default();
+    // Recent history, one sample per simulated frame.
+    let mut light_curve = Graph::new(HISTORY_SAMPLES, 0xFFD060).with_reference(1.0);
+    let mut selected_speed = Graph::new(HISTORY_SAMPLES, 0x66CCFF);
+    let mut speed_body = sim.selected;
+    let mut light_time = LightTime::new();
+    let mut show_heat_map = false;
+    let mut show_habitable_zone = true;
+    let mut flash_resonances = true;
+    let mut time_scale = TimeScale::new();
+    // Last frame's scene without the HUD, reused while nothing moves and
+    // blended in for motion blur.
+    let mut scene_layer: Vec<Vec3> = Vec::new();
+    let mut last_view = None;
+    let mut lod_picks = Vec::new();
+    let mut checkerboard = config.shading.checkerboard;
+    let mut odd_frame = false;
+    let mut inspector: Option<Inspector> = None;
+    let mut cull_back_faces = true;
+    let mut stats = StatsOverlay::new();
+    let mut window_title = WindowTitle::new(&config.window.title);
+    let picture = Letterbox::fit(framebuffer_width, framebuffer_height, config.window.aspect);
+    let mut gallery = Gallery::new();
+    // The inspector window has its own cursor.
+    let mut mouse_look = MouseLook::new(config.mouse.clone());
+    let mut inspector_mouse_look = MouseLook::new(config.mouse.clone());
+    let mut hover = Hover::new();
+    let mut recorder = ClipRecorder::new(&config.capture);
+    let mut input_recorder = record_path.and_then(|path| match InputRecorder::create(&path, &scene_args) {
+        Ok(input_recorder) => {
+            crash::log(format!("Recording input to {}", path));
+            Some(input_recorder)
+        }
+        Err(error) => {
+            crash::log_error(error);
+            None
+        }
+    });
+    let mut frame: u64 = 0;
+    let mut clock = Clock::new(playback.is_some() || input_recorder.is_some());
 
     while window.is_open() {
-        if window.is_key_down(Key::Escape) {
+        let delta = clock.tick();
+        let live_input = InputFrame::capture(&window);
+        // A real Escape always quits, even in the middle of a replay.
+        if live_input.is_key_down(Key::Escape) {
             break;
         }
+        let input = match playback.as_mut().and_then(|replay| replay.frame(frame)) {
+            Some(input) => input,
+            None => {
+                if playback.take().is_some() {
+                    crash::log("Replay finished, input is live again".to_string());
+                }
+                live_input
+            }
+        };
+        if let Some(recorder) = &mut input_recorder {
+            recorder.record(frame, &input);
+        }
+        let typed = console.handle_input(&input);
+        // An open console takes the keyboard; the mouse still works.
+        let input = if console.open { input.without_keys() } else { input };
+        frame += 1;
+        crash::update(&camera, frame, &scene, &sim, time);
+
+        // Set by anything that changes the scene without a key held down,
+        // like an asset arriving, so the next frame doesn't reuse the last
+        // one.
+        let mut scene_dirty = false;
+        for asset in loader.poll() {
+            scene_dirty = true;
+            match asset.data {
+                AssetData::Mesh(vertices) if asset.path == SPHERE_PATH => lod_meshes = Some(LodMeshes::new(vertices)),
+                AssetData::Mesh(vertices) => mesh_cache.insert(asset.path, vertices),
+                AssetData::Texture(texture) => {
+                    for (body_id, body) in scene.bodies.iter().enumerate() {
+                        if body.texture.as_ref() == Some(&asset.path) && asset.kind == AssetKind::Texture(body.texture_space) {
+                            body_textures[body_id] = Some(texture.clone().with_wrap(body.texture_wrap).with_filter(body.texture_filter));
+                        }
+                    }
+                    // A body may use the sky image too.
+                    if asset.path == SKY_TEXTURE_PATH && asset.kind == AssetKind::Texture(ColorSpace::Srgb) {
+                        space_texture = Some(texture);
+                    }
+                }
+            }
+        }
+
+        let steps = time_scale.advance(delta);
+        let sim_delta = steps as f32 / STEPS_PER_SECOND;
+        for _ in 0..steps {
+            time += 1;
+            let before = sim.body_position(&scene, sim.selected);
+            sim.step(&scene);
+            checkpoints.record(time, &sim);
+            solar_wind.step(&scene, &sim);
+            if let Some(variability) = light::sun_id(&scene).and_then(|sun| scene.bodies[sun].variability.as_ref()) {
+                light_curve.push(variability.luminosity(sim::seconds(time)));
+            }
+            if speed_body != sim.selected {
+                selected_speed.clear();
+                speed_body = sim.selected;
+            }
+            selected_speed.push((sim.body_position(&scene, sim.selected) - before).magnitude() / scene.au * STEPS_PER_SECOND * scene.day_length);
+        }
+        stats.tick();
 
-        time += 1;
+        if input.is_key_pressed(Key::F1, KeyRepeat::No) {
+            stats.visible = !stats.visible;
+        }
+        if input.is_key_pressed(Key::F2, KeyRepeat::No) {
+            checkerboard = !checkerboard;
+        }
+        if input.is_key_pressed(Key::F6, KeyRepeat::No) && recorder.export().is_none() {
+            crash::log_error("No frames recorded for a clip yet".to_string());
+        }
+        if input.is_key_pressed(Key::F4, KeyRepeat::No) {
+            cull_back_faces = !cull_back_faces;
+        }
+        if input.is_key_pressed(Key::F7, KeyRepeat::No) {
+            sun_view.toggle();
+        }
+        post_effects.handle_input(&input);
+        if input.is_key_pressed(Key::U, KeyRepeat::No) {
+            if input.is_key_down(Key::LeftShift) || input.is_key_down(Key::RightShift) {
+                tone_mapping.darker();
+            } else {
+                tone_mapping.brighter();
+            }
+        }
+        if input.is_key_pressed(Key::K, KeyRepeat::No) {
+            tone_mapping.next_operator();
+        }
+        if input.is_key_pressed(Key::J, KeyRepeat::No) {
+            let positions: Vec<Vec3> = (0..scene.bodies.len()).map(|body_id| sim.body_position(&scene, body_id)).collect();
+            if let Some((eye, center)) = transits.best_view(&scene, &positions, camera.eye) {
+                focus = None;
+                camera.jump_to(Pose { eye, center, ..camera.pose() });
+            }
+        }
+        if input.is_key_pressed(Key::F3, KeyRepeat::No) {
+            inspector = match inspector.take() {
+                Some(_) => None,
+                // The inspector reads its own window's input, which replays
+                // don't carry, so recorded sessions go without it.
+                None if playback.is_some() || input_recorder.is_some() => {
+                    crash::log_error("The inspector is not available while recording or replaying input".to_string());
+                    None
+                }
+                None => Inspector::open(510 + window_width as isize, 500)
+                    .map_err(|error| crash::log_error(format!("Could not open the inspector: {}", error)))
+                    .ok()
+                    .map(|mut inspector| {
+                        inspector.camera.smoothing = config.camera.smoothing;
+                        inspector
+                    }),
+            };
+        }
+
+        handle_input(&input, &mut camera, &mut mouse_look, |cursor| {
+            gallery.thumbnail_at(cursor, framebuffer_width, framebuffer_height).is_some() || orbit_gizmo.handle_at(cursor).is_some() || scrubber.contains(cursor)
+        });
+        camera.settle(delta);
+        let before_edits = Snapshot::take(&scene, &hidden);
+        let bodies_before = scene.bodies.len();
+        let mut removed = handle_selection_input(&input, &mut scene, &mut sim, &mut time);
+        let targets = selection.targets(sim.selected);
+        handle_group_input(&input, &mut scene, &targets, &mut hidden);
+        if input.is_key_pressed(Key::N, KeyRepeat::No) {
+            orbit_gizmo.toggle();
+        }
+        let orbit_edited = orbit_anchor(&scene, &sim, sim.selected)
+            .is_some_and(|(center, min_distance)| orbit_gizmo.update(&input, &mut scene.bodies[sim.selected].orbit, center, min_distance));
+        let mut picked_shot = None;
+        if let Some(line) = typed {
+            let message = match console::parse(&line) {
+                Ok(Command::Edit(edit)) => apply_edit(&mut scene, &mut hidden, &targets, edit),
+                Ok(Command::Spawn(template)) => format!("creado {}", spawn_body(&mut scene, &mut sim, template, camera.center)),
+                Ok(Command::Delete) => match delete_bodies(&mut scene, &mut sim, &targets) {
+                    Some(gone) => {
+                        removed = gone;
+                        format!("borrados {} cuerpo(s)", removed.len())
+                    }
+                    None => "no se pueden borrar todos los cuerpos".to_string(),
+                },
+                Ok(Command::WriteOrbits) => match &scene_path {
+                    Some(path) => match orbit_gizmo::write_back(path, &scene, &targets) {
+                        Ok(()) => format!("órbitas guardadas en {}", path),
+                        Err(message) => message,
+                    },
+                    None => "la escena no viene de un archivo".to_string(),
+                },
+                Ok(Command::Export(path)) => match export::save(&path, &scene, &sim, &camera) {
+                    // Read back at once, so a scene that no longer loads
+                    // (say, orbits edited into each other) is caught here.
+                    Ok(()) => match Scene::load(&path) {
+                        Ok(_) => format!("escena exportada a {}", path),
+                        Err(diagnostics) => {
+                            for diagnostic in &diagnostics {
+                                crash::log_error(diagnostic.to_string());
+                            }
+                            format!("escena exportada a {}, pero tiene {} problema(s) al cargarla", path, diagnostics.len())
+                        }
+                    },
+                    Err(message) => message,
+                },
+                Ok(Command::Shot(name)) => {
+                    let names = || scene.shots.iter().map(|shot| shot.name.as_str()).collect::<Vec<_>>().join(", ");
+                    match name {
+                        _ if scene.shots.is_empty() => "la escena no tiene tomas".to_string(),
+                        None => format!("tomas: {}", names()),
+                        Some(name) => match scene.shots.iter().position(|shot| shot.name.eq_ignore_ascii_case(&name)) {
+                            Some(shot_id) => {
+                                picked_shot = Some(shot_id);
+                                format!("toma {}", scene.shots[shot_id].name)
+                            }
+                            None => format!("toma desconocida `{}` ({})", name, names()),
+                        },
+                    }
+                }
+                Err(message) => message,
+            };
+            console.report(message);
+        }
+        if input.is_key_pressed(Key::Delete, KeyRepeat::No) {
+            match delete_bodies(&mut scene, &mut sim, &targets) {
+                Some(gone) => removed = gone,
+                None => crash::log_error("Cannot delete every body".to_string()),
+            }
+        }
+        // Checkpoints ahead of an edit show a sim that no longer follows.
+        if history.record(before_edits, &scene, &hidden, input.any_key_down() || orbit_gizmo.is_dragging()) {
+            scene_dirty = true;
+            checkpoints.forget_after(time);
+        }
+
+        // Bodies deleted: drop what was kept for them and renumber whatever
+        // pointed past them.
+        if !removed.is_empty() {
+            scene_dirty = true;
+            let remap = |body_id: usize| scene::remap(body_id, &removed);
+            for &body_id in removed.iter().rev() {
+                hidden.remove(body_id);
+                body_textures.remove(body_id);
+                surface_noise.remove(body_id);
+            }
+            ring_systems.retain_mut(|rings| remap(rings.body).map(|body_id| rings.body = body_id).is_some());
+            mesh_cache.remap_generated(remap);
+            selection.group = selection.group.iter().filter_map(|&body_id| remap(body_id)).collect();
+            (focus, ride) = (focus.and_then(remap), ride.and_then(remap));
+            if warp.is_some_and(|body_id| remap(body_id).is_none()) {
+                camera.cancel_animation();
+            }
+            warp = warp.and_then(remap);
+            overview = overview.map(|before| before.and_then(remap));
+            returning = returning.map(|before| before.and_then(remap));
+            // Shots of deleted bodies are gone, and the rest renumbered.
+            if shot.take().is_some() {
+                camera.cancel_animation();
+            }
+            transits = TransitWatch::new();
+        }
+        // Bodies and comets added at runtime get what the scene's own got
+        // at startup.
+        for body_id in hidden.len()..scene.bodies.len() {
+            let body = &scene.bodies[body_id];
+            let noise_seed = rng::noise_seed(scene.seed, body_id as u64);
+            hidden.push(false);
+            body_textures.push(None);
+            surface_noise.push((NoiseField::new(noise_seed, body.noise), VoronoiField::new(noise_seed, 1.0)));
+            if MeshRef::of(body) == MeshRef::Asteroid {
+                mesh_cache.insert_generated(body_id, asteroid::generate(scene.seed, body_id as u64));
+            }
+        }
+        for comet_id in comet_noise.len()..scene.comets.len() {
+            let noise_seed = rng::noise_seed(scene.seed, (scene.bodies.len() + comet_id) as u64);
+            comet_noise.push((NoiseField::new(noise_seed, NoisePreset::Ridged), VoronoiField::new(noise_seed, 1.0)));
+        }
+        // Undo steps and checkpoints refer to bodies by index, and a loaded
+        // save has a clock of its own.
+        if !removed.is_empty() || scene.bodies.len() != bodies_before {
+            history.clear();
+            checkpoints.clear();
+        }
+        if input.is_key_pressed(Key::F9, KeyRepeat::No) {
+            checkpoints.clear();
+        }
+        let ctrl = input.is_key_down(Key::LeftCtrl) || input.is_key_down(Key::RightCtrl);
+        if ctrl && input.is_key_pressed(Key::Z, KeyRepeat::Yes) && !history.undo(&mut scene, &mut hidden) {
+            crash::log("Nothing to undo".to_string());
+        }
+        if ctrl && input.is_key_pressed(Key::Y, KeyRepeat::Yes) && !history.redo(&mut scene, &mut hidden) {
+            crash::log("Nothing to redo".to_string());
+        }
+        // Ctrl+1-9 saves the view in that slot for this scene; Alt+1-9 flies
+        // back to it, in this run or a later one.
+        let alt = input.is_key_down(Key::LeftAlt) || input.is_key_down(Key::RightAlt);
+        if let Some(slot) = DIGIT_KEYS.iter().skip(1).position(|&key| (ctrl || alt) && input.is_key_pressed(key, KeyRepeat::No)).map(|slot| slot + 1) {
+            if ctrl {
+                bookmarks.set(&scene.name, slot, camera.pose());
+                match bookmarks.save(BOOKMARKS_PATH) {
+                    Ok(()) => crash::log(format!("View saved in slot {}", slot)),
+                    Err(error) => crash::log_error(error),
+                }
+            } else if let Some(pose) = bookmarks.get(&scene.name, slot) {
+                (focus, ride, warp, shot, follow_group, overview, returning) = (None, None, None, None, false, None, None);
+                recalling = true;
+                camera.animate_to(pose, BOOKMARK_SECONDS);
+            } else {
+                crash::log(format!("No view saved in slot {}", slot));
+            }
+        }
+
+        if input.is_key_pressed(Key::L, KeyRepeat::No) {
+            match probe::default_route(&scene) {
+                Some((origin, target)) => sim.probe = Some(Probe::launch(&scene, &sim, origin, target)),
+                None => crash::log_error("No `azul` planet with a destination for the probe".to_string()),
+            }
+        }
+        if input.is_key_pressed(Key::H, KeyRepeat::No) {
+            if input.is_key_down(Key::LeftShift) || input.is_key_down(Key::RightShift) {
+                scene.heat_palette = scene.heat_palette.next();
+            } else {
+                show_heat_map = !show_heat_map;
+            }
+        }
+        if input.is_key_pressed(Key::Equal, KeyRepeat::No) || input.is_key_pressed(Key::NumPadPlus, KeyRepeat::No) {
+            time_scale.faster();
+        }
+        if input.is_key_pressed(Key::Minus, KeyRepeat::No) || input.is_key_pressed(Key::NumPadMinus, KeyRepeat::No) {
+            time_scale.slower();
+        }
+        if input.is_key_pressed(Key::P, KeyRepeat::No) {
+            time_scale.toggle_pause();
+        }
+        // PageUp/PageDown step the clock a checkpoint back or ahead; while
+        // paused the bar under the clock scrubs through them.
+        let mut jump_to = scrubber.update(&input, checkpoints.range()).filter(|&target| target != time);
+        if input.is_key_pressed(Key::PageUp, KeyRepeat::Yes) {
+            jump_to = Some(time.saturating_sub(checkpoints::INTERVAL));
+        }
+        if input.is_key_pressed(Key::PageDown, KeyRepeat::Yes) {
+            jump_to = Some(time + checkpoints::INTERVAL);
+        }
+        let jumped = jump_to.is_some_and(|target| checkpoints.jump(target, &scene, &mut sim, &mut time));
+        if jumped {
+            selected_speed.clear();
+        }
+        if input.is_key_pressed(Key::R, KeyRepeat::No) {
+            flash_resonances = !flash_resonances;
+        }
+        if input.is_key_pressed(Key::Z, KeyRepeat::No) && !ctrl {
+            show_habitable_zone = !show_habitable_zone;
+        }
+        if input.is_key_pressed(Key::M, KeyRepeat::No) {
+            show_field_lines = !show_field_lines;
+        }
+        if input.is_key_pressed(Key::O, KeyRepeat::No) {
+            show_orbits = !show_orbits;
+        }
+        if input.is_key_pressed(Key::B, KeyRepeat::No) {
+            show_gravity_grid = !show_gravity_grid;
+        }
+        if input.is_key_pressed(Key::T, KeyRepeat::No) {
+            if input.is_key_down(Key::LeftShift) || input.is_key_down(Key::RightShift) {
+                light_time.next_slowdown();
+            } else {
+                light_time.toggle();
+            }
+        }
+        if input.is_key_pressed(Key::V, KeyRepeat::No) {
+            solar_wind.toggle();
+        }
+        if input.is_key_pressed(Key::I, KeyRepeat::No) {
+            if let Some(body) = scene.bodies.get(sim.selected) {
+                let index = sim.debris_rings.len() as u64;
+                let (flash, ring) = impact::scripted_impact(sim.selected, body.scale * SPHERE_RADIUS, scene.seed, index);
+                sim.flashes.push(flash);
+                sim.debris_rings.push(ring);
+            }
+        }
+        if input.is_key_pressed(Key::G, KeyRepeat::No) {
+            gallery.visible = !gallery.visible;
+        }
+        let chosen = gallery.clicked(&input, framebuffer_width, framebuffer_height);
+        if let Some(body_id) = chosen {
+            sim.selected = body_id;
+        }
+        // Plain left-drags orbit the camera; Shift picks a group instead.
+        let shift = input.is_key_down(Key::LeftShift) || input.is_key_down(Key::RightShift);
+        let dragged = selection.update(&input, |cursor| !shift || gallery.thumbnail_at(cursor, framebuffer_width, framebuffer_height).is_some());
+        if chosen.is_some() || input.is_key_pressed(Key::Enter, KeyRepeat::No) {
+            // Enter with a group follows all of it.
+            follow_group = chosen.is_none() && !selection.group.is_empty();
+            focus = (!follow_group).then_some(sim.selected);
+            ride = None;
+            warp = None;
+            shot = None;
+            camera.cancel_animation();
+            camera_blend = Tween::new(0.0, 1.0, 0.75, Easing::EaseInOut);
+            camera_blend_elapsed = 0.0;
+        }
+        if input.is_key_pressed(Key::F, KeyRepeat::No) {
+            ride = (ride != Some(sim.selected)).then_some(sim.selected);
+            ride_last = None;
+            warp = None;
+            shot = None;
+            camera.cancel_animation();
+            focus = None;
+            follow_group = false;
+            camera_blend = Tween::new(0.0, 1.0, 1.0, Easing::EaseInOut);
+            camera_blend_elapsed = 0.0;
+        }
+        if input.is_key_pressed(Key::C, KeyRepeat::No) {
+            let released = focus.take().is_some() | std::mem::take(&mut follow_group) | ride.take().is_some() | warp.take().is_some() | shot.take().is_some();
+            camera.cancel_animation();
+            if released {
+                // Also lets go of whatever field of view a shot set.
+                camera.fov = DEFAULT_FOV;
+            } else {
+                camera_mode = camera_mode.next();
+            }
+            camera_blend = Tween::new(0.0, 1.0, 0.75, Easing::EaseInOut);
+            camera_blend_elapsed = 0.0;
+        }
+        let framed = match focus.and_then(|body_id| scene.bodies.get(body_id).map(|body| (body_id, body))) {
+            Some((body_id, body)) => Some((sim.body_position(&scene, body_id), body.scale * SPHERE_RADIUS)),
+            None if follow_group => {
+                let bodies: Vec<(Vec3, f32)> = scene.bodies.iter().enumerate().map(|(body_id, body)| (sim.body_position(&scene, body_id), body.scale * SPHERE_RADIUS)).collect();
+                selection.bounds(&bodies)
+            }
+            None => None,
+        };
+        // Shift+1-9 warps to that planet, Shift+0 to the star.
+        if (input.is_key_down(Key::LeftShift) || input.is_key_down(Key::RightShift)) && !ctrl && !alt {
+            if let Some(digit) = DIGIT_KEYS.iter().position(|&key| input.is_key_pressed(key, KeyRepeat::No)) {
+                let target = if digit == 0 {
+                    scene.bodies.iter().position(|body| body.orbit.semi_major_axis == 0.0)
+                } else {
+                    scene.bodies.iter().enumerate().filter(|(_, body)| body.is_planet()).nth(digit - 1).map(|(body_id, _)| body_id)
+                };
+                if let Some(body_id) = target {
+                    sim.selected = body_id;
+                    warp = Some(body_id);
+                    (focus, ride, follow_group, shot) = (None, None, false, None);
+                    camera.animate_to(camera.pose(), WARP_SECONDS);
+                }
+            }
+        }
+
+        // F8 flies to the scene's next named shot; captions can cue one as
+        // they start.
+        if let Some(name) = scene.captions.cued_shot(cue_time, sim::seconds(time)) {
+            picked_shot = scene.shots.iter().position(|shot| shot.name == name);
+        }
+        cue_time = sim::seconds(time);
+        if input.is_key_pressed(Key::F8, KeyRepeat::No) && !scene.shots.is_empty() {
+            picked_shot = Some(next_shot % scene.shots.len());
+        }
+        if let Some(shot_id) = picked_shot {
+            next_shot = shot_id + 1;
+            shot = Some(shot_id);
+            (focus, ride, warp, follow_group) = (None, None, None, false);
+            camera.animate_to(camera.pose(), SHOT_SECONDS);
+        }
+        // F10 flies up over the system to show every orbit at once; F11 flies
+        // back to where the camera was and whatever it followed.
+        if input.is_key_pressed(Key::F10, KeyRepeat::No) {
+            if overview.is_none() {
+                camera.bookmark();
+                overview = Some(focus);
+            }
+            (focus, ride, warp, shot, follow_group, returning) = (None, None, None, None, false, None);
+            camera.animate_to(overview_pose(&scene, picture.aspect()), OVERVIEW_SECONDS);
+        }
+        if input.is_key_pressed(Key::F11, KeyRepeat::No) {
+            if let Some(before) = overview.take().filter(|_| camera.fly_to_bookmark(OVERVIEW_SECONDS)) {
+                (focus, ride, warp, shot, follow_group) = (None, None, None, None, false);
+                returning = Some(before);
+            }
+        }
 
-        handle_input(&window, &mut camera, &mut 0);
+        if let Some(body_id) = warp.filter(|&body_id| body_id < scene.bodies.len()) {
+            // Aimed again every frame, since the planet moves on during the flight.
+            let position = sim.body_position(&scene, body_id);
+            let direction = (camera.eye - position).try_normalize(1.0e-6).unwrap_or(Vec3::z());
+            let distance = scene.bodies[body_id].scale * SPHERE_RADIUS * FOCUS_DISTANCE;
+            camera.retarget(Pose { eye: position + direction * distance, center: position, ..camera.pose() });
+            if camera.tick(delta) {
+                warp = None;
+                focus = Some(body_id);
+                camera_blend = Tween::new(1.0, 1.0, 0.0, Easing::Linear);
+            }
+        } else if let Some(shot_id) = shot.filter(|&shot_id| shot_id < scene.shots.len()) {
+            // Aimed again every frame, since a focused body moves on.
+            camera.retarget(shot_pose(&scene, &sim, shot_id));
+            if camera.tick(delta) {
+                shot = None;
+                focus = scene.shots[shot_id].focus;
+                camera_blend = Tween::new(1.0, 1.0, 0.0, Easing::Linear);
+            }
+        } else if camera.is_animating() && (overview.is_some() || returning.is_some() || recalling) {
+            if camera.tick(delta) {
+                recalling = false;
+                if let Some(before) = returning.take() {
+                    focus = before;
+                    camera_blend = Tween::new(0.0, 1.0, 0.75, Easing::EaseInOut);
+                    camera_blend_elapsed = 0.0;
+                }
+            }
+        } else if let Some(body_id) = ride.filter(|&body_id| body_id < scene.bodies.len()) {
+            camera_blend_elapsed += delta;
+            let blend = camera_blend.value_at(camera_blend_elapsed);
+            let position = sim.body_position(&scene, body_id);
+            match scene.bodies[body_id].parent {
+                // A moon is watched from just above its planet's surface.
+                Some(parent) => {
+                    let planet = sim.body_position(&scene, parent);
+                    let planet_radius = scene.bodies[parent].scale * SPHERE_RADIUS;
+                    let toward_moon = (position - planet).try_normalize(1.0e-6).unwrap_or(Vec3::x());
+                    let from = planet + (toward_moon + Vec3::y() * 0.3).normalize() * planet_radius * RIDE_ALTITUDE;
+                    camera.watch(from, position, blend);
+                }
+                // Anything else is chased from behind along its path, or
+                // just followed while it isn't moving.
+                None => {
+                    let radius = scene.bodies[body_id].scale * SPHERE_RADIUS;
+                    let heading = ride_last.map(|last| position - last).and_then(|step| Vec3::new(step.x, 0.0, step.z).try_normalize(1.0e-6));
+                    match heading {
+                        Some(heading) => camera.watch(position + (Vec3::y() * 0.4 - heading) * radius * RIDE_DISTANCE, position, blend),
+                        None => camera.track(CameraMode::Follow, position, Vec3::zeros(), blend),
+                    }
+                }
+            }
+            ride_last = Some(position);
+        } else if let Some((position, radius)) = framed {
+            camera_blend_elapsed += delta;
+            let blend = camera_blend.value_at(camera_blend_elapsed);
+            if blend < 1.0 {
+                camera.focus(position, radius * FOCUS_DISTANCE, blend);
+            } else {
+                // Once framed, keep the offset so zooming and orbiting still work.
+                camera.track(CameraMode::Follow, position, Vec3::zeros(), 1.0);
+            }
+        } else if let Some(probe) = &sim.probe {
+            camera_blend_elapsed += delta;
+            camera.track(camera_mode, probe.position, probe.velocity, camera_blend.value_at(camera_blend_elapsed));
+        }
+
+        let (_, _, camera_up) = camera.basis();
+        let view_matrix = create_view_matrix(camera.eye, camera.center, camera_up);
+        let projection_matrix = create_perspective_matrix(camera.fov, picture.width as f32, picture.height as f32);
+        let viewport_matrix = create_viewport_matrix(picture.x as f32, picture.y as f32, picture.width as f32, picture.height as f32);
+        let screen_transform = viewport_matrix * projection_matrix * view_matrix;
+        let resonances = resonance::resonances(&scene, sim.selected);
+        // Where bodies are drawn: where they are, or where they were when
+        // the light now reaching the camera left them.
+        let retarded = light_time.enabled.then(|| light_time.retarded(&scene, &sim, camera.eye));
+        let drawn_states = retarded.as_ref().map_or(&sim.bodies[..], |(states, _)| &states[..]);
+        let light = Light::sun(&scene, drawn_states, time);
+        let drawn_positions: Vec<Vec3> = (0..scene.bodies.len()).map(|body_id| sim::world_position(&scene, drawn_states, body_id)).collect();
+        if let Some(rect) = dragged {
+            // A click on empty space lets the group go.
+            selection.group = rect.map_or_else(Vec::new, |rect| bodies_in_rect(&screen_transform, &drawn_positions, rect, &hidden));
+            if let Some(&body_id) = selection.group.first() {
+                sim.selected = body_id;
+            }
+            follow_group &= !selection.group.is_empty();
+        }
+        transits.update(&scene, &drawn_positions, camera.eye);
+        let occluders = light::occluders(&scene, &drawn_positions);
 
-        framebuffer.clear();
+        // With the sim paused, no keys held, nothing edited and the camera
+        // where it was, the scene is exactly last frame's: reuse it and only
+        // redraw the HUD.
+        let view = (camera.eye, camera.center, camera.up, camera.fov);
+        let idle = steps == 0 && !input.any_key_down() && !scene_dirty && loader.is_done() && last_view == Some(view) && !scene_layer.is_empty() && !orbit_edited && !jumped;
+        last_view = Some(view);
 
-        render_background(&mut framebuffer, &space_texture);
+        if idle {
+            framebuffer.hdr.copy_from_slice(&scene_layer);
+        } else {
+            framebuffer.clear();
 
-        let view_matrix = create_view_matrix(camera.eye, camera.center, camera.up);
-        let projection_matrix = create_perspective_matrix(window_width as f32, window_height as f32);
-        let viewport_matrix = create_viewport_matrix(framebuffer_width as f32, framebuffer_height as f32);
+            if let Some(starfield) = &starfield {
+                starfield.render(&mut framebuffer, &screen_transform, sim::seconds(time));
+            } else if let Some(texture) = &space_texture {
+                skybox::render(&mut framebuffer, texture, &(projection_matrix * view_matrix));
+            }
 
-        for (translation, scale, shader, rotation_speed, orbital_speed) in &planet_data {
-            let self_rotation = Vec3::new(0.0, time as f32 * rotation_speed, 0.0);
+            if let (true, Some(zone)) = (show_habitable_zone, &scene.habitable_zone) {
+                habitable::render_zone(&mut framebuffer, &screen_transform, zone);
+            }
 
-            let angle = time as f32 * orbital_speed;
-            let orbital_translation = Vec3::new(
-                translation.x * angle.cos() - translation.z * angle.sin(),
-                translation.y,
-                translation.x * angle.sin() + translation.z * angle.cos(),
-            );
+            // Trails are drawn before the bodies and without depth, so planets
+            // always cover them.
+            let mut trail_flashes = Vec::new();
+            if flash_resonances {
+                for resonance in &resonances {
+                    let strength = resonance::alignment(&sim, sim.selected, resonance.neighbor);
+                    if strength > 0.0 {
+                        trail_flashes.push((sim.selected, strength));
+                        trail_flashes.push((resonance.neighbor, strength));
+                    }
+                }
+            }
+            render_trails(&mut framebuffer, &screen_transform, &scene, &sim, &trail_flashes);
+
+            // Smaller on screen means a coarser mesh and fewer shader calls.
+            lod_picks.clear();
+            // The first frame has nothing to fill the other half from.
+            odd_frame = !odd_frame;
+            let interlace = checkerboard && !scene_layer.is_empty();
+            let mut level_of_detail = |center: Vec3, radius: f32, body: Option<(usize, MeshRef)>| {
+                let pixels = lod::screen_radius(&screen_transform, &camera, center, radius).unwrap_or(0.0);
+                let (detail, mut rate) = lod::pick(pixels, &config.shading);
+                if interlace && rate == ShadingRate::Full {
+                    rate = ShadingRate::Checkerboard { odd: odd_frame };
+                }
+                lod_picks.push((detail, rate));
+                let mesh = lod_meshes.as_ref().map_or(&[][..], |meshes| match body {
+                    Some((body_id, mesh)) => body_mesh(meshes, &mesh_cache, &scene, body_id, mesh, detail),
+                    None => meshes.mesh(detail, false),
+                });
+                (mesh, rate)
+            };
 
-            let model_matrix = create_model_matrix(orbital_translation, *scale, self_rotation);
-            let uniforms = Uniforms {
-                model_matrix,
+            let main_view = View {
                 view_matrix,
                 projection_matrix,
                 viewport_matrix,
-                time,
-                noise: create_noise(),
+                time: sim::seconds(time),
+                delta: sim_delta,
+                light,
+                eye: camera.eye,
+                cull_back_faces,
+                occluders: &occluders,
             };
 
-            render(&mut framebuffer, &uniforms, &vertex_arrays, *shader);
+            // Clouds, rings and atmospheres blend over whatever is behind
+            // them, so they wait until everything opaque is drawn.
+            let bodies = BodyPass { scene: &scene, states: drawn_states, hidden: &hidden, noise: &surface_noise, textures: &body_textures };
+            let (mut layers, atmospheres) = render_bodies(&mut framebuffer, &main_view, &bodies, show_heat_map, &mut level_of_detail);
+
+            if show_orbits {
+                render_orbits(&mut framebuffer, &main_view.uniforms(Mat4::identity(), &surface_noise[0]).unshadowed(), &scene, &sim);
+            }
+
+            if show_gravity_grid {
+                let uniforms = main_view.uniforms(Mat4::identity(), &surface_noise[0]).unshadowed();
+                for grid_line in gravity_grid::lines(&scene, &drawn_positions) {
+                    render_polyline(&mut framebuffer, &uniforms, &grid_line, GRID_COLOR);
+                }
+            }
+
+            render_comets(&mut framebuffer, &main_view, &scene, &sim, &comet_noise, &screen_transform, &mut level_of_detail);
+
+            if let Some(belt) = &asteroid_belt {
+                render_asteroid_belt(&mut framebuffer, &main_view, belt, &screen_transform, &camera);
+            }
+
+            if show_field_lines {
+                render_field_lines(&mut framebuffer, &main_view, &scene, &sim, &surface_noise);
+            }
+
+            solar_wind.stream.render(&mut framebuffer, &screen_transform);
+
+            for ring in &sim.debris_rings {
+                ring.render(&mut framebuffer, &screen_transform, sim.body_position(&scene, ring.body));
+            }
+            for flash in &sim.flashes {
+                flash.render(&mut framebuffer, &screen_transform, sim.body_position(&scene, flash.body));
+            }
+            sim.flares.render(&mut framebuffer, &screen_transform, |star| (!hidden[star]).then(|| sim.body_position(&scene, star)));
+
+            if let Some(probe) = &sim.probe {
+                render_probe(&mut framebuffer, &main_view, probe, &surface_noise[probe.origin], &screen_transform, &mut level_of_detail);
+            }
+
+            let shell_mesh = lod_meshes.as_ref().map_or(&[][..], |meshes| meshes.mesh(Detail::Medium, false));
+            render_coronas(&mut framebuffer, &main_view, &bodies, &sim, shell_mesh);
+
+            sort_back_to_front(&mut layers, camera.eye, |layer| layer.model_matrix().column(3).xyz());
+            render_layers(&mut framebuffer, &main_view, &bodies, &layers, &ring_systems, &accretion_disk, &screen_transform);
+            render_atmospheres(&mut framebuffer, &main_view, &atmospheres, &surface_noise);
+
+            framebuffer.resolve_deferred(&scene_layer);
+
+            for (body_id, body) in scene.bodies.iter().enumerate() {
+                let Some(einstein_radius) = body.lensing else {
+                    continue;
+                };
+                let position = drawn_positions[body_id];
+                let edge = position + camera.up.normalize() * body.scale * SPHERE_RADIUS * einstein_radius;
+                if let (Some(center), Some(edge)) = (project_to_screen(&screen_transform, position), project_to_screen(&screen_transform, edge)) {
+                    lensing::apply(&mut framebuffer, center, (edge - center).xy().magnitude());
+                }
+            }
+
+            let motion_blur = time_scale.motion_blur();
+            if motion_blur > 0.0 && scene_layer.len() == framebuffer.hdr.len() {
+                framebuffer.blend_frame(&scene_layer, motion_blur);
+            }
+            scene_layer.clone_from(&framebuffer.hdr);
+        }
+        post_effects.apply(&mut framebuffer);
+        framebuffer.tone_map(&tone_mapping);
+        picture.cover(&mut framebuffer);
+
+        if let (true, Some(meshes), Some(body)) = (sun_view.visible, &lod_meshes, scene.bodies.get(sim.selected)) {
+            if sun_view.aim(sim.body_position(&scene, sim.selected), body.scale * SPHERE_RADIUS) {
+                render_sun_view(&mut sun_view, &scene, &sim, meshes, &mesh_cache, &surface_noise, &body_textures, time, sim_delta, cull_back_faces, &tone_mapping);
+                sun_view.draw(&mut framebuffer, &format!("{} -> Sol (F7)", body.name));
+            }
         }
 
+        if let Some(probe) = &sim.probe {
+            // The probe is a few pixels wide at most; bracket it so it can be found.
+            if let Some(screen) = project_to_screen(&screen_transform, probe.position) {
+                draw_marker(&mut framebuffer, screen.x, screen.y, 6.0, 0xFFAA50);
+            }
+
+            let status = match probe.phase() {
+                Some(phase) => format!("fase    {} {:.0}%", phase.name(), probe.timeline.elapsed / probe.timeline.duration() * 100.0),
+                None => "fase    en órbita".to_string(),
+            };
+            draw_anchored_panel(&mut framebuffer, Anchor::TopRight, &[
+                format!("Sonda {} -> {}", scene.bodies[probe.origin].name, scene.bodies[probe.target].name),
+                status,
+                format!("cámara  {} (C)", camera_mode.name()),
+            ]);
+        }
+
+        if let (true, Some(meshes)) = (gallery.visible, &lod_meshes) {
+            let bodies: Vec<(Vec3, f32)> = scene
+                .bodies
+                .iter()
+                .enumerate()
+                .map(|(body_id, body)| (sim.body_position(&scene, body_id), body.scale * SPHERE_RADIUS))
+                .collect();
+            gallery.aim(&bodies, &light, framebuffer_width);
+            for (body_id, thumbnail) in gallery.thumbnails.iter_mut().enumerate() {
+                let body = &scene.bodies[body_id];
+                let mesh = body_mesh(meshes, &mesh_cache, &scene, body_id, MeshRef::of(body), Detail::Medium);
+                let (noise, texture) = (&surface_noise[body_id], body_textures[body_id].as_ref());
+                render_close_up(&mut thumbnail.target, &thumbnail.camera, &scene, &sim, body_id, mesh, noise, texture, gallery.spin, time, cull_back_faces, &tone_mapping);
+            }
+            gallery.draw(&mut framebuffer, sim.selected);
+        }
+
+        if retarded.is_some() {
+            // Ghosts bracket where each body really is, away from where it is drawn.
+            for (body_id, body) in scene.bodies.iter().enumerate() {
+                let position = sim.body_position(&scene, body_id);
+                let edge = position + camera.up.normalize() * body.scale * SPHERE_RADIUS;
+                let apparent = sim::world_position(&scene, drawn_states, body_id);
+                let screen = |point| project_to_screen(&screen_transform, point);
+                if let (Some(center), Some(edge), Some(drawn)) = (screen(position), screen(edge), screen(apparent)) {
+                    if (center - drawn).magnitude() > 2.0 {
+                        draw_marker(&mut framebuffer, center.x, center.y, (edge - center).magnitude() + 2.0, light_time::GHOST_COLOR);
+                    }
+                }
+            }
+        }
+
+        let mut bottom_left = Stack::new(Anchor::BottomLeft, 6);
+        if let Some(body) = scene.bodies.get(sim.selected) {
+            let position = sim::world_position(&scene, drawn_states, sim.selected);
+            let edge = position + camera.up.normalize() * body.scale * SPHERE_RADIUS;
+            if let (Some(center), Some(edge)) = (project_to_screen(&screen_transform, position), project_to_screen(&screen_transform, edge)) {
+                let half_size = (edge - center).magnitude() + 4.0;
+                draw_marker(&mut framebuffer, center.x, center.y, half_size, 0x66FF66);
+            }
+            for &body_id in selection.group.iter().filter(|&&body_id| body_id != sim.selected) {
+                let position = drawn_positions[body_id];
+                let edge = position + camera.up.normalize() * scene.bodies[body_id].scale * SPHERE_RADIUS;
+                if let (Some(center), Some(edge)) = (project_to_screen(&screen_transform, position), project_to_screen(&screen_transform, edge)) {
+                    draw_marker(&mut framebuffer, center.x, center.y, (edge - center).magnitude() + 4.0, selection::GROUP_COLOR);
+                }
+            }
+
+            let mut lines = vec![
+                format!("{} [{}/{}]", body.name, sim.selected + 1, scene.bodies.len()),
+                format!("órbita   {:+.3} rad/s  {}", body.orbit.mean_motion * STEPS_PER_SECOND, direction_label(body.is_retrograde_orbit())),
+                format!("rotación {:+.3} rad/s  {}", body.rotation_speed * STEPS_PER_SECOND, direction_label(body.is_retrograde_rotation())),
+            ];
+            if body.orbit.semi_major_axis > 0.0 {
+                lines.push(format!("flujo    {:.2} S", heatmap::relative_flux(position.magnitude(), scene.au)));
+                if scene.habitable_zone.is_some_and(|zone| zone.contains(position.magnitude())) {
+                    lines.push("en la zona habitable".to_string());
+                }
+            }
+            for resonance in &resonances {
+                lines.push(format!(
+                    "resonancia {}:{} con {} ({:.1}%)",
+                    resonance.ratio.0,
+                    resonance.ratio.1,
+                    scene.bodies[resonance.neighbor].name,
+                    resonance.error * 100.0
+                ));
+            }
+            if let Some((_, delays)) = &retarded {
+                lines.push(format!("luz      {:.1} s de retraso", sim::seconds(delays[sim.selected].round() as u32)));
+            }
+            if let Some(body_id) = ride {
+                let label = match scene.bodies[body_id].parent {
+                    Some(parent) => format!("cámara   mirando a {} desde {} (F)", scene.bodies[body_id].name, scene.bodies[parent].name),
+                    None => format!("cámara   a bordo de {} (F)", scene.bodies[body_id].name),
+                };
+                lines.push(label);
+            }
+            draw_stacked_panel(&mut framebuffer, &mut bottom_left, &lines);
+        }
+        if selection.group.len() > 1 {
+            let hidden_count = selection.group.iter().filter(|&&body_id| hidden[body_id]).count();
+            draw_stacked_panel(&mut framebuffer, &mut bottom_left, &[
+                format!("grupo: {} cuerpos ({} ocultos)", selection.group.len(), hidden_count),
+                format!("X ocultar  0-9 shader  Enter {}", if follow_group { "siguiendo" } else { "seguir" }),
+            ]);
+        }
+
+        let mut time_label = if time_scale.is_ramping() {
+            format!("tiempo x{:.2} -> x{}", time_scale.current, time_scale.target())
+        } else {
+            format!("tiempo x{}", time_scale.current)
+        };
+        if time_scale.paused {
+            time_label.push_str(" en pausa (P)");
+        }
+        let mut top_lines = vec![time_label];
+        if light_time.enabled {
+            top_lines.push(light_time.label());
+        }
+        if let Some(event) = transits.notification() {
+            top_lines.push(format!("{} (J para verlo)", event.describe(&scene)));
+        }
+        if orbit_gizmo.enabled {
+            top_lines.push("Edición de órbitas: arrastra las manijas (N para salir)".to_string());
+        }
+        if overview.is_some() {
+            top_lines.push("Vista general (F11 para volver)".to_string());
+        }
+        if let Some(shot) = shot.and_then(|shot_id| scene.shots.get(shot_id)) {
+            top_lines.push(format!("Toma: {} (F8 la siguiente, C suelta)", shot.name));
+        }
+        let mut top_center = Stack::new(Anchor::TopCenter, 6);
+        draw_stacked_panel(&mut framebuffer, &mut top_center, &top_lines);
+        let scrubber_at = time_scale.paused.then(|| top_center.place(&framebuffer, Scrubber::size()));
+        let scrub_label = format!("día {:.0}  arrastra para volver (RePág/AvPág)", sim::seconds(time) / scene.day_length);
+        scrubber.draw(&mut framebuffer, scrubber_at, &checkpoints, time, &scrub_label);
+
+        draw_captions(&mut framebuffer, &scene.captions.visible(sim::seconds(time)), gallery.height());
+
+        if let Some(sun) = light::sun_id(&scene).filter(|&sun| scene.bodies[sun].variability.is_some()) {
+            let label = format!("Brillo de {} {:.2}", scene.bodies[sun].name, light_curve.latest().unwrap_or(1.0));
+            let (x, y) = bottom_left.place(&framebuffer, light_curve.size());
+            light_curve.draw(&mut framebuffer, x, y, &label);
+        }
+
+        if show_heat_map {
+            heatmap::draw_legend(&mut framebuffer, scene.heat_palette);
+        }
+
+        let bodies: Vec<(Vec3, f32)> = scene.bodies.iter().zip(&drawn_positions).map(|(body, &position)| (position, body.scale * SPHERE_RADIUS)).collect();
+        let hovered = input
+            .mouse
+            .filter(|_| !selection.is_dragging())
+            .and_then(|cursor| body_under_cursor(&screen_transform, &camera, &bodies, cursor))
+            .filter(|&body_id| !hidden[body_id]);
+        selection.draw(&mut framebuffer);
+        let edited_orbit = orbit_anchor(&scene, &sim, sim.selected).map(|(center, _)| (&scene.bodies[sim.selected].orbit, center));
+        orbit_gizmo.draw(&mut framebuffer, &screen_transform, edited_orbit);
+        console.draw(&mut framebuffer);
+        if let (Some(body_id), Some(cursor)) = (hover.update(hovered), input.mouse) {
+            let distance = (drawn_positions[body_id] - camera.eye).magnitude() / scene.au;
+            draw_tooltip(&mut framebuffer, cursor, &[scene.bodies[body_id].name.clone(), format!("a {:.2} UA de la cámara", distance)]);
+        }
+
+        if !loader.is_done() {
+            render_loading_screen(&mut framebuffer, loader.progress(), !loader.errors().is_empty());
+        }
+
+        let memory_report = MemoryReport {
+            meshes: lod_meshes.as_ref().map_or(0, |meshes| meshes.memory_bytes())
+                + mesh_cache.memory_bytes()
+                + ring_systems.iter().map(|rings| rings.mesh.memory_bytes()).sum::<usize>(),
+            textures: space_texture.as_ref().map_or(0, |texture| texture.memory_bytes())
+                + body_textures.iter().flatten().map(|texture| texture.memory_bytes()).sum::<usize>(),
+            framebuffers: framebuffer.memory_bytes() + scene_layer.capacity() * std::mem::size_of::<Vec3>() + recorder.memory_bytes(),
+            particles: sim
+                .comets
+                .iter()
+                .map(|comet| comet.ion_tail.memory_bytes() + comet.dust_tail.memory_bytes())
+                .sum::<usize>()
+                + sim.probe.as_ref().map_or(0, |probe| probe.exhaust.memory_bytes())
+                + sim.flashes.iter().map(|flash| flash.sparks.memory_bytes()).sum::<usize>()
+                + sim.flares.flares.iter().map(|flare| flare.plasma.memory_bytes()).sum::<usize>()
+                + sim.debris_rings.iter().map(|ring| ring.memory_bytes()).sum::<usize>()
+                + ring_systems.iter().map(|rings| rings.memory_bytes()).sum::<usize>()
+                + solar_wind.stream.memory_bytes(),
+        };
+        let mut stats_lines = memory_report.lines();
+        let count = |wanted: Detail| lod_picks.iter().filter(|(detail, _)| *detail == wanted).count();
+        stats_lines.push(format!(
+            "LOD    {}/{}/{} media res {}",
+            count(Detail::Full),
+            count(Detail::Medium),
+            count(Detail::Low),
+            lod_picks.iter().filter(|(_, rate)| *rate == ShadingRate::Half).count()
+        ));
+        if !cull_back_faces {
+            stats_lines.push("sin descarte de caras (F4)".to_string());
+        }
+        if checkerboard {
+            stats_lines.push("tablero de ajedrez (F2)".to_string());
+        }
+        if idle {
+            stats_lines.push("escena  en cache".to_string());
+        }
+        stats_lines.push(format!("tono    {} (K, U, Shift+U)", tone_mapping.label()));
+        for effect in post_effects.enabled() {
+            stats_lines.push(format!("post    {}", effect));
+        }
+        let speed_label = format!("{} {:.4} UA/día", scene.bodies.get(sim.selected).map_or("", |body| &body.name), selected_speed.latest().unwrap_or(0.0));
+        stats.draw(&mut framebuffer, &stats_lines, &[(&selected_speed, speed_label.as_str())]);
+        recorder.record(&framebuffer.buffer, framebuffer_width, &picture);
+        window_title.update(&mut window, &TitleValues {
+            scene: &scene.name,
+            fps: stats.fps(),
+            day: sim::seconds(time) / scene.day_length,
+            speed: time_scale.current,
+            paused: time_scale.paused,
+        });
+
         window
             .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
             .unwrap();
 
-        std::thread::sleep(frame_delay);
+        if inspector.as_ref().is_some_and(|inspector| !inspector.window.is_open()) {
+            inspector = None;
+        }
+        if let (Some(inspector), Some(meshes)) = (&mut inspector, &lod_meshes) {
+            handle_input(&InputFrame::capture(&inspector.window), &mut inspector.camera, &mut inspector_mouse_look, |_| false);
+            inspector.camera.settle(delta);
+            let texture = body_textures.get(sim.selected).and_then(Option::as_ref);
+            let body = &scene.bodies[sim.selected];
+            let mesh = body_mesh(meshes, &mesh_cache, &scene, sim.selected, MeshRef::of(body), Detail::Full);
+            render_inspector(inspector, &scene, &sim, mesh, &surface_noise[sim.selected], texture, time, cull_back_faces, &tone_mapping);
+        }
+
+        clock.wait();
+    }
+
+    recorder.finish();
+    if let Some(input_recorder) = input_recorder {
+        input_recorder.finish(frame);
     }
 }
 
-fn load_texture(path: &str) -> DynamicImage {
-    open(path).expect("Failed to load texture")
+// The bodies one view draws from, and how each looks.
+struct BodyPass<'a> {
+    scene: &'a Scene,
+    // Where each body is drawn, which may lag where it is.
+    states: &'a [BodyState],
+    hidden: &'a [bool],
+    noise: &'a [(NoiseField, VoronoiField)],
+    textures: &'a [Option<Texture>],
 }
 
-fn render_background(framebuffer: &mut Framebuffer, texture: &DynamicImage) {
-    let texture = texture.to_rgb8();
-    let (texture_width, texture_height) = texture.dimensions();
+// A body's atmosphere shell: its model matrix, the body and the glow color.
+type AtmosphereNode<'a> = (Mat4, usize, Color, &'a [Vertex]);
 
-    for y in 0..framebuffer.height {
-        for x in 0..framebuffer.width {
-            let tx = (x as u32 * texture_width / framebuffer.width as u32) as u32;
-            let ty = (y as u32 * texture_height / framebuffer.height as u32) as u32;
+// Every body's opaque surface. Clouds and rings are handed back as layers to
+// blend later, and atmospheres to add last of all.
+fn render_bodies<'m>(
+    framebuffer: &mut Framebuffer,
+    view: &View,
+    bodies: &BodyPass,
+    show_heat_map: bool,
+    level_of_detail: &mut impl FnMut(Vec3, f32, Option<(usize, MeshRef)>) -> (&'m [Vertex], ShadingRate),
+) -> (Vec<Layer<'m>>, Vec<AtmosphereNode<'m>>) {
+    let scene = bodies.scene;
+    let mut layers = Vec::new();
+    let mut atmospheres = Vec::new();
+    SceneNode::from_scene(scene, bodies.states).walk(&Mat4::identity(), &mut |model_matrix, drawable| {
+        let body_id = drawable.body;
+        let body = &scene.bodies[body_id];
+        if bodies.hidden[body_id] {
+            return;
+        }
+        if drawable.mesh == MeshRef::Rings {
+            layers.push(Layer::Rings { model_matrix: *model_matrix, body: body_id });
+            return;
+        }
+        let orbital_translation = model_matrix.column(3).xyz();
+        let (mesh, rate) = level_of_detail(orbital_translation, body.scale * SPHERE_RADIUS, Some((body_id, drawable.mesh)));
+        // Shells use the same detail as the body, so its facets never
+        // poke through them.
+        if body.clouds {
+            let model_matrix = model_matrix * scaling(&Vec3::repeat(1.0 + clouds::CLOUD_HEIGHT));
+            layers.push(Layer::Clouds { model_matrix, body: body_id, mesh });
+        }
+        if let Some(color) = body.atmosphere {
+            atmospheres.push((*model_matrix, body_id, color, mesh));
+        }
+        if is_black_hole(drawable.shader) {
+            layers.push(Layer::AccretionDisk { model_matrix: *model_matrix, body: body_id });
+        }
 
-            let pixel = texture.get_pixel(tx, ty);
-            let color = (pixel[0] as u32) << 16 | (pixel[1] as u32) << 8 | (pixel[2] as u32);
+        let uniforms = view.uniforms(*model_matrix, &bodies.noise[body_id]).with_texture(bodies.textures[body_id].as_ref()).with_material(body.material);
 
-            framebuffer.set_current_color(color);
-            framebuffer.point(x, y, 1.0);
+        if show_heat_map {
+            let to_sun = (-orbital_translation).try_normalize(1.0e-6).unwrap_or(Vec3::zeros());
+            let flux = heatmap::relative_flux(orbital_translation.magnitude(), scene.au);
+            render(framebuffer, &uniforms, mesh, rate, |fragment| {
+                // The sun itself is drawn at full brightness.
+                if body.orbit.semi_major_axis == 0.0 {
+                    scene.heat_palette.sample(1.0)
+                } else {
+                    heatmap::heat_shader(fragment, to_sun, flux, scene.heat_palette)
+                }
+            });
+        } else if body.clouds || has_oceans(drawable.shader) {
+            // The sun at the origin and the camera, seen from the body's own frame.
+            let to_sun = (mat4_to_mat3(model_matrix).transpose() * -orbital_translation).try_normalize(1.0e-6).unwrap_or(Vec3::y());
+            let eye = (model_matrix.try_inverse().unwrap_or(Mat4::identity()) * view.eye.push(1.0)).xyz();
+            let oceans = has_oceans(drawable.shader);
+            let cap_edges = ice::cap_edges(to_sun);
+            render(framebuffer, &uniforms, mesh, rate, |fragment| {
+                let mut color = fragment_shader(fragment, &uniforms, drawable.shader);
+                let mut ice = 0.0;
+                if oceans {
+                    ice = ice::cover(uniforms.noise, fragment.vertex_position, cap_edges);
+                    color = ice::apply(color, ice, fragment.intensity);
+                }
+                let mut clear_sky = 1.0;
+                if body.clouds {
+                    color = clouds::shadowed(color, uniforms.noise, fragment.vertex_position, to_sun, uniforms.time);
+                    clear_sky -= clouds::coverage(uniforms.noise, fragment.vertex_position, uniforms.time);
+                }
+                let water = ocean(fragment, &uniforms, drawable.shader) * (1.0 - ice) * clear_sky;
+                if water > 0.0 {
+                    let normal = fragment.vertex_position.normalize();
+                    let to_eye = (eye - fragment.vertex_position).try_normalize(1.0e-6).unwrap_or(normal);
+                    color = glint::apply(color, water * glint::strength(normal, to_sun, to_eye));
+                }
+                color
+            });
+        } else {
+            render(framebuffer, &uniforms, mesh, rate, |fragment| fragment_shader(fragment, &uniforms, drawable.shader));
         }
+    });
+    (layers, atmospheres)
+}
+
+fn render_comets<'m>(
+    framebuffer: &mut Framebuffer,
+    view: &View,
+    scene: &Scene,
+    sim: &Simulation,
+    comet_noise: &[(NoiseField, VoronoiField)],
+    screen_transform: &Mat4,
+    level_of_detail: &mut impl FnMut(Vec3, f32, Option<(usize, MeshRef)>) -> (&'m [Vertex], ShadingRate),
+) {
+    for (comet_id, (comet, desc)) in sim.comets.iter().zip(&scene.comets).enumerate() {
+        let model_matrix = create_model_matrix(comet.position, desc.scale, Vec3::new(0.0, comet.mean_anomaly * 40.0, 0.0));
+        let (mesh, rate) = level_of_detail(comet.position, desc.scale * SPHERE_RADIUS, None);
+        let uniforms = view.uniforms(model_matrix, &comet_noise[comet_id]);
+        render(framebuffer, &uniforms, mesh, rate, |fragment| fragment_shader(fragment, &uniforms, comet::NUCLEUS_SHADER));
+
+        comet.dust_tail.render(framebuffer, screen_transform);
+        comet.ion_tail.render(framebuffer, screen_transform);
     }
 }
 
+fn render_asteroid_belt(framebuffer: &mut Framebuffer, view: &View, (belt, noise): &(AsteroidBelt, (NoiseField, VoronoiField)), screen_transform: &Mat4, camera: &Camera) {
+    // Rocks off screen are dropped before any of their vertices are touched.
+    let (width, height) = (framebuffer.width as f32, framebuffer.height as f32);
+    let on_screen = |center: Vec3, radius: f32| {
+        let Some(pixels) = lod::screen_radius(screen_transform, camera, center, radius) else {
+            return false;
+        };
+        project_to_screen(screen_transform, center).is_some_and(|screen| {
+            screen.x > -pixels && screen.y > -pixels && screen.x < width + pixels && screen.y < height + pixels
+        })
+    };
+    let uniforms = view.uniforms(Mat4::identity(), noise);
+    let instances = belt.instances(view.time, on_screen);
+    render_instances(framebuffer, &uniforms, &belt.mesh, &instances, |fragment| fragment_shader(fragment, &uniforms, belt::ROCK_SHADER));
+}
 
+fn render_field_lines(framebuffer: &mut Framebuffer, view: &View, scene: &Scene, sim: &Simulation, surface_noise: &[(NoiseField, VoronoiField)]) {
+    for (body_id, body) in scene.bodies.iter().enumerate().filter(|(_, body)| body.magnetosphere) {
+        let center = sim.body_position(scene, body_id);
+        let uniforms = view.uniforms(Mat4::identity(), &surface_noise[body_id]).unshadowed();
+        // The sun sits at the origin.
+        for field_line in magnetosphere::field_lines(center, body.scale * SPHERE_RADIUS, Vec3::zeros()) {
+            render_polyline(framebuffer, &uniforms, &field_line.points, field_line.color);
+        }
+    }
+}
 
-fn handle_input(window: &Window, camera: &mut Camera, current_shader: &mut u8) {
-    let movement_speed = 1.0;
-    let rotation_speed = PI / 50.0;
-    let zoom_speed = 0.1;
+fn render_atmospheres(framebuffer: &mut Framebuffer, view: &View, atmospheres: &[AtmosphereNode], surface_noise: &[(NoiseField, VoronoiField)]) {
+    for &(model_matrix, body_id, color, mesh) in atmospheres {
+        let model_matrix = model_matrix * scaling(&Vec3::repeat(atmosphere::SHELL_SCALE));
+        let to_local = model_matrix.try_inverse().unwrap_or(Mat4::identity());
+        let eye = (to_local * view.eye.push(1.0)).xyz();
+        let to_sun = (mat4_to_mat3(&model_matrix).transpose() * -model_matrix.column(3).xyz()).try_normalize(1.0e-6).unwrap_or(Vec3::y());
+        let uniforms = view.uniforms(model_matrix, &surface_noise[body_id]).with_culling(false);
+        render_translucent(framebuffer, &uniforms, mesh, BlendMode::Additive, |fragment| {
+            let to_eye = (eye - fragment.vertex_position).try_normalize(1.0e-6)?;
+            atmosphere::shade(fragment, color, to_eye, to_sun)
+        });
+    }
+}
 
-    if window.is_key_down(Key::Key0) {
-        *current_shader = 0;
+fn render_coronas(framebuffer: &mut Framebuffer, view: &View, bodies: &BodyPass, sim: &Simulation, shell_mesh: &[Vertex]) {
+    let scene = bodies.scene;
+    for (body_id, body) in scene.bodies.iter().enumerate().filter(|&(body_id, body)| body.orbit.semi_major_axis == 0.0 && !is_black_hole(body.shader) && !bodies.hidden[body_id]) {
+        let center = sim.body_position(scene, body_id);
+        let luminosity = body.variability.as_ref().map_or(1.0, |variability| variability.luminosity(view.time));
+        for mut shell in corona::shells(view.time) {
+            shell.strength *= luminosity;
+            let model_matrix = create_model_matrix(center, body.scale * shell.radius, Vec3::zeros());
+            let eye = (model_matrix.try_inverse().unwrap_or(Mat4::identity()) * view.eye.push(1.0)).xyz();
+            let uniforms = view.uniforms(model_matrix, &bodies.noise[body_id]).with_culling(true).unshadowed();
+            render_translucent(framebuffer, &uniforms, shell_mesh, BlendMode::Additive, |fragment| {
+                let to_eye = (eye - fragment.vertex_position).try_normalize(1.0e-6)?;
+                corona::shade(fragment, uniforms.noise, &shell, to_eye, uniforms.time)
+            });
+        }
     }
-    if window.is_key_down(Key::Key1) {
-        *current_shader = 1;
+}
+
+// Clouds, rings and accretion disks, in the order given.
+fn render_layers(
+    framebuffer: &mut Framebuffer,
+    view: &View,
+    bodies: &BodyPass,
+    layers: &[Layer],
+    ring_systems: &[RingSystem],
+    accretion_disk: &[Vertex],
+    screen_transform: &Mat4,
+) {
+    for layer in layers {
+        let (model_matrix, body_id) = (*layer.model_matrix(), layer.body());
+        let uniforms = view.uniforms(model_matrix, &bodies.noise[body_id]).with_culling(false);
+        match layer {
+            Layer::Clouds { mesh, .. } => {
+                render_translucent(framebuffer, &uniforms, mesh, BlendMode::Alpha, |fragment| clouds::shade(fragment, uniforms.noise, uniforms.time));
+            }
+            Layer::Rings { .. } => {
+                let Some(rings) = ring_systems.iter().find(|rings| rings.body == body_id) else {
+                    continue;
+                };
+                render_translucent(framebuffer, &uniforms, &rings.mesh, BlendMode::Alpha, |fragment| ring_shader(fragment, &uniforms, rings));
+                let center = model_matrix.column(3).xyz();
+                let body_radius = bodies.scene.bodies[body_id].scale * SPHERE_RADIUS;
+                if (view.eye - center).magnitude() < rings::PARTICLE_RANGE * rings.desc.outer * body_radius {
+                    rings.render_particles(framebuffer, &(screen_transform * model_matrix), view.time, rings::light(&model_matrix));
+                }
+            }
+            Layer::AccretionDisk { .. } => {
+                let eye = (model_matrix.try_inverse().unwrap_or(Mat4::identity()) * view.eye.push(1.0)).xyz();
+                render_translucent(framebuffer, &uniforms, accretion_disk, BlendMode::Additive, |fragment| {
+                    black_hole::shade(fragment, uniforms.noise, eye - fragment.vertex_position, uniforms.time)
+                });
+            }
+        }
     }
-    if window.is_key_down(Key::Key2) {
-        *current_shader = 2;
+}
+
+fn render_probe<'m>(
+    framebuffer: &mut Framebuffer,
+    view: &View,
+    probe: &Probe,
+    noise: &(NoiseField, VoronoiField),
+    screen_transform: &Mat4,
+    level_of_detail: &mut impl FnMut(Vec3, f32, Option<(usize, MeshRef)>) -> (&'m [Vertex], ShadingRate),
+) {
+    let model_matrix = create_model_matrix(probe.position, probe::PROBE_SCALE, Vec3::new(0.0, view.time * 6.0, 0.0));
+    let (mesh, rate) = level_of_detail(probe.position, probe::PROBE_SCALE * SPHERE_RADIUS, None);
+    let uniforms = view.uniforms(model_matrix, noise);
+    render(framebuffer, &uniforms, mesh, rate, |fragment| fragment_shader(fragment, &uniforms, probe::PROBE_SHADER));
+    probe.exhaust.render(framebuffer, screen_transform);
+}
+
+// A body's own model has a single level and stands in as the sphere until
+// it has loaded.
+fn body_mesh<'a>(meshes: &'a LodMeshes, cache: &'a MeshCache, scene: &Scene, body_id: usize, mesh: MeshRef, detail: Detail) -> &'a [Vertex] {
+    let body = &scene.bodies[body_id];
+    match mesh {
+        MeshRef::Model => body.mesh.as_deref().and_then(|path| cache.get(path)).unwrap_or_else(|| meshes.mesh(detail, false)),
+        MeshRef::Asteroid => cache.get_generated(body_id).unwrap_or_else(|| meshes.mesh(detail, false)),
+        MeshRef::TexturedSphere => meshes.mesh(detail, true),
+        MeshRef::Sphere => meshes.mesh(detail, false),
+        // Ring meshes belong to their RingSystem.
+        MeshRef::Rings => &[],
     }
-    if window.is_key_down(Key::Key3) {
-        *current_shader = 3;
+}
+
+// Close-up of the selected body in the inspector window, drawn with the
+// full detail mesh regardless of the main view's LOD.
+#[allow(clippy::too_many_arguments)]
+fn render_inspector(
+    inspector: &mut Inspector,
+    scene: &Scene,
+    sim: &Simulation,
+    mesh: &[Vertex],
+    noise: &(NoiseField, VoronoiField),
+    texture: Option<&Texture>,
+    time: u32,
+    cull_back_faces: bool,
+    tone_mapping: &ToneMapping,
+) {
+    let Some(body) = scene.bodies.get(sim.selected) else {
+        return;
+    };
+    let position = sim.body_position(scene, sim.selected);
+    inspector.follow(sim.selected, position, body.scale * SPHERE_RADIUS, &Light::sun(scene, &sim.bodies, time));
+    render_close_up(&mut inspector.target, &inspector.camera, scene, sim, sim.selected, mesh, noise, texture, 0.0, time, cull_back_faces, tone_mapping);
+
+    let mut lines = vec![
+        body.name.clone(),
+        format!("radio     {:.3}", body.scale * SPHERE_RADIUS),
+    ];
+    if body.orbit.semi_major_axis > 0.0 {
+        lines.push(format!("distancia {:.2} UA", position.magnitude() / scene.au));
     }
-    if window.is_key_down(Key::Key4) {
-        *current_shader = 4;
+    let framebuffer = &mut inspector.target.framebuffer;
+    draw_anchored_panel(framebuffer, Anchor::BottomLeft, &lines);
+
+    if let Err(error) = inspector.window.update_with_buffer(&framebuffer.buffer, framebuffer.width, framebuffer.height) {
+        crash::log_error(error.to_string());
     }
-    if window.is_key_down(Key::Key5) {
-        *current_shader = 5;
+}
+
+// One body on its own, seen from `camera`, into an offscreen target. `spin`
+// turns it further than the simulation has.
+#[allow(clippy::too_many_arguments)]
+fn render_close_up(
+    target: &mut RenderTarget,
+    camera: &Camera,
+    scene: &Scene,
+    sim: &Simulation,
+    body_id: usize,
+    mesh: &[Vertex],
+    noise: &(NoiseField, VoronoiField),
+    texture: Option<&Texture>,
+    spin: f32,
+    time: u32,
+    cull_back_faces: bool,
+    tone_mapping: &ToneMapping,
+) {
+    let body = &scene.bodies[body_id];
+    let (width, height) = (target.width() as f32, target.height() as f32);
+    let positions: Vec<Vec3> = (0..scene.bodies.len()).map(|body_id| sim.body_position(scene, body_id)).collect();
+    let occluders = light::occluders(scene, &positions);
+    let view = View {
+        view_matrix: create_view_matrix(camera.eye, camera.center, camera.up),
+        projection_matrix: create_perspective_matrix(camera.fov, width, height),
+        viewport_matrix: create_viewport_matrix(0.0, 0.0, width, height),
+        time: sim::seconds(time),
+        // A still, with no frame before it to move on from.
+        delta: 0.0,
+        light: Light::sun(scene, &sim.bodies, time),
+        eye: camera.eye,
+        cull_back_faces,
+        occluders: &occluders,
+    };
+    let model_matrix = create_model_matrix(sim.body_position(scene, body_id), body.scale, Vec3::new(0.0, sim.bodies[body_id].spin_angle + spin, body.axial_tilt));
+    let uniforms = view.uniforms(model_matrix, noise).with_texture(texture).with_material(body.material);
+
+    target.framebuffer.clear();
+    render(&mut target.framebuffer, &uniforms, mesh, ShadingRate::Full, |fragment| fragment_shader(fragment, &uniforms, body.shader));
+    target.framebuffer.tone_map(tone_mapping);
+}
+
+// Every body as seen from the sun view's camera, at medium detail and with
+// the plain surface shaders; rings and the translucent layers are left to
+// the main view.
+#[allow(clippy::too_many_arguments)]
+fn render_sun_view(
+    sun_view: &mut SunView,
+    scene: &Scene,
+    sim: &Simulation,
+    meshes: &LodMeshes,
+    mesh_cache: &MeshCache,
+    surface_noise: &[(NoiseField, VoronoiField)],
+    body_textures: &[Option<Texture>],
+    time: u32,
+    delta: f32,
+    cull_back_faces: bool,
+    tone_mapping: &ToneMapping,
+) {
+    let (width, height) = (sun_view.target.width() as f32, sun_view.target.height() as f32);
+    let camera = &sun_view.camera;
+    let positions: Vec<Vec3> = (0..scene.bodies.len()).map(|body_id| sim.body_position(scene, body_id)).collect();
+    let occluders = light::occluders(scene, &positions);
+    let view = View {
+        view_matrix: create_view_matrix(camera.eye, camera.center, camera.up),
+        projection_matrix: create_perspective_matrix(camera.fov, width, height),
+        viewport_matrix: create_viewport_matrix(0.0, 0.0, width, height),
+        time: sim::seconds(time),
+        delta,
+        light: Light::sun(scene, &sim.bodies, time),
+        eye: camera.eye,
+        cull_back_faces,
+        occluders: &occluders,
+    };
+    let framebuffer = &mut sun_view.target.framebuffer;
+
+    framebuffer.clear();
+    SceneNode::from_scene(scene, &sim.bodies).walk(&Mat4::identity(), &mut |model_matrix, drawable| {
+        if drawable.mesh == MeshRef::Rings {
+            return;
+        }
+        let body_id = drawable.body;
+        let uniforms = view.uniforms(*model_matrix, &surface_noise[body_id]).with_texture(body_textures[body_id].as_ref()).with_material(scene.bodies[body_id].material);
+        let mesh = body_mesh(meshes, mesh_cache, scene, body_id, drawable.mesh, Detail::Medium);
+        render(framebuffer, &uniforms, mesh, ShadingRate::Full, |fragment| fragment_shader(fragment, &uniforms, drawable.shader));
+    });
+    framebuffer.tone_map(tone_mapping);
+}
+
+fn render_loading_screen(framebuffer: &mut Framebuffer, progress: f32, has_errors: bool) {
+    let bar_width = framebuffer.width / 2;
+    let bar_height = 12;
+    let (x, y) = layout::place(framebuffer, Anchor::Center, (bar_width, bar_height), 0);
+    let fill_color = if has_errors { 0xCC3333 } else { 0xFFDF00 };
+    let filled = (bar_width as f32 * progress.clamp(0.0, 1.0)) as usize;
+
+    draw2d::fill_rect(framebuffer, x - 2, y - 2, bar_width + 4, bar_height + 4, 0xFFFFFF);
+    draw2d::fill_rect(framebuffer, x, y, bar_width, bar_height, 0x000000);
+    draw2d::fill_rect(framebuffer, x, y, filled, bar_height, fill_color);
+}
+
+const TRAIL_PROGRADE_COLOR: Color = Color::new(90, 150, 255);
+const TRAIL_RETROGRADE_COLOR: Color = Color::new(255, 110, 70);
+const TRAIL_FLASH_COLOR: Color = Color::new(255, 230, 120);
+const ORBIT_COLOR: Color = Color::new(55, 65, 90);
+const ORBIT_SELECTED_COLOR: Color = Color::new(120, 140, 190);
+const ORBIT_SEGMENTS: usize = 180;
+const GRID_COLOR: Color = Color::new(35, 75, 65);
+
+fn direction_label(retrograde: bool) -> &'static str {
+    if retrograde { "retrógrada" } else { "prograda" }
+}
+
+// Dotted trail behind each orbiting body, fading with age. Retrograde orbits
+// get a warm color so the reversed direction is visible at a glance.
+// `flashes` lists bodies whose trail should light up, with a strength in [0, 1].
+fn render_trails(framebuffer: &mut Framebuffer, transform: &Mat4, scene: &Scene, sim: &Simulation, flashes: &[(usize, f32)]) {
+    for (body_id, (body, state)) in scene.bodies.iter().zip(&sim.bodies).enumerate() {
+        if body.orbit.semi_major_axis == 0.0 || state.trail.is_empty() {
+            continue;
+        }
+
+        let base = if body.is_retrograde_orbit() { TRAIL_RETROGRADE_COLOR } else { TRAIL_PROGRADE_COLOR };
+        let flash = flashes.iter().filter(|(id, _)| *id == body_id).map(|(_, strength)| *strength).fold(0.0, f32::max);
+        let base = base.lerp(&TRAIL_FLASH_COLOR, flash);
+        let count = state.trail.len() as f32;
+        for (i, point) in state.trail.iter().enumerate() {
+            let Some(screen) = project_to_screen(transform, *point) else {
+                continue;
+            };
+            if screen.x < 0.0 || screen.y < 0.0 {
+                continue;
+            }
+
+            // Older points wash out to gray as they dim.
+            let fade = (i + 1) as f32 / count;
+            let color = base.saturate(fade).brighten(fade * 0.8);
+            framebuffer.fill_scene_rect(screen.x as usize, screen.y as usize, 2, 2, color.to_hex());
+        }
     }
-    if window.is_key_down(Key::Key6) {
-        *current_shader = 6;
+}
+
+// Every body's full orbit, around wherever its parent is right now.
+fn render_orbits(framebuffer: &mut Framebuffer, uniforms: &Uniforms, scene: &Scene, sim: &Simulation) {
+    for (body_id, body) in scene.bodies.iter().enumerate().filter(|(_, body)| body.orbit.semi_major_axis > 0.0) {
+        let center = body.parent.map_or(Vec3::zeros(), |parent| sim.body_position(scene, parent));
+        let points: Vec<Vec3> = body.orbit.path(ORBIT_SEGMENTS).into_iter().map(|point| center + point).collect();
+        let color = if body_id == sim.selected { ORBIT_SELECTED_COLOR } else { ORBIT_COLOR };
+        render_polyline(framebuffer, uniforms, &points, color);
     }
-    if window.is_key_down(Key::Key7) {
-        *current_shader = 7;
+}
+
+// Straight down on the star from high enough to fit every planet's orbit,
+// at its far end, and the asteroid belt.
+fn overview_pose(scene: &Scene, aspect: f32) -> Pose {
+    let reach = scene
+        .bodies
+        .iter()
+        .filter(|body| body.parent.is_none())
+        .map(|body| body.orbit.semi_major_axis * (1.0 + body.orbit.eccentricity) + body.scale * SPHERE_RADIUS)
+        .chain(scene.asteroid_belt.as_ref().map(|belt| belt.outer))
+        .fold(0.0, f32::max);
+    // The lens is narrowest across the screen's shorter side.
+    let distance = reach * OVERVIEW_MARGIN / ((OVERVIEW_FOV / 2.0).tan() * aspect.min(1.0));
+    Pose { eye: Vec3::new(0.0, distance.min(OVERVIEW_MAX_DISTANCE), 0.0), center: Vec3::zeros(), up: -Vec3::z(), fov: OVERVIEW_FOV }
+}
+
+// Where a named shot puts the camera now; shots with a focus move with it.
+fn shot_pose(scene: &Scene, sim: &Simulation, shot_id: usize) -> Pose {
+    let shot = &scene.shots[shot_id];
+    let origin = shot.focus.map_or(Vec3::zeros(), |body_id| sim.body_position(scene, body_id));
+    Pose { eye: origin + shot.eye, center: origin + shot.center, up: Vec3::y(), fov: shot.fov }
+}
+
+// Where a body's orbit is centered and how close to that center it may
+// come without touching what is there; None for bodies that don't orbit.
+fn orbit_anchor(scene: &Scene, sim: &Simulation, body_id: usize) -> Option<(Vec3, f32)> {
+    let body = scene.bodies.get(body_id).filter(|body| body.orbit.semi_major_axis > 0.0)?;
+    let (center, center_scale) = match body.parent {
+        Some(parent) => (sim.body_position(scene, parent), scene.bodies[parent].scale),
+        None => (Vec3::zeros(), scene.bodies.iter().filter(|star| star.orbit.semi_major_axis == 0.0).map(|star| star.scale).fold(0.0, f32::max)),
+    };
+    Some((center, (center_scale + body.scale) * SPHERE_RADIUS))
+}
+
+// Depth-tested line strip through world-space points.
+fn render_polyline(framebuffer: &mut Framebuffer, uniforms: &Uniforms, points: &[Vec3], color: Color) {
+    let vertices: Vec<Vertex> = points
+        .iter()
+        .map(|point| vertex_shader(&Vertex::new_with_color(*point, color), uniforms))
+        .collect();
+
+    for segment in vertices.windows(2) {
+        if !segment.iter().all(|vertex| (-1.0..=1.0).contains(&vertex.transformed_position.z)) {
+            continue;
+        }
+        for fragment in line(&segment[0], &segment[1]) {
+            if fragment.position.x < 0.0 || fragment.position.y < 0.0 {
+                continue;
+            }
+            framebuffer.set_current_color(fragment.color.to_hex());
+            framebuffer.point(fragment.position.x as usize, fragment.position.y as usize, fragment.depth);
+        }
     }
-    if window.is_key_down(Key::Key8) {
-        *current_shader = 8;
+}
+
+// Screen position of a world-space point, or None when it is behind the camera.
+fn project_to_screen(transform: &Mat4, point: Vec3) -> Option<Vec3> {
+    let clip = transform * Vec4::new(point.x, point.y, point.z, 1.0);
+    if clip.w <= 0.0 {
+        return None;
     }
-    if window.is_key_down(Key::Key9) {
-        *current_shader = 9;
+    Some(Vec3::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w))
+}
+
+// The body drawn under `cursor`: of those whose disc on screen contains it,
+// the one nearest the camera. `bodies` are world centers and radii.
+fn body_under_cursor(transform: &Mat4, camera: &Camera, bodies: &[(Vec3, f32)], cursor: (f32, f32)) -> Option<usize> {
+    let cursor = Vec2::new(cursor.0, cursor.1);
+    bodies
+        .iter()
+        .enumerate()
+        .filter_map(|(body_id, &(center, radius))| {
+            let screen = project_to_screen(transform, center)?;
+            let edge = project_to_screen(transform, center + camera.up.normalize() * radius)?;
+            let inside = (screen.xy() - cursor).magnitude() <= (edge.xy() - screen.xy()).magnitude().max(3.0);
+            inside.then_some((body_id, (center - camera.eye).magnitude()))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(body_id, _)| body_id)
+}
+
+// Bodies whose centers land inside a screen rectangle, hidden ones left out.
+fn bodies_in_rect(transform: &Mat4, positions: &[Vec3], (left, top, right, bottom): selection::Rect, hidden: &[bool]) -> Vec<usize> {
+    positions
+        .iter()
+        .enumerate()
+        .filter(|&(body_id, _)| !hidden[body_id])
+        .filter_map(|(body_id, &position)| {
+            let screen = project_to_screen(transform, position)?;
+            (screen.x >= left && screen.x <= right && screen.y >= top && screen.y <= bottom).then_some(body_id)
+        })
+        .collect()
+}
+
+// Keys for the dragged group, or the selected body when there is none: X
+// hides them or shows them again, Shift+X shows every body, 0-9 give them
+// that shader, and the bracket, period, comma and slash keys change how
+// fast they orbit and spin.
+fn handle_group_input(input: &InputFrame, scene: &mut Scene, targets: &[usize], hidden: &mut [bool]) {
+    let speed_step = 1.25;
+    // Lets a stationary body start moving when sped up.
+    let minimum_speed = 0.001;
+    let speed_up = |speed: f32| if speed == 0.0 { minimum_speed } else { speed * speed_step };
+
+    if input.is_key_pressed(Key::X, KeyRepeat::No) {
+        if input.is_key_down(Key::LeftShift) || input.is_key_down(Key::RightShift) {
+            hidden.fill(false);
+        } else {
+            // Anything still shown gets hidden; an all-hidden group comes back.
+            let hide = targets.iter().any(|&body_id| !hidden[body_id]);
+            apply_edit(scene, hidden, targets, if hide { Edit::Hide } else { Edit::Show });
+        }
     }
 
+    let modifier = [Key::LeftShift, Key::RightShift, Key::LeftCtrl, Key::RightCtrl, Key::LeftAlt, Key::RightAlt].iter().any(|&key| input.is_key_down(key));
+    if let Some(shader) = DIGIT_KEYS.iter().position(|&key| input.is_key_pressed(key, KeyRepeat::No) && !modifier) {
+        apply_edit(scene, hidden, targets, Edit::Shader(shader as u8));
+    }
+
+    for &body_id in targets {
+        let Some(body) = scene.bodies.get_mut(body_id) else {
+            continue;
+        };
+        if input.is_key_pressed(Key::RightBracket, KeyRepeat::Yes) {
+            body.orbit.mean_motion = speed_up(body.orbit.mean_motion);
+        }
+        if input.is_key_pressed(Key::LeftBracket, KeyRepeat::Yes) {
+            body.orbit.mean_motion /= speed_step;
+        }
+        if input.is_key_pressed(Key::Backslash, KeyRepeat::No) {
+            body.orbit.mean_motion = -body.orbit.mean_motion;
+        }
+
+        if input.is_key_pressed(Key::Period, KeyRepeat::Yes) {
+            body.rotation_speed = speed_up(body.rotation_speed);
+        }
+        if input.is_key_pressed(Key::Comma, KeyRepeat::Yes) {
+            body.rotation_speed /= speed_step;
+        }
+        if input.is_key_pressed(Key::Slash, KeyRepeat::No) {
+            body.rotation_speed = -body.rotation_speed;
+        }
+    }
+}
+
+// Applies a console edit to each target body and says how it went.
+fn apply_edit(scene: &mut Scene, hidden: &mut [bool], targets: &[usize], edit: Edit) -> String {
+    let mut count = 0;
+    for &body_id in targets {
+        let Some(body) = scene.bodies.get_mut(body_id) else {
+            continue;
+        };
+        match edit {
+            Edit::Shader(shader) => body.shader = shader,
+            Edit::Scale(factor) => body.scale *= factor,
+            Edit::Speed(factor) => body.orbit.mean_motion *= factor,
+            Edit::Hide => hidden[body_id] = true,
+            Edit::Show => hidden[body_id] = false,
+        }
+        count += 1;
+    }
+    format!("listo: {} cuerpo(s)", count)
+}
+
+// Adds a body made from `template` around where the camera is looking,
+// selects it and returns its name.
+fn spawn_body(scene: &mut Scene, sim: &mut Simulation, template: Template, at: Vec3) -> String {
+    match spawn::create(template, scene, at) {
+        Spawned::Body(body) => {
+            let name = body.name.clone();
+            sim.selected = sim.add_body(scene, body);
+            name
+        }
+        Spawned::Comet(comet) => {
+            let name = comet.name.clone();
+            sim.add_comet(scene, comet);
+            name
+        }
+    }
+}
+
+// Deletes the targets and their moons, as long as something is left; None
+// if that would be nothing.
+fn delete_bodies(scene: &mut Scene, sim: &mut Simulation, targets: &[usize]) -> Option<Vec<usize>> {
+    let everything = scene.bodies.iter().enumerate().all(|(body_id, body)| body.parent.is_some() || targets.contains(&body_id));
+    (!everything).then(|| sim.remove_bodies(scene, targets))
+}
+
+// Returns the bodies a loaded state deleted, as `Scene::remove_bodies` does.
+fn handle_selection_input(input: &InputFrame, scene: &mut Scene, sim: &mut Simulation, time: &mut u32) -> Vec<usize> {
+    if input.is_key_pressed(Key::Tab, KeyRepeat::No) {
+        sim.select_next();
+    }
+
+    if input.is_key_pressed(Key::F5, KeyRepeat::No) {
+        match state::save(state::STATE_PATH, scene, sim, *time) {
+            Ok(()) => crash::log(format!("State saved to {}", state::STATE_PATH)),
+            Err(error) => crash::log_error(error),
+        }
+    }
+    if input.is_key_pressed(Key::F9, KeyRepeat::No) {
+        match state::load(state::STATE_PATH, scene, sim, time) {
+            Ok(removed) => return removed,
+            Err(error) => crash::log_error(error),
+        }
+    }
+    Vec::new()
+}
+
+// `blocked` says where presses belong to something else, like the gallery,
+// rather than to the camera.
+fn handle_input(input: &InputFrame, camera: &mut Camera, mouse_look: &mut MouseLook, blocked: impl Fn((f32, f32)) -> bool) {
+    let movement_speed = 1.0;
+    let rotation_speed = PI / 50.0;
+    let zoom_speed = 0.1;
+    let shift = input.is_key_down(Key::LeftShift) || input.is_key_down(Key::RightShift);
+
    
     //  camera orbit controls
-    if window.is_key_down(Key::Left) {
+    if input.is_key_down(Key::Left) && !shift {
       camera.orbit(rotation_speed, 0.0);
     }
-    if window.is_key_down(Key::Right) {
+    if input.is_key_down(Key::Right) && !shift {
       camera.orbit(-rotation_speed, 0.0);
     }
-    if window.is_key_down(Key::W) {
+
+    // Shift+Left/Right roll the view, Shift+Up levels it again.
+    if input.is_key_down(Key::Left) && shift {
+      camera.roll(-rotation_speed / 2.0);
+    }
+    if input.is_key_down(Key::Right) && shift {
+      camera.roll(rotation_speed / 2.0);
+    }
+    if input.is_key_pressed(Key::Up, KeyRepeat::No) && shift {
+      camera.level();
+    }
+    if input.is_key_down(Key::W) {
       camera.orbit(0.0, -rotation_speed);
     }
-    if window.is_key_down(Key::S) {
+    if input.is_key_down(Key::S) {
       camera.orbit(0.0, rotation_speed);
     }
 
     // Camera movement controls
     let mut movement = Vec3::new(0.0, 0.0, 0.0);
-    if window.is_key_down(Key::A) {
+    if input.is_key_down(Key::A) {
       movement.x -= movement_speed;
     }
-    if window.is_key_down(Key::D) {
+    if input.is_key_down(Key::D) {
       movement.x += movement_speed;
     }
-    if window.is_key_down(Key::Q) {
+    if input.is_key_down(Key::Q) {
       movement.y += movement_speed;
     }
-    if window.is_key_down(Key::E) {
+    if input.is_key_down(Key::E) {
       movement.y -= movement_speed;
     }
     if movement.magnitude() > 0.0 {
@@ -324,10 +2343,22 @@ fn handle_input(window: &Window, camera: &mut Camera, current_shader: &mut u8) {
     }
 
     // Camera zoom controls
-    if window.is_key_down(Key::Up) {
+    if input.is_key_down(Key::Up) && !shift {
       camera.zoom(zoom_speed);
     }
-    if window.is_key_down(Key::Down) {
+    if input.is_key_down(Key::Down) {
       camera.zoom(-zoom_speed);
     }
+
+    // Mouse: left-drag orbits, right-drag pans, the wheel zooms.
+    let motion = mouse_look.update(input, blocked);
+    if motion.orbit != (0.0, 0.0) {
+      camera.orbit(motion.orbit.0, motion.orbit.1);
+    }
+    if motion.pan != (0.0, 0.0) {
+      camera.pan(motion.pan.0, motion.pan.1);
+    }
+    if motion.zoom != 0.0 {
+      camera.zoom((camera.center - camera.eye).magnitude() * motion.zoom);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,62 @@
+use crate::shaders::shader_id;
+
+// How a surface answers the sun's light: a floor of ambient light, how much
+// of the direct light it scatters, and the strength and tightness of the
+// Blinn-Phong highlight it reflects towards the camera.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Material {
+    pub ambient: f32,
+    pub diffuse: f32,
+    pub specular: f32,
+    pub shininess: f32,
+    // Scale from the shader's color to radiance: 1 for lit surfaces, more
+    // for ones that shine by themselves and outdo white.
+    pub brightness: f32,
+}
+
+impl Material {
+    // Rock and dust: no highlight at all.
+    pub const MATTE: Material = Material { ambient: 0.04, diffuse: 1.0, specular: 0.0, shininess: 1.0, brightness: 1.0 };
+    // Open water, a small sharp reflection of the sun.
+    pub const OCEAN: Material = Material { ambient: 0.04, diffuse: 0.95, specular: 0.7, shininess: 80.0, brightness: 1.0 };
+    // Ice and snow, bright and with a broad sheen.
+    pub const ICE: Material = Material { ambient: 0.06, diffuse: 1.05, specular: 0.35, shininess: 20.0, brightness: 1.0 };
+    // Cloud tops of gas giants, barely glossy.
+    pub const GAS: Material = Material { ambient: 0.04, diffuse: 1.0, specular: 0.08, shininess: 6.0, brightness: 1.0 };
+    // The glowing surface of a star, several times brighter than anything
+    // it lights.
+    pub const STAR: Material = Material { ambient: 1.0, diffuse: 0.0, specular: 0.0, shininess: 1.0, brightness: 2.0 };
+
+    pub const NAMES: [&'static str; 5] = ["mate", "oceano", "hielo", "gas", "estrella"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "mate" => Some(Material::MATTE),
+            "oceano" => Some(Material::OCEAN),
+            "hielo" => Some(Material::ICE),
+            "gas" => Some(Material::GAS),
+            "estrella" => Some(Material::STAR),
+            _ => None,
+        }
+    }
+
+    // The scene file name of one of the named materials.
+    pub fn name(&self) -> Option<&'static str> {
+        let materials = [Material::MATTE, Material::OCEAN, Material::ICE, Material::GAS, Material::STAR];
+        materials.iter().position(|material| material == self).map(|index| Material::NAMES[index])
+    }
+
+    // What a body gets without a `material` key, from the look of its shader.
+    pub fn for_shader(shader: u8) -> Self {
+        let is = |name| shader_id(name) == Some(shader);
+        if is("azul") || is("tectonico") {
+            Material::OCEAN
+        } else if is("saturno") || is("gaseoso") {
+            Material::GAS
+        } else if is("sol") {
+            Material::STAR
+        } else {
+            Material::MATTE
+        }
+    }
+}
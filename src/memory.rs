@@ -0,0 +1,102 @@
+use std::mem::size_of;
+use nalgebra_glm::Vec3;
+use crate::framebuffer::Framebuffer;
+use crate::impact::{Debris, DebrisRing};
+use crate::lod::LodMeshes;
+use crate::mesh::MeshCache;
+use crate::particles::{Particle, ParticleSystem};
+use crate::rings::RingSystem;
+use crate::texture::Texture;
+use crate::vertex::Vertex;
+
+// Approximate heap usage of the big buffers the renderer keeps alive.
+pub trait MemoryFootprint {
+    fn memory_bytes(&self) -> usize;
+}
+
+impl MemoryFootprint for [Vertex] {
+    fn memory_bytes(&self) -> usize {
+        std::mem::size_of_val(self)
+    }
+}
+
+impl MemoryFootprint for LodMeshes {
+    fn memory_bytes(&self) -> usize {
+        self.full.memory_bytes() + self.medium.memory_bytes() + self.low.memory_bytes() + self.textured.memory_bytes()
+    }
+}
+
+impl MemoryFootprint for MeshCache {
+    fn memory_bytes(&self) -> usize {
+        self.meshes().map(|mesh| mesh.memory_bytes()).sum()
+    }
+}
+
+impl MemoryFootprint for Texture {
+    fn memory_bytes(&self) -> usize {
+        std::mem::size_of_val(self.texels.as_slice())
+    }
+}
+
+impl MemoryFootprint for Framebuffer {
+    fn memory_bytes(&self) -> usize {
+        self.buffer.len() * size_of::<u32>() + self.hdr.len() * size_of::<Vec3>() + self.zbuffer.len() * size_of::<f32>()
+    }
+}
+
+impl MemoryFootprint for ParticleSystem {
+    fn memory_bytes(&self) -> usize {
+        self.particles.capacity() * size_of::<Particle>()
+    }
+}
+
+impl MemoryFootprint for DebrisRing {
+    fn memory_bytes(&self) -> usize {
+        self.debris.capacity() * size_of::<Debris>()
+    }
+}
+
+// Just the particles; the mesh is counted with the other meshes.
+impl MemoryFootprint for RingSystem {
+    fn memory_bytes(&self) -> usize {
+        self.particle_bytes()
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryReport {
+    pub meshes: usize,
+    pub textures: usize,
+    pub framebuffers: usize,
+    pub particles: usize,
+}
+
+impl MemoryReport {
+    pub fn total(&self) -> usize {
+        self.meshes + self.textures + self.framebuffers + self.particles
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        vec![
+            format!("MEM    {}", format_bytes(self.total())),
+            format!(" mesh  {}", format_bytes(self.meshes)),
+            format!(" tex   {}", format_bytes(self.textures)),
+            format!(" fb    {}", format_bytes(self.framebuffers)),
+            format!(" part  {}", format_bytes(self.particles)),
+        ]
+    }
+}
+
+pub fn format_bytes(bytes: usize) -> String {
+    const KIB: f32 = 1024.0;
+    const MIB: f32 = 1024.0 * 1024.0;
+
+    let bytes_f = bytes as f32;
+    if bytes_f >= MIB {
+        format!("{:.1} MiB", bytes_f / MIB)
+    } else if bytes_f >= KIB {
+        format!("{:.1} KiB", bytes_f / KIB)
+    } else {
+        format!("{} B", bytes)
+    }
+}
@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+use nalgebra_glm::Vec3;
+use crate::scene::SPHERE_RADIUS;
+use crate::vertex::Vertex;
+
+// Models used by bodies instead of the sphere: files, one copy each no
+// matter how many bodies share them, and generated meshes, one per body.
+// Each is recentred and resized to the sphere's radius on the way in, so
+// `scale`, clearances and LOD treat it like any other body.
+#[derive(Default)]
+pub struct MeshCache {
+    meshes: HashMap<String, Vec<Vertex>>,
+    generated: HashMap<usize, Vec<Vertex>>,
+}
+
+impl MeshCache {
+    pub fn insert(&mut self, path: String, mut vertices: Vec<Vertex>) {
+        fit_to_sphere(&mut vertices);
+        self.meshes.insert(path, vertices);
+    }
+
+    pub fn insert_generated(&mut self, body_id: usize, mut vertices: Vec<Vertex>) {
+        fit_to_sphere(&mut vertices);
+        self.generated.insert(body_id, vertices);
+    }
+
+    pub fn get(&self, path: &str) -> Option<&[Vertex]> {
+        self.meshes.get(path).map(Vec::as_slice)
+    }
+
+    pub fn get_generated(&self, body_id: usize) -> Option<&[Vertex]> {
+        self.generated.get(&body_id).map(Vec::as_slice)
+    }
+
+    // Renumbers generated meshes after bodies were deleted; `remap` gives a
+    // body's new index, or None if it went.
+    pub fn remap_generated(&mut self, remap: impl Fn(usize) -> Option<usize>) {
+        self.generated = self.generated.drain().filter_map(|(body_id, vertices)| Some((remap(body_id)?, vertices))).collect();
+    }
+
+    pub fn meshes(&self) -> impl Iterator<Item = &[Vertex]> {
+        self.meshes.values().chain(self.generated.values()).map(Vec::as_slice)
+    }
+}
+
+fn fit_to_sphere(vertices: &mut [Vertex]) {
+    let Some(first) = vertices.first() else {
+        return;
+    };
+    let (mut min, mut max) = (first.position, first.position);
+    for vertex in vertices.iter() {
+        min = min.inf(&vertex.position);
+        max = max.sup(&vertex.position);
+    }
+    let center: Vec3 = (min + max) * 0.5;
+    let extent = vertices.iter().map(|vertex| (vertex.position - center).magnitude()).fold(0.0, f32::max);
+    if extent == 0.0 {
+        return;
+    }
+
+    let scale = SPHERE_RADIUS / extent;
+    for vertex in vertices.iter_mut() {
+        vertex.position = (vertex.position - center) * scale;
+    }
+}
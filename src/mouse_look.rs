@@ -0,0 +1,76 @@
+use minifb::Key;
+use crate::config::MouseConfig;
+use crate::input::InputFrame;
+
+// At a sensitivity of 1: radians turned per pixel dragged, fraction of the
+// viewing distance slid per pixel, and fraction of it closed per notch.
+const ORBIT_PER_PIXEL: f32 = 0.006;
+const PAN_PER_PIXEL: f32 = 0.0015;
+const ZOOM_PER_NOTCH: f32 = 0.1;
+// A single notch never closes more than this much of the distance, so the
+// camera can't reach what it looks at.
+const MAX_ZOOM_STEP: f32 = 0.5;
+
+// What the mouse asks of the camera this frame.
+#[derive(Default)]
+pub struct MouseMotion {
+    // Yaw and pitch, in radians.
+    pub orbit: (f32, f32),
+    // Right and up, in fractions of the viewing distance.
+    pub pan: (f32, f32),
+    // Fraction of the viewing distance to move closer; negative backs off.
+    pub zoom: f32,
+}
+
+// Turns drags and the wheel into camera motion. A drag only moves the
+// camera if it started on the view itself: Shift+left-drag belongs to the
+// group selection and presses on the gallery pick a body.
+pub struct MouseLook {
+    pub settings: MouseConfig,
+    last: Option<(f32, f32)>,
+    // Which drags, left and right, the camera has taken.
+    orbiting: bool,
+    panning: bool,
+    was_down: (bool, bool),
+}
+
+impl MouseLook {
+    pub fn new(settings: MouseConfig) -> Self {
+        MouseLook { settings, last: None, orbiting: false, panning: false, was_down: (false, false) }
+    }
+
+    pub fn update(&mut self, input: &InputFrame, blocked: impl Fn((f32, f32)) -> bool) -> MouseMotion {
+        let shift = input.is_key_down(Key::LeftShift) || input.is_key_down(Key::RightShift);
+        let free = input.mouse.is_some_and(|cursor| !blocked(cursor));
+        if input.mouse_down && !self.was_down.0 {
+            self.orbiting = free && !shift;
+        }
+        if input.right_down && !self.was_down.1 {
+            self.panning = free;
+        }
+        self.orbiting &= input.mouse_down;
+        self.panning &= input.right_down;
+        self.was_down = (input.mouse_down, input.right_down);
+
+        let (dx, dy) = match (self.last, input.mouse) {
+            (Some(last), Some(cursor)) => (cursor.0 - last.0, cursor.1 - last.1),
+            _ => (0.0, 0.0),
+        };
+        self.last = input.mouse;
+
+        let mut motion = MouseMotion::default();
+        if self.orbiting {
+            let turn = ORBIT_PER_PIXEL * self.settings.orbit;
+            motion.orbit = (dx * turn, -dy * turn);
+        }
+        if self.panning {
+            // The scene follows the cursor, so the camera goes the other way.
+            let slide = PAN_PER_PIXEL * self.settings.pan;
+            motion.pan = (-dx * slide, dy * slide);
+        }
+        if free {
+            motion.zoom = (input.scroll * ZOOM_PER_NOTCH * self.settings.zoom).clamp(-MAX_ZOOM_STEP, MAX_ZOOM_STEP);
+        }
+        motion
+    }
+}
@@ -0,0 +1,322 @@
+use fastnoise_lite::{
+    CellularDistanceFunction, CellularReturnType, DomainWarpType, FastNoiseLite, FractalType, NoiseType,
+};
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+// Named fractal configurations selectable per body from the scene file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NoisePreset {
+    // Single octave, what every shader was tuned with.
+    Plain,
+    // Soft, cloud-like detail.
+    Fbm,
+    // Sharp crests, good for mountain ranges and cracks.
+    Ridged,
+    // Puffy rounded lumps.
+    Billow,
+    // Fbm sampled through a warped domain, for marbled, flowing patterns.
+    DomainWarped,
+}
+
+impl NoisePreset {
+    pub const NAMES: [&'static str; 5] = ["plain", "fbm", "ridged", "billow", "warped"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "plain" => Some(NoisePreset::Plain),
+            "fbm" => Some(NoisePreset::Fbm),
+            "ridged" => Some(NoisePreset::Ridged),
+            "billow" => Some(NoisePreset::Billow),
+            "warped" => Some(NoisePreset::DomainWarped),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NoisePreset::Plain => "plain",
+            NoisePreset::Fbm => "fbm",
+            NoisePreset::Ridged => "ridged",
+            NoisePreset::Billow => "billow",
+            NoisePreset::DomainWarped => "warped",
+        }
+    }
+}
+
+// FastNoiseLite plus the bits of a preset it can't express on its own
+// (billow folding and a separate domain warp). Exposes the same sampling
+// methods so shaders don't care which preset is active.
+pub struct NoiseField {
+    noise: FastNoiseLite,
+    warp: Option<FastNoiseLite>,
+    billow: bool,
+    equalizer: Equalizer,
+}
+
+impl NoiseField {
+    pub fn new(seed: i32, preset: NoisePreset) -> Self {
+        let mut noise = FastNoiseLite::with_seed(seed);
+        noise.set_noise_type(Some(NoiseType::OpenSimplex2));
+
+        let fractal = |noise: &mut FastNoiseLite, fractal_type: FractalType| {
+            noise.set_fractal_type(Some(fractal_type));
+            noise.set_fractal_octaves(Some(4));
+            noise.set_fractal_lacunarity(Some(2.0));
+            noise.set_fractal_gain(Some(0.5));
+        };
+
+        let mut warp = None;
+        match preset {
+            NoisePreset::Plain => {}
+            NoisePreset::Fbm | NoisePreset::Billow => fractal(&mut noise, FractalType::FBm),
+            NoisePreset::Ridged => fractal(&mut noise, FractalType::Ridged),
+            NoisePreset::DomainWarped => {
+                fractal(&mut noise, FractalType::FBm);
+
+                let mut warp_noise = FastNoiseLite::with_seed(seed.wrapping_add(1));
+                warp_noise.set_domain_warp_type(Some(DomainWarpType::OpenSimplex2));
+                warp_noise.set_domain_warp_amp(Some(40.0));
+                warp_noise.set_fractal_type(Some(FractalType::DomainWarpProgressive));
+                warp_noise.set_fractal_octaves(Some(3));
+                warp = Some(warp_noise);
+            }
+        }
+
+        let mut field = NoiseField {
+            noise,
+            warp,
+            billow: preset == NoisePreset::Billow,
+            equalizer: Equalizer::identity(),
+        };
+        let equalizer = Equalizer::measure(|x, y, z| field.get_noise_3d(x, y, z));
+        field.equalizer = equalizer;
+        field
+    }
+
+    // Maps a raw sample from this field to [0, 1] so that every output value
+    // is about equally likely. Thresholds and palette stops can then be
+    // spaced evenly instead of being hand-tuned around zero.
+    pub fn equalize(&self, value: f32) -> f32 {
+        self.equalizer.apply(value)
+    }
+
+    fn shape(&self, value: f32) -> f32 {
+        if self.billow {
+            value.abs() * 2.0 - 1.0
+        } else {
+            value
+        }
+    }
+
+    pub fn get_noise_2d(&self, x: f32, y: f32) -> f32 {
+        let (x, y) = match &self.warp {
+            Some(warp) => warp.domain_warp_2d(x, y),
+            None => (x, y),
+        };
+        self.shape(self.noise.get_noise_2d(x, y))
+    }
+
+    pub fn get_noise_3d(&self, x: f32, y: f32, z: f32) -> f32 {
+        let (x, y, z) = match &self.warp {
+            Some(warp) => warp.domain_warp_3d(x, y, z),
+            None => (x, y, z),
+        };
+        self.shape(self.noise.get_noise_3d(x, y, z))
+    }
+}
+
+// Length in sim seconds of one animation loop for shaders that must repeat
+// exactly, e.g. when recording a video that will be played on a loop.
+pub const LOOP_PERIOD: f32 = 34.0;
+
+const EQUALIZER_BINS: usize = 64;
+const EQUALIZER_GRID: usize = 16;
+const EQUALIZER_SPACING: f32 = 23.7;
+
+// Approximate histogram equalization: the quantiles of the field's output,
+// measured once by sampling it on a grid.
+struct Equalizer {
+    quantiles: [f32; EQUALIZER_BINS + 1],
+}
+
+impl Equalizer {
+    fn identity() -> Self {
+        let mut quantiles = [0.0; EQUALIZER_BINS + 1];
+        for (i, quantile) in quantiles.iter_mut().enumerate() {
+            *quantile = i as f32 / EQUALIZER_BINS as f32 * 2.0 - 1.0;
+        }
+        Equalizer { quantiles }
+    }
+
+    fn measure(sample: impl Fn(f32, f32, f32) -> f32) -> Self {
+        let mut values = Vec::with_capacity(EQUALIZER_GRID.pow(3));
+        for i in 0..EQUALIZER_GRID {
+            for j in 0..EQUALIZER_GRID {
+                for k in 0..EQUALIZER_GRID {
+                    values.push(sample(
+                        i as f32 * EQUALIZER_SPACING,
+                        j as f32 * EQUALIZER_SPACING,
+                        k as f32 * EQUALIZER_SPACING,
+                    ));
+                }
+            }
+        }
+        values.sort_by(|a, b| a.total_cmp(b));
+
+        let mut quantiles = [0.0; EQUALIZER_BINS + 1];
+        for (i, quantile) in quantiles.iter_mut().enumerate() {
+            *quantile = values[i * (values.len() - 1) / EQUALIZER_BINS];
+        }
+        Equalizer { quantiles }
+    }
+
+    fn apply(&self, value: f32) -> f32 {
+        let index = self.quantiles.partition_point(|&quantile| quantile <= value);
+        if index == 0 {
+            return 0.0;
+        }
+        if index > EQUALIZER_BINS {
+            return 1.0;
+        }
+
+        let low = self.quantiles[index - 1];
+        let high = self.quantiles[index];
+        let fraction = if high > low { (value - low) / (high - low) } else { 0.0 };
+        ((index - 1) as f32 + fraction) / EQUALIZER_BINS as f32
+    }
+}
+
+pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Linearly maps `value` from one range to another, clamped to the target.
+pub fn remap(value: f32, from_min: f32, from_max: f32, to_min: f32, to_max: f32) -> f32 {
+    let t = ((value - from_min) / (from_max - from_min)).clamp(0.0, 1.0);
+    to_min + (to_max - to_min) * t
+}
+
+// Schlick's bias: pushes t in [0, 1] towards 0 (b < 0.5) or 1 (b > 0.5)
+// while keeping the end points fixed.
+pub fn bias(t: f32, b: f32) -> f32 {
+    t / ((1.0 / b - 2.0) * (1.0 - t) + 1.0)
+}
+
+// Schlick's gain: contrast around the midpoint, flattening (g < 0.5) or
+// sharpening (g > 0.5) the transition.
+pub fn gain(t: f32, g: f32) -> f32 {
+    if t < 0.5 {
+        bias(t * 2.0, 1.0 - g) / 2.0
+    } else {
+        1.0 - bias(2.0 - t * 2.0, 1.0 - g) / 2.0
+    }
+}
+
+pub struct CellSample {
+    // Stable identifier of the Voronoi cell containing the point.
+    pub cell_id: u32,
+    // Same identity as a value in [0, 1), handy for palette lookups.
+    pub cell_value: f32,
+    // Distance to the nearest cell border, in noise units (0 on the border).
+    pub border_distance: f32,
+}
+
+// Worley/Voronoi regions: which cell a point falls in and how close it is to
+// the edge, e.g. for tectonic plates with darkened boundaries.
+pub struct VoronoiField {
+    cells: FastNoiseLite,
+    borders: FastNoiseLite,
+}
+
+impl VoronoiField {
+    pub fn new(seed: i32, jitter: f32) -> Self {
+        let configure = |return_type: CellularReturnType| {
+            let mut noise = FastNoiseLite::with_seed(seed);
+            noise.set_noise_type(Some(NoiseType::Cellular));
+            noise.set_cellular_distance_function(Some(CellularDistanceFunction::Euclidean));
+            noise.set_cellular_return_type(Some(return_type));
+            noise.set_cellular_jitter(Some(jitter));
+            noise
+        };
+
+        VoronoiField {
+            cells: configure(CellularReturnType::CellValue),
+            borders: configure(CellularReturnType::Distance2Sub),
+        }
+    }
+
+    pub fn sample(&self, point: Vec3) -> CellSample {
+        let value = self.cells.get_noise_3d(point.x, point.y, point.z);
+        let cell_value = ((value + 1.0) * 0.5).clamp(0.0, 0.999_999);
+
+        // F2 - F1 is zero exactly on the bisector between two cells.
+        let border_distance = self.borders.get_noise_3d(point.x, point.y, point.z) + 1.0;
+
+        CellSample {
+            cell_id: (cell_value * 65536.0) as u32,
+            cell_value,
+            border_distance: border_distance.max(0.0),
+        }
+    }
+}
+
+// Position within the current loop, in [0, 1).
+pub fn loop_phase(time: f32, period: f32) -> f32 {
+    (time / period).rem_euclid(1.0)
+}
+
+// Periodic wave that completes `cycles` full oscillations per loop.
+pub fn loop_wave(time: f32, cycles: f32) -> f32 {
+    (loop_phase(time, LOOP_PERIOD) * cycles * 2.0 * PI).sin()
+}
+
+// Animated 3D noise that returns to its starting state every `period` seconds.
+// The sample point drifts by `drift` over one loop; a second sample one full
+// drift behind is cross-faded in so the end of the loop matches its start,
+// avoiding the visible jump (and endless one-way drift) of `p + time`.
+pub fn looping_noise_3d(noise: &NoiseField, point: Vec3, drift: Vec3, time: f32, period: f32) -> f32 {
+    let phase = loop_phase(time, period);
+    let ahead = point + drift * phase;
+    let behind = point + drift * (phase - 1.0);
+
+    let value_ahead = noise.get_noise_3d(ahead.x, ahead.y, ahead.z);
+    let value_behind = noise.get_noise_3d(behind.x, behind.y, behind.z);
+    let blended = value_ahead * (1.0 - phase) + value_behind * phase;
+
+    // Averaging two uncorrelated samples lowers contrast mid-loop; rescale
+    // so the pattern keeps the same spread throughout.
+    blended / ((1.0 - phase).powi(2) + phase.powi(2)).sqrt()
+}
+
+// Divergence-free flow on the surface of a sphere. The noise is treated as a
+// stream function ψ over the sphere; crossing the surface normal with its
+// tangential gradient gives a velocity that circulates around highs and lows
+// without sources or sinks, which is what makes storms swirl.
+pub fn curl_flow(noise: &NoiseField, point: Vec3, zoom: f32) -> Vec3 {
+    let normal = point.normalize();
+    let epsilon = 0.01;
+    let sample = |p: Vec3| noise.get_noise_3d(p.x * zoom, p.y * zoom, p.z * zoom);
+
+    let gradient = Vec3::new(
+        sample(normal + Vec3::x() * epsilon) - sample(normal - Vec3::x() * epsilon),
+        sample(normal + Vec3::y() * epsilon) - sample(normal - Vec3::y() * epsilon),
+        sample(normal + Vec3::z() * epsilon) - sample(normal - Vec3::z() * epsilon),
+    ) / (2.0 * epsilon);
+
+    let tangent_gradient = gradient - normal * gradient.dot(&normal);
+    normal.cross(&tangent_gradient)
+}
+
+// Flow-map style advection: two copies of the pattern are pushed along the
+// flow with phases half a cycle apart and cross-faded, so distortion never
+// accumulates past one cycle. Returns both offsets and the weight of the
+// second one.
+pub fn flow_offsets(velocity: Vec3, time: f32, cycle: f32) -> (Vec3, Vec3, f32) {
+    let phase_a = loop_phase(time, cycle);
+    let phase_b = loop_phase(time + cycle * 0.5, cycle);
+    let weight_b = (1.0 - 2.0 * phase_a).abs();
+
+    (-velocity * phase_a, -velocity * phase_b, weight_b)
+}
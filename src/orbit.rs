@@ -0,0 +1,130 @@
+use std::f32::consts::PI;
+use nalgebra_glm::Vec3;
+use crate::sim::STEPS_PER_SECOND;
+
+// A Kepler orbit around whatever sits at the origin of the parent frame.
+// Angles are in radians; with zero eccentricity and inclination it is the
+// plain circle in the XZ plane the bodies always used.
+#[derive(Clone, Copy, Debug)]
+pub struct Orbit {
+    pub semi_major_axis: f32,
+    pub eccentricity: f32,
+    // Tilt of the orbital plane around the X axis.
+    pub inclination: f32,
+    // Direction of closest approach, measured in the orbital plane.
+    pub periapsis: f32,
+    // Mean anomaly advanced per sim step; negative runs retrograde.
+    pub mean_motion: f32,
+    // Mean anomaly at step 0.
+    pub phase: f32,
+}
+
+impl Orbit {
+    pub fn circular(radius: f32, mean_motion: f32) -> Self {
+        Orbit { semi_major_axis: radius, eccentricity: 0.0, inclination: 0.0, periapsis: 0.0, mean_motion, phase: 0.0 }
+    }
+
+    // Sim seconds per revolution, or None for a body that doesn't move.
+    pub fn period(&self) -> Option<f32> {
+        (self.mean_motion != 0.0).then(|| 2.0 * PI / (self.mean_motion.abs() * STEPS_PER_SECOND))
+    }
+
+    pub fn periapsis_distance(&self) -> f32 {
+        self.semi_major_axis * (1.0 - self.eccentricity)
+    }
+
+    pub fn apoapsis_distance(&self) -> f32 {
+        self.semi_major_axis * (1.0 + self.eccentricity)
+    }
+
+    // Position on the ellipse with the focus at the origin, turned to the
+    // periapsis direction and tilted by the inclination.
+    pub fn position(&self, mean_anomaly: f32) -> Vec3 {
+        self.point(eccentric_anomaly(mean_anomaly, self.eccentricity))
+    }
+
+    // Closed loop of `segments` pieces around the whole ellipse. Steps are
+    // even in eccentric anomaly, which keeps them short near periapsis
+    // where the curve bends most.
+    pub fn path(&self, segments: usize) -> Vec<Vec3> {
+        (0..=segments).map(|i| self.point(i as f32 / segments as f32 * 2.0 * PI)).collect()
+    }
+
+    fn point(&self, anomaly: f32) -> Vec3 {
+        let e = self.eccentricity;
+        let x = self.semi_major_axis * (anomaly.cos() - e);
+        let z = self.semi_major_axis * (1.0 - e * e).sqrt() * anomaly.sin();
+
+        let (sin_w, cos_w) = self.periapsis.sin_cos();
+        let (x, z) = (x * cos_w - z * sin_w, x * sin_w + z * cos_w);
+
+        let (sin_i, cos_i) = self.inclination.sin_cos();
+        Vec3::new(x, z * sin_i, z * cos_i)
+    }
+}
+
+// Solves Kepler's equation M = E - e sin E with Newton's method.
+fn eccentric_anomaly(mean_anomaly: f32, eccentricity: f32) -> f32 {
+    if eccentricity == 0.0 {
+        return mean_anomaly;
+    }
+    // Body angles grow without bound; Newton wants them near the answer.
+    let mean_anomaly = mean_anomaly.rem_euclid(2.0 * PI);
+    let mut anomaly = if eccentricity > 0.8 { PI } else { mean_anomaly };
+    for _ in 0..8 {
+        let f = anomaly - eccentricity * anomaly.sin() - mean_anomaly;
+        anomaly -= f / (1.0 - eccentricity * anomaly.cos());
+    }
+    anomaly
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // How far E - e sin E lands from M, as an angle, so a whole turn
+    // either way counts as no error.
+    fn kepler_residual(mean_anomaly: f32, eccentricity: f32) -> f32 {
+        let anomaly = eccentric_anomaly(mean_anomaly, eccentricity);
+        let error = (anomaly - eccentricity * anomaly.sin() - mean_anomaly).rem_euclid(2.0 * PI);
+        error.min(2.0 * PI - error)
+    }
+
+    #[test]
+    fn kepler_converges_up_to_high_eccentricity() {
+        for eccentricity in [0.0, 0.1, 0.5, 0.8, 0.81, 0.9, 0.97, 0.99] {
+            for step in 0..=720 {
+                let mean_anomaly = step as f32 / 720.0 * 2.0 * PI;
+                let residual = kepler_residual(mean_anomaly, eccentricity);
+                assert!(residual < 1e-4, "e = {}, M = {}: residual {}", eccentricity, mean_anomaly, residual);
+            }
+        }
+    }
+
+    #[test]
+    fn kepler_wraps_large_mean_anomalies() {
+        let turns = 1000.0 * 2.0 * PI;
+        assert!(kepler_residual(turns + 1.0, 0.9) < 1e-3);
+        assert!(kepler_residual(-turns - 1.0, 0.9) < 1e-3);
+    }
+
+    #[test]
+    fn apsides_lie_on_the_ellipse() {
+        let orbit = Orbit { semi_major_axis: 10.0, eccentricity: 0.95, inclination: 0.3, periapsis: 1.2, mean_motion: 0.01, phase: 0.0 };
+        let periapsis = orbit.position(0.0);
+        let apoapsis = orbit.position(PI);
+        assert!((periapsis.magnitude() - orbit.periapsis_distance()).abs() < 1e-4);
+        assert!((apoapsis.magnitude() - orbit.apoapsis_distance()).abs() < 1e-3);
+        assert!((periapsis.normalize() + apoapsis.normalize()).magnitude() < 1e-4);
+    }
+
+    #[test]
+    fn circular_orbit_keeps_its_radius_and_period() {
+        let orbit = Orbit::circular(4.0, -0.5);
+        for step in 0..16 {
+            assert!((orbit.position(step as f32).magnitude() - 4.0).abs() < 1e-4);
+        }
+        assert!((orbit.period().unwrap() - 4.0 * PI / STEPS_PER_SECOND).abs() < 1e-6);
+        assert_eq!(Orbit::circular(4.0, 0.0).period(), None);
+    }
+}
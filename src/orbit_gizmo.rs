@@ -0,0 +1,259 @@
+use std::f32::consts::PI;
+use std::fs;
+use nalgebra_glm::{Mat4, Vec2, Vec3, Vec4};
+use crate::draw2d;
+use crate::framebuffer::Framebuffer;
+use crate::input::InputFrame;
+use crate::orbit::Orbit;
+use crate::scene::Scene;
+use crate::toml_lite::{self, Value};
+
+// Presses closer than this, in pixels, grab a handle.
+const GRAB_RADIUS: f32 = 8.0;
+const HANDLE_RADIUS: f32 = 5.0;
+const ORBIT_SEGMENTS: usize = 96;
+const MAX_ECCENTRICITY: f32 = 0.95;
+// Inclination stops short of standing the orbit on its edge.
+const MAX_INCLINATION: f32 = PI / 2.0 - 0.05;
+// Small tilt, in radians, used to see which way the handle moves on screen.
+const TILT_PROBE: f32 = 0.05;
+const ORBIT_COLOR: u32 = 0x6688AA;
+const HANDLE_COLORS: [u32; 3] = [0xFFCC44, 0xFF6666, 0x66DD88];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Handle {
+    // At periapsis; moves the whole orbit in and out.
+    Size,
+    // At apoapsis; stretches the far end while the size stays.
+    Eccentricity,
+    // Where the plane rises highest; tilts it around the X axis.
+    Inclination,
+}
+
+impl Handle {
+    const ALL: [Handle; 3] = [Handle::Size, Handle::Eccentricity, Handle::Inclination];
+
+    // Where the handle sits, relative to the body's parent.
+    fn anchor(self, orbit: &Orbit) -> Vec3 {
+        match self {
+            Handle::Size => orbit.position(0.0),
+            Handle::Eccentricity => orbit.position(PI),
+            Handle::Inclination => {
+                let (sin_i, cos_i) = orbit.inclination.sin_cos();
+                Vec3::new(0.0, sin_i, cos_i) * orbit.semi_major_axis
+            }
+        }
+    }
+}
+
+// Draggable handles on the selected body's orbit, shown while orbit editing
+// is on (N). Handles are found on screen where they were last drawn, so a
+// press can be claimed before the camera takes it.
+pub struct OrbitGizmo {
+    pub enabled: bool,
+    handles: Vec<(Handle, (f32, f32))>,
+    transform: Option<Mat4>,
+    grabbed: Option<Handle>,
+    was_down: bool,
+    last_cursor: Option<(f32, f32)>,
+}
+
+impl OrbitGizmo {
+    pub fn new() -> Self {
+        OrbitGizmo { enabled: false, handles: Vec::new(), transform: None, grabbed: None, was_down: false, last_cursor: None }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        self.grabbed = None;
+        self.handles.clear();
+    }
+
+    pub fn handle_at(&self, cursor: (f32, f32)) -> Option<Handle> {
+        if !self.enabled {
+            return None;
+        }
+        self.handles
+            .iter()
+            .find(|(_, (x, y))| (x - cursor.0).hypot(y - cursor.1) <= GRAB_RADIUS)
+            .map(|&(handle, _)| handle)
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.grabbed.is_some()
+    }
+
+    // Follows the left button and reshapes `orbit` while a handle is held.
+    // `center` is where the parent is and `min_distance` how close the
+    // orbit may come to it. Returns whether the orbit changed.
+    pub fn update(&mut self, input: &InputFrame, orbit: &mut Orbit, center: Vec3, min_distance: f32) -> bool {
+        let down = input.mouse_down;
+        if down && !self.was_down {
+            self.grabbed = input.mouse.and_then(|cursor| self.handle_at(cursor));
+        }
+        self.was_down = down;
+        if !down || !self.enabled {
+            self.grabbed = None;
+        }
+        let last_cursor = std::mem::replace(&mut self.last_cursor, input.mouse);
+        let (Some(handle), Some(cursor), Some(transform)) = (self.grabbed, input.mouse, self.transform) else {
+            return false;
+        };
+
+        let before = *orbit;
+        match handle {
+            Handle::Size | Handle::Eccentricity => {
+                let (sin_i, cos_i) = orbit.inclination.sin_cos();
+                let normal = Vec3::new(0.0, cos_i, -sin_i);
+                let Some(hit) = cursor_ray(&transform, cursor).and_then(|(origin, direction)| intersect_plane(origin, direction, center, normal)) else {
+                    return false;
+                };
+                let distance = (hit - center).magnitude();
+                if handle == Handle::Size {
+                    orbit.semi_major_axis = distance.max(min_distance) / (1.0 - orbit.eccentricity);
+                } else {
+                    let most = MAX_ECCENTRICITY.min(1.0 - min_distance / orbit.semi_major_axis).max(0.0);
+                    orbit.eccentricity = (distance / orbit.semi_major_axis - 1.0).clamp(0.0, most);
+                }
+            }
+            // The handle turns on a circle that is often seen edge-on, so
+            // it follows the cursor along its path as drawn on screen.
+            Handle::Inclination => {
+                let Some(last) = last_cursor else {
+                    return false;
+                };
+                let step = |inclination: f32| {
+                    let (sin_i, cos_i) = inclination.sin_cos();
+                    let clip = transform * (center + Vec3::new(0.0, sin_i, cos_i) * orbit.semi_major_axis).push(1.0);
+                    (clip.w > 0.0).then(|| clip.xy() / clip.w)
+                };
+                let (Some(from), Some(to)) = (step(orbit.inclination), step(orbit.inclination + TILT_PROBE)) else {
+                    return false;
+                };
+                let tangent = to - from;
+                if tangent.magnitude_squared() < 1.0e-6 {
+                    return false;
+                }
+                let moved = Vec2::new(cursor.0 - last.0, cursor.1 - last.1);
+                let turn = moved.dot(&tangent) / tangent.magnitude_squared() * TILT_PROBE;
+                orbit.inclination = (orbit.inclination + turn).clamp(-MAX_INCLINATION, MAX_INCLINATION);
+            }
+        }
+        (orbit.semi_major_axis, orbit.eccentricity, orbit.inclination) != (before.semi_major_axis, before.eccentricity, before.inclination)
+    }
+
+    // The orbit around `center` and its handles, over the finished frame.
+    pub fn draw(&mut self, framebuffer: &mut Framebuffer, transform: &Mat4, target: Option<(&Orbit, Vec3)>) {
+        self.transform = Some(*transform);
+        self.handles.clear();
+        let (true, Some((orbit, center))) = (self.enabled, target) else {
+            return;
+        };
+        let screen = |point: Vec3| {
+            let clip = transform * Vec4::new(point.x, point.y, point.z, 1.0);
+            (clip.w > 0.0).then(|| (clip.x / clip.w, clip.y / clip.w))
+        };
+
+        let points: Vec<Option<(f32, f32)>> = orbit.path(ORBIT_SEGMENTS).into_iter().map(|point| screen(center + point)).collect();
+        // Points just in front of the camera land far off screen.
+        let reach = (framebuffer.width + framebuffer.height) as f32 * 2.0;
+        for segment in points.windows(2) {
+            if let [Some(from), Some(to)] = segment {
+                if [from.0, from.1, to.0, to.1].iter().all(|coordinate| coordinate.abs() < reach) {
+                    draw2d::line(framebuffer, *from, *to, ORBIT_COLOR);
+                }
+            }
+        }
+        for (handle, color) in Handle::ALL.into_iter().zip(HANDLE_COLORS) {
+            let Some(position) = screen(center + handle.anchor(orbit)) else {
+                continue;
+            };
+            if handle == Handle::Inclination {
+                if let Some(pivot) = screen(center) {
+                    draw2d::line(framebuffer, pivot, position, color);
+                }
+            }
+            draw2d::fill_circle(framebuffer, position, HANDLE_RADIUS, color);
+            if self.grabbed == Some(handle) {
+                draw2d::stroke_circle(framebuffer, position, HANDLE_RADIUS + 3.0, 0xFFFFFF);
+            }
+            self.handles.push((handle, position));
+        }
+    }
+}
+
+// Where the cursor looks into the scene, from the near plane outwards.
+fn cursor_ray(transform: &Mat4, cursor: (f32, f32)) -> Option<(Vec3, Vec3)> {
+    let inverse = transform.try_inverse()?;
+    let unproject = |z: f32| {
+        let point = inverse * Vec4::new(cursor.0, cursor.1, z, 1.0);
+        point.xyz() / point.w
+    };
+    let near = unproject(-1.0);
+    Some((near, (unproject(1.0) - near).try_normalize(1.0e-6)?))
+}
+
+// None when the ray runs along the plane or away from it.
+fn intersect_plane(origin: Vec3, direction: Vec3, point: Vec3, normal: Vec3) -> Option<Vec3> {
+    let facing = direction.dot(&normal);
+    if facing.abs() < 1.0e-4 {
+        return None;
+    }
+    let t = (point - origin).dot(&normal) / facing;
+    (t > 0.0).then(|| origin + direction * t)
+}
+
+// Writes the orbits of `body_ids` into their tables in the scene file at
+// `path`, replacing `orbit_radius`, `eccentricity` and `inclination` or
+// adding them under the table header. Comments and everything else stay.
+pub fn write_back(path: &str, scene: &Scene, body_ids: &[usize]) -> Result<(), String> {
+    let source = fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    let document = toml_lite::parse(&source).map_err(|_| format!("{} has errors, not writing to it", path))?;
+    let mut lines: Vec<String> = source.lines().map(str::to_string).collect();
+
+    // Line edits as (line, replaces it, text), applied bottom-up so earlier
+    // line numbers stay valid and keys added to one table keep their order.
+    let mut edits = Vec::new();
+    for &body_id in body_ids {
+        let body = &scene.bodies[body_id];
+        if body.orbit.semi_major_axis == 0.0 {
+            continue;
+        }
+        let table = document
+            .tables
+            .iter()
+            .filter(|table| table.name == "body" || table.name == "body.moon")
+            .find(|table| matches!(table.get("name").map(|entry| &entry.value), Some(Value::String(name)) if name == &body.name))
+            .ok_or_else(|| format!("`{}` is not in {}", body.name, path))?;
+
+        // Lines already holding the value, and missing keys still at their
+        // default of zero, are left alone.
+        let orbit = &body.orbit;
+        let values = [
+            ("orbit_radius", orbit.semi_major_axis, format!("{}", orbit.semi_major_axis)),
+            ("eccentricity", orbit.eccentricity, format!("{}", orbit.eccentricity)),
+            ("inclination", orbit.inclination, format!("\"{:.2} deg\"", orbit.inclination.to_degrees())),
+        ];
+        for (key, value, text) in values {
+            let text = format!("{} = {}", key, text);
+            match table.get(key) {
+                Some(entry) if matches!(entry.value, Value::Number(written) if written == value) => {}
+                Some(entry) => edits.push((entry.line, true, text)),
+                None if value != 0.0 => edits.push((table.line, false, text)),
+                None => {}
+            }
+        }
+    }
+
+    edits.sort_by_key(|(line, _, _)| *line);
+    for (line, replace, text) in edits.into_iter().rev() {
+        if replace {
+            lines[line - 1] = text;
+        } else {
+            lines.insert(line, text);
+        }
+    }
+    let mut out = lines.join("\n");
+    out.push('\n');
+    fs::write(path, out).map_err(|e| format!("cannot write {}: {}", path, e))
+}
@@ -0,0 +1,175 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::color::{Color, Gradient, Rgba};
+use crate::framebuffer::{BlendMode, Framebuffer};
+use crate::rng::Rng;
+
+#[derive(Clone)]
+pub struct Particle {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub age: f32,
+    pub lifetime: f32,
+}
+
+// A number that changes over a particle's life: keys of (life, value) with
+// life from 0 at birth to 1 at death, linear in between and held past the
+// ends.
+#[derive(Clone, Copy, Debug)]
+pub struct Curve {
+    keys: &'static [(f32, f32)],
+}
+
+impl Curve {
+    pub const ONE: Curve = Curve::new(&[(0.0, 1.0)]);
+    pub const FADE_OUT: Curve = Curve::new(&[(0.0, 1.0), (1.0, 0.0)]);
+
+    pub const fn new(keys: &'static [(f32, f32)]) -> Self {
+        Curve { keys }
+    }
+
+    pub fn at(&self, life: f32) -> f32 {
+        let next = self.keys.partition_point(|&(key, _)| key <= life);
+        if next == 0 {
+            return self.keys[0].1;
+        }
+        if next == self.keys.len() {
+            return self.keys[next - 1].1;
+        }
+        let (from, from_value) = self.keys[next - 1];
+        let (to, to_value) = self.keys[next];
+        from_value + (to_value - from_value) * (life - from) / (to - from)
+    }
+}
+
+const WHITE: Gradient = Gradient::new(&[(0.0, Color::new(255, 255, 255))]);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shape {
+    // One pixel each.
+    Point,
+    // Squares `size` pixels across, the same on screen at any distance.
+    Quad,
+}
+
+// Knobs of an emitter. They are plain fields so the owner can retune them
+// every frame (e.g. a comet brightening as it nears the sun).
+#[derive(Clone)]
+pub struct EmitterParams {
+    // Particles spawned per frame; fractions carry over to the next frame.
+    pub rate: f32,
+    pub lifetime: f32,
+    // Random velocity added to every new particle, as a fraction of its speed.
+    pub spread: f32,
+    // Constant acceleration applied to live particles.
+    pub acceleration: Vec3,
+    pub brightness: f32,
+    // Over each particle's life: its color, how bright it is, how fast it
+    // moves (below 1 reads as drag) and, for quads, its size in pixels.
+    pub color: Gradient,
+    pub fade: Curve,
+    pub speed: Curve,
+    pub size: Curve,
+    pub shape: Shape,
+}
+
+impl Default for EmitterParams {
+    fn default() -> Self {
+        EmitterParams {
+            rate: 0.0,
+            lifetime: 60.0,
+            spread: 0.0,
+            acceleration: Vec3::zeros(),
+            brightness: 1.0,
+            color: WHITE,
+            fade: Curve::FADE_OUT,
+            speed: Curve::ONE,
+            size: Curve::ONE,
+            shape: Shape::Point,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ParticleSystem {
+    pub params: EmitterParams,
+    pub particles: Vec<Particle>,
+    rng: Rng,
+    pending: f32,
+}
+
+impl ParticleSystem {
+    pub fn new(params: EmitterParams, rng: Rng) -> Self {
+        ParticleSystem {
+            params,
+            particles: Vec::new(),
+            rng,
+            pending: 0.0,
+        }
+    }
+
+    // Adds one particle as is, for owners that pick their own directions.
+    pub fn spawn(&mut self, position: Vec3, velocity: Vec3) {
+        let lifetime = self.params.lifetime * self.rng.range(0.7, 1.0);
+        self.particles.push(Particle { position, velocity, age: 0.0, lifetime });
+    }
+
+    pub fn emit(&mut self, origin: Vec3, velocity: Vec3) {
+        self.pending += self.params.rate;
+        while self.pending >= 1.0 {
+            self.pending -= 1.0;
+
+            let jitter = Vec3::new(
+                self.rng.range(-1.0, 1.0),
+                self.rng.range(-1.0, 1.0),
+                self.rng.range(-1.0, 1.0),
+            ) * velocity.magnitude() * self.params.spread;
+            self.spawn(origin, velocity + jitter);
+        }
+    }
+
+    pub fn update(&mut self) {
+        let acceleration = self.params.acceleration;
+        let speed = self.params.speed;
+        for particle in &mut self.particles {
+            particle.velocity += acceleration;
+            particle.position += particle.velocity * speed.at(particle.age / particle.lifetime);
+            particle.age += 1.0;
+        }
+        self.particles.retain(|particle| particle.age < particle.lifetime);
+    }
+
+    // Additive splats, depth tested against the scene but not writing depth,
+    // so particles glow over each other and hide behind planets.
+    pub fn render(&self, framebuffer: &mut Framebuffer, transform: &Mat4) {
+        let params = &self.params;
+        for particle in &self.particles {
+            let clip = transform * Vec4::new(particle.position.x, particle.position.y, particle.position.z, 1.0);
+            if clip.w <= 0.0 {
+                continue;
+            }
+            let (x, y, depth) = (clip.x / clip.w, clip.y / clip.w, clip.z / clip.w);
+            let life = particle.age / particle.lifetime;
+            let color = params.color.at(life) * (params.fade.at(life) * params.brightness);
+
+            match params.shape {
+                Shape::Point => splat(framebuffer, x, y, depth, color),
+                Shape::Quad => {
+                    let size = params.size.at(life);
+                    // Quads smaller than a pixel dim instead of vanishing.
+                    let (color, half) = if size < 1.0 { (color * size * size, 0) } else { (color, (size * 0.5) as i32) };
+                    for dy in -half..=half {
+                        for dx in -half..=half {
+                            splat(framebuffer, x + dx as f32, y + dy as f32, depth, color);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn splat(framebuffer: &mut Framebuffer, x: f32, y: f32, depth: f32, color: Color) {
+    if x >= 0.0 && y >= 0.0 {
+        framebuffer.blend_point(x as usize, y as usize, depth, Rgba::new(color, 1.0), BlendMode::Additive);
+    }
+}
@@ -0,0 +1,184 @@
+use crate::color::Color;
+use crate::material::Material;
+
+// Physical data for the real solar system preset (`--real`). Sources: NASA
+// planetary fact sheets, rounded.
+pub struct PlanetData {
+    pub name: &'static str,
+    pub shader: u8,
+    // Equatorial radius in kilometres.
+    pub radius_km: f32,
+    pub semi_major_axis_au: f32,
+    pub eccentricity: f32,
+    pub inclination_degrees: f32,
+    pub periapsis_degrees: f32,
+    pub orbital_period_days: f32,
+    // Where the planet was on 1 January 2000, used as the starting point.
+    pub mean_longitude_degrees: f32,
+    // Sidereal day; negative for retrograde spin. Venus and Uranus spin
+    // backwards with the tilt measured from the other pole, so it stays
+    // under 90 degrees.
+    pub rotation_period_hours: f32,
+    pub axial_tilt_degrees: f32,
+    pub magnetosphere: bool,
+    pub clouds: bool,
+    // Tint of the glow around the limb.
+    pub atmosphere: Option<Color>,
+    // Overrides the material that goes with the shader.
+    pub material: Option<Material>,
+    // Inner and outer edge of the main rings, in planet radii.
+    pub rings: Option<(f32, f32)>,
+}
+
+pub const SUN_RADIUS_KM: f32 = 696_340.0;
+pub const SUN_ROTATION_PERIOD_HOURS: f32 = 609.1;
+pub const SUN_AXIAL_TILT_DEGREES: f32 = 7.25;
+pub const EARTH_RADIUS_KM: f32 = 6371.0;
+pub const EARTH_YEAR_DAYS: f32 = 365.25;
+
+pub const PLANETS: [PlanetData; 8] = [
+    PlanetData {
+        name: "Mercurio",
+        shader: 7,
+        radius_km: 2439.7,
+        semi_major_axis_au: 0.387,
+        eccentricity: 0.2056,
+        inclination_degrees: 7.0,
+        periapsis_degrees: 77.46,
+        orbital_period_days: 87.97,
+        mean_longitude_degrees: 252.25,
+        rotation_period_hours: 1407.6,
+        axial_tilt_degrees: 0.03,
+        magnetosphere: false,
+        clouds: false,
+        atmosphere: None,
+        material: None,
+        rings: None,
+    },
+    PlanetData {
+        name: "Venus",
+        shader: 9,
+        radius_km: 6051.8,
+        semi_major_axis_au: 0.723,
+        eccentricity: 0.0068,
+        inclination_degrees: 3.39,
+        periapsis_degrees: 131.53,
+        orbital_period_days: 224.7,
+        mean_longitude_degrees: 181.98,
+        rotation_period_hours: -5832.5,
+        axial_tilt_degrees: 2.64,
+        magnetosphere: false,
+        clouds: false,
+        atmosphere: Some(Color::new(255, 225, 160)),
+        material: None,
+        rings: None,
+    },
+    PlanetData {
+        name: "Tierra",
+        shader: 3,
+        radius_km: 6371.0,
+        semi_major_axis_au: 1.0,
+        eccentricity: 0.0167,
+        inclination_degrees: 0.0,
+        periapsis_degrees: 102.95,
+        orbital_period_days: 365.25,
+        mean_longitude_degrees: 100.46,
+        rotation_period_hours: 23.93,
+        axial_tilt_degrees: 23.44,
+        magnetosphere: true,
+        clouds: true,
+        atmosphere: Some(Color::new(110, 165, 255)),
+        material: None,
+        rings: None,
+    },
+    PlanetData {
+        name: "Marte",
+        shader: 5,
+        radius_km: 3389.5,
+        semi_major_axis_au: 1.524,
+        eccentricity: 0.0934,
+        inclination_degrees: 1.85,
+        periapsis_degrees: 336.04,
+        orbital_period_days: 687.0,
+        mean_longitude_degrees: 355.45,
+        rotation_period_hours: 24.62,
+        axial_tilt_degrees: 25.19,
+        magnetosphere: false,
+        clouds: false,
+        atmosphere: Some(Color::new(230, 150, 110)),
+        material: None,
+        rings: None,
+    },
+    PlanetData {
+        name: "Jupiter",
+        shader: 8,
+        radius_km: 69_911.0,
+        semi_major_axis_au: 5.203,
+        eccentricity: 0.0489,
+        inclination_degrees: 1.3,
+        periapsis_degrees: 14.75,
+        orbital_period_days: 4332.6,
+        mean_longitude_degrees: 34.4,
+        rotation_period_hours: 9.93,
+        axial_tilt_degrees: 3.13,
+        magnetosphere: true,
+        clouds: false,
+        atmosphere: Some(Color::new(230, 200, 160)),
+        material: None,
+        rings: None,
+    },
+    PlanetData {
+        name: "Saturno",
+        shader: 2,
+        radius_km: 58_232.0,
+        semi_major_axis_au: 9.537,
+        eccentricity: 0.0565,
+        inclination_degrees: 2.49,
+        periapsis_degrees: 92.43,
+        orbital_period_days: 10_759.0,
+        mean_longitude_degrees: 49.94,
+        rotation_period_hours: 10.66,
+        axial_tilt_degrees: 26.73,
+        magnetosphere: true,
+        clouds: false,
+        atmosphere: Some(Color::new(240, 215, 160)),
+        material: None,
+        rings: Some((1.24, 2.27)),
+    },
+    PlanetData {
+        name: "Urano",
+        shader: 4,
+        radius_km: 25_362.0,
+        semi_major_axis_au: 19.19,
+        eccentricity: 0.0457,
+        inclination_degrees: 0.77,
+        periapsis_degrees: 170.96,
+        orbital_period_days: 30_687.0,
+        mean_longitude_degrees: 313.23,
+        rotation_period_hours: -17.24,
+        axial_tilt_degrees: 82.23,
+        magnetosphere: true,
+        clouds: false,
+        atmosphere: Some(Color::new(160, 225, 235)),
+        material: Some(Material::ICE),
+        rings: None,
+    },
+    PlanetData {
+        name: "Neptuno",
+        shader: 3,
+        radius_km: 24_622.0,
+        semi_major_axis_au: 30.07,
+        eccentricity: 0.0113,
+        inclination_degrees: 1.77,
+        periapsis_degrees: 44.97,
+        orbital_period_days: 60_190.0,
+        mean_longitude_degrees: 304.88,
+        rotation_period_hours: 16.11,
+        axial_tilt_degrees: 28.32,
+        magnetosphere: true,
+        clouds: false,
+        atmosphere: Some(Color::new(110, 150, 255)),
+        material: Some(Material::ICE),
+        rings: None,
+    },
+];
@@ -0,0 +1,174 @@
+use minifb::{Key, KeyRepeat};
+use nalgebra_glm::Vec3;
+use rayon::prelude::*;
+use crate::bloom::Bloom;
+use crate::framebuffer::Framebuffer;
+use crate::input::InputFrame;
+use crate::noise::smoothstep;
+
+// A full-screen pass over the finished scene, before it is tone mapped and
+// the HUD goes on top. It may read and change both the HDR color and the
+// depth buffer.
+pub trait PostEffect {
+    // Shown in the stats overlay while the effect is on.
+    fn name(&self) -> &'static str;
+    fn apply(&self, framebuffer: &mut Framebuffer);
+}
+
+struct Stage {
+    effect: Box<dyn PostEffect>,
+    key: Key,
+    enabled: bool,
+}
+
+// The post effects in the order they run, each switched on and off by its
+// own key.
+pub struct PostChain {
+    stages: Vec<Stage>,
+}
+
+impl PostChain {
+    pub fn new() -> Self {
+        PostChain { stages: Vec::new() }
+    }
+
+    // Bloom (F8), vignette (F10), gamma (F11) and blur (F12); only bloom
+    // starts on.
+    pub fn standard() -> Self {
+        let mut chain = PostChain::new();
+        chain.push(Box::new(Bloom), Key::F8, true);
+        chain.push(Box::new(Vignette { strength: 0.55 }), Key::F10, false);
+        chain.push(Box::new(Gamma { gamma: 1.25 }), Key::F11, false);
+        chain.push(Box::new(Blur { sigma: 1.5 }), Key::F12, false);
+        chain
+    }
+
+    pub fn push(&mut self, effect: Box<dyn PostEffect>, key: Key, enabled: bool) {
+        self.stages.push(Stage { effect, key, enabled });
+    }
+
+    pub fn handle_input(&mut self, input: &InputFrame) {
+        for stage in &mut self.stages {
+            if input.is_key_pressed(stage.key, KeyRepeat::No) {
+                stage.enabled = !stage.enabled;
+            }
+        }
+    }
+
+    pub fn apply(&self, framebuffer: &mut Framebuffer) {
+        for stage in self.stages.iter().filter(|stage| stage.enabled) {
+            stage.effect.apply(framebuffer);
+        }
+    }
+
+    // Names and keys of the effects that are on, for the stats overlay.
+    pub fn enabled(&self) -> Vec<String> {
+        self.stages
+            .iter()
+            .filter(|stage| stage.enabled)
+            .map(|stage| format!("{} ({:?})", stage.effect.name(), stage.key))
+            .collect()
+    }
+}
+
+// Darkens the frame towards its corners.
+pub struct Vignette {
+    // How dark the corners get, 0 to 1.
+    pub strength: f32,
+}
+
+impl PostEffect for Vignette {
+    fn name(&self) -> &'static str {
+        "vineta"
+    }
+
+    fn apply(&self, framebuffer: &mut Framebuffer) {
+        let (width, height) = (framebuffer.width as f32, framebuffer.height as f32);
+        let half_diagonal = (width * width + height * height).sqrt() * 0.5;
+        framebuffer.hdr.par_chunks_mut(framebuffer.width).enumerate().for_each(|(y, row)| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let (dx, dy) = (x as f32 + 0.5 - width * 0.5, y as f32 + 0.5 - height * 0.5);
+                let distance = (dx * dx + dy * dy).sqrt() / half_diagonal;
+                *pixel *= 1.0 - self.strength * smoothstep(0.45, 1.0, distance);
+            }
+        });
+    }
+}
+
+// Raises every channel to 1 / `gamma`, brightening the mid tones while
+// black and white stay put.
+pub struct Gamma {
+    pub gamma: f32,
+}
+
+impl PostEffect for Gamma {
+    fn name(&self) -> &'static str {
+        "gamma"
+    }
+
+    fn apply(&self, framebuffer: &mut Framebuffer) {
+        let exponent = 1.0 / self.gamma;
+        framebuffer.hdr.par_iter_mut().for_each(|pixel| *pixel = pixel.map(|channel| channel.max(0.0).powf(exponent)));
+    }
+}
+
+// Softens the whole frame with a Gaussian of `sigma` pixels.
+pub struct Blur {
+    pub sigma: f32,
+}
+
+impl PostEffect for Blur {
+    fn name(&self) -> &'static str {
+        "desenfoque"
+    }
+
+    fn apply(&self, framebuffer: &mut Framebuffer) {
+        framebuffer.hdr = gaussian_blur(&framebuffer.hdr, framebuffer.width, framebuffer.height, self.sigma);
+    }
+}
+
+// Separable Gaussian blur of a `width` by `height` image, clamping samples
+// at the edges.
+pub fn gaussian_blur(source: &[Vec3], width: usize, height: usize, sigma: f32) -> Vec<Vec3> {
+    let kernel = gaussian_kernel(sigma);
+    let horizontal = blur_pass(source, width, height, &kernel, 1, width);
+    blur_pass(&horizontal, width, height, &kernel, width, 1)
+}
+
+// Weights from the center outwards, summing to 1 over both sides.
+fn gaussian_kernel(sigma: f32) -> Vec<f32> {
+    let radius = (sigma * 3.0).ceil() as usize;
+    let weights: Vec<f32> = (0..=radius).map(|offset| (-((offset * offset) as f32) / (2.0 * sigma * sigma)).exp()).collect();
+    let total = weights[0] + 2.0 * weights[1..].iter().sum::<f32>();
+    weights.iter().map(|weight| weight / total).collect()
+}
+
+// One pass of the blur, along x when `step` is 1 and along y when it is the
+// row length; `lines_step` goes from one line of the pass to the next.
+fn blur_pass(source: &[Vec3], width: usize, height: usize, kernel: &[f32], step: usize, lines_step: usize) -> Vec<Vec3> {
+    let (length, lines) = if step == 1 { (width, height) } else { (height, width) };
+    let mut output = vec![Vec3::zeros(); width * height];
+    let blurred_lines: Vec<Vec<Vec3>> = (0..lines)
+        .into_par_iter()
+        .map(|line| {
+            let start = line * lines_step;
+            (0..length)
+                .map(|position| {
+                    let mut sum = source[start + position * step] * kernel[0];
+                    for (offset, weight) in kernel.iter().enumerate().skip(1) {
+                        let before = source[start + position.saturating_sub(offset) * step];
+                        let after = source[start + (position + offset).min(length - 1) * step];
+                        sum += (before + after) * *weight;
+                    }
+                    sum
+                })
+                .collect()
+        })
+        .collect();
+    for (line, values) in blurred_lines.into_iter().enumerate() {
+        for (position, value) in values.into_iter().enumerate() {
+            output[line * lines_step + position * step] = value;
+        }
+    }
+    output
+}
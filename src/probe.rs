@@ -0,0 +1,183 @@
+use std::f32::consts::PI;
+use nalgebra_glm::Vec3;
+use crate::color::{Color, Gradient};
+use crate::particles::{EmitterParams, ParticleSystem};
+use crate::rng::{Purpose, Rng};
+use crate::scene::{Scene, SPHERE_RADIUS};
+use crate::sim::Simulation;
+use crate::timeline::{Easing, Timeline};
+
+// "neon": bright enough to spot the probe against the planets.
+pub const PROBE_SHADER: u8 = 0;
+pub const PROBE_SCALE: f32 = 0.12;
+
+const LAUNCH_FRAMES: f32 = 90.0;
+const ARRIVAL_FRAMES: f32 = 120.0;
+// Coast time of a transfer between orbits of radius 6 and 12; others scale
+// with Kepler's third law.
+const COAST_FRAMES: f32 = 420.0;
+const REFERENCE_AXIS: f32 = 9.0;
+// How far above the planet surfaces the probe starts and parks.
+const CLEARANCE: f32 = 0.6;
+const EXHAUST_COLORS: Gradient = Gradient::new(&[(0.0, Color::new(255, 220, 160)), (1.0, Color::new(255, 140, 60))]);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ProbePhase {
+    Launch,
+    Coast,
+    Arrival,
+}
+
+impl ProbePhase {
+    pub fn name(self) -> &'static str {
+        match self {
+            ProbePhase::Launch => "lanzamiento",
+            ProbePhase::Coast => "transferencia",
+            ProbePhase::Arrival => "llegada",
+        }
+    }
+}
+
+// Scripted flight from one planet to another: a launch climb, half a
+// Hohmann transfer ellipse and a braking approach into a parking spot above
+// the target.
+#[derive(Clone)]
+pub struct Probe {
+    pub origin: usize,
+    pub target: usize,
+    pub timeline: Timeline<ProbePhase>,
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub exhaust: ParticleSystem,
+    departure_angle: f32,
+    direction: f32,
+    departure_radius: f32,
+    arrival_radius: f32,
+    coast_end: Vec3,
+}
+
+impl Probe {
+    pub fn launch(scene: &Scene, sim: &Simulation, origin: usize, target: usize) -> Self {
+        let origin_body = &scene.bodies[origin];
+        let target_body = &scene.bodies[target];
+
+        // Transfer starts where the origin will be once the climb is done.
+        let departure_angle = sim.bodies[origin].orbit_angle + origin_body.orbit.mean_motion * LAUNCH_FRAMES;
+        let direction = if origin_body.is_retrograde_orbit() { -1.0 } else { 1.0 };
+        // Leave from just outside the origin and aim just short of the
+        // target, on whichever side faces the other orbit.
+        let outwards = if target_body.orbit.semi_major_axis > origin_body.orbit.semi_major_axis { 1.0 } else { -1.0 };
+        let departure_radius = origin_body.orbit.semi_major_axis + outwards * (origin_body.scale * SPHERE_RADIUS + CLEARANCE);
+        let arrival_radius = target_body.orbit.semi_major_axis - outwards * (target_body.scale * SPHERE_RADIUS + CLEARANCE);
+
+        let semi_major_axis = (departure_radius + arrival_radius) / 2.0;
+        let coast_frames = COAST_FRAMES * (semi_major_axis / REFERENCE_AXIS).powf(1.5);
+        // A transfer is fastest at periapsis: quick start when climbing
+        // outwards, quick finish when falling inwards.
+        let coast_easing = if arrival_radius > departure_radius { Easing::EaseOut } else { Easing::EaseIn };
+
+        let timeline = Timeline::new()
+            .then(ProbePhase::Launch, LAUNCH_FRAMES, Easing::EaseIn)
+            .then(ProbePhase::Coast, coast_frames, coast_easing)
+            .then(ProbePhase::Arrival, ARRIVAL_FRAMES, Easing::EaseInOut);
+
+        let exhaust_params = EmitterParams {
+            lifetime: 40.0,
+            spread: 0.3,
+            color: EXHAUST_COLORS,
+            ..EmitterParams::default()
+        };
+
+        let mut probe = Probe {
+            origin,
+            target,
+            timeline,
+            position: Vec3::zeros(),
+            velocity: Vec3::zeros(),
+            exhaust: ParticleSystem::new(exhaust_params, Rng::keyed(scene.seed, origin as u64, Purpose::Exhaust)),
+            departure_angle,
+            direction,
+            departure_radius,
+            arrival_radius,
+            coast_end: Vec3::zeros(),
+        };
+        probe.coast_end = probe.transfer_position(1.0);
+        probe.position = probe.launch_pad(scene, sim);
+        probe
+    }
+
+    pub fn phase(&self) -> Option<ProbePhase> {
+        if self.timeline.is_finished() {
+            None
+        } else {
+            self.timeline.current().map(|(phase, _)| *phase)
+        }
+    }
+
+    pub fn step(&mut self, scene: &Scene, sim: &Simulation) {
+        self.timeline.advance(1.0);
+        let Some((phase, t)) = self.timeline.current().map(|(phase, t)| (*phase, t)) else {
+            return;
+        };
+
+        let position = match phase {
+            ProbePhase::Launch => self.launch_pad(scene, sim).lerp(&self.transfer_position(0.0), t),
+            ProbePhase::Coast => self.transfer_position(t),
+            ProbePhase::Arrival => self.coast_end.lerp(&self.parking_spot(scene, sim), t),
+        };
+        self.velocity = position - self.position;
+        self.position = position;
+
+        let speed = self.velocity.magnitude();
+        let heading = if speed > 0.0 { self.velocity / speed } else { Vec3::zeros() };
+        let (rate, exhaust_velocity) = match phase {
+            ProbePhase::Launch => (6.0, -heading * 0.06),
+            // Retro burn: the plume points ahead while braking.
+            ProbePhase::Arrival if t < 0.6 => (3.0, heading * 0.04),
+            _ => (0.0, Vec3::zeros()),
+        };
+        self.exhaust.params.rate = rate;
+        self.exhaust.emit(self.position, exhaust_velocity);
+        self.exhaust.update();
+    }
+
+    // Point on the half transfer ellipse, t = 0 at departure, 1 at arrival.
+    fn transfer_position(&self, t: f32) -> Vec3 {
+        let (r1, r2) = (self.departure_radius, self.arrival_radius);
+        let semi_major_axis = (r1 + r2) / 2.0;
+        let eccentricity = (r2 - r1).abs() / (r1 + r2);
+        let swept = PI * t;
+        // Departure is the periapsis when going outwards, the apoapsis when
+        // going inwards.
+        let cosine = if r2 >= r1 { swept.cos() } else { -swept.cos() };
+        let radius = semi_major_axis * (1.0 - eccentricity * eccentricity) / (1.0 + eccentricity * cosine);
+
+        let angle = self.departure_angle + self.direction * swept;
+        Vec3::new(radius * angle.cos(), 0.0, radius * angle.sin())
+    }
+
+    fn launch_pad(&self, scene: &Scene, sim: &Simulation) -> Vec3 {
+        let radius = scene.bodies[self.origin].scale * SPHERE_RADIUS;
+        sim.body_position(scene, self.origin) + Vec3::new(0.0, radius + CLEARANCE * 0.5, 0.0)
+    }
+
+    // Above the target's north pole, where the probe stays once arrived.
+    pub fn parking_spot(&self, scene: &Scene, sim: &Simulation) -> Vec3 {
+        let radius = scene.bodies[self.target].scale * SPHERE_RADIUS;
+        sim.body_position(scene, self.target) + Vec3::new(0.0, radius + CLEARANCE, 0.0)
+    }
+}
+
+// The Earth-like planet ("azul") launches towards the next planet out, or
+// the previous one if it is the outermost.
+pub fn default_route(scene: &Scene) -> Option<(usize, usize)> {
+    let origin = scene.bodies.iter().position(|body| body.shader == 3 && body.is_planet())?;
+    let mut planets: Vec<usize> = (0..scene.bodies.len())
+        .filter(|&body_id| scene.bodies[body_id].is_planet())
+        .collect();
+    planets.sort_by(|&a, &b| scene.bodies[a].orbit.semi_major_axis.total_cmp(&scene.bodies[b].orbit.semi_major_axis));
+
+    let index = planets.iter().position(|&body_id| body_id == origin)?;
+    let target = planets.get(index + 1).or_else(|| index.checked_sub(1).and_then(|i| planets.get(i)))?;
+    Some((origin, *target))
+}
@@ -0,0 +1,34 @@
+use crate::framebuffer::Framebuffer;
+
+// Offscreen color and depth buffers of any size. The pipeline draws into the
+// inner framebuffer exactly as it does on screen; the result can then be
+// copied into another framebuffer.
+pub struct RenderTarget {
+    pub framebuffer: Framebuffer,
+}
+
+impl RenderTarget {
+    pub fn new(width: usize, height: usize) -> Self {
+        RenderTarget { framebuffer: Framebuffer::new(width, height) }
+    }
+
+    pub fn width(&self) -> usize {
+        self.framebuffer.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.framebuffer.height
+    }
+
+    // Copies the color buffer with its top-left corner at (x, y), clipped to
+    // the destination.
+    pub fn blit(&self, destination: &mut Framebuffer, x: usize, y: usize) {
+        let columns = self.width().min(destination.width.saturating_sub(x));
+        let rows = self.height().min(destination.height.saturating_sub(y));
+        for row in 0..rows {
+            let source = row * self.width();
+            let target = (y + row) * destination.width + x;
+            destination.buffer[target..target + columns].copy_from_slice(&self.framebuffer.buffer[source..source + columns]);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use std::f32::consts::PI;
+use crate::scene::Scene;
+use crate::sim::Simulation;
+
+const MAX_TERM: u32 = 6;
+// Relative error allowed between the period ratio and the small fraction.
+const TOLERANCE: f32 = 0.02;
+// Angular separation (radians) under which a resonant pair counts as lined up.
+const ALIGNMENT_WINDOW: f32 = 0.08;
+
+pub struct Resonance {
+    pub neighbor: usize,
+    // Orbits completed by the selected body and by the neighbor in the same
+    // time, e.g. 2:1 means the selected body goes around twice as often.
+    pub ratio: (u32, u32),
+    pub error: f32,
+}
+
+// Smallest p:q (both at most MAX_TERM) within tolerance of `ratio`.
+pub fn small_ratio(ratio: f32) -> Option<((u32, u32), f32)> {
+    for sum in 2..=MAX_TERM * 2 {
+        for p in 1..sum {
+            let q = sum - p;
+            if p > MAX_TERM || q > MAX_TERM || gcd(p, q) != 1 {
+                continue;
+            }
+            let error = (ratio / (p as f32 / q as f32) - 1.0).abs();
+            if error <= TOLERANCE {
+                return Some(((p, q), error));
+            }
+        }
+    }
+    None
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+// Planets on the next orbit in and out from `body_id`, ignoring the sun and
+// moons.
+pub fn neighbors(scene: &Scene, body_id: usize) -> Vec<usize> {
+    let radius = scene.bodies[body_id].orbit.semi_major_axis;
+    let planets = || {
+        scene
+            .bodies
+            .iter()
+            .enumerate()
+            .filter(move |&(other, body)| other != body_id && body.is_planet())
+    };
+    let inner = planets()
+        .filter(|(_, body)| body.orbit.semi_major_axis <= radius)
+        .max_by(|(_, a), (_, b)| a.orbit.semi_major_axis.total_cmp(&b.orbit.semi_major_axis))
+        .map(|(other, _)| other);
+    let outer = planets()
+        .filter(|(_, body)| body.orbit.semi_major_axis > radius)
+        .min_by(|(_, a), (_, b)| a.orbit.semi_major_axis.total_cmp(&b.orbit.semi_major_axis))
+        .map(|(other, _)| other);
+    inner.into_iter().chain(outer).collect()
+}
+
+pub fn resonances(scene: &Scene, body_id: usize) -> Vec<Resonance> {
+    let body = &scene.bodies[body_id];
+    if !body.is_planet() {
+        return Vec::new();
+    }
+    let Some(own_period) = body.orbit.period() else {
+        return Vec::new();
+    };
+
+    neighbors(scene, body_id)
+        .into_iter()
+        .filter_map(|neighbor| {
+            let other_period = scene.bodies[neighbor].orbit.period()?;
+            let (ratio, error) = small_ratio(other_period / own_period)?;
+            Some(Resonance { neighbor, ratio, error })
+        })
+        .collect()
+}
+
+// How lined up two bodies are as seen from the sun: 1 at conjunction, 0
+// outside the alignment window.
+pub fn alignment(sim: &Simulation, a: usize, b: usize) -> f32 {
+    let difference = (sim.bodies[a].orbit_angle - sim.bodies[b].orbit_angle).rem_euclid(2.0 * PI);
+    let separation = difference.min(2.0 * PI - difference);
+    (1.0 - separation / ALIGNMENT_WINDOW).max(0.0)
+}
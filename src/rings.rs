@@ -0,0 +1,181 @@
+use std::f32::consts::PI;
+use nalgebra_glm::{Mat4, Vec2, Vec3, Vec4};
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::noise::smoothstep;
+use crate::rng::{Purpose, Rng};
+use crate::vertex::Vertex;
+
+// Radial resolution of the color/opacity profile.
+const PROFILE_SAMPLES: usize = 512;
+// Random values across the span that the per-band grain interpolates.
+const GRAIN_KNOTS: usize = 96;
+const SEGMENTS: usize = 128;
+const PARTICLE_COUNT: usize = 2500;
+// Particles show up once the camera is closer than this many outer radii.
+pub const PARTICLE_RANGE: f32 = 3.0;
+// Dusty ice tones the bands are tinted from.
+const PALETTE: [(f32, f32, f32); 4] = [(214.0, 194.0, 154.0), (192.0, 182.0, 170.0), (228.0, 218.0, 198.0), (166.0, 144.0, 116.0)];
+
+// Ring layout from the scene file, in radii of the body it circles.
+#[derive(Clone, Debug)]
+pub struct RingDesc {
+    pub inner: f32,
+    pub outer: f32,
+    // Empty divisions like Saturn's Cassini gap.
+    pub gaps: usize,
+}
+
+struct Band {
+    end: f32,
+    color: (f32, f32, f32),
+    opacity: f32,
+}
+
+struct RingParticle {
+    radius: f32,
+    angle: f32,
+    height: f32,
+}
+
+// Concentric bands of different color and density, generated once from the
+// seed. The flat mesh lies in the body's equatorial plane, in body radii;
+// fragments look their color up in the profile by distance from the center.
+pub struct RingSystem {
+    pub body: usize,
+    pub desc: RingDesc,
+    pub mesh: Vec<Vertex>,
+    // Color and opacity from the inner to the outer edge.
+    profile: Vec<(Color, f32)>,
+    particles: Vec<RingParticle>,
+}
+
+impl RingSystem {
+    pub fn generate(desc: &RingDesc, seed: u64, body: usize) -> Self {
+        let mut rng = Rng::keyed(seed, body as u64, Purpose::Rings);
+
+        let band_count = 6 + rng.below(7);
+        let mut ends: Vec<f32> = (1..band_count).map(|_| rng.next_f32()).collect();
+        ends.push(1.0);
+        ends.sort_by(f32::total_cmp);
+        let base = PALETTE[rng.below(PALETTE.len())];
+        let bands: Vec<Band> = ends
+            .into_iter()
+            .map(|end| {
+                let tint = rng.range(0.75, 1.1);
+                Band {
+                    end,
+                    color: (base.0 * tint, base.1 * rng.range(0.9, 1.05) * tint, base.2 * rng.range(0.85, 1.05) * tint),
+                    opacity: rng.range(0.25, 0.9),
+                }
+            })
+            .collect();
+
+        // Gaps stay clear of the edges, where they would just look thinner.
+        let gaps: Vec<(f32, f32)> = (0..desc.gaps).map(|_| (rng.range(0.2, 0.85), rng.range(0.008, 0.025))).collect();
+        let grain: Vec<f32> = (0..=GRAIN_KNOTS).map(|_| rng.range(0.55, 1.0)).collect();
+        let grain_at = |t: f32| {
+            let position = t * GRAIN_KNOTS as f32;
+            let knot = (position as usize).min(GRAIN_KNOTS - 1);
+            let blend = smoothstep(0.0, 1.0, position - knot as f32);
+            grain[knot] + (grain[knot + 1] - grain[knot]) * blend
+        };
+
+        let profile = (0..PROFILE_SAMPLES)
+            .map(|sample| {
+                let t = (sample as f32 + 0.5) / PROFILE_SAMPLES as f32;
+                let band = bands.iter().find(|band| t <= band.end).unwrap_or(&bands[bands.len() - 1]);
+                let grain = grain_at(t);
+                let mut opacity = band.opacity * grain;
+                // Soft inner and outer edges.
+                opacity *= smoothstep(0.0, 0.03, t) * smoothstep(1.0, 0.97, t);
+                for &(center, half_width) in &gaps {
+                    opacity *= smoothstep(half_width, half_width * 1.6, (t - center).abs());
+                }
+                let shade = 0.85 + 0.15 * grain;
+                let color = Color::new(band.color.0 as u8, band.color.1 as u8, band.color.2 as u8) * shade;
+                (color, opacity)
+            })
+            .collect::<Vec<_>>();
+
+        // Sprinkled where the ring is dense, so gaps stay empty up close too.
+        let mut particles = Vec::with_capacity(PARTICLE_COUNT);
+        while particles.len() < PARTICLE_COUNT {
+            let t = rng.next_f32();
+            if rng.next_f32() > profile[(t * PROFILE_SAMPLES as f32) as usize % PROFILE_SAMPLES].1 {
+                continue;
+            }
+            particles.push(RingParticle {
+                radius: desc.inner + (desc.outer - desc.inner) * t,
+                angle: rng.range(0.0, 2.0 * PI),
+                height: rng.range(-0.01, 0.01),
+            });
+        }
+
+        RingSystem { body, desc: desc.clone(), mesh: annulus(desc.inner, desc.outer), profile, particles }
+    }
+
+    // Color and opacity at `radius` body radii from the center, or None off
+    // the ring.
+    pub fn sample(&self, radius: f32) -> Option<(Color, f32)> {
+        let t = (radius - self.desc.inner) / (self.desc.outer - self.desc.inner);
+        if !(0.0..1.0).contains(&t) {
+            return None;
+        }
+        Some(self.profile[(t * PROFILE_SAMPLES as f32) as usize])
+    }
+
+    // Additive specks orbiting in the ring plane; inner ones go faster.
+    // `transform` takes ring space to the screen.
+    pub fn render_particles(&self, framebuffer: &mut Framebuffer, transform: &Mat4, time: f32, light: f32) {
+        for particle in &self.particles {
+            let angle = particle.angle + time * 0.6 * (self.desc.inner / particle.radius).powf(1.5);
+            let (sin, cos) = angle.sin_cos();
+            let clip = transform * Vec4::new(cos * particle.radius, particle.height, sin * particle.radius, 1.0);
+            if clip.w <= 0.0 {
+                continue;
+            }
+            let (x, y) = (clip.x / clip.w, clip.y / clip.w);
+            if x < 0.0 || y < 0.0 {
+                continue;
+            }
+            let Some((color, _)) = self.sample(particle.radius) else {
+                continue;
+            };
+            framebuffer.add_point(x as usize, y as usize, clip.z / clip.w, (color * (0.35 * light)).to_hex());
+        }
+    }
+
+    pub fn particle_bytes(&self) -> usize {
+        self.particles.capacity() * std::mem::size_of::<RingParticle>()
+    }
+}
+
+// Brightness of a ring placed by `model_matrix` with the sun at the origin:
+// full face-on to the sun, dim edge-on; both faces catch light.
+pub fn light(model_matrix: &Mat4) -> f32 {
+    let plane_normal = (model_matrix * Vec4::new(0.0, 1.0, 0.0, 0.0)).xyz().normalize();
+    let to_sun = (-model_matrix.column(3).xyz()).try_normalize(1.0e-6).unwrap_or(plane_normal);
+    0.3 + 0.7 * plane_normal.dot(&to_sun).abs()
+}
+
+// Flat ring in the XZ plane facing +Y. Culling has to be off to see it from
+// below.
+pub fn annulus(inner: f32, outer: f32) -> Vec<Vertex> {
+    let normal = Vec3::y();
+    let point = |segment: usize, radius: f32| {
+        let angle = segment as f32 / SEGMENTS as f32 * 2.0 * PI;
+        let (sin, cos) = angle.sin_cos();
+        let u = segment as f32 / SEGMENTS as f32;
+        let v = if radius == inner { 0.0 } else { 1.0 };
+        Vertex::new(Vec3::new(cos * radius, 0.0, sin * radius), normal, Vec2::new(u, v))
+    };
+
+    let mut vertices = Vec::with_capacity(SEGMENTS * 6);
+    for segment in 0..SEGMENTS {
+        let (a, b) = (point(segment, inner), point(segment, outer));
+        let (c, d) = (point(segment + 1, inner), point(segment + 1, outer));
+        vertices.extend([a, c.clone(), b.clone(), b, c, d]);
+    }
+    vertices
+}
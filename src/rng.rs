@@ -0,0 +1,112 @@
+// Small deterministic RNG (SplitMix64). Unlike `rand`'s default generators
+// its output is fixed by the algorithm itself, so a given seed produces the
+// same universe on every run and platform.
+
+#[derive(Clone, Copy)]
+pub enum Purpose {
+    Shader,
+    System,
+    IonTail,
+    DustTail,
+    Exhaust,
+    Impact,
+    SolarWind,
+    Asteroid,
+    Rings,
+    Starfield,
+    Belt,
+    Flares,
+}
+
+#[derive(Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    // Independent stream for one body and one use, so adding a new consumer
+    // never shifts the numbers another consumer sees.
+    pub fn keyed(seed: u64, body_id: u64, purpose: Purpose) -> Self {
+        let key = mix(seed ^ mix(body_id.wrapping_add(0x9E37_79B9_7F4A_7C15) ^ mix(purpose as u64 + 1)));
+        Rng::new(key)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        mix(self.state)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    // Uniform in [0, 1).
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+// Seed for a FastNoiseLite instance belonging to one body.
+pub fn noise_seed(seed: u64, body_id: u64) -> i32 {
+    Rng::keyed(seed, body_id, Purpose::Shader).next_u32() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first(mut rng: Rng) -> [u64; 4] {
+        std::array::from_fn(|_| rng.next_u64())
+    }
+
+    #[test]
+    fn matches_reference_splitmix64() {
+        assert_eq!(first(Rng::new(0))[..2], [0xE220_A839_7B1D_CDAF, 0x6E78_9E6A_A1B9_65F4]);
+    }
+
+    #[test]
+    fn keyed_streams_repeat_for_the_same_key() {
+        assert_eq!(first(Rng::keyed(42, 3, Purpose::System)), first(Rng::keyed(42, 3, Purpose::System)));
+        assert_eq!(noise_seed(42, 3), noise_seed(42, 3));
+    }
+
+    #[test]
+    fn keyed_streams_differ_by_seed_body_and_purpose() {
+        let base = first(Rng::keyed(42, 3, Purpose::System));
+        assert_ne!(base, first(Rng::keyed(43, 3, Purpose::System)));
+        assert_ne!(base, first(Rng::keyed(42, 4, Purpose::System)));
+        assert_ne!(base, first(Rng::keyed(42, 3, Purpose::Shader)));
+        // Swapping body and seed must not land on the same stream either.
+        assert_ne!(first(Rng::keyed(1, 2, Purpose::Shader)), first(Rng::keyed(2, 1, Purpose::Shader)));
+        assert_ne!(noise_seed(42, 3), noise_seed(42, 4));
+    }
+
+    #[test]
+    fn floats_and_bounds_stay_in_range() {
+        let mut rng = Rng::new(7);
+        for _ in 0..10_000 {
+            let unit = rng.next_f32();
+            assert!((0.0..1.0).contains(&unit));
+            assert!((-2.0..3.0).contains(&rng.range(-2.0, 3.0)));
+            assert!(rng.below(5) < 5);
+        }
+    }
+}
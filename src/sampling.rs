@@ -0,0 +1,112 @@
+use nalgebra_glm::Vec2;
+use crate::color::Color;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Filter {
+    // The closest texel as is, crisp edges for pixel art.
+    Nearest,
+    // Blends the four texels around the sample point.
+    Bilinear,
+}
+
+// What happens to coordinates outside [0, 1].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Wrap {
+    Repeat,
+    Clamp,
+    // Repeats, flipping every other copy so edges always meet.
+    Mirror,
+}
+
+// How an image is read at texture coordinates. UVs follow the usual
+// convention: (0, 0) is the bottom-left corner and v grows upwards.
+#[derive(Clone, Copy, Debug)]
+pub struct Sampler {
+    pub filter: Filter,
+    pub wrap_u: Wrap,
+    pub wrap_v: Wrap,
+}
+
+impl Sampler {
+    pub const fn new(filter: Filter, wrap_u: Wrap, wrap_v: Wrap) -> Self {
+        Sampler { filter, wrap_u, wrap_v }
+    }
+
+    // `fetch` reads the texel at a column and row of an image that size,
+    // with row 0 at the top.
+    pub fn sample(&self, (width, height): (u32, u32), fetch: impl Fn(u32, u32) -> Color, uv: Vec2) -> Color {
+        // Texel space, with row 0 at the top of the image.
+        let x = uv.x * width as f32;
+        let y = (1.0 - uv.y) * height as f32;
+
+        let texel = |column: i64, row: i64| fetch(wrap(column, width, self.wrap_u), wrap(row, height, self.wrap_v));
+
+        match self.filter {
+            Filter::Nearest => texel(x.floor() as i64, y.floor() as i64),
+            Filter::Bilinear => {
+                // Texel centers sit at half coordinates.
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (column, row) = (x.floor(), y.floor());
+                let (fx, fy) = (x - column, y - row);
+                let (column, row) = (column as i64, row as i64);
+
+                let top = texel(column, row).lerp(&texel(column + 1, row), fx);
+                let bottom = texel(column, row + 1).lerp(&texel(column + 1, row + 1), fx);
+                top.lerp(&bottom, fy)
+            }
+        }
+    }
+}
+
+impl Filter {
+    pub const NAMES: [&'static str; 2] = ["nearest", "bilinear"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "nearest" => Some(Filter::Nearest),
+            "bilinear" => Some(Filter::Bilinear),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Filter::Nearest => "nearest",
+            Filter::Bilinear => "bilinear",
+        }
+    }
+}
+
+impl Wrap {
+    pub const NAMES: [&'static str; 3] = ["repeat", "clamp", "mirror"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "repeat" => Some(Wrap::Repeat),
+            "clamp" => Some(Wrap::Clamp),
+            "mirror" => Some(Wrap::Mirror),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Wrap::Repeat => "repeat",
+            Wrap::Clamp => "clamp",
+            Wrap::Mirror => "mirror",
+        }
+    }
+}
+
+fn wrap(index: i64, size: u32, mode: Wrap) -> u32 {
+    let size = size as i64;
+    let wrapped = match mode {
+        Wrap::Repeat => index.rem_euclid(size),
+        Wrap::Clamp => index.clamp(0, size - 1),
+        Wrap::Mirror => {
+            let period = index.rem_euclid(size * 2);
+            if period < size { period } else { size * 2 - 1 - period }
+        }
+    };
+    wrapped as u32
+}
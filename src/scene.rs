@@ -0,0 +1,1497 @@
+use std::f32::consts::PI;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use nalgebra_glm::Vec3;
+use crate::atmosphere;
+use crate::camera::DEFAULT_FOV;
+use crate::color::Color;
+use crate::habitable::{main_sequence_luminosity, HabitableZone, SUN_TEMPERATURE};
+use crate::heatmap::HeatPalette;
+use crate::material::Material;
+use crate::variability::{LightCurve, Variability};
+use crate::noise::NoisePreset;
+use crate::orbit::Orbit;
+use crate::planet_data::{self, PLANETS};
+use crate::rings::RingDesc;
+use crate::rng::{Purpose, Rng};
+use crate::sampling::{Filter, Wrap};
+use crate::shaders::{shader_id, SHADER_NAMES};
+use crate::sim::STEPS_PER_SECOND;
+use crate::spawn::Template;
+use crate::starfield::{StarfieldDesc, DEFAULT_DENSITY};
+use crate::texture::{ColorSpace, PLANET_WRAP};
+use crate::belt::{self, BeltDesc};
+use crate::timeline::{Caption, CaptionTrack};
+use crate::toml_lite::{self, Table, Value};
+use crate::units::{speed_from_period, Quantity, UnitScale};
+
+// Radius of assets/models/sphere.obj before scaling.
+pub const SPHERE_RADIUS: f32 = 0.5;
+pub const DEFAULT_SEED: u64 = 1337;
+
+const SUN_SHADER: u8 = 6;
+const TEXTURE_SHADER: &str = "textura";
+// `mesh` value that asks for a generated rock instead of a file.
+pub const ASTEROID_MESH: &str = "asteroide";
+const PLANET_SHADERS: [u8; 9] = [0, 1, 2, 3, 4, 5, 7, 8, 9];
+
+// Layout of the real system preset at any compression: where the outermost
+// orbit lands, Earth's size and how long its year lasts on screen. Spins use
+// their own, much slower clock so fast rotators don't strobe.
+pub const DEFAULT_COMPRESSION: f32 = 0.5;
+const REAL_OUTER_ORBIT: f32 = 60.0;
+const REAL_EARTH_SCALE: f32 = 0.6;
+const REAL_YEAR_SECONDS: f32 = 20.0;
+const REAL_SPIN_SECONDS: f32 = 5.0;
+
+const SCENE_KEYS: [&str; 3] = ["name", "seed", "heat_palette"];
+const UNITS_KEYS: [&str; 3] = ["distance_scale", "size_scale", "day_length"];
+const HABITABLE_KEYS: [&str; 4] = ["inner", "outer", "star_temperature", "star_luminosity"];
+const COMET_KEYS: [&str; 9] = [
+    "name", "scale", "semi_major_axis", "eccentricity", "periapsis", "inclination",
+    "mean_anomaly", "orbital_speed", "orbital_period",
+];
+const CAPTION_KEYS: [&str; 4] = ["text", "start", "duration", "shot"];
+const SHOT_KEYS: [&str; 5] = ["name", "eye", "center", "focus", "fov"];
+// Narrowest and widest field of view a shot may ask for.
+const MIN_FOV: f32 = 5.0 * PI / 180.0;
+const MAX_FOV: f32 = 120.0 * PI / 180.0;
+const CAMERA_KEYS: [&str; 3] = ["eye", "center", "focus"];
+const STARFIELD_KEYS: [&str; 3] = ["density", "seed", "twinkle"];
+const BELT_KEYS: [&str; 4] = ["inner", "outer", "count", "scale"];
+const MAX_BELT_ROCKS: usize = 5000;
+// More than this many stars per square degree would take longer to draw
+// than the planets.
+const MAX_STAR_DENSITY: f32 = 10.0;
+const BODY_KEYS: [&str; 34] = [
+    "name", "shader", "noise", "scale", "radius", "orbit_radius", "orbit_phase",
+    "eccentricity", "inclination", "periapsis",
+    "rotation_speed", "rotation_period", "axial_tilt", "orbital_speed", "orbital_period",
+    "retrograde_orbit", "retrograde_rotation", "magnetosphere", "texture", "texture_wrap", "texture_filter", "texture_space",
+    "parent", "mesh",
+    "ring_inner", "ring_outer", "ring_gaps", "clouds", "atmosphere", "material", "lensing",
+    "variability_period", "variability_amplitude", "light_curve",
+];
+
+pub struct BodyDesc {
+    pub name: String,
+    pub shader: u8,
+    pub noise: NoisePreset,
+    // How glossy the surface is.
+    pub material: Material,
+    pub scale: f32,
+    // A zero semi-major axis marks the sun.
+    pub orbit: Orbit,
+    pub rotation_speed: f32,
+    // Lean of the spin axis from the orbit's up direction, in radians.
+    pub axial_tilt: f32,
+    // Bodies with an atmosphere or magnetic field deflect the solar wind.
+    pub magnetosphere: bool,
+    // A cloud deck whose shadows drift over the surface.
+    pub clouds: bool,
+    // Color of the glow around the limb, for bodies with air.
+    pub atmosphere: Option<Color>,
+    // Einstein radius, in body radii, of an ultra-massive body that bends
+    // the starlight behind it.
+    pub lensing: Option<f32>,
+    // A star whose brightness, and the light it sheds, rises and falls.
+    pub variability: Option<Variability>,
+    pub texture: Option<String>,
+    // How the texture is addressed past its edges, as (u, v).
+    pub texture_wrap: (Wrap, Wrap),
+    // Bilinear, or Nearest to keep the texels' hard edges.
+    pub texture_filter: Filter,
+    // Srgb for color maps, Linear for data maps read as plain values.
+    pub texture_space: ColorSpace,
+    // OBJ model drawn instead of the sphere, or ASTEROID_MESH.
+    pub mesh: Option<String>,
+    // Moons orbit the body at this index, which always comes earlier in the
+    // list, instead of the sun.
+    pub parent: Option<usize>,
+    pub rings: Option<RingDesc>,
+    // Template of a body added while the viewer runs; None for bodies from
+    // the scene itself.
+    pub spawned: Option<Template>,
+}
+
+impl BodyDesc {
+    pub fn new(name: &str, shader: u8, scale: f32, orbit_radius: f32, rotation_speed: f32, orbital_speed: f32) -> Self {
+        BodyDesc {
+            name: name.to_string(),
+            shader,
+            noise: NoisePreset::Plain,
+            material: Material::for_shader(shader),
+            scale,
+            orbit: Orbit::circular(orbit_radius, orbital_speed),
+            rotation_speed,
+            axial_tilt: 0.0,
+            magnetosphere: false,
+            clouds: false,
+            atmosphere: None,
+            lensing: None,
+            variability: None,
+            texture: None,
+            texture_wrap: PLANET_WRAP,
+            texture_filter: Filter::Bilinear,
+            texture_space: ColorSpace::Srgb,
+            mesh: None,
+            parent: None,
+            rings: None,
+            spawned: None,
+        }
+    }
+
+    fn with_magnetosphere(mut self) -> Self {
+        self.magnetosphere = true;
+        self
+    }
+
+    // Negative speeds run clockwise seen from above, like Venus' spin or
+    // Triton's orbit.
+    pub fn is_retrograde_orbit(&self) -> bool {
+        self.orbit.mean_motion < 0.0
+    }
+
+    pub fn is_retrograde_rotation(&self) -> bool {
+        self.rotation_speed < 0.0
+    }
+
+    // Orbits the sun, as opposed to being the sun or a moon.
+    pub fn is_planet(&self) -> bool {
+        self.orbit.semi_major_axis > 0.0 && self.parent.is_none()
+    }
+}
+
+// Comets orbit the sun (at the origin) like bodies, but carry dust and ion
+// tails and are never selected.
+pub struct CometDesc {
+    pub name: String,
+    pub scale: f32,
+    pub orbit: Orbit,
+    // Added while the viewer runs rather than read from the scene.
+    pub spawned: bool,
+}
+
+// Where the camera starts. With `focus` set it eases onto that body and
+// follows it, as if it had been picked from the gallery.
+pub struct CameraStart {
+    pub eye: Vec3,
+    pub center: Vec3,
+    pub focus: Option<usize>,
+}
+
+impl Default for CameraStart {
+    fn default() -> Self {
+        CameraStart { eye: Vec3::new(0.0, 0.0, 20.0), center: Vec3::zeros(), focus: None }
+    }
+}
+
+// A named viewpoint the camera flies to when picked (F8 or the console) or
+// cued by a caption. With `focus` set, `eye` and `center` are measured from
+// that body wherever it is, and the camera follows it once there.
+pub struct Shot {
+    pub name: String,
+    pub eye: Vec3,
+    pub center: Vec3,
+    pub focus: Option<usize>,
+    // Vertical field of view, in radians.
+    pub fov: f32,
+}
+
+pub struct Scene {
+    pub name: String,
+    pub seed: u64,
+    pub bodies: Vec<BodyDesc>,
+    pub comets: Vec<CometDesc>,
+    // Scene units per astronomical unit.
+    pub au: f32,
+    // Sim seconds per day.
+    pub day_length: f32,
+    pub heat_palette: HeatPalette,
+    pub habitable_zone: Option<HabitableZone>,
+    // Narration timed against the simulation clock.
+    pub captions: CaptionTrack,
+    pub camera: CameraStart,
+    pub shots: Vec<Shot>,
+    // Generated stars instead of the sky image.
+    pub starfield: Option<StarfieldDesc>,
+    pub asteroid_belt: Option<BeltDesc>,
+    // Names of the scene's bodies deleted while the viewer runs, so saves
+    // can delete them again.
+    pub deleted: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub file: String,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.message)
+    }
+}
+
+impl Scene {
+    pub fn default_system() -> Self {
+        Scene {
+            name: "Sistema Solar".to_string(),
+            seed: DEFAULT_SEED,
+            bodies: vec![
+                BodyDesc::new("Sol", 6, 2.0, 0.0, 0.0, 0.0),
+                BodyDesc::new("Raro", 1, 0.5, 3.0, 0.05, 0.02),
+                BodyDesc::new("Saturno", 2, 0.7, 6.0, 0.03, 0.015).with_magnetosphere(),
+                BodyDesc::new("Azul", 3, 0.9, 9.0, 0.02, 0.01).with_magnetosphere(),
+                BodyDesc::new("Celular", 4, 1.2, 12.0, 0.01, 0.007),
+                BodyDesc::new("Mancha", 5, 1.5, 15.0, -0.04, 0.005),
+                BodyDesc::new("Rocoso", 7, 1.7, 18.0, 0.02, 0.003),
+                BodyDesc::new("Gaseoso", 8, 1.8, 21.0, 0.03, 0.002).with_magnetosphere(),
+            ],
+            comets: vec![CometDesc {
+                name: "Halley".to_string(),
+                scale: 0.25,
+                orbit: Orbit {
+                    semi_major_axis: 13.0,
+                    eccentricity: 0.8,
+                    inclination: 0.35,
+                    periapsis: 0.6,
+                    mean_motion: speed_from_period(40.0),
+                    phase: 2.5,
+                },
+                spawned: false,
+            }],
+            au: UnitScale::default().distance_scale,
+            day_length: UnitScale::default().day_length,
+            heat_palette: HeatPalette::Thermal,
+            habitable_zone: Some(HabitableZone::from_star(SUN_TEMPERATURE, 1.0, UnitScale::default().distance_scale)),
+            captions: CaptionTrack::default(),
+            camera: CameraStart::default(),
+            shots: Vec::new(),
+            starfield: None,
+            asteroid_belt: None,
+            deleted: Vec::new(),
+        }
+    }
+
+    // Generates a plausible system from a seed: well separated orbits with
+    // roughly Keplerian speeds, so nothing overlaps or races around.
+    pub fn random_system(seed: u64) -> Self {
+        let mut rng = Rng::keyed(seed, 0, Purpose::System);
+
+        let sun_scale = rng.range(1.6, 2.6);
+        let mut bodies = vec![BodyDesc::new("Sol", SUN_SHADER, sun_scale, 0.0, 0.0, 0.0)];
+
+        let planet_count = 4 + rng.below(6);
+        let mut orbit_radius = 0.0;
+        let mut previous_scale = sun_scale;
+        for i in 0..planet_count {
+            let scale = rng.range(0.4, 1.9);
+            orbit_radius += (previous_scale + scale) * SPHERE_RADIUS + rng.range(1.0, 3.0);
+            previous_scale = scale;
+
+            let shader = PLANET_SHADERS[rng.below(PLANET_SHADERS.len())];
+            let orbital_speed = 0.1 * (3.0 / orbit_radius).powf(1.5);
+            let rotation_speed = rng.range(0.005, 0.05);
+
+            let mut body = BodyDesc::new(&format!("Planeta {}", i + 1), shader, scale, orbit_radius, rotation_speed, orbital_speed);
+            body.orbit.phase = rng.range(0.0, 2.0 * PI);
+            body.magnetosphere = scale > 1.2;
+            bodies.push(body);
+        }
+
+        Scene {
+            name: format!("Sistema aleatorio {}", seed),
+            seed,
+            bodies,
+            comets: Vec::new(),
+            au: UnitScale::default().distance_scale,
+            day_length: UnitScale::default().day_length,
+            heat_palette: HeatPalette::Thermal,
+            habitable_zone: None,
+            captions: CaptionTrack::default(),
+            camera: CameraStart::default(),
+            shots: Vec::new(),
+            starfield: None,
+            asteroid_belt: None,
+            deleted: Vec::new(),
+        }
+    }
+
+    // The eight planets with their real radii, distances, periods and orbit
+    // shapes. Every ratio between them is raised to `1 - compression`: 0
+    // keeps true proportions, 1 makes them all alike. Sizes and distances
+    // are scaled separately either way, or the planets would be specks.
+    pub fn real_system(compression: f32) -> Self {
+        let exponent = 1.0 - compression;
+        let outermost = PLANETS.iter().map(|planet| planet.semi_major_axis_au).fold(0.0, f32::max);
+        let distance = |au: f32| REAL_OUTER_ORBIT * (au / outermost).powf(exponent);
+        let size = |radius_km: f32| REAL_EARTH_SCALE * (radius_km / planet_data::EARTH_RADIUS_KM).powf(exponent);
+        let year = |days: f32| REAL_YEAR_SECONDS * (days / planet_data::EARTH_YEAR_DAYS).powf(exponent);
+        let spin = |hours: f32| hours.signum() * speed_from_period(REAL_SPIN_SECONDS * (hours.abs() / 24.0).powf(exponent));
+
+        // At true proportions the sun would swallow Mercury; it is kept
+        // inside the innermost periapsis instead.
+        let innermost = PLANETS
+            .iter()
+            .map(|planet| distance(planet.semi_major_axis_au) * (1.0 - planet.eccentricity))
+            .fold(f32::INFINITY, f32::min);
+        let sun_scale = size(planet_data::SUN_RADIUS_KM).min(0.6 * innermost / SPHERE_RADIUS);
+        let mut sun = BodyDesc::new("Sol", SUN_SHADER, sun_scale, 0.0, spin(planet_data::SUN_ROTATION_PERIOD_HOURS), 0.0);
+        sun.axial_tilt = planet_data::SUN_AXIAL_TILT_DEGREES.to_radians();
+        let mut bodies = vec![sun];
+
+        for planet in &PLANETS {
+            let mut body = BodyDesc::new(
+                planet.name,
+                planet.shader,
+                size(planet.radius_km),
+                distance(planet.semi_major_axis_au),
+                spin(planet.rotation_period_hours),
+                speed_from_period(year(planet.orbital_period_days)),
+            );
+            body.orbit.eccentricity = planet.eccentricity;
+            body.orbit.inclination = planet.inclination_degrees.to_radians();
+            body.orbit.periapsis = planet.periapsis_degrees.to_radians();
+            body.orbit.phase = (planet.mean_longitude_degrees - planet.periapsis_degrees).to_radians();
+            body.axial_tilt = planet.axial_tilt_degrees.to_radians();
+            body.magnetosphere = planet.magnetosphere;
+            body.clouds = planet.clouds;
+            body.atmosphere = planet.atmosphere;
+            if let Some(material) = planet.material {
+                body.material = material;
+            }
+            body.rings = planet.rings.map(|(inner, outer)| RingDesc { inner, outer, gaps: 1 });
+            bodies.push(body);
+        }
+
+        let au = distance(1.0);
+        Scene {
+            name: "Sistema Solar real".to_string(),
+            seed: DEFAULT_SEED,
+            bodies,
+            comets: Vec::new(),
+            au,
+            day_length: REAL_YEAR_SECONDS / planet_data::EARTH_YEAR_DAYS,
+            heat_palette: HeatPalette::Thermal,
+            habitable_zone: Some(HabitableZone::from_star(SUN_TEMPERATURE, 1.0, au)),
+            captions: CaptionTrack::default(),
+            camera: CameraStart { eye: Vec3::new(0.0, 25.0, 75.0), center: Vec3::zeros(), focus: None },
+            shots: Vec::new(),
+            starfield: None,
+            asteroid_belt: None,
+            deleted: Vec::new(),
+        }
+    }
+
+    // Loads and validates a scene file. Every problem found is reported, not
+    // just the first one, so a broken file can be fixed in a single pass.
+    pub fn load(path: &str) -> Result<Self, Vec<Diagnostic>> {
+        match fs::read_to_string(path) {
+            Ok(source) => Scene::parse(path, &source),
+            Err(e) => Err(vec![Diagnostic { file: path.to_string(), line: 0, message: format!("cannot read scene file: {}", e) }]),
+        }
+    }
+
+    // Validates the text of a scene file; `file` only labels diagnostics.
+    fn parse(file: &str, source: &str) -> Result<Self, Vec<Diagnostic>> {
+        let mut validator = Validator {
+            file: file.to_string(),
+            diagnostics: Vec::new(),
+            units: UnitScale::default(),
+        };
+
+        let document = match toml_lite::parse(source) {
+            Ok(document) => document,
+            Err(errors) => {
+                for error in errors {
+                    validator.error(error.line, error.message);
+                }
+                return Err(validator.diagnostics);
+            }
+        };
+
+        validator.check_keys(&document.root, &SCENE_KEYS);
+        let name = validator.string(&document.root, "name").unwrap_or_else(|| "Escena".to_string());
+        let seed = validator.seed(&document.root).unwrap_or(DEFAULT_SEED);
+
+        let heat_palette = match validator.string(&document.root, "heat_palette") {
+            Some(palette_name) => HeatPalette::from_name(&palette_name).unwrap_or_else(|| {
+                validator.error(
+                    Validator::line_of(&document.root, "heat_palette"),
+                    format!("unknown heat palette `{}` (available: {})", palette_name, HeatPalette::NAMES.join(", ")),
+                );
+                HeatPalette::Thermal
+            }),
+            None => HeatPalette::Thermal,
+        };
+
+        // Units must be known before any body values are converted.
+        if let Some(units) = document.table("units") {
+            validator.units(units);
+        }
+
+        let mut bodies = Vec::new();
+        let mut body_lines = Vec::new();
+        let mut comet_tables = Vec::new();
+        let mut caption_tables = Vec::new();
+        let mut shot_tables = Vec::new();
+        // Index of the latest `[[body]]`, which `[[body.moon]]` tables orbit;
+        // Some(None) when that body had errors.
+        let mut planet: Option<Option<usize>> = None;
+        for table in &document.tables {
+            match table.name.as_str() {
+                "units" | "habitable_zone" | "camera" | "starfield" | "asteroid_belt" => {}
+                "comet" => comet_tables.push(table),
+                "caption" => caption_tables.push(table),
+                "shot" => shot_tables.push(table),
+                "body" => {
+                    planet = Some(None);
+                    if let Some(body) = validator.body(table, &bodies, None) {
+                        planet = Some(Some(bodies.len()));
+                        body_lines.push(table.line);
+                        bodies.push(body);
+                    }
+                }
+                "body.moon" => match planet {
+                    None => validator.error(table.line, "[[body.moon]] must come after the [[body]] it orbits".to_string()),
+                    Some(None) => {}
+                    Some(Some(planet_id)) => {
+                        if let Some(body) = validator.body(table, &bodies, Some(planet_id)) {
+                            body_lines.push(table.line);
+                            bodies.push(body);
+                        }
+                    }
+                },
+                _ => validator.error(table.line, format!("unknown table `[{}]`", table.name)),
+            }
+        }
+
+        let habitable_zone = document.table("habitable_zone").and_then(|table| validator.habitable_zone(table));
+        let camera = document.table("camera").map_or_else(CameraStart::default, |table| validator.camera(table, &bodies));
+        let starfield = document.table("starfield").and_then(|table| validator.starfield(table, seed));
+        let asteroid_belt = document.table("asteroid_belt").and_then(|table| validator.asteroid_belt(table));
+
+        // Comets are checked against the sun, so bodies go first.
+        let sun_radius = bodies
+            .iter()
+            .filter(|body| body.orbit.semi_major_axis == 0.0)
+            .map(|body| body.scale * SPHERE_RADIUS)
+            .fold(0.0, f32::max);
+        let comets = comet_tables
+            .into_iter()
+            .filter_map(|table| validator.comet(table, sun_radius))
+            .collect();
+
+        if bodies.is_empty() && validator.diagnostics.is_empty() {
+            validator.error(0, "scene defines no `[[body]]` entries".to_string());
+        }
+
+        validator.check_duplicate_names(&bodies, &body_lines);
+        validator.check_overlapping_orbits(&bodies, &body_lines);
+
+        // Shots name bodies and captions name shots, so they come last.
+        let mut shots: Vec<Shot> = Vec::new();
+        for table in shot_tables {
+            if let Some(shot) = validator.shot(table, &bodies, &shots) {
+                shots.push(shot);
+            }
+        }
+        let mut captions: Vec<Caption> = caption_tables.into_iter().filter_map(|table| validator.caption(table, &shots)).collect();
+        captions.sort_by(|a: &Caption, b: &Caption| a.start.total_cmp(&b.start));
+        let captions = CaptionTrack { captions };
+
+        if validator.diagnostics.is_empty() {
+            Ok(Scene { name, seed, bodies, comets, au: validator.units.distance_scale, day_length: validator.units.day_length, heat_palette, habitable_zone, captions, camera, shots, starfield, asteroid_belt, deleted: Vec::new() })
+        } else {
+            validator.diagnostics.sort_by_key(|diagnostic| diagnostic.line);
+            Err(validator.diagnostics)
+        }
+    }
+
+    // Deletes the given bodies along with every moon orbiting them, and
+    // returns the indices that went, in increasing order. Parents of the
+    // bodies left are renumbered; see `remap` for everything else.
+    pub fn remove_bodies(&mut self, body_ids: &[usize]) -> Vec<usize> {
+        let mut removed: Vec<usize> = body_ids.iter().copied().filter(|&body_id| body_id < self.bodies.len()).collect();
+        // Parents always come before their moons, so one pass finds them all.
+        for (body_id, body) in self.bodies.iter().enumerate() {
+            if body.parent.is_some_and(|parent| removed.contains(&parent)) && !removed.contains(&body_id) {
+                removed.push(body_id);
+            }
+        }
+        removed.sort_unstable();
+        removed.dedup();
+
+        for &body_id in removed.iter().rev() {
+            let body = self.bodies.remove(body_id);
+            if body.spawned.is_none() {
+                self.deleted.push(body.name);
+            }
+        }
+        for body in &mut self.bodies {
+            body.parent = body.parent.and_then(|parent| remap(parent, &removed));
+        }
+        // Shots of a deleted body go with it; captions cueing them just show.
+        self.shots.retain(|shot| shot.focus.is_none_or(|body_id| remap(body_id, &removed).is_some()));
+        for shot in &mut self.shots {
+            shot.focus = shot.focus.and_then(|body_id| remap(body_id, &removed));
+        }
+        removed
+    }
+}
+
+// Where a body index points after `removed` (as returned by
+// `remove_bodies`) are gone, or None if it was one of them.
+pub fn remap(body_id: usize, removed: &[usize]) -> Option<usize> {
+    match removed.binary_search(&body_id) {
+        Ok(_) => None,
+        Err(below) => Some(body_id - below),
+    }
+}
+
+struct Validator {
+    file: String,
+    diagnostics: Vec<Diagnostic>,
+    units: UnitScale,
+}
+
+impl Validator {
+    fn error(&mut self, line: usize, message: String) {
+        self.diagnostics.push(Diagnostic { file: self.file.clone(), line, message });
+    }
+
+    fn check_keys(&mut self, table: &Table, allowed: &[&str]) {
+        for entry in &table.entries {
+            if !allowed.contains(&entry.key.as_str()) {
+                self.error(entry.line, format!("unknown key `{}` (expected one of: {})", entry.key, allowed.join(", ")));
+            }
+        }
+    }
+
+    fn string(&mut self, table: &Table, key: &str) -> Option<String> {
+        let entry = table.get(key)?;
+        match &entry.value {
+            Value::String(value) => Some(value.clone()),
+            other => {
+                self.error(entry.line, format!("`{}` must be a string, found {}", key, other.type_name()));
+                None
+            }
+        }
+    }
+
+    fn flag(&mut self, table: &Table, key: &str) -> Option<bool> {
+        let entry = table.get(key)?;
+        match &entry.value {
+            Value::Bool(value) => Some(*value),
+            other => {
+                self.error(entry.line, format!("`{}` must be true or false, found {}", key, other.type_name()));
+                None
+            }
+        }
+    }
+
+    fn number(&mut self, table: &Table, key: &str) -> Option<f32> {
+        let entry = table.get(key)?;
+        match &entry.value {
+            Value::Number(value) => Some(*value),
+            other => {
+                self.error(entry.line, format!("`{}` must be a number, found {}", key, other.type_name()));
+                None
+            }
+        }
+    }
+
+    // A non-negative integer, or one written as a string of digits for seeds
+    // too large to survive as a number, which is read as f32.
+    fn seed(&mut self, table: &Table) -> Option<u64> {
+        let entry = table.get("seed")?;
+        let seed = match &entry.value {
+            Value::Number(seed) if *seed >= 0.0 && seed.fract() == 0.0 => Some(*seed as u64),
+            Value::String(digits) => digits.parse().ok(),
+            _ => None,
+        };
+        if seed.is_none() {
+            self.error(entry.line, "`seed` must be a non-negative integer".to_string());
+        }
+        seed
+    }
+
+    // Reads a position written as [x, y, z].
+    fn vector(&mut self, table: &Table, key: &str) -> Option<Vec3> {
+        let entry = table.get(key)?;
+        match &entry.value {
+            Value::Array(items) => match items[..] {
+                [Value::Number(x), Value::Number(y), Value::Number(z)] => Some(Vec3::new(x, y, z)),
+                _ => {
+                    self.error(entry.line, format!("`{}` must be three numbers, e.g. [0, 5, 20]", key));
+                    None
+                }
+            },
+            other => {
+                self.error(entry.line, format!("`{}` must be an array of three numbers, found {}", key, other.type_name()));
+                None
+            }
+        }
+    }
+
+    // Reads a value that may be given either as a bare number in scene units
+    // or as a string with a unit, e.g. "5.2 AU" or "23.4 deg".
+    fn measure(&mut self, table: &Table, key: &str, quantity: Quantity) -> Option<f32> {
+        let entry = table.get(key)?;
+        match &entry.value {
+            Value::Number(value) => Some(*value),
+            Value::String(text) => match self.units.parse(text, quantity) {
+                Ok(value) => Some(value),
+                Err(message) => {
+                    self.error(entry.line, format!("`{}`: {}", key, message));
+                    None
+                }
+            },
+            other => {
+                self.error(entry.line, format!("`{}` must be a number or a quantity with units, found {}", key, other.type_name()));
+                None
+            }
+        }
+    }
+
+    // Angular speeds can be written directly or derived from a period, both
+    // in sim seconds.
+    fn speed_or_period(&mut self, table: &Table, name: &str, speed_key: &str, period_key: &str) -> f32 {
+        let speed = self.number(table, speed_key);
+        let period = self.measure(table, period_key, Quantity::Duration);
+
+        match (speed, period) {
+            (Some(_), Some(_)) => {
+                self.error(Self::line_of(table, period_key), format!("`{}` sets both `{}` and `{}`", name, speed_key, period_key));
+                0.0
+            }
+            (_, Some(0.0)) => {
+                self.error(Self::line_of(table, period_key), format!("`{}` of `{}` cannot be zero", period_key, name));
+                0.0
+            }
+            (_, Some(period)) => speed_from_period(period),
+            // Written per second, stepped per sim step.
+            (speed, None) => speed.unwrap_or(0.0) / STEPS_PER_SECOND,
+        }
+    }
+
+    // One mode for both directions, or a [u, v] pair, so a tiled detail
+    // map can repeat both ways and a gradient can clamp at its ends.
+    fn texture_wrap(&mut self, table: &Table, name: &str) -> (Wrap, Wrap) {
+        let Some(entry) = table.get("texture_wrap") else {
+            return PLANET_WRAP;
+        };
+        let mode = |value: &Value| match value {
+            Value::String(mode) => Wrap::from_name(mode),
+            _ => None,
+        };
+        let modes = match &entry.value {
+            Value::Array(items) if items.len() == 2 => mode(&items[0]).zip(mode(&items[1])),
+            single => mode(single).map(|mode| (mode, mode)),
+        };
+        modes.unwrap_or_else(|| {
+            self.error(
+                entry.line,
+                format!("`texture_wrap` of `{}` must be one of {} or a [u, v] pair of them", name, Wrap::NAMES.join(", ")),
+            );
+            PLANET_WRAP
+        })
+    }
+
+    // `retrograde_* = true` flips a positive speed. A negative speed already
+    // means retrograde, so combining it with the flag is reported instead of
+    // silently cancelling out.
+    fn direction(&mut self, table: &Table, name: &str, speed: f32, flag_key: &str) -> f32 {
+        match self.flag(table, flag_key) {
+            Some(true) if speed < 0.0 => {
+                self.error(
+                    Self::line_of(table, flag_key),
+                    format!("`{}` of `{}` is already retrograde (negative); drop the sign or `{}`", flag_key, name, flag_key),
+                );
+                speed
+            }
+            Some(false) if speed < 0.0 => {
+                self.error(
+                    Self::line_of(table, flag_key),
+                    format!("`{}` is false but `{}` has a negative (retrograde) speed", flag_key, name),
+                );
+                speed
+            }
+            Some(true) => -speed,
+            _ => speed,
+        }
+    }
+
+    fn units(&mut self, table: &Table) {
+        self.check_keys(table, &UNITS_KEYS);
+        let defaults = UnitScale::default();
+        let read = |validator: &mut Self, key: &str, default: f32| {
+            let value = validator.number(table, key).unwrap_or(default);
+            if value <= 0.0 {
+                validator.error(Self::line_of(table, key), format!("`{}` must be greater than zero", key));
+                default
+            } else {
+                value
+            }
+        };
+
+        self.units = UnitScale {
+            distance_scale: read(self, "distance_scale", defaults.distance_scale),
+            size_scale: read(self, "size_scale", defaults.size_scale),
+            day_length: read(self, "day_length", defaults.day_length),
+        };
+    }
+
+    fn line_of(table: &Table, key: &str) -> usize {
+        table.get(key).map_or(table.line, |entry| entry.line)
+    }
+
+    // Times are sim seconds, or durations with units like "30 d".
+    fn caption(&mut self, table: &Table, shots: &[Shot]) -> Option<Caption> {
+        let errors_before = self.diagnostics.len();
+        self.check_keys(table, &CAPTION_KEYS);
+
+        let text = self.string(table, "text");
+        if table.get("text").is_none() {
+            self.error(table.line, "caption is missing `text`".to_string());
+        }
+        let start = self.measure(table, "start", Quantity::Duration).unwrap_or(0.0);
+        if start < 0.0 {
+            self.error(Self::line_of(table, "start"), "caption `start` cannot be negative".to_string());
+        }
+        let duration = self.measure(table, "duration", Quantity::Duration);
+        match duration {
+            None if table.get("duration").is_none() => self.error(table.line, "caption is missing `duration`".to_string()),
+            Some(duration) if duration <= 0.0 => {
+                self.error(Self::line_of(table, "duration"), "caption `duration` must be greater than zero".to_string())
+            }
+            _ => {}
+        }
+        let shot = self.string(table, "shot");
+        if let Some(name) = shot.as_ref().filter(|name| !shots.iter().any(|shot| &shot.name == *name)) {
+            self.error(Self::line_of(table, "shot"), format!("caption `shot` names unknown shot `{}`", name));
+        }
+
+        if self.diagnostics.len() > errors_before {
+            return None;
+        }
+        Some(Caption { text: text?, start, duration: duration?, shot })
+    }
+
+    // Angles like `fov` default to radians, so "40 deg" reads best.
+    fn shot(&mut self, table: &Table, bodies: &[BodyDesc], earlier: &[Shot]) -> Option<Shot> {
+        let errors_before = self.diagnostics.len();
+        self.check_keys(table, &SHOT_KEYS);
+
+        let name = self.string(table, "name");
+        match &name {
+            None if table.get("name").is_none() => self.error(table.line, "shot is missing `name`".to_string()),
+            Some(name) if earlier.iter().any(|shot| &shot.name == name) => {
+                self.error(Self::line_of(table, "name"), format!("there is already a shot named `{}`", name))
+            }
+            _ => {}
+        }
+        let eye = self.vector(table, "eye");
+        if table.get("eye").is_none() {
+            self.error(table.line, "shot is missing `eye`".to_string());
+        }
+        let center = self.vector(table, "center").unwrap_or(Vec3::zeros());
+        if eye == Some(center) {
+            self.error(Self::line_of(table, "eye"), "shot `eye` and `center` cannot be the same point".to_string());
+        }
+        let focus = self.string(table, "focus").and_then(|name| {
+            let body_id = bodies.iter().position(|body| body.name == name);
+            if body_id.is_none() {
+                self.error(Self::line_of(table, "focus"), format!("shot `focus` names unknown body `{}`", name));
+            }
+            body_id
+        });
+        let fov = self.measure(table, "fov", Quantity::Angle).unwrap_or(DEFAULT_FOV);
+        if !(MIN_FOV..=MAX_FOV).contains(&fov) {
+            self.error(Self::line_of(table, "fov"), format!("shot `fov` must be between {:.0} and {:.0} degrees", MIN_FOV.to_degrees(), MAX_FOV.to_degrees()));
+        }
+
+        if self.diagnostics.len() > errors_before {
+            return None;
+        }
+        Some(Shot { name: name?, eye: eye?, center, focus, fov })
+    }
+
+    fn camera(&mut self, table: &Table, bodies: &[BodyDesc]) -> CameraStart {
+        self.check_keys(table, &CAMERA_KEYS);
+        let defaults = CameraStart::default();
+        let eye = self.vector(table, "eye").unwrap_or(defaults.eye);
+        let center = self.vector(table, "center").unwrap_or(defaults.center);
+        if eye == center {
+            self.error(Self::line_of(table, "eye"), "camera `eye` and `center` cannot be the same point".to_string());
+        }
+
+        let focus = self.string(table, "focus").and_then(|name| {
+            let body_id = bodies.iter().position(|body| body.name == name);
+            if body_id.is_none() {
+                self.error(Self::line_of(table, "focus"), format!("camera `focus` names unknown body `{}`", name));
+            }
+            body_id
+        });
+        CameraStart { eye, center, focus }
+    }
+
+    // `planet` is set for `[[body.moon]]` tables, which orbit that body
+    // without naming it.
+    fn body(&mut self, table: &Table, earlier: &[BodyDesc], planet: Option<usize>) -> Option<BodyDesc> {
+        let errors_before = self.diagnostics.len();
+        self.check_keys(table, &BODY_KEYS);
+
+        let name = self.string(table, "name");
+        if name.is_none() && table.get("name").is_none() {
+            self.error(table.line, "body is missing `name`".to_string());
+        }
+        let name = name.unwrap_or_default();
+
+        let shader = match self.string(table, "shader") {
+            Some(shader_name) => {
+                let id = shader_id(&shader_name);
+                if id.is_none() {
+                    self.error(
+                        Self::line_of(table, "shader"),
+                        format!("unknown shader `{}` for `{}` (available: {})", shader_name, name, SHADER_NAMES.join(", ")),
+                    );
+                }
+                id
+            }
+            // A map alone is enough to pick the textured shader.
+            None if table.get("shader").is_none() && table.get("texture").is_some() => shader_id(TEXTURE_SHADER),
+            None => {
+                if table.get("shader").is_none() {
+                    self.error(table.line, format!("body `{}` is missing `shader`", name));
+                }
+                None
+            }
+        };
+
+        let noise = match self.string(table, "noise") {
+            Some(preset_name) => NoisePreset::from_name(&preset_name).unwrap_or_else(|| {
+                self.error(
+                    Self::line_of(table, "noise"),
+                    format!("unknown noise preset `{}` for `{}` (available: {})", preset_name, name, NoisePreset::NAMES.join(", ")),
+                );
+                NoisePreset::Plain
+            }),
+            None => NoisePreset::Plain,
+        };
+
+        let material = match self.string(table, "material") {
+            Some(material_name) => Material::from_name(&material_name).or_else(|| {
+                self.error(
+                    Self::line_of(table, "material"),
+                    format!("unknown material `{}` for `{}` (available: {})", material_name, name, Material::NAMES.join(", ")),
+                );
+                None
+            }),
+            None => None,
+        };
+
+        let radius = self.measure(table, "radius", Quantity::BodySize);
+        let scale = match (self.number(table, "scale"), radius) {
+            (Some(scale), Some(_)) => {
+                self.error(Self::line_of(table, "radius"), format!("`{}` sets both `scale` and `radius`", name));
+                scale
+            }
+            (None, Some(radius)) => radius / SPHERE_RADIUS,
+            (scale, None) => scale.unwrap_or(1.0),
+        };
+        if scale <= 0.0 {
+            self.error(Self::line_of(table, "scale"), format!("scale of `{}` must be greater than zero, found {}", name, scale));
+        }
+
+        let orbit_radius = self.measure(table, "orbit_radius", Quantity::OrbitDistance).unwrap_or(0.0);
+        if orbit_radius < 0.0 {
+            self.error(Self::line_of(table, "orbit_radius"), format!("orbit_radius of `{}` cannot be negative", name));
+        }
+
+        let orbit_phase = self.measure(table, "orbit_phase", Quantity::Angle).unwrap_or(0.0);
+        let (eccentricity, inclination, periapsis) = self.orbit_shape(table, &name);
+        let rotation_speed = self.speed_or_period(table, &name, "rotation_speed", "rotation_period");
+        let rotation_speed = self.direction(table, &name, rotation_speed, "retrograde_rotation");
+        let axial_tilt = self.measure(table, "axial_tilt", Quantity::Angle).unwrap_or(0.0);
+        let orbital_speed = self.speed_or_period(table, &name, "orbital_speed", "orbital_period");
+        let orbital_speed = self.direction(table, &name, orbital_speed, "retrograde_orbit");
+
+        let magnetosphere = self.flag(table, "magnetosphere").unwrap_or(false);
+        let clouds = self.flag(table, "clouds").unwrap_or(false);
+        let atmosphere = self.atmosphere(table);
+        let lensing = self.number(table, "lensing");
+        if lensing.is_some_and(|radius| radius <= 0.0) {
+            self.error(Self::line_of(table, "lensing"), format!("lensing of `{}` must be greater than zero", name));
+        }
+        let rings = self.rings(table, &name);
+        let variability = self.variability(table, &name, orbit_radius == 0.0 && planet.is_none() && table.get("parent").is_none());
+
+        let named_parent = self.string(table, "parent");
+        if planet.is_some() && named_parent.is_some() {
+            self.error(Self::line_of(table, "parent"), format!("moon `{}` already orbits the body it is listed under; remove `parent`", name));
+        }
+        let parent = planet.or_else(|| {
+            let parent_name = named_parent?;
+            let parent_id = earlier.iter().position(|body| body.name == parent_name);
+            if parent_id.is_none() {
+                self.error(
+                    Self::line_of(table, "parent"),
+                    format!("parent `{}` of `{}` must be a body defined before it", parent_name, name),
+                );
+            }
+            parent_id
+        });
+        if let Some(parent_id) = parent {
+            let clearance = (earlier[parent_id].scale + scale) * SPHERE_RADIUS;
+            if orbit_radius < clearance {
+                self.error(
+                    Self::line_of(table, "orbit_radius"),
+                    format!("moon `{}` orbits inside `{}`: orbit_radius must be at least {:.2}", name, earlier[parent_id].name, clearance),
+                );
+            }
+        }
+
+        let texture = self.string(table, "texture");
+        if let Some(texture_path) = &texture {
+            if !Path::new(texture_path).is_file() {
+                self.error(Self::line_of(table, "texture"), format!("texture `{}` for `{}` does not exist", texture_path, name));
+            }
+        } else if shader.is_some() && shader == shader_id(TEXTURE_SHADER) {
+            self.error(Self::line_of(table, "shader"), format!("shader `{}` of `{}` needs a `texture`", TEXTURE_SHADER, name));
+        }
+        let texture_wrap = self.texture_wrap(table, &name);
+        let texture_filter = match self.string(table, "texture_filter") {
+            Some(filter_name) => Filter::from_name(&filter_name).unwrap_or_else(|| {
+                self.error(
+                    Self::line_of(table, "texture_filter"),
+                    format!("unknown texture_filter `{}` for `{}` (available: {})", filter_name, name, Filter::NAMES.join(", ")),
+                );
+                Filter::Bilinear
+            }),
+            None => Filter::Bilinear,
+        };
+        let texture_space = match self.string(table, "texture_space") {
+            Some(space_name) => ColorSpace::from_name(&space_name).unwrap_or_else(|| {
+                self.error(
+                    Self::line_of(table, "texture_space"),
+                    format!("unknown texture_space `{}` for `{}` (available: {})", space_name, name, ColorSpace::NAMES.join(", ")),
+                );
+                ColorSpace::Srgb
+            }),
+            None => ColorSpace::Srgb,
+        };
+
+        let mesh = self.string(table, "mesh");
+        if let Some(mesh_path) = mesh.as_ref().filter(|path| *path != ASTEROID_MESH) {
+            if !mesh_path.to_ascii_lowercase().ends_with(".obj") {
+                self.error(
+                    Self::line_of(table, "mesh"),
+                    format!("mesh `{}` for `{}` must be an .obj file or `{}`", mesh_path, name, ASTEROID_MESH),
+                );
+            } else if !Path::new(mesh_path).is_file() {
+                self.error(Self::line_of(table, "mesh"), format!("mesh `{}` for `{}` does not exist", mesh_path, name));
+            }
+        }
+
+        if self.diagnostics.len() > errors_before {
+            return None;
+        }
+
+        Some(BodyDesc {
+            name,
+            shader: shader?,
+            noise,
+            material: material.unwrap_or_else(|| Material::for_shader(shader.unwrap_or_default())),
+            scale,
+            orbit: Orbit {
+                semi_major_axis: orbit_radius,
+                eccentricity,
+                inclination,
+                periapsis,
+                mean_motion: orbital_speed,
+                phase: orbit_phase,
+            },
+            rotation_speed,
+            axial_tilt,
+            magnetosphere,
+            clouds,
+            atmosphere,
+            lensing,
+            variability,
+            texture,
+            texture_wrap,
+            texture_filter,
+            texture_space,
+            mesh,
+            parent,
+            rings,
+            spawned: None,
+        })
+    }
+
+    // Either explicit radii or a star to derive them from, not both.
+    fn habitable_zone(&mut self, table: &Table) -> Option<HabitableZone> {
+        self.check_keys(table, &HABITABLE_KEYS);
+        let inner = self.measure(table, "inner", Quantity::OrbitDistance);
+        let outer = self.measure(table, "outer", Quantity::OrbitDistance);
+        let temperature = self.number(table, "star_temperature");
+        let luminosity = self.number(table, "star_luminosity");
+
+        match (inner, outer, temperature) {
+            (Some(_), _, Some(_)) | (_, Some(_), Some(_)) => {
+                self.error(table.line, "[habitable_zone] sets both radii and `star_temperature`; use one or the other".to_string());
+                None
+            }
+            (Some(inner), Some(outer), None) => {
+                if inner <= 0.0 || outer <= inner {
+                    self.error(Self::line_of(table, "outer"), format!("habitable zone needs 0 < inner < outer, found {} and {}", inner, outer));
+                    return None;
+                }
+                Some(HabitableZone { inner, outer })
+            }
+            (None, None, temperature) => {
+                let temperature = temperature.unwrap_or(SUN_TEMPERATURE);
+                if temperature <= 0.0 {
+                    self.error(Self::line_of(table, "star_temperature"), "`star_temperature` must be greater than zero".to_string());
+                    return None;
+                }
+                let luminosity = luminosity.unwrap_or_else(|| main_sequence_luminosity(temperature));
+                if luminosity <= 0.0 {
+                    self.error(Self::line_of(table, "star_luminosity"), "`star_luminosity` must be greater than zero".to_string());
+                    return None;
+                }
+                Some(HabitableZone::from_star(temperature, luminosity, self.units.distance_scale))
+            }
+            _ => {
+                self.error(table.line, "[habitable_zone] needs both `inner` and `outer`".to_string());
+                None
+            }
+        }
+    }
+
+    // The seed defaults to the scene's, so each system gets its own sky.
+    fn starfield(&mut self, table: &Table, scene_seed: u64) -> Option<StarfieldDesc> {
+        let errors_before = self.diagnostics.len();
+        self.check_keys(table, &STARFIELD_KEYS);
+        let density = self.number(table, "density").unwrap_or(DEFAULT_DENSITY);
+        if density <= 0.0 || density > MAX_STAR_DENSITY {
+            self.error(Self::line_of(table, "density"), format!("star `density` must be greater than zero and at most {}, found {}", MAX_STAR_DENSITY, density));
+        }
+        let seed = self.seed(table).unwrap_or(scene_seed);
+        let twinkle = self.flag(table, "twinkle").unwrap_or(false);
+
+        if self.diagnostics.len() > errors_before {
+            return None;
+        }
+        Some(StarfieldDesc { density, seed, twinkle })
+    }
+
+    fn asteroid_belt(&mut self, table: &Table) -> Option<BeltDesc> {
+        let errors_before = self.diagnostics.len();
+        self.check_keys(table, &BELT_KEYS);
+        let inner = self.measure(table, "inner", Quantity::OrbitDistance);
+        let outer = self.measure(table, "outer", Quantity::OrbitDistance);
+        let (inner, outer) = match (inner, outer) {
+            (Some(inner), Some(outer)) if inner > 0.0 && outer > inner => (inner, outer),
+            (Some(inner), Some(outer)) => {
+                self.error(Self::line_of(table, "outer"), format!("asteroid belt needs 0 < inner < outer, found {} and {}", inner, outer));
+                return None;
+            }
+            _ => {
+                self.error(table.line, "[asteroid_belt] needs both `inner` and `outer`".to_string());
+                return None;
+            }
+        };
+        let count = match self.number(table, "count") {
+            Some(count) if count < 1.0 || count.fract() != 0.0 || count as usize > MAX_BELT_ROCKS => {
+                self.error(Self::line_of(table, "count"), format!("`count` must be a whole number from 1 to {}, found {}", MAX_BELT_ROCKS, count));
+                belt::DEFAULT_COUNT
+            }
+            Some(count) => count as usize,
+            None => belt::DEFAULT_COUNT,
+        };
+        let scale = self.number(table, "scale").unwrap_or(belt::DEFAULT_SCALE);
+        if scale <= 0.0 {
+            self.error(Self::line_of(table, "scale"), format!("asteroid `scale` must be greater than zero, found {}", scale));
+        }
+
+        if self.diagnostics.len() > errors_before {
+            return None;
+        }
+        Some(BeltDesc { inner, outer, count, scale })
+    }
+
+    fn comet(&mut self, table: &Table, sun_radius: f32) -> Option<CometDesc> {
+        let errors_before = self.diagnostics.len();
+        self.check_keys(table, &COMET_KEYS);
+
+        let name = self.string(table, "name").unwrap_or_else(|| "Cometa".to_string());
+
+        let scale = self.number(table, "scale").unwrap_or(0.25);
+        if scale <= 0.0 {
+            self.error(Self::line_of(table, "scale"), format!("scale of comet `{}` must be greater than zero, found {}", name, scale));
+        }
+
+        let semi_major_axis = match self.measure(table, "semi_major_axis", Quantity::OrbitDistance) {
+            Some(axis) => axis,
+            None => {
+                if table.get("semi_major_axis").is_none() {
+                    self.error(table.line, format!("comet `{}` is missing `semi_major_axis`", name));
+                }
+                1.0
+            }
+        };
+        if semi_major_axis <= 0.0 {
+            self.error(Self::line_of(table, "semi_major_axis"), format!("semi_major_axis of comet `{}` must be greater than zero", name));
+        }
+
+        let (eccentricity, inclination, periapsis) = self.orbit_shape(table, &name);
+        let mean_anomaly = self.measure(table, "mean_anomaly", Quantity::Angle).unwrap_or(0.0);
+        let mean_motion = self.speed_or_period(table, &name, "orbital_speed", "orbital_period");
+
+        if self.diagnostics.len() > errors_before {
+            return None;
+        }
+
+        let comet = CometDesc {
+            name,
+            scale,
+            orbit: Orbit { semi_major_axis, eccentricity, inclination, periapsis, mean_motion, phase: mean_anomaly },
+            spawned: false,
+        };
+        let clearance = sun_radius + scale * SPHERE_RADIUS;
+        if comet.orbit.periapsis_distance() < clearance {
+            self.error(
+                table.line,
+                format!(
+                    "comet `{}` dives into the sun: periapsis {:.2} is closer than {:.2}",
+                    comet.name,
+                    comet.orbit.periapsis_distance(),
+                    clearance
+                ),
+            );
+            return None;
+        }
+        Some(comet)
+    }
+
+    // `ring_inner` and `ring_outer` come together, in radii of the body.
+    // `true` for the default sky blue, or an [r, g, b] color from 0 to 255.
+    fn atmosphere(&mut self, table: &Table) -> Option<Color> {
+        let entry = table.get("atmosphere")?;
+        match &entry.value {
+            Value::Bool(enabled) => enabled.then_some(atmosphere::DEFAULT_COLOR),
+            Value::Array(_) => {
+                let rgb = self.vector(table, "atmosphere")?;
+                if rgb.iter().any(|channel| !(0.0..=255.0).contains(channel)) {
+                    self.error(entry.line, "`atmosphere` color channels must be between 0 and 255".to_string());
+                    return None;
+                }
+                Some(Color::new(rgb.x as u8, rgb.y as u8, rgb.z as u8))
+            }
+            other => {
+                self.error(entry.line, format!("`atmosphere` must be true or an [r, g, b] color, found {}", other.type_name()));
+                None
+            }
+        }
+    }
+
+    fn rings(&mut self, table: &Table, name: &str) -> Option<RingDesc> {
+        let inner = self.number(table, "ring_inner");
+        let outer = self.number(table, "ring_outer");
+        let gaps = self.number(table, "ring_gaps");
+        let (inner, outer) = match (inner, outer) {
+            (Some(inner), Some(outer)) => (inner, outer),
+            (None, None) => {
+                if gaps.is_some() {
+                    self.error(Self::line_of(table, "ring_gaps"), format!("`ring_gaps` of `{}` needs `ring_inner` and `ring_outer`", name));
+                }
+                return None;
+            }
+            _ => {
+                let line = Self::line_of(table, if inner.is_some() { "ring_inner" } else { "ring_outer" });
+                self.error(line, format!("rings of `{}` need both `ring_inner` and `ring_outer`", name));
+                return None;
+            }
+        };
+        if inner < 1.0 || outer <= inner {
+            self.error(
+                Self::line_of(table, "ring_inner"),
+                format!("rings of `{}` must satisfy 1 <= ring_inner < ring_outer, found {}..{}", name, inner, outer),
+            );
+            return None;
+        }
+        let gaps = match gaps {
+            Some(gaps) if gaps < 0.0 || gaps.fract() != 0.0 => {
+                self.error(Self::line_of(table, "ring_gaps"), format!("`ring_gaps` of `{}` must be a non-negative integer", name));
+                1
+            }
+            Some(gaps) => gaps as usize,
+            None => 1,
+        };
+        Some(RingDesc { inner, outer, gaps })
+    }
+
+    // Either a sinusoid of `variability_amplitude` or a tabulated
+    // `light_curve`, repeating every `variability_period`; only stars vary.
+    fn variability(&mut self, table: &Table, name: &str, is_star: bool) -> Option<Variability> {
+        let period = self.measure(table, "variability_period", Quantity::Duration);
+        let amplitude = self.number(table, "variability_amplitude");
+        let curve = self.light_curve(table, name);
+        let key = ["variability_period", "variability_amplitude", "light_curve"].into_iter().find(|key| table.get(key).is_some())?;
+        if !is_star {
+            self.error(Self::line_of(table, key), format!("only stars can vary in brightness, but `{}` has an orbit", name));
+            return None;
+        }
+        let curve = match (amplitude, curve) {
+            (Some(_), Some(_)) => {
+                self.error(Self::line_of(table, "light_curve"), format!("`{}` sets both `variability_amplitude` and `light_curve`", name));
+                return None;
+            }
+            (Some(amplitude), None) if !(0.0..=1.0).contains(&amplitude) => {
+                self.error(
+                    Self::line_of(table, "variability_amplitude"),
+                    format!("variability_amplitude of `{}` must be in [0, 1], found {}", name, amplitude),
+                );
+                return None;
+            }
+            (Some(amplitude), None) => LightCurve::Sinusoid { amplitude },
+            (None, Some(samples)) => LightCurve::Table(samples),
+            (None, None) => {
+                if table.get("light_curve").is_none() && table.get("variability_amplitude").is_none() {
+                    self.error(
+                        Self::line_of(table, key),
+                        format!("variable star `{}` needs `variability_amplitude` or `light_curve`", name),
+                    );
+                }
+                return None;
+            }
+        };
+        match period {
+            Some(period) if period > 0.0 => Some(Variability { period, curve }),
+            Some(_) => {
+                self.error(Self::line_of(table, "variability_period"), format!("variability_period of `{}` must be greater than zero", name));
+                None
+            }
+            None => {
+                if table.get("variability_period").is_none() {
+                    self.error(Self::line_of(table, key), format!("variable star `{}` needs `variability_period`", name));
+                }
+                None
+            }
+        }
+    }
+
+    // Brightness samples over one period, at least two and none negative.
+    fn light_curve(&mut self, table: &Table, name: &str) -> Option<Vec<f32>> {
+        let entry = table.get("light_curve")?;
+        let samples: Option<Vec<f32>> = match &entry.value {
+            Value::Array(items) => items
+                .iter()
+                .map(|item| match item {
+                    Value::Number(sample) if *sample >= 0.0 => Some(*sample),
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        };
+        match samples {
+            Some(samples) if samples.len() >= 2 => Some(samples),
+            _ => {
+                self.error(entry.line, format!("light_curve of `{}` must be an array of at least two non-negative numbers", name));
+                None
+            }
+        }
+    }
+
+    // Eccentricity, inclination and periapsis direction, shared by bodies
+    // and comets. All default to a flat circle.
+    fn orbit_shape(&mut self, table: &Table, name: &str) -> (f32, f32, f32) {
+        let eccentricity = self.number(table, "eccentricity").unwrap_or(0.0);
+        if !(0.0..1.0).contains(&eccentricity) {
+            self.error(
+                Self::line_of(table, "eccentricity"),
+                format!("eccentricity of `{}` must be in [0, 1), found {}", name, eccentricity),
+            );
+        }
+        let inclination = self.measure(table, "inclination", Quantity::Angle).unwrap_or(0.0);
+        let periapsis = self.measure(table, "periapsis", Quantity::Angle).unwrap_or(0.0);
+        (eccentricity, inclination, periapsis)
+    }
+
+    fn check_duplicate_names(&mut self, bodies: &[BodyDesc], lines: &[usize]) {
+        for (i, body) in bodies.iter().enumerate() {
+            if bodies[..i].iter().any(|other| other.name == body.name) {
+                self.error(lines[i], format!("duplicate body name `{}`", body.name));
+            }
+        }
+    }
+
+    fn check_overlapping_orbits(&mut self, bodies: &[BodyDesc], lines: &[usize]) {
+        for (i, a) in bodies.iter().enumerate() {
+            // Moons only share space with their siblings.
+            for (b, line) in bodies.iter().zip(lines).skip(i + 1).filter(|(b, _)| b.parent == a.parent) {
+                // Eccentric orbits sweep from periapsis to apoapsis.
+                let (inner, outer) = if a.orbit.semi_major_axis <= b.orbit.semi_major_axis { (a, b) } else { (b, a) };
+                let gap = outer.orbit.periapsis_distance() - inner.orbit.apoapsis_distance();
+                let clearance = (a.scale + b.scale) * SPHERE_RADIUS;
+                if gap < clearance {
+                    let span = |body: &BodyDesc| match body.orbit.eccentricity {
+                        0.0 => format!("r = {}", body.orbit.semi_major_axis),
+                        _ => format!("r = {:.2}..{:.2}", body.orbit.periapsis_distance(), body.orbit.apoapsis_distance()),
+                    };
+                    self.error(
+                        *line,
+                        format!(
+                            "orbit of `{}` ({}) overlaps `{}` ({}); they need at least {:.2} units between orbits",
+                            b.name, span(b), a.name, span(a), clearance
+                        ),
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostics(source: &str) -> Vec<(usize, String)> {
+        let diagnostics = Scene::parse("prueba.toml", source).err().expect("scene should not load");
+        assert!(diagnostics.iter().all(|diagnostic| diagnostic.file == "prueba.toml"));
+        diagnostics.into_iter().map(|diagnostic| (diagnostic.line, diagnostic.message)).collect()
+    }
+
+    #[test]
+    fn loads_a_valid_scene() {
+        let scene = Scene::parse(
+            "prueba.toml",
+            "name = \"Prueba\"\n\
+             [[body]]\n\
+             name = \"Sol\"\n\
+             shader = \"sol\"\n\
+             scale = 4\n\
+             [[body]]\n\
+             name = \"Roca\"\n\
+             shader = \"rocoso\"\n\
+             orbit_radius = \"1 AU\"\n",
+        )
+        .unwrap();
+        assert_eq!(scene.name, "Prueba");
+        assert_eq!(scene.bodies.len(), 2);
+        assert_eq!(scene.bodies[1].orbit.semi_major_axis, 10.0);
+    }
+
+    #[test]
+    fn reports_every_problem_sorted_by_line() {
+        let found = diagnostics(
+            "name = \"Prueba\"\n\
+             colour = \"azul\"\n\
+             \n\
+             [[body]]\n\
+             name = \"Sol\"\n\
+             shader = \"sol\"\n\
+             scale = 4\n\
+             \n\
+             [[body]]\n\
+             name = \"Lava\"\n\
+             shader = \"lava\"\n\
+             orbit_radius = 10\n\
+             \n\
+             [[body]]\n\
+             name = \"Polvo\"\n\
+             shader = \"rocoso\"\n\
+             scale = 0\n\
+             orbit_radius = 12\n\
+             \n\
+             [[body]]\n\
+             name = \"Sol\"\n\
+             shader = \"azul\"\n\
+             scale = -1\n\
+             orbit_radius = 14\n\
+             \n\
+             [[body]]\n\
+             name = \"Roca\"\n\
+             shader = \"rocoso\"\n\
+             orbit_radius = 20\n\
+             \n\
+             [[body]]\n\
+             name = \"Sol\"\n\
+             shader = \"rocoso\"\n\
+             orbit_radius = 20.5\n\
+             \n\
+             [[body]]\n\
+             name = \"Luna\"\n\
+             shader = \"rocoso\"\n\
+             orbit_radius = 30\n\
+             color = \"gris\"\n",
+        );
+        let shaders = SHADER_NAMES.join(", ");
+        let body_keys = BODY_KEYS.join(", ");
+
+        assert_eq!(
+            found,
+            [
+                (2, format!("unknown key `colour` (expected one of: {})", SCENE_KEYS.join(", "))),
+                (11, format!("unknown shader `lava` for `Lava` (available: {})", shaders)),
+                (17, "scale of `Polvo` must be greater than zero, found 0".to_string()),
+                (23, "scale of `Sol` must be greater than zero, found -1".to_string()),
+                (31, "duplicate body name `Sol`".to_string()),
+                (31, "orbit of `Sol` (r = 20.5) overlaps `Roca` (r = 20); they need at least 1.00 units between orbits".to_string()),
+                (40, format!("unknown key `color` (expected one of: {})", body_keys)),
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_moons_against_their_siblings_only() {
+        let found = diagnostics(
+            "[[body]]\n\
+             name = \"Sol\"\n\
+             shader = \"sol\"\n\
+             [[body]]\n\
+             name = \"Tierra\"\n\
+             shader = \"rocoso\"\n\
+             orbit_radius = 10\n\
+             [[body.moon]]\n\
+             name = \"Luna\"\n\
+             shader = \"rocoso\"\n\
+             scale = 0.2\n\
+             orbit_radius = 1\n\
+             [[body.moon]]\n\
+             name = \"Luna\"\n\
+             shader = \"rocoso\"\n\
+             scale = 0.2\n\
+             orbit_radius = 1.1\n",
+        );
+        assert_eq!(
+            found,
+            [
+                (13, "duplicate body name `Luna`".to_string()),
+                (13, "orbit of `Luna` (r = 1.1) overlaps `Luna` (r = 1); they need at least 0.20 units between orbits".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn syntax_errors_stop_before_validation() {
+        assert_eq!(
+            diagnostics("name = \"Prueba\"\n[[body]]\nname = \"Sol\nshader = sol\n"),
+            [(3, "unterminated string `\"Sol`".to_string()), (4, "invalid value `sol`".to_string())]
+        );
+        assert_eq!(diagnostics(""), [(0, "scene defines no `[[body]]` entries".to_string())]);
+    }
+
+    #[test]
+    fn diagnostics_print_as_file_and_line() {
+        let diagnostic = Diagnostic { file: "escena.toml".to_string(), line: 12, message: "duplicate body name `Sol`".to_string() };
+        assert_eq!(diagnostic.to_string(), "escena.toml:12: duplicate body name `Sol`");
+        let missing = Scene::load("/nonexistent/escena.toml").err().unwrap();
+        assert_eq!((missing.len(), missing[0].line), (1, 0));
+    }
+}
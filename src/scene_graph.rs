@@ -0,0 +1,108 @@
+use nalgebra_glm::{rotation, scaling, translation, Mat4, Vec3};
+use crate::scene::{BodyDesc, Scene, ASTEROID_MESH, SPHERE_RADIUS};
+use crate::shaders::RING_SHADER;
+use crate::sim::{local_position, BodyState};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MeshRef {
+    Sphere,
+    // The UV-mapped sphere, for bodies with a surface map.
+    TexturedSphere,
+    // The body's own model from the mesh cache.
+    Model,
+    // A rock generated for this body.
+    Asteroid,
+    // The flat ring system around the body, drawn translucent.
+    Rings,
+}
+
+impl MeshRef {
+    pub fn of(body: &BodyDesc) -> Self {
+        if body.mesh.as_deref() == Some(ASTEROID_MESH) {
+            MeshRef::Asteroid
+        } else if body.mesh.is_some() {
+            MeshRef::Model
+        } else if body.texture.is_some() {
+            MeshRef::TexturedSphere
+        } else {
+            MeshRef::Sphere
+        }
+    }
+}
+
+// What a node draws: the body whose uniforms to use, with its mesh and
+// shader.
+#[derive(Clone, Copy)]
+pub struct Drawable {
+    pub body: usize,
+    pub shader: u8,
+    pub mesh: MeshRef,
+}
+
+// A transform relative to the parent node, optionally something to draw
+// with it, and the nodes that move along with it.
+pub struct SceneNode {
+    pub transform: Mat4,
+    pub drawable: Option<Drawable>,
+    pub children: Vec<SceneNode>,
+}
+
+impl SceneNode {
+    fn group(transform: Mat4) -> Self {
+        SceneNode { transform, drawable: None, children: Vec::new() }
+    }
+
+    // Rebuilds the graph for the given body states, usually the simulation's
+    // current ones. Each body gets an orbit node placing it relative to its
+    // parent, holding a tilted, spinning, scaled node that draws it, its
+    // rings if any, plus the orbit nodes of its moons, so moons follow the
+    // planet without inheriting its spin.
+    pub fn from_scene(scene: &Scene, bodies: &[BodyState]) -> Self {
+        let mut root = SceneNode::group(Mat4::identity());
+        root.children = (0..scene.bodies.len())
+            .filter(|&body_id| scene.bodies[body_id].parent.is_none())
+            .map(|body_id| orbit_node(scene, bodies, body_id))
+            .collect();
+        root
+    }
+
+    // Visits every drawable with its model matrix, parents before children.
+    pub fn walk(&self, parent: &Mat4, visit: &mut impl FnMut(&Mat4, &Drawable)) {
+        let model_matrix = parent * self.transform;
+        if let Some(drawable) = &self.drawable {
+            visit(&model_matrix, drawable);
+        }
+        for child in &self.children {
+            child.walk(&model_matrix, visit);
+        }
+    }
+}
+
+fn orbit_node(scene: &Scene, bodies: &[BodyState], body_id: usize) -> SceneNode {
+    let body = &scene.bodies[body_id];
+    let mut node = SceneNode::group(translation(&local_position(scene, bodies, body_id)));
+
+    // Spins around its own axis, which is then leant over.
+    let tilt = rotation(body.axial_tilt, &Vec3::z());
+    let spin = tilt * rotation(bodies[body_id].spin_angle, &Vec3::y());
+    node.children.push(SceneNode {
+        transform: spin * scaling(&Vec3::repeat(body.scale)),
+        drawable: Some(Drawable { body: body_id, shader: body.shader, mesh: MeshRef::of(body) }),
+        children: Vec::new(),
+    });
+    // Rings share the equator's tilt but not the spin; the mesh is in body
+    // radii.
+    if body.rings.is_some() {
+        node.children.push(SceneNode {
+            transform: tilt * scaling(&Vec3::repeat(body.scale * SPHERE_RADIUS)),
+            drawable: Some(Drawable { body: body_id, shader: RING_SHADER, mesh: MeshRef::Rings }),
+            children: Vec::new(),
+        });
+    }
+    node.children.extend(
+        (0..scene.bodies.len())
+            .filter(|&moon_id| scene.bodies[moon_id].parent == Some(body_id))
+            .map(|moon_id| orbit_node(scene, bodies, moon_id)),
+    );
+    node
+}
@@ -0,0 +1,86 @@
+use nalgebra_glm::Vec3;
+use crate::draw2d;
+use crate::framebuffer::Framebuffer;
+use crate::input::InputFrame;
+
+// Smaller drags than this, in pixels, count as a click, which clears the
+// group.
+const MIN_DRAG: f32 = 4.0;
+const FILL_COLOR: u32 = 0x66CCFF;
+const FILL_ALPHA: f32 = 0.15;
+pub const GROUP_COLOR: u32 = 0x66CCFF;
+
+// Screen rectangle as (left, top, right, bottom).
+pub type Rect = (f32, f32, f32, f32);
+
+// Several bodies picked at once by Shift+dragging a rectangle over the view
+// with the left button, to hide them, recolor them or follow them together.
+pub struct Selection {
+    pub group: Vec<usize>,
+    // Where the drag started and where the cursor is now.
+    drag: Option<((f32, f32), (f32, f32))>,
+    mouse_was_down: bool,
+}
+
+impl Selection {
+    pub fn new() -> Self {
+        Selection { group: Vec::new(), drag: None, mouse_was_down: false }
+    }
+
+    // Follows the left button. A drag can't start where `blocked` says the
+    // press belongs to something else, like the gallery. Returns the
+    // rectangle once a drag is released, or Some(None) for a plain click.
+    pub fn update(&mut self, input: &InputFrame, blocked: impl Fn((f32, f32)) -> bool) -> Option<Option<Rect>> {
+        let down = input.mouse_down;
+        let pressed = down && !self.mouse_was_down;
+        self.mouse_was_down = down;
+
+        if pressed {
+            self.drag = input.mouse.filter(|&cursor| !blocked(cursor)).map(|cursor| (cursor, cursor));
+        }
+        let (start, end) = self.drag?;
+        if down {
+            if let Some(cursor) = input.mouse {
+                self.drag = Some((start, cursor));
+            }
+            return None;
+        }
+
+        self.drag = None;
+        let rect = (start.0.min(end.0), start.1.min(end.1), start.0.max(end.0), start.1.max(end.1));
+        Some((rect.2 - rect.0 >= MIN_DRAG || rect.3 - rect.1 >= MIN_DRAG).then_some(rect))
+    }
+
+    // What group edits apply to: the group, or the selected body alone.
+    pub fn targets(&self, selected: usize) -> Vec<usize> {
+        if self.group.is_empty() { vec![selected] } else { self.group.clone() }
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    // Center and radius of a sphere around every body of the group, given
+    // each body's (center, radius).
+    pub fn bounds(&self, bodies: &[(Vec3, f32)]) -> Option<(Vec3, f32)> {
+        let members: Vec<(Vec3, f32)> = self.group.iter().filter_map(|&body_id| bodies.get(body_id).copied()).collect();
+        if members.is_empty() {
+            return None;
+        }
+        let center = members.iter().map(|(position, _)| position).sum::<Vec3>() / members.len() as f32;
+        let radius = members.iter().map(|(position, radius)| (position - center).magnitude() + radius).fold(0.0, f32::max);
+        Some((center, radius))
+    }
+
+    // The rectangle being dragged, if any.
+    pub fn draw(&self, framebuffer: &mut Framebuffer) {
+        let Some((start, end)) = self.drag else {
+            return;
+        };
+        let (left, top) = (start.0.min(end.0).max(0.0) as usize, start.1.min(end.1).max(0.0) as usize);
+        let (right, bottom) = (start.0.max(end.0).max(0.0) as usize, start.1.max(end.1).max(0.0) as usize);
+        let (width, height) = (right - left + 1, bottom - top + 1);
+        draw2d::blend_rect(framebuffer, left, top, width, height, FILL_COLOR, FILL_ALPHA);
+        draw2d::stroke_rect(framebuffer, left, top, width, height, FILL_COLOR);
+    }
+}
@@ -1,15 +1,37 @@
-
-use nalgebra_glm::{Vec3, Vec4, Mat3, dot, mat4_to_mat3};
+use std::f32::consts::PI;
+use nalgebra_glm::{Vec3, Vec4, Mat3, Mat4, mat4_to_mat3};
 use crate::vertex::Vertex;
 use crate::Uniforms;
 use crate::fragment::Fragment;
-use crate::color::Color;
-use std::f32::consts::PI;
-use rand::Rng;
-use rand::SeedableRng;
-use rand::rngs::StdRng;
+use crate::color::{Color, Gradient, Rgba};
+use crate::clipping::to_screen;
+use crate::rings::{self, RingSystem};
+use crate::noise::{curl_flow, flow_offsets, gain, loop_wave, looping_noise_3d, remap, smoothstep, CellSample, LOOP_PERIOD};
 
 pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
+  VertexTransform::new(&uniforms.model_matrix, uniforms).apply(vertex)
+}
+
+// The matrices `vertex_shader` needs for one placement of a mesh, worked out
+// once for all its vertices instead of for each.
+pub struct VertexTransform {
+  model: Mat4,
+  model_view_projection: Mat4,
+  normal: Mat3,
+  viewport: Mat4,
+}
+
+impl VertexTransform {
+  pub fn new(model_matrix: &Mat4, uniforms: &Uniforms) -> Self {
+    VertexTransform {
+      model: *model_matrix,
+      model_view_projection: uniforms.projection_matrix * uniforms.view_matrix * model_matrix,
+      normal: mat4_to_mat3(model_matrix).transpose().try_inverse().unwrap_or(Mat3::identity()),
+      viewport: uniforms.viewport_matrix,
+    }
+  }
+
+  pub fn apply(&self, vertex: &Vertex) -> Vertex {
     let position = Vec4::new(
         vertex.position.x,
         vertex.position.y,
@@ -17,35 +39,60 @@ pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
         1.0
     );
 
-    let transformed = uniforms.projection_matrix * uniforms.view_matrix * uniforms.model_matrix * position;
-
-    let w = transformed.w;
-    let transformed_position = Vec4::new(
-        transformed.x / w,
-        transformed.y / w,
-        transformed.z / w,
-        1.0
-    );
-
-    let screen_position = uniforms.viewport_matrix * transformed_position;
-
-    let model_mat3 = mat4_to_mat3(&uniforms.model_matrix);
-    let normal_matrix = model_mat3.transpose().try_inverse().unwrap_or(Mat3::identity());
+    let transformed = self.model_view_projection * position;
+    let screen_position = to_screen(&transformed, &self.viewport);
 
-    let transformed_normal = normal_matrix * vertex.normal;
+    let transformed_normal = self.normal * vertex.normal;
+    let world_position = (self.model * position).xyz();
 
     Vertex {
         position: vertex.position,
         normal: vertex.normal,
         tex_coords: vertex.tex_coords,
         color: vertex.color,
-        transformed_position: Vec3::new(screen_position.x, screen_position.y, screen_position.z),
-        transformed_normal: transformed_normal
+        clip_position: transformed,
+        transformed_position: screen_position,
+        transformed_normal,
+        world_position,
     }
+  }
+}
+
+// Names used by scene files, indexed by shader id.
+pub const SHADER_NAMES: [&str; 13] = [
+    "neon", "raro", "saturno", "azul", "celular", "mancha", "sol", "rocoso", "gaseoso", "arcilla",
+    "tectonico", "textura", "agujero",
+];
+
+// Not one of the body shaders: ring nodes in the scene graph carry this id
+// and are shaded with `ring_shader`, which also gives an opacity.
+pub const RING_SHADER: u8 = SHADER_NAMES.len() as u8;
+
+pub fn shader_id(name: &str) -> Option<u8> {
+    SHADER_NAMES.iter().position(|&shader| shader == name).map(|id| id as u8)
+}
+
+// Well distributed palette position from two noise samples. Each sample is
+// equalized to a uniform [0, 1] value; their mean is triangular, and a gain
+// of 0.75 spreads it back out to roughly uniform.
+fn palette_t(uniforms: &Uniforms, noise_a: f32, noise_b: f32) -> f32 {
+  let mean = (uniforms.noise.equalize(noise_a) + uniforms.noise.equalize(noise_b)) * 0.5;
+  gain(mean, 0.75)
+}
+
+fn palette_index(t: f32, len: usize) -> usize {
+  ((t * len as f32) as usize).min(len - 1)
+}
+
+// How much of a wobble repeating every `period` seconds to keep when a frame
+// spans `delta` seconds of sim time. At high time scales a fast one would
+// jump to a random point of its cycle every frame and only flicker.
+fn steady(period: f32, delta: f32) -> f32 {
+  (1.0 - delta / period).clamp(0.0, 1.0)
 }
 
 pub fn fragment_shader(fragment: &Fragment, uniforms: &Uniforms, current_shader: u8) -> Color {
-  match current_shader {
+  let color = match current_shader {
       0 => planeta_neon(fragment, uniforms),
       1 => planeta_raro(fragment, uniforms),
       2 => planeta_saturno(fragment, uniforms),
@@ -56,23 +103,49 @@ pub fn fragment_shader(fragment: &Fragment, uniforms: &Uniforms, current_shader:
       7 => planeta_rocoso(fragment, uniforms),
       8 => planeta_gaseoso(fragment, uniforms),
       9 => planeta_arcilla(fragment, uniforms),
+      10 => planeta_tectonico(fragment, uniforms),
+      11 => planeta_textura(fragment, uniforms),
+      // A black hole's horizon: no light comes back from it.
+      12 => Color::black(),
       _ => planeta_mancha(fragment, uniforms),
+  };
+  color + highlight(fragment, uniforms, current_shader)
+}
+
+// Blinn-Phong reflection of the sun for glossy materials. On planets with
+// oceans only the water shines; the land stays matte.
+fn highlight(fragment: &Fragment, uniforms: &Uniforms, shader: u8) -> Color {
+  let material = &uniforms.material;
+  if material.specular <= 0.0 {
+      return Color::black();
+  }
+  let mut strength = material.specular
+      * fragment.sunlight
+      * uniforms.light.specular(fragment.world_position, fragment.normal, uniforms.eye, material.shininess);
+  if strength > 0.0 && has_oceans(shader) {
+      strength *= ocean(fragment, uniforms, shader);
   }
+  Color::new(255, 250, 235) * strength
 }
 
 
 
 fn planeta_raro(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let color_1 = Color::new(255, 0, 255); 
-    let color_2 = Color::new(0, 255, 255); 
-    let color_3 = Color::new(0, 255, 127);
-    let color_4 = Color::new(255, 105, 180); 
-    let color_5 = Color::new(255, 165, 0);  
+    // Each band fades into the next one's color with the noise, the last
+    // back into the first.
+    const COLORS: Gradient = Gradient::new(&[
+        (0.0, Color::new(255, 0, 255)),
+        (1.0, Color::new(0, 255, 255)),
+        (2.0, Color::new(0, 255, 127)),
+        (3.0, Color::new(255, 105, 180)),
+        (4.0, Color::new(255, 165, 0)),
+        (5.0, Color::new(255, 0, 255)),
+    ]);
 
     let position = fragment.vertex_position;
 
-    let t = uniforms.time as f32 * 0.04; 
-    let swirl = (position.x * 10.0 + position.y * 10.0 + t).sin(); 
+    let t = uniforms.time * 2.4; 
+    let swirl = (position.x * 10.0 + position.y * 10.0 + t).sin() * steady(2.0 * PI / 2.4, uniforms.delta); 
 
     let noise_zoom = 7.0;
     let noise_value = uniforms.noise.get_noise_3d(
@@ -83,119 +156,84 @@ fn planeta_raro(fragment: &Fragment, uniforms: &Uniforms) -> Color {
 
     let wave_value = (position.y * 12.0 + swirl * 5.0).sin();
 
-    let threshold_1 = -0.6;
-    let threshold_2 = -0.2;
-    let threshold_3 = 0.2;
-    let threshold_4 = 0.6;
-
-    let base_color = if wave_value < threshold_1 {
-        color_1.lerp(&color_2, noise_value)
-    } else if wave_value < threshold_2 {
-        color_2.lerp(&color_3, noise_value)
-    } else if wave_value < threshold_3 {
-        color_3.lerp(&color_4, noise_value)
-    } else if wave_value < threshold_4 {
-        color_4.lerp(&color_5, noise_value)
-    } else {
-        color_5.lerp(&color_1, noise_value)
-    };
+    // Five bands split at -0.6, -0.2, 0.2 and 0.6.
+    let band = ((wave_value + 1.0) / 0.4).floor().clamp(0.0, 4.0);
+    let base_color = COLORS.at(band + noise_value.min(1.0));
 
     base_color * fragment.intensity
 }
   
 fn planeta_saturno(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-  let color_1 = Color::new(255, 204, 102); 
-  let color_2 = Color::new(255, 153, 51);  
-  let color_3 = Color::new(204, 102, 0);  
-  let color_4 = Color::new(153, 76, 0);   
-  let color_5 = Color::new(102, 51, 0);  
-
   let position = fragment.vertex_position;
 
-  let t = uniforms.time as f32 * 0.02; 
+  let t = uniforms.time * 1.2; 
   let pulsate = (t * 0.5).sin() * 0.5; 
 
+  // Advect the bands along a curl-noise flow so storms swirl instead of
+  // the whole pattern just sliding.
+  let storm_zoom = 300.0;
+  let storm_strength = 0.008;
+  let flow_cycle = LOOP_PERIOD / 8.0;
+  let velocity = curl_flow(uniforms.noise, position, storm_zoom) * storm_strength;
+  let (offset_a, offset_b, weight_b) = flow_offsets(velocity, uniforms.time, flow_cycle);
+
+  let color_a = saturno_bands(position + offset_a, pulsate);
+  let color_b = saturno_bands(position + offset_b, pulsate);
+
+  color_a.lerp(&color_b, weight_b) * fragment.intensity
+}
+
+fn saturno_bands(position: Vec3, pulsate: f32) -> Color {
+  const BANDS: Gradient = Gradient::new(&[
+      (-1.0, Color::new(255, 204, 102)),
+      (-0.8, Color::new(255, 153, 51)),
+      (-0.4, Color::new(204, 102, 0)),
+      (0.0, Color::new(153, 76, 0)),
+      (0.4, Color::new(102, 51, 0)),
+  ]);
+
   let zoom = 10.0; 
   let bands_value = ((position.y * zoom) + pulsate).sin(); 
 
-  let threshold_1 = -0.8;
-  let threshold_2 = -0.4;
-  let threshold_3 = 0.0;
-  let threshold_4 = 0.4;
-
-  let base_color = if bands_value < threshold_1 {
-      color_1
-  } else if bands_value < threshold_2 {
-      color_2
-  } else if bands_value < threshold_3 {
-      color_3
-  } else if bands_value < threshold_4 {
-      color_4
-  } else {
-      color_5
-  };
-
-  base_color * fragment.intensity
+  BANDS.band(bands_value)
 }
   
 fn planeta_azul(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let color_1 = Color::new(173, 216, 230); 
-    let color_2 = Color::new(135, 206, 250);
-    let color_3 = Color::new(0, 191, 255); 
-    let color_4 = Color::new(64, 224, 208); 
-    let color_5 = Color::new(0, 206, 209);   
-    let color_6 = Color::new(70, 130, 180); 
-    let color_7 = Color::new(0, 105, 148); 
-    let color_8 = Color::new(25, 25, 112);   
-
-    let position = fragment.vertex_position;
+    const BANDS: Gradient = Gradient::new(&[
+        (-1.0, Color::new(173, 216, 230)),
+        (-0.8, Color::new(135, 206, 250)),
+        (-0.6, Color::new(0, 191, 255)),
+        (-0.4, Color::new(64, 224, 208)),
+        (-0.2, Color::new(0, 206, 209)),
+        (0.0, Color::new(70, 130, 180)),
+        (0.2, Color::new(0, 105, 148)),
+        (0.4, Color::new(25, 25, 112)),
+    ]);
+
+    let bands_value = azul_bands(fragment.vertex_position, uniforms);
+
+    BANDS.band(bands_value) * fragment.intensity
+}
 
-    let t = uniforms.time as f32 * 0.02;
-    let pulsate = (t * 0.5).sin() * 0.5; 
-
-    let zoom = 15.0; 
-    let bands_value = ((position.y * zoom) + pulsate).sin(); 
-
-    let threshold_1 = -0.8;
-    let threshold_2 = -0.6;
-    let threshold_3 = -0.4;
-    let threshold_4 = -0.2;
-    let threshold_5 = 0.0;
-    let threshold_6 = 0.2;
-    let threshold_7 = 0.4;
-    let threshold_8 = 0.6;
-
-    // Asignar colores basados en el valor de las bandas
-    let base_color = if bands_value < threshold_1 {
-        color_1
-    } else if bands_value < threshold_2 {
-        color_2
-    } else if bands_value < threshold_3 {
-        color_3
-    } else if bands_value < threshold_4 {
-        color_4
-    } else if bands_value < threshold_5 {
-        color_5
-    } else if bands_value < threshold_6 {
-        color_6
-    } else if bands_value < threshold_7 {
-        color_7
-    } else {
-        color_8
-    };
+fn azul_bands(position: Vec3, uniforms: &Uniforms) -> f32 {
+    let t = uniforms.time * 1.2;
+    let pulsate = (t * 0.5).sin() * 0.5;
 
-    base_color * fragment.intensity
+    let zoom = 15.0;
+    ((position.y * zoom) + pulsate).sin()
 }
   
 fn planeta_celular(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-  let ring_color_1 = Color::new(85, 107, 47);   
-  let ring_color_2 = Color::new(124, 252, 0);  
-  let ring_color_3 = Color::new(34, 139, 34);   
-  let ring_color_4 = Color::new(173, 255, 47);  
+  const RINGS: Gradient = Gradient::new(&[
+      (0.0, Color::new(85, 107, 47)),
+      (0.1, Color::new(124, 252, 0)),
+      (0.3, Color::new(34, 139, 34)),
+      (0.5, Color::new(173, 255, 47)),
+  ]);
 
   let position = fragment.vertex_position;
 
-  let t = uniforms.time as f32 * 0.03; 
+  let t = uniforms.time * 1.8; 
   let pulsate = (t * 0.5).sin() * 0.2; 
 
   let zoom = 600.0; 
@@ -204,22 +242,7 @@ fn planeta_celular(fragment: &Fragment, uniforms: &Uniforms) -> Color {
       position.z * zoom + t,         
   ).abs();
 
-  let ring_threshold_1 = 0.1;
-  let ring_threshold_2 = 0.3;
-  let ring_threshold_3 = 0.5;
-  let ring_threshold_4 = 0.7;
-
-  let ring_color = if noise_value < ring_threshold_1 {
-      ring_color_1
-  } else if noise_value < ring_threshold_2 {
-      ring_color_2
-  } else if noise_value < ring_threshold_3 {
-      ring_color_3
-  } else {
-      ring_color_4
-  };
-
-  ring_color * fragment.intensity
+  RINGS.band(noise_value) * fragment.intensity
 }
 
   
@@ -231,7 +254,7 @@ fn planeta_mancha(fragment: &Fragment, uniforms: &Uniforms) -> Color {
 
     let position = fragment.vertex_position;
 
-    let t = uniforms.time as f32 * 0.03;
+    let t = uniforms.time * 1.8;
     let pulsate = (t * 0.6).sin() * 0.5 + 0.5; 
 
     let rock_zoom = 15.0; 
@@ -284,30 +307,35 @@ fn sol(fragment: &Fragment, uniforms: &Uniforms) -> Color {
       fragment.depth,
   );
  
-  let base_frequency = 0.5;  
+  let pulsate_cycles = 3.0;
   let pulsate_amplitude = 0.6;  
-  let t = uniforms.time as f32 * 0.02;  
- 
-  let pulsate = (t * base_frequency).sin() * pulsate_amplitude;
+  let time = uniforms.time;
+  let pulsate = loop_wave(time, pulsate_cycles) * pulsate_amplitude;
 
   let zoom = 1000.0;  
-  let noise_value1 = uniforms.noise.get_noise_3d(
-      position.x * zoom,
-      position.y * zoom,
-      (position.z + pulsate) * zoom,
+  let churn = Vec3::new(0.0, 150.0, 0.0);
+  let noise_value1 = looping_noise_3d(
+      uniforms.noise,
+      Vec3::new(position.x, position.y, position.z + pulsate) * zoom,
+      churn,
+      time,
+      LOOP_PERIOD,
   );
-  let noise_value2 = uniforms.noise.get_noise_3d(
-      (position.x + 1000.0) * zoom,
-      (position.y + 1000.0) * zoom,
-      (position.z + 1000.0 + pulsate) * zoom,
+  let noise_value2 = looping_noise_3d(
+      uniforms.noise,
+      Vec3::new(position.x + 1000.0, position.y + 1000.0, position.z + 1000.0 + pulsate) * zoom,
+      churn,
+      time,
+      LOOP_PERIOD,
   );
   let noise_value = (noise_value1 + noise_value2) * 0.5;  
  
-  let blended_color = core_color
+  // The light source itself, so it is not lit by it, only brightened and
+  // dimmed with its own luminosity.
+  core_color
       .lerp(&mid_color, noise_value.abs())
-      .lerp(&corona_color, (noise_value * 0.5 + 0.5).clamp(0.0, 1.0));
- 
-  blended_color * fragment.intensity
+      .lerp(&corona_color, remap(noise_value, -1.0, 1.0, 0.0, 1.0))
+      * uniforms.light.intensity
 }
 
 fn planeta_rocoso(fragment: &Fragment, uniforms: &Uniforms) -> Color {
@@ -325,7 +353,7 @@ fn planeta_rocoso(fragment: &Fragment, uniforms: &Uniforms) -> Color {
       fragment.depth,
   );
  
-  let t = uniforms.time as f32 * 0.01; 
+  let t = uniforms.time * 0.6; 
   let pulsate = (t * 0.5).sin() * 0.1;  
  
   let zoom = 1000.0;  
@@ -339,37 +367,11 @@ fn planeta_rocoso(fragment: &Fragment, uniforms: &Uniforms) -> Color {
       (position.y + 1000.0 + pulsate) * zoom,
       position.z * zoom + t, 
   );
-  let noise_value = (noise_value1 + noise_value2) * 0.5; 
+  let stone_colors = [color_7, color_6, color_5, color_4, color_3, color_2, color_1];
+  let t = palette_t(uniforms, noise_value1, noise_value2);
+  let base_color = stone_colors[palette_index(t, stone_colors.len())];
 
-  let stone_threshold_1 = -0.4;
-  let stone_threshold_2 = -0.2;
-  let stone_threshold_3 = 0.0;
-  let stone_threshold_4 = 0.2;
-  let stone_threshold_5 = 0.4;
-  let stone_threshold_6 = 0.6;
- 
-  let base_color = if noise_value > stone_threshold_6 {
-      color_1
-  } else if noise_value > stone_threshold_5 {
-      color_2
-  } else if noise_value > stone_threshold_4 {
-      color_3
-  } else if noise_value > stone_threshold_3 {
-      color_4
-  } else if noise_value > stone_threshold_2 {
-      color_5
-  } else if noise_value > stone_threshold_1 {
-      color_6
-  } else {
-      color_7
-  };
- 
-  let light_dir = Vec3::new(1.0, 1.0, 0.5).normalize(); 
-  let diffuse_intensity = dot(&light_dir, &fragment.normal).max(0.0);
- 
-  let final_color = base_color * (0.6 + 0.4 * diffuse_intensity);
-
-  final_color * fragment.intensity
+  base_color * fragment.intensity
 }
 
 
@@ -383,19 +385,26 @@ fn planeta_gaseoso(fragment: &Fragment, uniforms: &Uniforms) -> Color {
       fragment.depth,
   );
 
-  let t = uniforms.time as f32 * 0.01; 
-  let pulsate = (t * 0.3).sin() * 0.5; 
+  let time = uniforms.time;
+  let pulsate = loop_wave(time, 1.0) * 0.5; 
 
+  // Looping drift keeps the clouds moving at the same rate as before while
+  // letting the animation repeat seamlessly every LOOP_PERIOD seconds.
   let zoom = 200.0; 
-  let noise_value1 = uniforms.noise.get_noise_3d(
-      (position.x + pulsate) * zoom,
-      (position.y + pulsate) * zoom,
-      position.z * zoom + t, 
+  let drift = Vec3::new(0.0, 0.0, 0.6 * LOOP_PERIOD);
+  let noise_value1 = looping_noise_3d(
+      uniforms.noise,
+      Vec3::new((position.x + pulsate) * zoom, (position.y + pulsate) * zoom, position.z * zoom),
+      drift,
+      time,
+      LOOP_PERIOD,
   );
-  let noise_value2 = uniforms.noise.get_noise_3d(
-      (position.x - pulsate) * zoom,
-      (position.y - pulsate) * zoom,
-      position.z * zoom - t, 
+  let noise_value2 = looping_noise_3d(
+      uniforms.noise,
+      Vec3::new((position.x - pulsate) * zoom, (position.y - pulsate) * zoom, position.z * zoom),
+      -drift,
+      time,
+      LOOP_PERIOD,
   );
   let noise_value = (noise_value1 + noise_value2) * 0.5; 
 
@@ -422,7 +431,7 @@ fn planeta_arcilla(fragment: &Fragment, uniforms: &Uniforms) -> Color {
       fragment.depth,
   );
 
-  let t = uniforms.time as f32 * 0.02; 
+  let t = uniforms.time * 1.2; 
   let pulsate = (t * 0.3).sin() * 0.3; 
 
   let zoom = 500.0; 
@@ -436,64 +445,121 @@ fn planeta_arcilla(fragment: &Fragment, uniforms: &Uniforms) -> Color {
       (position.y - pulsate) * zoom,
       position.z * zoom - t, 
   );
-  let noise_value = (noise_value1 + noise_value2) * 0.5; 
-
   let gradient = (1.0 - position.y.abs()).clamp(0.0, 1.0); 
 
-  let threshold_1 = -0.2;
-  let threshold_2 = 0.0;
-  let threshold_3 = 0.2;
-  let threshold_4 = 0.4;
-
-  let base_color = if noise_value > threshold_4 {
-      color_1
-  } else if noise_value > threshold_3 {
-      color_2
-  } else if noise_value > threshold_2 {
-      color_3
-  } else if noise_value > threshold_1 {
-      color_4
-  } else {
-      color_5
-  };
+  let clay_colors = [color_5, color_4, color_3, color_2, color_1];
+  let t = palette_t(uniforms, noise_value1, noise_value2);
+  let base_color = clay_colors[palette_index(t, clay_colors.len())];
 
-  let final_color = base_color
+  base_color
       .lerp(&color_5, 1.0 - gradient) 
-      * fragment.intensity;
-
-  final_color
+      * fragment.intensity
 }
 
 fn planeta_neon(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-  let color_1 = Color::new(255, 20, 147);  
-  let color_2 = Color::new(0, 191, 255);   
-  let color_3 = Color::new(50, 205, 50);   
-  let color_4 = Color::new(255, 255, 0);   
-  let color_5 = Color::new(75, 0, 130);    
+  const BANDS: Gradient = Gradient::new(&[
+      (-1.0, Color::new(255, 20, 147)),
+      (-0.8, Color::new(0, 191, 255)),
+      (-0.4, Color::new(50, 205, 50)),
+      (0.0, Color::new(255, 255, 0)),
+      (0.4, Color::new(75, 0, 130)),
+  ]);
 
   let position = fragment.vertex_position;
 
-  let t = uniforms.time as f32 * 0.04; 
-  let wave_movement = (position.x * 10.0 + position.y * 10.0 + t).sin(); 
+  let t = uniforms.time * 2.4; 
+  let wave_movement = (position.x * 10.0 + position.y * 10.0 + t).sin() * steady(2.0 * PI / 2.4, uniforms.delta); 
 
   let zoom = 10.0; 
   let wave_value = ((position.x * zoom) + wave_movement).sin(); 
 
-  let threshold_1 = -0.8;
-  let threshold_2 = -0.4;
-  let threshold_3 = 0.0;
-  let threshold_4 = 0.4;
-
-  let base_color = if wave_value < threshold_1 {
-      color_1
-  } else if wave_value < threshold_2 {
-      color_2
-  } else if wave_value < threshold_3 {
-      color_3
-  } else if wave_value < threshold_4 {
-      color_4
+  BANDS.band(wave_value) * fragment.intensity
+}
+
+fn planeta_tectonico(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+  let ocean_plates = [Color::new(20, 60, 140), Color::new(30, 80, 160), Color::new(25, 70, 125)];
+  let land_plates = [
+      Color::new(110, 140, 60),
+      Color::new(150, 120, 70),
+      Color::new(90, 110, 50),
+      Color::new(170, 150, 100),
+  ];
+  let boundary_color = Color::new(60, 30, 20);
+
+  let position = fragment.vertex_position;
+
+  let plate = tectonic_plate(position, uniforms);
+  let base_color = if is_continental(&plate) {
+      land_plates[plate.cell_id as usize % land_plates.len()]
   } else {
-      color_5
+      ocean_plates[plate.cell_id as usize % ocean_plates.len()]
+  };
+
+  let detail_zoom = 800.0;
+  let detail = uniforms.noise.get_noise_3d(
+      position.x * detail_zoom,
+      position.y * detail_zoom,
+      position.z * detail_zoom,
+  );
+  let base_color = base_color * (1.0 + detail * 0.1);
+
+  let boundary_width = 0.15;
+  let boundary = 1.0 - smoothstep(0.0, boundary_width, plate.border_distance);
+
+  base_color.lerp(&boundary_color, boundary) * fragment.intensity
+}
+
+fn tectonic_plate(position: Vec3, uniforms: &Uniforms) -> CellSample {
+  let plate_zoom = 400.0;
+  uniforms.cells.sample(position * plate_zoom)
+}
+
+// Roughly a third of the plates are continental.
+fn is_continental(plate: &CellSample) -> bool {
+  plate.cell_value < 0.35
+}
+
+// Black holes get an accretion disk and no corona.
+pub fn is_black_hole(shader: u8) -> bool {
+  shader == 12
+}
+
+// Shaders with open water that reflects the sun.
+pub fn has_oceans(shader: u8) -> bool {
+  matches!(shader, 3 | 10)
+}
+
+// How much of the surface under `fragment` is open water, from 0 (land) to
+// 1. Only meaningful for shaders where `has_oceans` is true.
+pub fn ocean(fragment: &Fragment, uniforms: &Uniforms, shader: u8) -> f32 {
+  match shader {
+      // The deep blue bands are sea; the pale ones read as shallows and ice.
+      3 => smoothstep(-0.2, 0.2, azul_bands(fragment.vertex_position, uniforms)),
+      10 if !is_continental(&tectonic_plate(fragment.vertex_position, uniforms)) => 1.0,
+      _ => 0.0,
+  }
+}
+
+// The ring's band profile broken into fine concentric ringlets by noise
+// along the radius, so even a wide band shows grooves.
+pub fn ring_shader(fragment: &Fragment, uniforms: &Uniforms, rings: &RingSystem) -> Option<Rgba> {
+  let radius = fragment.vertex_position.xz().magnitude();
+  let (color, opacity) = rings.sample(radius)?;
+
+  let ringlet_zoom = 2500.0;
+  let ringlets = uniforms.noise.get_noise_2d(radius * ringlet_zoom, 0.0)
+      + 0.5 * uniforms.noise.get_noise_2d(radius * ringlet_zoom * 2.7, 17.0);
+  let distance = (uniforms.model_matrix.column(3).xyz() - uniforms.light.position).magnitude();
+  let shade = rings::light(&uniforms.model_matrix) * uniforms.light.attenuation(distance) * fragment.sunlight * (0.9 + 0.1 * ringlets);
+  Some(Rgba::new(color * shade, (opacity * (0.8 + 0.25 * ringlets)).clamp(0.0, 1.0)))
+}
+
+// Image map from the body's `texture` (equirectangular, e.g. an Earth or
+// Mars map), lit like the procedural planets. Gray until the map loads.
+fn planeta_textura(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+  let base_color = match uniforms.texture {
+      Some(texture) => texture.sample(fragment.tex_coords),
+      None => Color::new(128, 128, 128),
   };
 
   base_color * fragment.intensity
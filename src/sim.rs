@@ -0,0 +1,256 @@
+use std::collections::VecDeque;
+use nalgebra_glm::Vec3;
+use crate::comet::Comet;
+use crate::flares::SolarFlares;
+use crate::impact::{DebrisRing, Flash};
+use crate::probe::Probe;
+use crate::scene::{self, BodyDesc, CometDesc, Scene};
+
+// The sim moves in fixed steps of 1/STEPS_PER_SECOND of a second, however
+// often frames are drawn, so it plays out the same on any machine and
+// checkpoints and replays can step it again exactly. Speeds are radians per
+// second in scene and save files and radians per step inside.
+pub const STEPS_PER_SECOND: f32 = 60.0;
+
+// A trail point is recorded every TRAIL_INTERVAL steps and the oldest is
+// dropped past TRAIL_LENGTH.
+const TRAIL_INTERVAL: u32 = 3;
+const TRAIL_LENGTH: usize = 120;
+
+// Speed multipliers picked with +/-; the sim eases between them.
+const TIME_SCALES: [f32; 8] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0, 32.0];
+// Fraction of the remaining distance to the target covered each step's
+// worth of real time, measured on a log scale so speeding up and slowing
+// down feel the same.
+const RAMP_RATE: f32 = 0.08;
+const MAX_MOTION_BLUR: f32 = 0.6;
+
+// Dynamic per-body state. Angles are integrated every step rather than
+// computed from `time * speed`, so speeds can change at runtime without the
+// body jumping to a different point of its orbit.
+#[derive(Clone)]
+pub struct BodyState {
+    // Mean anomaly; equal to the angle around the orbit when it's a circle.
+    pub orbit_angle: f32,
+    pub spin_angle: f32,
+    // Recent positions, newest last.
+    pub trail: VecDeque<Vec3>,
+}
+
+#[derive(Clone)]
+pub struct Simulation {
+    pub bodies: Vec<BodyState>,
+    pub comets: Vec<Comet>,
+    pub probe: Option<Probe>,
+    pub flashes: Vec<Flash>,
+    pub flares: SolarFlares,
+    pub debris_rings: Vec<DebrisRing>,
+    pub selected: usize,
+    steps: u32,
+}
+
+impl Simulation {
+    pub fn new(scene: &Scene) -> Self {
+        Simulation {
+            bodies: scene
+                .bodies
+                .iter()
+                .map(|body| BodyState { orbit_angle: body.orbit.phase, spin_angle: 0.0, trail: VecDeque::new() })
+                .collect(),
+            comets: scene
+                .comets
+                .iter()
+                .enumerate()
+                .map(|(comet_id, comet)| Comet::new(comet, scene.seed, comet_id as u64))
+                .collect(),
+            probe: None,
+            flashes: Vec::new(),
+            flares: SolarFlares::new(scene.seed),
+            debris_rings: Vec::new(),
+            selected: 0,
+            steps: 0,
+        }
+    }
+
+    pub fn step(&mut self, scene: &Scene) {
+        for (state, body) in self.bodies.iter_mut().zip(&scene.bodies) {
+            state.orbit_angle += body.orbit.mean_motion;
+            state.spin_angle += body.rotation_speed;
+        }
+        for (comet, desc) in self.comets.iter_mut().zip(&scene.comets) {
+            comet.step(desc);
+        }
+        for flash in &mut self.flashes {
+            flash.step();
+        }
+        self.flashes.retain(|flash| !flash.is_done());
+        self.flares.step(scene);
+        for ring in &mut self.debris_rings {
+            ring.step();
+        }
+        // The probe reads planet positions, so it steps after them.
+        if let Some(mut probe) = self.probe.take() {
+            probe.step(scene, self);
+            self.probe = Some(probe);
+        }
+
+        self.steps += 1;
+        if self.steps.is_multiple_of(TRAIL_INTERVAL) {
+            for body_id in 0..self.bodies.len() {
+                let position = self.body_position(scene, body_id);
+                let trail = &mut self.bodies[body_id].trail;
+                trail.push_back(position);
+                if trail.len() > TRAIL_LENGTH {
+                    trail.pop_front();
+                }
+            }
+        }
+    }
+
+    // Called when bodies jump (e.g. loading a saved state) so trails don't
+    // draw a streak across the system.
+    pub fn clear_trails(&mut self) {
+        for state in &mut self.bodies {
+            state.trail.clear();
+        }
+    }
+
+    // Moons add their orbit to the parent's position.
+    pub fn body_position(&self, scene: &Scene, body_id: usize) -> Vec3 {
+        world_position(scene, &self.bodies, body_id)
+    }
+
+    // Adds a body at the end of the scene, starting where its phase puts it.
+    pub fn add_body(&mut self, scene: &mut Scene, body: BodyDesc) -> usize {
+        self.bodies.push(BodyState { orbit_angle: body.orbit.phase, spin_angle: 0.0, trail: VecDeque::new() });
+        scene.bodies.push(body);
+        scene.bodies.len() - 1
+    }
+
+    pub fn add_comet(&mut self, scene: &mut Scene, comet: CometDesc) {
+        self.comets.push(Comet::new(&comet, scene.seed, scene.comets.len() as u64));
+        scene.comets.push(comet);
+    }
+
+    // Deletes bodies as `Scene::remove_bodies` does, then drops or renumbers
+    // whatever here pointed at them. Returns the indices that went.
+    pub fn remove_bodies(&mut self, scene: &mut Scene, body_ids: &[usize]) -> Vec<usize> {
+        let removed = scene.remove_bodies(body_ids);
+        for &body_id in removed.iter().rev() {
+            self.bodies.remove(body_id);
+        }
+        let remap = |body_id: &mut usize| match scene::remap(*body_id, &removed) {
+            Some(new_id) => {
+                *body_id = new_id;
+                true
+            }
+            None => false,
+        };
+
+        if !remap(&mut self.selected) {
+            self.selected = 0;
+        }
+        if let Some(mut probe) = self.probe.take() {
+            if remap(&mut probe.origin) && remap(&mut probe.target) {
+                self.probe = Some(probe);
+            }
+        }
+        self.flashes.retain_mut(|flash| remap(&mut flash.body));
+        self.flares.flares.retain_mut(|flare| remap(&mut flare.star));
+        self.debris_rings.retain_mut(|ring| remap(&mut ring.body));
+        removed
+    }
+
+    pub fn select_next(&mut self) {
+        if !self.bodies.is_empty() {
+            self.selected = (self.selected + 1) % self.bodies.len();
+        }
+    }
+}
+
+// Position of a body given the state of every body, e.g. states other than
+// the simulation's current ones.
+pub fn world_position(scene: &Scene, bodies: &[BodyState], body_id: usize) -> Vec3 {
+    let local = local_position(scene, bodies, body_id);
+    match scene.bodies[body_id].parent {
+        Some(parent) => world_position(scene, bodies, parent) + local,
+        None => local,
+    }
+}
+
+// Sim time in seconds after `time` steps.
+pub fn seconds(time: u32) -> f32 {
+    time as f32 / STEPS_PER_SECOND
+}
+
+pub fn local_position(scene: &Scene, bodies: &[BodyState], body_id: usize) -> Vec3 {
+    scene.bodies[body_id].orbit.position(bodies[body_id].orbit_angle)
+}
+
+// How many simulation steps run per rendered frame. Steps keep a fixed size
+// so trails, tails and scripted animations look the same at any speed; the
+// fractional part of the multiplier carries over to the next frame.
+pub struct TimeScale {
+    pub current: f32,
+    pub paused: bool,
+    target_index: usize,
+    pending: f32,
+}
+
+impl TimeScale {
+    pub fn new() -> Self {
+        TimeScale {
+            current: 1.0,
+            paused: false,
+            target_index: TIME_SCALES.iter().position(|&scale| scale == 1.0).unwrap_or(0),
+            pending: 0.0,
+        }
+    }
+
+    pub fn target(&self) -> f32 {
+        TIME_SCALES[self.target_index]
+    }
+
+    pub fn faster(&mut self) {
+        self.target_index = (self.target_index + 1).min(TIME_SCALES.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.target_index = self.target_index.saturating_sub(1);
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn is_ramping(&self) -> bool {
+        self.current != self.target()
+    }
+
+    // Eases the multiplier towards the target and returns the steps due
+    // after `delta` seconds of real time.
+    pub fn advance(&mut self, delta: f32) -> u32 {
+        if self.paused {
+            return 0;
+        }
+
+        let elapsed_steps = delta * STEPS_PER_SECOND;
+        let target = self.target();
+        self.current *= (target / self.current).powf(1.0 - (1.0 - RAMP_RATE).powf(elapsed_steps));
+        if (self.current / target - 1.0).abs() < 0.01 {
+            self.current = target;
+        }
+
+        self.pending += self.current * elapsed_steps;
+        let steps = self.pending.floor();
+        self.pending -= steps;
+        steps as u32
+    }
+
+    // Blend factor with the previous frame: none up to 1x, growing with the
+    // logarithm of the multiplier.
+    pub fn motion_blur(&self) -> f32 {
+        let top = TIME_SCALES[TIME_SCALES.len() - 1].log2();
+        (self.current.log2() / top).clamp(0.0, 1.0) * MAX_MOTION_BLUR
+    }
+}
@@ -0,0 +1,49 @@
+use std::f32::consts::PI;
+use nalgebra_glm::{Mat4, Vec2, Vec3, Vec4};
+use rayon::prelude::*;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::texture::Texture;
+
+// Fills the screen with an equirectangular sky image seen from the camera:
+// each pixel shows the sky in the direction it looks, so the stars stay put
+// while the camera turns around them. `view_projection` is the matrix the
+// scene is drawn with.
+pub fn render(framebuffer: &mut Framebuffer, texture: &Texture, view_projection: &Mat4) {
+    let Some(inverse) = view_projection.try_inverse() else {
+        return;
+    };
+    // Rays vary linearly across the screen, so only the corners need
+    // unprojecting.
+    let ray = |x: f32, y: f32| {
+        let unproject = |z: f32| {
+            let point = inverse * Vec4::new(x, y, z, 1.0);
+            point.xyz() / point.w
+        };
+        unproject(1.0) - unproject(-1.0)
+    };
+    let top_left = ray(-1.0, 1.0);
+    let across = ray(1.0, 1.0) - top_left;
+    let down = ray(-1.0, -1.0) - top_left;
+
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let sky: Vec<Color> = (0..width * height)
+        .into_par_iter()
+        .map(|index| {
+            let u = ((index % width) as f32 + 0.5) / width as f32;
+            let v = ((index / width) as f32 + 0.5) / height as f32;
+            texture.sample(direction_to_uv(top_left + across * u + down * v))
+        })
+        .collect();
+
+    for (index, color) in sky.into_iter().enumerate() {
+        framebuffer.set_current_radiance(color.radiance());
+        framebuffer.point(index % width, index / width, 1.0);
+    }
+}
+
+// Longitude across the image, latitude up it.
+fn direction_to_uv(direction: Vec3) -> Vec2 {
+    let direction = direction.normalize();
+    Vec2::new(0.5 + direction.z.atan2(direction.x) / (2.0 * PI), 0.5 + direction.y.clamp(-1.0, 1.0).asin() / PI)
+}
@@ -0,0 +1,94 @@
+use std::f32::consts::PI;
+use nalgebra_glm::Vec3;
+use crate::color::{Color, Gradient};
+use crate::particles::{EmitterParams, ParticleSystem};
+use crate::rng::{Purpose, Rng};
+use crate::scene::{Scene, SPHERE_RADIUS};
+use crate::sim::Simulation;
+
+const WIND_SPEED: f32 = 0.12;
+const PARTICLES_PER_FRAME: usize = 14;
+const WIND_COLOR: Gradient = Gradient::new(&[(0.0, Color::new(255, 205, 120))]);
+// Keep the stream near the orbital plane, where the planets are.
+const MAX_LATITUDE: f32 = 0.25;
+// The magnetopause sits this many body radii out on the sunward side.
+const SHIELD_RADII: f32 = 3.0;
+
+// Sparse particles streaming out of the sun. Planets with a magnetosphere
+// push them aside, leaving a visible bow and a shadowed wake behind them.
+pub struct SolarWind {
+    pub enabled: bool,
+    pub stream: ParticleSystem,
+    rng: Rng,
+}
+
+impl SolarWind {
+    pub fn new(scene: &Scene) -> Self {
+        let reach = scene.bodies.iter().map(|body| body.orbit.semi_major_axis).fold(0.0, f32::max) + 6.0;
+        let params = EmitterParams {
+            lifetime: reach / WIND_SPEED,
+            color: WIND_COLOR,
+            brightness: 0.7,
+            ..EmitterParams::default()
+        };
+        SolarWind {
+            enabled: false,
+            stream: ParticleSystem::new(params, Rng::keyed(scene.seed, 0, Purpose::SolarWind)),
+            rng: Rng::keyed(scene.seed, 1, Purpose::SolarWind),
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        if !self.enabled {
+            self.stream.particles.clear();
+        }
+    }
+
+    pub fn step(&mut self, scene: &Scene, sim: &Simulation) {
+        if !self.enabled {
+            return;
+        }
+
+        let sun_radius = scene
+            .bodies
+            .iter()
+            .filter(|body| body.orbit.semi_major_axis == 0.0)
+            .map(|body| body.scale * SPHERE_RADIUS)
+            .fold(0.0, f32::max);
+        for _ in 0..PARTICLES_PER_FRAME {
+            let longitude = self.rng.range(0.0, 2.0 * PI);
+            let latitude = self.rng.range(-MAX_LATITUDE, MAX_LATITUDE);
+            let direction = Vec3::new(latitude.cos() * longitude.cos(), latitude.sin(), latitude.cos() * longitude.sin());
+            self.stream.spawn(direction * sun_radius, direction * WIND_SPEED);
+        }
+
+        let shields: Vec<(Vec3, f32)> = scene
+            .bodies
+            .iter()
+            .enumerate()
+            .filter(|(_, body)| body.magnetosphere && body.orbit.semi_major_axis > 0.0)
+            .map(|(body_id, body)| (sim.body_position(scene, body_id), body.scale * SPHERE_RADIUS * SHIELD_RADII))
+            .collect();
+
+        for particle in &mut self.stream.particles {
+            for &(center, radius) in &shields {
+                let offset = particle.position - center;
+                let distance = offset.magnitude();
+                if distance >= radius || distance == 0.0 {
+                    continue;
+                }
+                // Drop the part of the velocity heading into the shield and
+                // keep the speed, so the particle slides around it.
+                let normal = offset / distance;
+                let inward = particle.velocity.dot(&normal).min(0.0);
+                let speed = particle.velocity.magnitude();
+                let deflected = particle.velocity - normal * inward * 1.5;
+                particle.velocity = deflected.normalize() * speed;
+                particle.position = center + normal * radius;
+            }
+        }
+
+        self.stream.update();
+    }
+}
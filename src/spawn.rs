@@ -0,0 +1,97 @@
+use std::f32::consts::PI;
+use nalgebra_glm::Vec3;
+use crate::belt;
+use crate::noise::NoisePreset;
+use crate::orbit::Orbit;
+use crate::scene::{BodyDesc, CometDesc, Scene, ASTEROID_MESH, SPHERE_RADIUS};
+
+const ROCKY_SHADER: u8 = 7;
+const GAS_SHADER: u8 = 8;
+const COMET_ECCENTRICITY: f32 = 0.7;
+// New orbits keep at least this many star radii from the star.
+const MIN_ORBIT: f32 = 2.5;
+
+// Kinds of body that can be added while the viewer runs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Template {
+    Rocky,
+    Gas,
+    Comet,
+    Asteroid,
+}
+
+impl Template {
+    pub const ALL: [Template; 4] = [Template::Rocky, Template::Gas, Template::Comet, Template::Asteroid];
+
+    // Also how the template is written in the console and in save files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Template::Rocky => "rocoso",
+            Template::Gas => "gaseoso",
+            Template::Comet => "cometa",
+            Template::Asteroid => "asteroide",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Template::ALL.into_iter().find(|template| template.name() == name)
+    }
+}
+
+pub enum Spawned {
+    Body(BodyDesc),
+    Comet(CometDesc),
+}
+
+// A new body from `template` going round the star through `at`, flattened
+// onto the orbital plane. A comet starts there at its farthest point.
+pub fn create(template: Template, scene: &Scene, at: Vec3) -> Spawned {
+    let star_radius = scene
+        .bodies
+        .iter()
+        .filter(|body| body.orbit.semi_major_axis == 0.0)
+        .map(|body| body.scale * SPHERE_RADIUS)
+        .fold(SPHERE_RADIUS, f32::max);
+    let angle = at.z.atan2(at.x);
+    let radius = Vec3::new(at.x, 0.0, at.z).magnitude().max(star_radius * MIN_ORBIT);
+    let name = unused_name(scene, template);
+
+    let (shader, scale, rotation_speed) = match template {
+        Template::Comet => {
+            let semi_major_axis = radius / (1.0 + COMET_ECCENTRICITY);
+            let orbit = Orbit {
+                semi_major_axis,
+                eccentricity: COMET_ECCENTRICITY,
+                inclination: 0.0,
+                periapsis: angle + PI,
+                mean_motion: belt::mean_motion(scene, semi_major_axis),
+                phase: PI,
+            };
+            return Spawned::Comet(CometDesc { name, scale: 0.25, orbit, spawned: true });
+        }
+        Template::Rocky => (ROCKY_SHADER, 0.6, 0.02),
+        Template::Gas => (GAS_SHADER, 1.4, 0.04),
+        Template::Asteroid => (ROCKY_SHADER, 0.3, 0.05),
+    };
+    let mut body = BodyDesc::new(&name, shader, scale, radius, rotation_speed, belt::mean_motion(scene, radius));
+    body.orbit.phase = angle;
+    body.noise = match template {
+        Template::Gas => NoisePreset::DomainWarped,
+        _ => NoisePreset::Ridged,
+    };
+    if template == Template::Asteroid {
+        body.mesh = Some(ASTEROID_MESH.to_string());
+    }
+    body.spawned = Some(template);
+    Spawned::Body(body)
+}
+
+// "Rocoso 1", "Rocoso 2"... skipping names already in the scene.
+fn unused_name(scene: &Scene, template: Template) -> String {
+    let taken = |name: &str| scene.bodies.iter().any(|body| body.name == name) || scene.comets.iter().any(|comet| comet.name == name);
+    let word = template.name();
+    (1..)
+        .map(|n| format!("{}{} {}", word[..1].to_uppercase(), &word[1..], n))
+        .find(|name| !taken(name))
+        .unwrap_or_default()
+}
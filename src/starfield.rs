@@ -0,0 +1,92 @@
+use std::f32::consts::PI;
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::color::{Color, Gradient};
+use crate::framebuffer::Framebuffer;
+use crate::rng::{Purpose, Rng};
+
+// The whole sky, in square degrees.
+const SKY_AREA: f32 = 41_253.0;
+pub const DEFAULT_DENSITY: f32 = 0.1;
+// Cool stars are orange and hot ones blue-white, but only faintly: from far
+// away most look white.
+const TEMPERATURE: Gradient = Gradient::new(&[
+    (0.0, Color::new(255, 196, 140)),
+    (0.45, Color::new(255, 240, 225)),
+    (0.7, Color::new(245, 245, 255)),
+    (1.0, Color::new(190, 210, 255)),
+]);
+// How much a twinkling star's brightness swings either way.
+const TWINKLE_DEPTH: f32 = 0.35;
+
+// A `[starfield]` from the scene file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StarfieldDesc {
+    // Stars per square degree of sky.
+    pub density: f32,
+    pub seed: u64,
+    pub twinkle: bool,
+}
+
+struct Star {
+    direction: Vec3,
+    color: Color,
+    // Radiance at its brightest moment; the brightest few go past white and
+    // pick up some bloom.
+    brightness: f32,
+    twinkle_phase: f32,
+    // Radians per second.
+    twinkle_speed: f32,
+}
+
+// A generated background of stars at infinity, used instead of the sky
+// image. The same description always gives the same sky.
+pub struct Starfield {
+    stars: Vec<Star>,
+    twinkle: bool,
+}
+
+impl Starfield {
+    pub fn new(desc: &StarfieldDesc) -> Self {
+        let mut rng = Rng::keyed(desc.seed, 0, Purpose::Starfield);
+        let count = (desc.density * SKY_AREA).round() as usize;
+        let stars = (0..count)
+            .map(|_| {
+                // Uniform over the sphere.
+                let z = rng.range(-1.0, 1.0);
+                let angle = rng.range(0.0, 2.0 * PI);
+                let ring = (1.0 - z * z).sqrt();
+                Star {
+                    direction: Vec3::new(ring * angle.cos(), ring * angle.sin(), z),
+                    color: TEMPERATURE.at(rng.next_f32()),
+                    // Many faint stars and a handful of bright ones.
+                    brightness: 0.15 + rng.next_f32().powi(6) * 2.0,
+                    twinkle_phase: rng.range(0.0, 2.0 * PI),
+                    twinkle_speed: rng.range(3.0, 15.0),
+                }
+            })
+            .collect();
+        Starfield { stars, twinkle: desc.twinkle }
+    }
+
+    // One pixel per star, behind everything. `screen_transform` is the
+    // scene's full transform; stars ignore its translation, being infinitely
+    // far away.
+    pub fn render(&self, framebuffer: &mut Framebuffer, screen_transform: &Mat4, time: f32) {
+        for star in &self.stars {
+            let clip = screen_transform * Vec4::new(star.direction.x, star.direction.y, star.direction.z, 0.0);
+            if clip.w <= 0.0 {
+                continue;
+            }
+            let (x, y) = (clip.x / clip.w, clip.y / clip.w);
+            if x < 0.0 || y < 0.0 {
+                continue;
+            }
+            let mut brightness = star.brightness;
+            if self.twinkle {
+                brightness *= 1.0 - TWINKLE_DEPTH * (0.5 + 0.5 * (time * star.twinkle_speed + star.twinkle_phase).sin());
+            }
+            framebuffer.set_current_radiance(star.color.radiance() * brightness);
+            framebuffer.point(x as usize, y as usize, 1.0);
+        }
+    }
+}
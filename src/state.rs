@@ -0,0 +1,297 @@
+use std::f32::consts::PI;
+use std::fmt::Write as _;
+use std::fs;
+use nalgebra_glm::Vec3;
+use crate::impact::DebrisRing;
+use crate::scene::{Scene, SPHERE_RADIUS};
+use crate::sim::{Simulation, STEPS_PER_SECOND};
+use crate::spawn::{self, Spawned, Template};
+use crate::toml_lite::{self, Value};
+
+pub const STATE_PATH: &str = "savestate.toml";
+
+// Snapshot of the runtime state: simulation clock, selection, and every
+// body's current angles and (possibly edited) speeds. Bodies are matched by
+// name so a save still loads if the scene gained or lost bodies. Bodies
+// added or deleted at runtime are added or deleted again on load.
+pub fn save(path: &str, scene: &Scene, sim: &Simulation, time: u32) -> Result<(), String> {
+    let mut state = State::new();
+    state.capture(scene, sim, time);
+    fs::write(path, state.to_toml()).map_err(|e| format!("cannot write {}: {}", path, e))
+}
+
+// What a save file holds, copied out of the scene and the simulation.
+// Capturing reuses the buffers of the last capture and formats nothing, so
+// it is cheap enough to do every frame.
+pub struct State {
+    scene: String,
+    time: u32,
+    selected: usize,
+    deleted: Vec<String>,
+    bodies: Vec<SavedBody>,
+    comets: Vec<SavedComet>,
+    rings: Vec<SavedRing>,
+}
+
+#[derive(Default)]
+struct SavedBody {
+    name: String,
+    orbital_speed: f32,
+    rotation_speed: f32,
+    orbit_angle: f32,
+    spin_angle: f32,
+    // The template and spot of a body added at runtime.
+    spawned: Option<(Template, Vec3)>,
+}
+
+#[derive(Default)]
+struct SavedComet {
+    name: String,
+    mean_anomaly: f32,
+    // Where a comet added at runtime was placed.
+    spawned_at: Option<Vec3>,
+}
+
+#[derive(Default)]
+struct SavedRing {
+    body: String,
+    normal: Vec3,
+    impact_angle: f32,
+    seed: u64,
+    age: f32,
+}
+
+// Makes `items` as long as `count`, keeping the elements it already had.
+fn resize<T: Default>(items: &mut Vec<T>, count: usize) -> &mut [T] {
+    items.resize_with(count, T::default);
+    items
+}
+
+impl State {
+    pub const fn new() -> Self {
+        State { scene: String::new(), time: 0, selected: 0, deleted: Vec::new(), bodies: Vec::new(), comets: Vec::new(), rings: Vec::new() }
+    }
+
+    pub fn capture(&mut self, scene: &Scene, sim: &Simulation, time: u32) {
+        self.scene.clone_from(&scene.name);
+        self.time = time;
+        self.selected = sim.selected;
+        self.deleted.clone_from(&scene.deleted);
+
+        for (saved, (body, state)) in resize(&mut self.bodies, scene.bodies.len()).iter_mut().zip(scene.bodies.iter().zip(&sim.bodies)) {
+            saved.name.clone_from(&body.name);
+            saved.orbital_speed = body.orbit.mean_motion * STEPS_PER_SECOND;
+            saved.rotation_speed = body.rotation_speed * STEPS_PER_SECOND;
+            saved.orbit_angle = state.orbit_angle;
+            saved.spin_angle = state.spin_angle;
+            saved.spawned = body.spawned.map(|template| (template, body.orbit.position(body.orbit.phase)));
+        }
+
+        for (saved, (desc, comet)) in resize(&mut self.comets, scene.comets.len()).iter_mut().zip(scene.comets.iter().zip(&sim.comets)) {
+            saved.name.clone_from(&desc.name);
+            saved.mean_anomaly = comet.mean_anomaly;
+            saved.spawned_at = desc.spawned.then(|| desc.orbit.position(PI));
+        }
+
+        for (saved, ring) in resize(&mut self.rings, sim.debris_rings.len()).iter_mut().zip(&sim.debris_rings) {
+            saved.body.clone_from(&scene.bodies[ring.body].name);
+            saved.normal = ring.normal;
+            saved.impact_angle = ring.impact_angle;
+            saved.seed = ring.seed;
+            saved.age = ring.age;
+        }
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "scene = {:?}", self.scene);
+        let _ = writeln!(out, "time = {}", self.time);
+        let _ = writeln!(out, "selected = {}", self.selected);
+        if !self.deleted.is_empty() {
+            let names: Vec<String> = self.deleted.iter().map(|name| format!("{:?}", name)).collect();
+            let _ = writeln!(out, "deleted = [{}]", names.join(", "));
+        }
+
+        for body in &self.bodies {
+            let _ = writeln!(out);
+            let _ = writeln!(out, "[[body]]");
+            let _ = writeln!(out, "name = {:?}", body.name);
+            let _ = writeln!(out, "orbital_speed = {}", body.orbital_speed);
+            let _ = writeln!(out, "rotation_speed = {}", body.rotation_speed);
+            let _ = writeln!(out, "orbit_angle = {}", body.orbit_angle);
+            let _ = writeln!(out, "spin_angle = {}", body.spin_angle);
+            if let Some((template, at)) = body.spawned {
+                write_spawned(&mut out, template, at);
+            }
+        }
+
+        for comet in &self.comets {
+            let _ = writeln!(out);
+            let _ = writeln!(out, "[[comet]]");
+            let _ = writeln!(out, "name = {:?}", comet.name);
+            let _ = writeln!(out, "mean_anomaly = {}", comet.mean_anomaly);
+            if let Some(at) = comet.spawned_at {
+                write_spawned(&mut out, Template::Comet, at);
+            }
+        }
+
+        // Rings are stored by their generating parameters and replayed on
+        // load. The seed is written as hex because numbers are read back as
+        // f32.
+        for ring in &self.rings {
+            let _ = writeln!(out);
+            let _ = writeln!(out, "[[ring]]");
+            let _ = writeln!(out, "body = {:?}", ring.body);
+            let _ = writeln!(out, "normal = [{}, {}, {}]", ring.normal.x, ring.normal.y, ring.normal.z);
+            let _ = writeln!(out, "impact_angle = {}", ring.impact_angle);
+            let _ = writeln!(out, "seed = \"{:x}\"", ring.seed);
+            let _ = writeln!(out, "age = {}", ring.age);
+        }
+
+        out
+    }
+}
+
+// What `spawn::create` needs to make the same body again.
+fn write_spawned(out: &mut String, template: Template, at: Vec3) {
+    let _ = writeln!(out, "spawned = {:?}", template.name());
+    let _ = writeln!(out, "spawned_at = [{}, {}, {}]", at.x, at.y, at.z);
+}
+
+// Makes a body or comet added at runtime again from its template.
+fn respawn(table: &toml_lite::Table, name: &str, scene: &Scene) -> Option<Spawned> {
+    let template = match table.get("spawned").map(|entry| &entry.value) {
+        Some(Value::String(template)) => Template::parse(template)?,
+        _ => return None,
+    };
+    let at = match table.get("spawned_at").map(|entry| &entry.value) {
+        Some(Value::Array(items)) => match items.as_slice() {
+            [Value::Number(x), Value::Number(y), Value::Number(z)] => Vec3::new(*x, *y, *z),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let mut spawned = spawn::create(template, scene, at);
+    match &mut spawned {
+        Spawned::Body(body) => body.name = name.to_string(),
+        Spawned::Comet(comet) => comet.name = name.to_string(),
+    }
+    Some(spawned)
+}
+
+// Returns the bodies deleted to match the save, as `Scene::remove_bodies`
+// does; bodies it added are at the end of the scene.
+pub fn load(path: &str, scene: &mut Scene, sim: &mut Simulation, time: &mut u32) -> Result<Vec<usize>, String> {
+    let source = fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    let document = toml_lite::parse(&source).map_err(|errors| {
+        errors
+            .iter()
+            .map(|error| format!("{}:{}: {}", path, error.line, error.message))
+            .collect::<Vec<_>>()
+            .join("\n")
+    })?;
+
+    let number = |table: &toml_lite::Table, key: &str| match table.get(key).map(|entry| &entry.value) {
+        Some(Value::Number(value)) => Some(*value),
+        _ => None,
+    };
+
+    if let Some(saved_time) = number(&document.root, "time") {
+        *time = saved_time as u32;
+    }
+    let removed = match document.root.get("deleted").map(|entry| &entry.value) {
+        Some(Value::Array(names)) => {
+            let body_ids: Vec<usize> = names
+                .iter()
+                .filter_map(|name| match name {
+                    Value::String(name) => scene.bodies.iter().position(|body| &body.name == name),
+                    _ => None,
+                })
+                .collect();
+            // The last body stays, as it does when deleting by hand.
+            if body_ids.len() < scene.bodies.len() { sim.remove_bodies(scene, &body_ids) } else { Vec::new() }
+        }
+        _ => Vec::new(),
+    };
+    if let Some(selected) = number(&document.root, "selected") {
+        sim.selected = (selected as usize).min(sim.bodies.len().saturating_sub(1));
+    }
+
+    for table in document.tables_named("body") {
+        let Some(Value::String(name)) = table.get("name").map(|entry| &entry.value) else {
+            continue;
+        };
+        let found = scene.bodies.iter().position(|body| &body.name == name);
+        let body_id = match found.ok_or_else(|| respawn(table, name, scene)) {
+            Ok(body_id) => body_id,
+            Err(Some(Spawned::Body(body))) => sim.add_body(scene, body),
+            Err(_) => {
+                eprintln!("{}:{}: no body named `{}` in this scene, skipping", path, table.line, name);
+                continue;
+            }
+        };
+
+        let body = &mut scene.bodies[body_id];
+        // Speeds are saved per second, like in scene files.
+        body.orbit.mean_motion = number(table, "orbital_speed").map_or(body.orbit.mean_motion, |speed| speed / STEPS_PER_SECOND);
+        body.rotation_speed = number(table, "rotation_speed").map_or(body.rotation_speed, |speed| speed / STEPS_PER_SECOND);
+
+        let state = &mut sim.bodies[body_id];
+        state.orbit_angle = number(table, "orbit_angle").unwrap_or(state.orbit_angle);
+        state.spin_angle = number(table, "spin_angle").unwrap_or(state.spin_angle);
+    }
+
+    for table in document.tables_named("comet") {
+        let Some(Value::String(name)) = table.get("name").map(|entry| &entry.value) else {
+            continue;
+        };
+        let found = scene.comets.iter().position(|comet| &comet.name == name);
+        let comet_id = match found.ok_or_else(|| respawn(table, name, scene)) {
+            Ok(comet_id) => comet_id,
+            Err(Some(Spawned::Comet(comet))) => {
+                sim.add_comet(scene, comet);
+                scene.comets.len() - 1
+            }
+            Err(_) => {
+                eprintln!("{}:{}: no comet named `{}` in this scene, skipping", path, table.line, name);
+                continue;
+            }
+        };
+        if let Some(mean_anomaly) = number(table, "mean_anomaly") {
+            sim.comets[comet_id].jump_to(&scene.comets[comet_id], mean_anomaly);
+        }
+    }
+
+    sim.flashes.clear();
+    sim.debris_rings.clear();
+    for table in document.tables_named("ring") {
+        let Some(Value::String(name)) = table.get("body").map(|entry| &entry.value) else {
+            continue;
+        };
+        let Some(body_id) = scene.bodies.iter().position(|body| &body.name == name) else {
+            eprintln!("{}:{}: no body named `{}` in this scene, skipping ring", path, table.line, name);
+            continue;
+        };
+        let normal = match table.get("normal").map(|entry| &entry.value) {
+            Some(Value::Array(items)) => match items.as_slice() {
+                [Value::Number(x), Value::Number(y), Value::Number(z)] => Vec3::new(*x, *y, *z),
+                _ => continue,
+            },
+            _ => continue,
+        };
+        let seed = match table.get("seed").map(|entry| &entry.value) {
+            Some(Value::String(hex)) => u64::from_str_radix(hex, 16).ok(),
+            _ => None,
+        };
+        let (Some(seed), Some(impact_angle), Some(age)) = (seed, number(table, "impact_angle"), number(table, "age")) else {
+            eprintln!("{}:{}: incomplete ring, skipping", path, table.line);
+            continue;
+        };
+
+        let body_radius = scene.bodies[body_id].scale * SPHERE_RADIUS;
+        sim.debris_rings.push(DebrisRing::restore(body_id, body_radius, normal, impact_angle, seed, age));
+    }
+    sim.clear_trails();
+
+    Ok(removed)
+}
@@ -0,0 +1,66 @@
+use nalgebra_glm::Vec3;
+use crate::camera::Camera;
+use crate::draw2d;
+use crate::framebuffer::Framebuffer;
+use crate::layout::{self, Anchor};
+use crate::render_target::RenderTarget;
+use crate::text::draw_text;
+
+const WIDTH: usize = 200;
+const HEIGHT: usize = 150;
+const MARGIN: usize = 6;
+const BACKGROUND_COLOR: u32 = 0x000000;
+const BORDER_COLOR: u32 = 0x404050;
+const LABEL_COLOR: u32 = 0xE0E0E0;
+// Where the camera sits, in radii of the body: behind it on the night side,
+// and raised a little so the body fills the bottom of the inset instead of
+// covering the sun.
+const BEHIND_RADII: f32 = 3.0;
+const LIFT_RADII: f32 = 2.0;
+
+// Inset in the top-right corner (F7) looking past the selected body towards
+// the sun, so transits and eclipses can be watched while the main camera is
+// elsewhere. It is drawn into its own target with its own camera.
+pub struct SunView {
+    pub visible: bool,
+    pub target: RenderTarget,
+    pub camera: Camera,
+}
+
+impl SunView {
+    pub fn new() -> Self {
+        let mut target = RenderTarget::new(WIDTH, HEIGHT);
+        target.framebuffer.set_background_color(BACKGROUND_COLOR);
+        SunView {
+            visible: false,
+            target,
+            camera: Camera::new(Vec3::new(0.0, 0.0, 1.0), Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0)),
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    // Places the camera behind the body at `position`, looking at the sun in
+    // the origin. Returns false for the sun itself, which has no behind.
+    pub fn aim(&mut self, position: Vec3, radius: f32) -> bool {
+        let Some(away) = position.try_normalize(1.0e-6) else {
+            return false;
+        };
+        // Any direction across the line of sight; the ecliptic's up unless
+        // the body sits right over the pole.
+        let up = Vec3::y().cross(&away).cross(&away).try_normalize(1.0e-6).map_or(Vec3::x(), |up| -up);
+        self.camera.eye = position + (away * BEHIND_RADII + up * LIFT_RADII) * radius;
+        self.camera.center = Vec3::zeros();
+        self.camera.up = up;
+        true
+    }
+
+    pub fn draw(&self, framebuffer: &mut Framebuffer, label: &str) {
+        let (x, y) = layout::place(framebuffer, Anchor::TopRight, (WIDTH, HEIGHT), MARGIN);
+        draw2d::fill_rect(framebuffer, x - 1, y - 1, WIDTH + 2, HEIGHT + 2, BORDER_COLOR);
+        self.target.blit(framebuffer, x, y);
+        draw_text(framebuffer, x + 4, y + 4, label, LABEL_COLOR, 1);
+    }
+}
@@ -0,0 +1,341 @@
+use std::sync::OnceLock;
+use crate::draw2d;
+use crate::framebuffer::Framebuffer;
+use crate::ttf::{Font, GlyphBitmap};
+
+pub const GLYPH_WIDTH: usize = 5;
+pub const GLYPH_HEIGHT: usize = 7;
+pub const GLYPH_ADVANCE: usize = GLYPH_WIDTH + 1;
+pub const LINE_HEIGHT: usize = GLYPH_HEIGHT + 3;
+
+// Classic 5x7 font for ASCII 0x20..=0x7E. Each glyph is stored as five
+// columns, bit 0 being the top row.
+const FONT_5X7: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5F, 0x00, 0x00], // '!'
+    [0x00, 0x07, 0x00, 0x07, 0x00], // '"'
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], // '#'
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12], // '$'
+    [0x23, 0x13, 0x08, 0x64, 0x62], // '%'
+    [0x36, 0x49, 0x55, 0x22, 0x50], // '&'
+    [0x00, 0x05, 0x03, 0x00, 0x00], // '''
+    [0x00, 0x1C, 0x22, 0x41, 0x00], // '('
+    [0x00, 0x41, 0x22, 0x1C, 0x00], // ')'
+    [0x14, 0x08, 0x3E, 0x08, 0x14], // '*'
+    [0x08, 0x08, 0x3E, 0x08, 0x08], // '+'
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ','
+    [0x08, 0x08, 0x08, 0x08, 0x08], // '-'
+    [0x00, 0x60, 0x60, 0x00, 0x00], // '.'
+    [0x20, 0x10, 0x08, 0x04, 0x02], // '/'
+    [0x3E, 0x51, 0x49, 0x45, 0x3E], // '0'
+    [0x00, 0x42, 0x7F, 0x40, 0x00], // '1'
+    [0x42, 0x61, 0x51, 0x49, 0x46], // '2'
+    [0x21, 0x41, 0x45, 0x4B, 0x31], // '3'
+    [0x18, 0x14, 0x12, 0x7F, 0x10], // '4'
+    [0x27, 0x45, 0x45, 0x45, 0x39], // '5'
+    [0x3C, 0x4A, 0x49, 0x49, 0x30], // '6'
+    [0x01, 0x71, 0x09, 0x05, 0x03], // '7'
+    [0x36, 0x49, 0x49, 0x49, 0x36], // '8'
+    [0x06, 0x49, 0x49, 0x29, 0x1E], // '9'
+    [0x00, 0x36, 0x36, 0x00, 0x00], // ':'
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ';'
+    [0x08, 0x14, 0x22, 0x41, 0x00], // '<'
+    [0x14, 0x14, 0x14, 0x14, 0x14], // '='
+    [0x00, 0x41, 0x22, 0x14, 0x08], // '>'
+    [0x02, 0x01, 0x51, 0x09, 0x06], // '?'
+    [0x32, 0x49, 0x79, 0x41, 0x3E], // '@'
+    [0x7E, 0x11, 0x11, 0x11, 0x7E], // 'A'
+    [0x7F, 0x49, 0x49, 0x49, 0x36], // 'B'
+    [0x3E, 0x41, 0x41, 0x41, 0x22], // 'C'
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], // 'D'
+    [0x7F, 0x49, 0x49, 0x49, 0x41], // 'E'
+    [0x7F, 0x09, 0x09, 0x09, 0x01], // 'F'
+    [0x3E, 0x41, 0x49, 0x49, 0x7A], // 'G'
+    [0x7F, 0x08, 0x08, 0x08, 0x7F], // 'H'
+    [0x00, 0x41, 0x7F, 0x41, 0x00], // 'I'
+    [0x20, 0x40, 0x41, 0x3F, 0x01], // 'J'
+    [0x7F, 0x08, 0x14, 0x22, 0x41], // 'K'
+    [0x7F, 0x40, 0x40, 0x40, 0x40], // 'L'
+    [0x7F, 0x02, 0x0C, 0x02, 0x7F], // 'M'
+    [0x7F, 0x04, 0x08, 0x10, 0x7F], // 'N'
+    [0x3E, 0x41, 0x41, 0x41, 0x3E], // 'O'
+    [0x7F, 0x09, 0x09, 0x09, 0x06], // 'P'
+    [0x3E, 0x41, 0x51, 0x21, 0x5E], // 'Q'
+    [0x7F, 0x09, 0x19, 0x29, 0x46], // 'R'
+    [0x46, 0x49, 0x49, 0x49, 0x31], // 'S'
+    [0x01, 0x01, 0x7F, 0x01, 0x01], // 'T'
+    [0x3F, 0x40, 0x40, 0x40, 0x3F], // 'U'
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], // 'V'
+    [0x3F, 0x40, 0x38, 0x40, 0x3F], // 'W'
+    [0x63, 0x14, 0x08, 0x14, 0x63], // 'X'
+    [0x07, 0x08, 0x70, 0x08, 0x07], // 'Y'
+    [0x61, 0x51, 0x49, 0x45, 0x43], // 'Z'
+    [0x00, 0x7F, 0x41, 0x41, 0x00], // '['
+    [0x02, 0x04, 0x08, 0x10, 0x20], // '\'
+    [0x00, 0x41, 0x41, 0x7F, 0x00], // ']'
+    [0x04, 0x02, 0x01, 0x02, 0x04], // '^'
+    [0x40, 0x40, 0x40, 0x40, 0x40], // '_'
+    [0x00, 0x01, 0x02, 0x04, 0x00], // '`'
+    [0x20, 0x54, 0x54, 0x54, 0x78], // 'a'
+    [0x7F, 0x48, 0x44, 0x44, 0x38], // 'b'
+    [0x38, 0x44, 0x44, 0x44, 0x20], // 'c'
+    [0x38, 0x44, 0x44, 0x48, 0x7F], // 'd'
+    [0x38, 0x54, 0x54, 0x54, 0x18], // 'e'
+    [0x08, 0x7E, 0x09, 0x01, 0x02], // 'f'
+    [0x0C, 0x52, 0x52, 0x52, 0x3E], // 'g'
+    [0x7F, 0x08, 0x04, 0x04, 0x78], // 'h'
+    [0x00, 0x44, 0x7D, 0x40, 0x00], // 'i'
+    [0x20, 0x40, 0x44, 0x3D, 0x00], // 'j'
+    [0x7F, 0x10, 0x28, 0x44, 0x00], // 'k'
+    [0x00, 0x41, 0x7F, 0x40, 0x00], // 'l'
+    [0x7C, 0x04, 0x18, 0x04, 0x78], // 'm'
+    [0x7C, 0x08, 0x04, 0x04, 0x78], // 'n'
+    [0x38, 0x44, 0x44, 0x44, 0x38], // 'o'
+    [0x7C, 0x14, 0x14, 0x14, 0x08], // 'p'
+    [0x08, 0x14, 0x14, 0x18, 0x7C], // 'q'
+    [0x7C, 0x08, 0x04, 0x04, 0x08], // 'r'
+    [0x48, 0x54, 0x54, 0x54, 0x20], // 's'
+    [0x04, 0x3F, 0x44, 0x40, 0x20], // 't'
+    [0x3C, 0x40, 0x40, 0x20, 0x7C], // 'u'
+    [0x1C, 0x20, 0x40, 0x20, 0x1C], // 'v'
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], // 'w'
+    [0x44, 0x28, 0x10, 0x28, 0x44], // 'x'
+    [0x0C, 0x50, 0x50, 0x50, 0x3C], // 'y'
+    [0x44, 0x64, 0x54, 0x4C, 0x44], // 'z'
+    [0x00, 0x08, 0x36, 0x41, 0x00], // '{'
+    [0x00, 0x00, 0x7F, 0x00, 0x00], // '|'
+    [0x00, 0x41, 0x36, 0x08, 0x00], // '}'
+    [0x08, 0x04, 0x08, 0x10, 0x08], // '~'
+];
+
+// Glyphs live in a taller cell than the 5x7 letters: two rows above for the
+// accents on capitals and one below for cedillas, both drawn into the gap
+// between lines. Bit 0 is the top of the cell.
+const ROWS_ABOVE: usize = 2;
+const CELL_ROWS: usize = ROWS_ABOVE + GLYPH_HEIGHT + 1;
+type Glyph = [u16; 5];
+
+// Accents as two rows of five columns, low bit first.
+#[derive(Clone, Copy)]
+enum Mark {
+    Grave,
+    Acute,
+    Circumflex,
+    Tilde,
+    Diaeresis,
+    Ring,
+    Cedilla,
+}
+
+impl Mark {
+    fn rows(self) -> [u8; 2] {
+        match self {
+            Mark::Grave => [0b00010, 0b00100],
+            Mark::Acute => [0b01000, 0b00100],
+            Mark::Circumflex => [0b00100, 0b01010],
+            Mark::Tilde => [0b10110, 0b01001],
+            Mark::Diaeresis => [0b00000, 0b01010],
+            Mark::Ring => [0b01110, 0b01010],
+            Mark::Cedilla => [0b01100, 0b00000],
+        }
+    }
+}
+
+// Latin-1 letters from U+00C0 on, as an ASCII letter and its mark. The few
+// that are not (Æ, Ð, Ø, Þ, ß and their lowercase, × and ÷) fall back to the
+// missing glyph box.
+const ACCENTED: [Option<(char, Mark)>; 64] = {
+    use Mark::*;
+    [
+        Some(('A', Grave)), Some(('A', Acute)), Some(('A', Circumflex)), Some(('A', Tilde)),
+        Some(('A', Diaeresis)), Some(('A', Ring)), None, Some(('C', Cedilla)),
+        Some(('E', Grave)), Some(('E', Acute)), Some(('E', Circumflex)), Some(('E', Diaeresis)),
+        Some(('I', Grave)), Some(('I', Acute)), Some(('I', Circumflex)), Some(('I', Diaeresis)),
+        None, Some(('N', Tilde)), Some(('O', Grave)), Some(('O', Acute)),
+        Some(('O', Circumflex)), Some(('O', Tilde)), Some(('O', Diaeresis)), None,
+        None, Some(('U', Grave)), Some(('U', Acute)), Some(('U', Circumflex)),
+        Some(('U', Diaeresis)), Some(('Y', Acute)), None, None,
+        Some(('a', Grave)), Some(('a', Acute)), Some(('a', Circumflex)), Some(('a', Tilde)),
+        Some(('a', Diaeresis)), Some(('a', Ring)), None, Some(('c', Cedilla)),
+        Some(('e', Grave)), Some(('e', Acute)), Some(('e', Circumflex)), Some(('e', Diaeresis)),
+        Some(('i', Grave)), Some(('i', Acute)), Some(('i', Circumflex)), Some(('i', Diaeresis)),
+        None, Some(('n', Tilde)), Some(('o', Grave)), Some(('o', Acute)),
+        Some(('o', Circumflex)), Some(('o', Tilde)), Some(('o', Diaeresis)), None,
+        None, Some(('u', Grave)), Some(('u', Acute)), Some(('u', Circumflex)),
+        Some(('u', Diaeresis)), Some(('y', Acute)), None, Some(('y', Diaeresis)),
+    ]
+};
+
+// Latin-1 symbols drawn by hand, in the same 5x7 columns as the ASCII font.
+const SYMBOLS: [(char, [u8; 5]); 6] = [
+    ('¡', [0x00, 0x00, 0x7D, 0x00, 0x00]),
+    ('¿', [0x30, 0x48, 0x45, 0x40, 0x20]),
+    ('°', [0x00, 0x06, 0x09, 0x09, 0x06]),
+    ('«', [0x08, 0x14, 0x2A, 0x14, 0x22]),
+    ('»', [0x22, 0x14, 0x2A, 0x14, 0x08]),
+    ('·', [0x00, 0x00, 0x08, 0x00, 0x00]),
+];
+
+// An empty box, for characters the font has no glyph for.
+const MISSING: Glyph = [0x1FC, 0x104, 0x104, 0x104, 0x1FC];
+
+// Every glyph packed into one table, with a slot for each of the first 256
+// code points. Built on first use.
+struct FontAtlas {
+    glyphs: Vec<Glyph>,
+    slots: [u16; 256],
+}
+
+impl FontAtlas {
+    fn build() -> Self {
+        // Slot 0 is the missing glyph box.
+        let mut glyphs = vec![MISSING];
+        let mut slots = [0u16; 256];
+        let mut add = |slots: &mut [u16; 256], code: usize, glyph: Glyph| {
+            slots[code] = glyphs.len() as u16;
+            glyphs.push(glyph);
+        };
+
+        for (index, columns) in FONT_5X7.iter().enumerate() {
+            add(&mut slots, 0x20 + index, columns.map(|column| (column as u16) << ROWS_ABOVE));
+        }
+        // A no-break space is still a space.
+        add(&mut slots, 0xA0, [0; 5]);
+        for (c, columns) in SYMBOLS {
+            add(&mut slots, c as usize, columns.map(|column| (column as u16) << ROWS_ABOVE));
+        }
+        for (index, accented) in ACCENTED.iter().enumerate() {
+            if let Some((base, mark)) = *accented {
+                add(&mut slots, 0xC0 + index, compose(base, mark));
+            }
+        }
+        FontAtlas { glyphs, slots }
+    }
+
+    fn glyph(&self, c: char) -> &Glyph {
+        let slot = self.slots.get(c as usize).copied().unwrap_or(0);
+        &self.glyphs[slot as usize]
+    }
+}
+
+// `base` with `mark` above it, or below for a cedilla. Capitals take the
+// accent in the rows above the cell; lowercase letters in their own top two
+// rows, which also drops the dot of the i.
+fn compose(base: char, mark: Mark) -> Glyph {
+    let mut glyph = FONT_5X7[base as usize - 0x20].map(|column| (column as u16) << ROWS_ABOVE);
+    let top = match mark {
+        Mark::Cedilla => CELL_ROWS - 1,
+        _ if base.is_ascii_uppercase() => 0,
+        _ => {
+            for column in &mut glyph {
+                *column &= !(0b11 << ROWS_ABOVE);
+            }
+            ROWS_ABOVE
+        }
+    };
+    for (row, bits) in mark.rows().iter().enumerate() {
+        for (column, cell) in glyph.iter_mut().enumerate() {
+            if bits & (1 << column) != 0 && top + row < CELL_ROWS {
+                *cell |= 1 << (top + row);
+            }
+        }
+    }
+    glyph
+}
+
+fn atlas() -> &'static FontAtlas {
+    static ATLAS: OnceLock<FontAtlas> = OnceLock::new();
+    ATLAS.get_or_init(FontAtlas::build)
+}
+
+// Text scales the TrueType font is rendered at; larger text falls back to
+// the bitmap font.
+const OUTLINE_SCALES: usize = 3;
+// Past Latin-1, where the font's own missing glyph is kept.
+const OUTLINE_MISSING: usize = 256;
+
+// The same first 256 code points rendered from a TrueType font, once per
+// text scale, sized so capitals stand as tall as the bitmap ones and line
+// spacing stays the same.
+struct OutlineAtlas {
+    sizes: Vec<Vec<GlyphBitmap>>,
+}
+
+impl OutlineAtlas {
+    fn glyph(&self, c: char, scale: usize) -> &GlyphBitmap {
+        &self.sizes[scale - 1][(c as usize).min(OUTLINE_MISSING)]
+    }
+}
+
+static OUTLINE_ATLAS: OnceLock<OutlineAtlas> = OnceLock::new();
+
+// Draws HUD text with the font at `path` from now on. Only one font can be
+// loaded per run.
+pub fn load_font(path: &str) -> Result<(), String> {
+    let font = Font::load(path)?;
+    let em_pixels = GLYPH_HEIGHT as f32 / font.cap_height();
+    let sizes = (1..=OUTLINE_SCALES)
+        .map(|scale| {
+            let missing = std::iter::once(0);
+            let glyphs = (0..OUTLINE_MISSING as u32).map(|code| char::from_u32(code).map_or(0, |c| font.glyph_index(c)));
+            glyphs.chain(missing).map(|glyph| font.rasterize(glyph, em_pixels * scale as f32)).collect()
+        })
+        .collect();
+    OUTLINE_ATLAS.set(OutlineAtlas { sizes }).map_err(|_| "a font is already loaded".to_string())
+}
+
+fn outline_atlas(scale: usize) -> Option<&'static OutlineAtlas> {
+    OUTLINE_ATLAS.get().filter(|_| (1..=OUTLINE_SCALES).contains(&scale))
+}
+
+pub fn text_width(text: &str, scale: usize) -> usize {
+    match outline_atlas(scale) {
+        Some(outline) => text.chars().map(|c| outline.glyph(c, scale).advance).sum(),
+        None => text.chars().count() * GLYPH_ADVANCE * scale,
+    }
+}
+
+// Draws text over whatever is already in the color buffer; depth is ignored.
+// `y` is the top of the letters; accents and cedillas reach a little past.
+pub fn draw_text(framebuffer: &mut Framebuffer, x: usize, y: usize, text: &str, color: u32, scale: usize) {
+    if let Some(outline) = outline_atlas(scale) {
+        draw_outline_text(framebuffer, outline, x, y, text, color, scale);
+        return;
+    }
+    let atlas = atlas();
+    let mut cursor_x = x;
+    for c in text.chars() {
+        let columns = atlas.glyph(c);
+        for (col, bits) in columns.iter().enumerate() {
+            for row in 0..CELL_ROWS {
+                // Accent rows above the top of the screen are cut off.
+                let Some(pixel_y) = (y + row * scale).checked_sub(ROWS_ABOVE * scale) else {
+                    continue;
+                };
+                if bits & (1 << row) != 0 {
+                    draw2d::fill_rect(framebuffer, cursor_x + col * scale, pixel_y, scale, scale, color);
+                }
+            }
+        }
+        cursor_x += GLYPH_ADVANCE * scale;
+    }
+}
+
+fn draw_outline_text(framebuffer: &mut Framebuffer, outline: &OutlineAtlas, x: usize, y: usize, text: &str, color: u32, scale: usize) {
+    let baseline = (y + GLYPH_HEIGHT * scale) as i32;
+    let mut pen_x = x as i32;
+    for c in text.chars() {
+        let glyph = outline.glyph(c, scale);
+        let (left, top) = (pen_x + glyph.left, baseline - glyph.top);
+        for row in 0..glyph.height {
+            for column in 0..glyph.width {
+                let coverage = glyph.coverage[row * glyph.width + column];
+                let (pixel_x, pixel_y) = (left + column as i32, top + row as i32);
+                if coverage > 0 && pixel_x >= 0 && pixel_y >= 0 {
+                    draw2d::blend_rect(framebuffer, pixel_x as usize, pixel_y as usize, 1, 1, color, coverage as f32 / 255.0);
+                }
+            }
+        }
+        pen_x += glyph.advance as i32;
+    }
+}
@@ -0,0 +1,85 @@
+use image::RgbImage;
+use nalgebra_glm::Vec2;
+use crate::color::Color;
+use crate::sampling::{Filter, Sampler, Wrap};
+
+// How planet maps are addressed unless a body's `texture_wrap` says
+// otherwise, as (u, v).
+pub const PLANET_WRAP: (Wrap, Wrap) = (Wrap::Repeat, Wrap::Clamp);
+
+// What the bytes of an image file stand for. Color maps (planet surfaces,
+// the sky) are sRGB encoded like any picture and are decoded to linear
+// light; data maps such as heightmaps hold plain values and are only
+// scaled to 0..1.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorSpace {
+    Srgb,
+    Linear,
+}
+
+impl ColorSpace {
+    pub const NAMES: [&'static str; 2] = ["srgb", "linear"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "srgb" => Some(ColorSpace::Srgb),
+            "linear" => Some(ColorSpace::Linear),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorSpace::Srgb => "srgb",
+            ColorSpace::Linear => "linear",
+        }
+    }
+}
+
+// An image sampled by texture coordinates, decoded once when loaded so
+// sampling and filtering work on linear values. Planet maps are
+// equirectangular: u runs once around the equator and wraps, v goes from
+// the south pole (0) to the north pole (1) and stops there.
+#[derive(Clone)]
+pub struct Texture {
+    pub width: u32,
+    pub height: u32,
+    // Row by row from the top, as in the file.
+    pub texels: Vec<Color>,
+    pub sampler: Sampler,
+}
+
+impl Texture {
+    pub fn new(image: &RgbImage, space: ColorSpace) -> Self {
+        let texels = image
+            .pixels()
+            .map(|pixel| match space {
+                ColorSpace::Srgb => Color::new(pixel[0], pixel[1], pixel[2]),
+                ColorSpace::Linear => Color::rgb(pixel[0] as f32 / 255.0, pixel[1] as f32 / 255.0, pixel[2] as f32 / 255.0),
+            })
+            .collect();
+        Texture {
+            width: image.width(),
+            height: image.height(),
+            texels,
+            sampler: Sampler::new(Filter::Bilinear, PLANET_WRAP.0, PLANET_WRAP.1),
+        }
+    }
+
+    // The same texels addressed another way, for a body that tiles or
+    // clamps a file others share.
+    pub fn with_wrap(mut self, (wrap_u, wrap_v): (Wrap, Wrap)) -> Self {
+        self.sampler.wrap_u = wrap_u;
+        self.sampler.wrap_v = wrap_v;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.sampler.filter = filter;
+        self
+    }
+
+    pub fn sample(&self, uv: Vec2) -> Color {
+        self.sampler.sample((self.width, self.height), |column, row| self.texels[(row * self.width + column) as usize], uv)
+    }
+}
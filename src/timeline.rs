@@ -0,0 +1,163 @@
+use nalgebra_glm::Vec3;
+
+#[derive(Clone, Copy)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+pub trait Lerp {
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for Vec3 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+// A single value animated between two endpoints over `duration` frames.
+pub struct Tween<T: Lerp> {
+    pub from: T,
+    pub to: T,
+    pub duration: f32,
+    pub easing: Easing,
+}
+
+impl<T: Lerp> Tween<T> {
+    pub fn new(from: T, to: T, duration: f32, easing: Easing) -> Self {
+        Tween { from, to, duration, easing }
+    }
+
+    pub fn value_at(&self, elapsed: f32) -> T {
+        let t = if self.duration > 0.0 { elapsed / self.duration } else { 1.0 };
+        self.from.lerp(&self.to, self.easing.apply(t))
+    }
+}
+
+#[derive(Clone)]
+struct Segment<S> {
+    label: S,
+    start: f32,
+    duration: f32,
+    easing: Easing,
+}
+
+// Back-to-back segments played in order. Each frame the owner asks which
+// segment is active and how far into it (already eased) the clock is, and
+// animates whatever that segment stands for.
+#[derive(Clone)]
+pub struct Timeline<S> {
+    segments: Vec<Segment<S>>,
+    pub elapsed: f32,
+}
+
+impl<S> Timeline<S> {
+    pub fn new() -> Self {
+        Timeline { segments: Vec::new(), elapsed: 0.0 }
+    }
+
+    pub fn then(mut self, label: S, duration: f32, easing: Easing) -> Self {
+        let start = self.duration();
+        self.segments.push(Segment { label, start, duration, easing });
+        self
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.segments.last().map_or(0.0, |segment| segment.start + segment.duration)
+    }
+
+    pub fn advance(&mut self, frames: f32) {
+        self.elapsed = (self.elapsed + frames).min(self.duration());
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration()
+    }
+
+    // Active segment and its eased progress in [0, 1]. Once finished, the
+    // last segment is reported as complete.
+    pub fn current(&self) -> Option<(&S, f32)> {
+        let segment = self
+            .segments
+            .iter()
+            .find(|segment| self.elapsed < segment.start + segment.duration)
+            .or(self.segments.last())?;
+        let t = if segment.duration > 0.0 { (self.elapsed - segment.start) / segment.duration } else { 1.0 };
+        Some((&segment.label, segment.easing.apply(t)))
+    }
+}
+
+// Seconds a caption takes to fade in, and again to fade out.
+const CAPTION_FADE: f32 = 1.0 / 3.0;
+
+// A line of narration shown from `start` for `duration` sim seconds, which
+// can cut to one of the scene's named shots as it starts.
+pub struct Caption {
+    pub text: String,
+    pub start: f32,
+    pub duration: f32,
+    pub shot: Option<String>,
+}
+
+impl Caption {
+    // Opacity in [0, 1]: zero outside the caption's window, eased in and out
+    // at its ends.
+    pub fn opacity(&self, elapsed: f32) -> f32 {
+        let local = elapsed - self.start;
+        if local < 0.0 || local >= self.duration {
+            return 0.0;
+        }
+        let fade = CAPTION_FADE.min(self.duration / 2.0);
+        let fade_in = Easing::EaseInOut.apply(local / fade);
+        let fade_out = Easing::EaseInOut.apply((self.duration - local) / fade);
+        fade_in.min(fade_out)
+    }
+}
+
+// Subtitle track that plays alongside the simulation clock. Captions may
+// overlap; all visible ones are returned in start order.
+#[derive(Default)]
+pub struct CaptionTrack {
+    pub captions: Vec<Caption>,
+}
+
+impl CaptionTrack {
+    pub fn visible(&self, elapsed: f32) -> Vec<(&str, f32)> {
+        self.captions
+            .iter()
+            .map(|caption| (caption.text.as_str(), caption.opacity(elapsed)))
+            .filter(|(_, opacity)| *opacity > 0.0)
+            .collect()
+    }
+
+    // Shots cued by captions starting after `from` and up to `to`; the last
+    // one wins when several start together.
+    pub fn cued_shot(&self, from: f32, to: f32) -> Option<&str> {
+        self.captions
+            .iter()
+            .filter(|caption| caption.start > from && caption.start <= to)
+            .filter_map(|caption| caption.shot.as_deref())
+            .next_back()
+    }
+}
@@ -0,0 +1,68 @@
+use std::time::{Duration, Instant};
+use minifb::Window;
+
+const FIELDS: [&str; 4] = ["scene", "fps", "day", "speed"];
+// Often enough to read as live, rarely enough not to flicker the taskbar.
+const REFRESH: Duration = Duration::from_secs(1);
+
+pub struct TitleValues<'a> {
+    pub scene: &'a str,
+    pub fps: f32,
+    // Simulation days elapsed.
+    pub day: f32,
+    pub speed: f32,
+    pub paused: bool,
+}
+
+// Checks that every `{field}` in a template is known and braces are closed.
+pub fn check(template: &str) -> Result<(), String> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(length) = rest[start..].find('}') else {
+            return Err(format!("unclosed `{{` in title `{}`", template));
+        };
+        let field = &rest[start + 1..start + length];
+        if !FIELDS.contains(&field) {
+            return Err(format!("unknown title field `{{{}}}` (available: {})", field, FIELDS.map(|name| format!("{{{}}}", name)).join(", ")));
+        }
+        rest = &rest[start + length + 1..];
+    }
+    Ok(())
+}
+
+// Replaces the fields of a template that already passed `check`.
+pub fn expand(template: &str, values: &TitleValues) -> String {
+    let speed = if values.paused { "pausa".to_string() } else { format!("x{}", (values.speed * 100.0).round() / 100.0) };
+    template
+        .replace("{scene}", values.scene)
+        .replace("{fps}", &format!("{:.0}", values.fps))
+        .replace("{day}", &format!("{:.0}", values.day))
+        .replace("{speed}", &speed)
+}
+
+// Keeps the window title in sync with a template, at most once per second.
+pub struct WindowTitle {
+    template: String,
+    last_update: Option<Instant>,
+    current: String,
+}
+
+impl WindowTitle {
+    pub fn new(template: &str) -> Self {
+        WindowTitle { template: template.to_string(), last_update: None, current: String::new() }
+    }
+
+    pub fn update(&mut self, window: &mut Window, values: &TitleValues) {
+        let now = Instant::now();
+        if self.last_update.is_some_and(|last| now.duration_since(last) < REFRESH) {
+            return;
+        }
+        self.last_update = Some(now);
+
+        let title = expand(&self.template, values);
+        if title != self.current {
+            window.set_title(&title);
+            self.current = title;
+        }
+    }
+}
@@ -0,0 +1,323 @@
+// Minimal parser for the subset of TOML used by scene and config files:
+// `[table]` and `[[array]]` headers, `key = value` pairs, strings, numbers,
+// booleans and single-line arrays. Every entry remembers its line number so
+// callers can point at the offending line when something is wrong.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Number(f32),
+    Bool(bool),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Number(_) => "number",
+            Value::Bool(_) => "boolean",
+            Value::Array(_) => "array",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub key: String,
+    pub value: Value,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Table {
+    pub name: String,
+    pub line: usize,
+    pub entries: Vec<Entry>,
+}
+
+impl Table {
+    pub fn get(&self, key: &str) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.key == key)
+    }
+}
+
+#[derive(Debug)]
+pub struct Document {
+    pub root: Table,
+    pub tables: Vec<Table>,
+}
+
+impl Document {
+    pub fn table(&self, name: &str) -> Option<&Table> {
+        self.tables.iter().find(|table| table.name == name)
+    }
+
+    pub fn tables_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Table> + 'a {
+        self.tables.iter().filter(move |table| table.name == name)
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+pub fn parse(source: &str) -> Result<Document, Vec<ParseError>> {
+    let mut root = Table { name: String::new(), line: 0, entries: Vec::new() };
+    let mut tables: Vec<Table> = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, raw_line) in source.lines().enumerate() {
+        let line = index + 1;
+        let content = strip_comment(raw_line).trim();
+        if content.is_empty() {
+            continue;
+        }
+
+        if content.starts_with('[') {
+            let name = content
+                .strip_prefix("[[").and_then(|rest| rest.strip_suffix("]]"))
+                .or_else(|| content.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')));
+            match name.map(str::trim) {
+                Some(name) if !name.is_empty() => {
+                    tables.push(Table { name: name.to_string(), line, entries: Vec::new() });
+                }
+                _ => errors.push(ParseError { line, message: format!("malformed table header `{}`", content) }),
+            }
+            continue;
+        }
+
+        let Some((key, value)) = content.split_once('=') else {
+            errors.push(ParseError { line, message: format!("expected `key = value`, found `{}`", content) });
+            continue;
+        };
+
+        let key = key.trim();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            errors.push(ParseError { line, message: format!("invalid key `{}`", key) });
+            continue;
+        }
+
+        match parse_value(value.trim()) {
+            Ok(value) => {
+                let table = tables.last_mut().unwrap_or(&mut root);
+                if table.get(key).is_some() {
+                    errors.push(ParseError { line, message: format!("duplicate key `{}`", key) });
+                } else {
+                    table.entries.push(Entry { key: key.to_string(), value, line });
+                }
+            }
+            Err(message) => errors.push(ParseError { line, message }),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(Document { root, tables })
+    } else {
+        Err(errors)
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut previous = ' ';
+    for (i, c) in line.char_indices() {
+        if c == '"' && previous != '\\' {
+            in_string = !in_string;
+        } else if c == '#' && !in_string {
+            return &line[..i];
+        }
+        previous = c;
+    }
+    line
+}
+
+fn parse_value(text: &str) -> Result<Value, String> {
+    if text.is_empty() {
+        return Err("missing value".to_string());
+    }
+
+    if let Some(inner) = text.strip_prefix('"') {
+        let Some(inner) = inner.strip_suffix('"') else {
+            return Err(format!("unterminated string `{}`", text));
+        };
+        return Ok(Value::String(unescape(inner)));
+    }
+
+    if let Some(inner) = text.strip_prefix('[') {
+        let Some(inner) = inner.strip_suffix(']') else {
+            return Err(format!("unterminated array `{}`", text));
+        };
+        let mut items = Vec::new();
+        for item in split_array_items(inner) {
+            let item = item.trim();
+            if !item.is_empty() {
+                items.push(parse_value(item)?);
+            }
+        }
+        return Ok(Value::Array(items));
+    }
+
+    match text {
+        "true" => return Ok(Value::Bool(true)),
+        "false" => return Ok(Value::Bool(false)),
+        _ => {}
+    }
+
+    text.replace('_', "")
+        .parse::<f32>()
+        .map(Value::Number)
+        .map_err(|_| format!("invalid value `{}`", text))
+}
+
+fn split_array_items(text: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut start = 0;
+
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '[' if !in_string => depth += 1,
+            ']' if !in_string => depth -= 1,
+            ',' if !in_string && depth == 0 => {
+                items.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&text[start..]);
+    items
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => result.push('\n'),
+                Some('t') => result.push('\t'),
+                Some(other) => result.push(other),
+                None => {}
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(source: &str) -> Vec<(usize, String)> {
+        parse(source).expect_err("source should not parse").into_iter().map(|error| (error.line, error.message)).collect()
+    }
+
+    #[test]
+    fn parses_tables_arrays_of_tables_and_root_keys() {
+        let document = parse(
+            "seed = 7\n\
+             [camera]\n\
+             fov = 60\n\
+             [[body]]\n\
+             name = \"Sol\"\n\
+             [[body]]\n\
+             name = \"Tierra\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(document.root.get("seed").unwrap().value, Value::Number(7.0));
+        assert_eq!(document.table("camera").unwrap().get("fov").unwrap().line, 3);
+        let names: Vec<_> = document.tables_named("body").map(|table| table.get("name").unwrap().value.clone()).collect();
+        assert_eq!(names, [Value::String("Sol".into()), Value::String("Tierra".into())]);
+    }
+
+    #[test]
+    fn parses_values() {
+        let document = parse(
+            "a = \"tab\\there \\\"quoted\\\"\"\n\
+             b = 1_000.5\n\
+             c = -2e3\n\
+             d = false\n\
+             e = [1, \"x, y\", [true, []], ]\n\
+             f = []\n",
+        )
+        .unwrap();
+        let value = |key| document.root.get(key).unwrap().value.clone();
+
+        assert_eq!(value("a"), Value::String("tab\there \"quoted\"".into()));
+        assert_eq!(value("b"), Value::Number(1000.5));
+        assert_eq!(value("c"), Value::Number(-2000.0));
+        assert_eq!(value("d"), Value::Bool(false));
+        assert_eq!(
+            value("e"),
+            Value::Array(vec![
+                Value::Number(1.0),
+                Value::String("x, y".into()),
+                Value::Array(vec![Value::Bool(true), Value::Array(Vec::new())]),
+            ])
+        );
+        assert_eq!(value("f"), Value::Array(Vec::new()));
+    }
+
+    #[test]
+    fn comments_stop_outside_strings_only() {
+        let document = parse("# header\n\n  name = \"# not a comment\" # a comment\n[t] # trailing\n").unwrap();
+        assert_eq!(document.root.get("name").unwrap().value, Value::String("# not a comment".into()));
+        assert_eq!(document.tables[0].name, "t");
+    }
+
+    #[test]
+    fn same_key_in_different_tables_is_not_a_duplicate() {
+        assert!(parse("name = 1\n[[body]]\nname = 2\n[[body]]\nname = 3\n").is_ok());
+    }
+
+    #[test]
+    fn reports_every_error_with_its_line() {
+        let errors = errors(
+            "ok = 1\n\
+             ok = 2\n\
+             []\n\
+             [unclosed\n\
+             just words\n\
+             bad key = 1\n\
+             empty =\n\
+             text = \"open\n\
+             list = [1, 2\n\
+             number = 1.2.3\n\
+             inner = [1, nope]\n",
+        );
+
+        assert_eq!(
+            errors,
+            [
+                (2, "duplicate key `ok`".to_string()),
+                (3, "malformed table header `[]`".to_string()),
+                (4, "malformed table header `[unclosed`".to_string()),
+                (5, "expected `key = value`, found `just words`".to_string()),
+                (6, "invalid key `bad key`".to_string()),
+                (7, "missing value".to_string()),
+                (8, "unterminated string `\"open`".to_string()),
+                (9, "unterminated array `[1, 2`".to_string()),
+                (10, "invalid value `1.2.3`".to_string()),
+                (11, "invalid value `nope`".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn type_names() {
+        let names: Vec<_> = [Value::String(String::new()), Value::Number(0.0), Value::Bool(true), Value::Array(Vec::new())]
+            .iter()
+            .map(Value::type_name)
+            .collect();
+        assert_eq!(names, ["string", "number", "boolean", "array"]);
+    }
+}
@@ -0,0 +1,77 @@
+use nalgebra_glm::Vec3;
+use crate::color::encode;
+
+// Half a photographic stop per key press.
+const EXPOSURE_STEP: f32 = 0.5;
+const MAX_EXPOSURE: f32 = 4.0;
+
+// Curve that squeezes any brightness into what the screen can show.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Operator {
+    // x / (1 + x): gentle, never quite reaches white.
+    Reinhard,
+    // Narkowicz's fit of the ACES filmic curve: keeps the mid tones close to
+    // where they were and rolls highlights off into white.
+    Aces,
+}
+
+impl Operator {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Operator::Reinhard => "Reinhard",
+            Operator::Aces => "ACES",
+        }
+    }
+
+    fn apply(&self, x: f32) -> f32 {
+        match self {
+            Operator::Reinhard => x / (1.0 + x),
+            Operator::Aces => (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14),
+        }
+    }
+}
+
+// How the scene's radiance, where 1 is the white of a fully lit surface and
+// the sun goes well past it, becomes the colors on screen.
+#[derive(Clone, Copy, Debug)]
+pub struct ToneMapping {
+    pub operator: Operator,
+    // In stops: each one doubles the light before the curve.
+    pub exposure: f32,
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self {
+        ToneMapping { operator: Operator::Aces, exposure: 0.0 }
+    }
+}
+
+impl ToneMapping {
+    pub fn brighter(&mut self) {
+        self.exposure = (self.exposure + EXPOSURE_STEP).min(MAX_EXPOSURE);
+    }
+
+    pub fn darker(&mut self) {
+        self.exposure = (self.exposure - EXPOSURE_STEP).max(-MAX_EXPOSURE);
+    }
+
+    pub fn next_operator(&mut self) {
+        self.operator = match self.operator {
+            Operator::Reinhard => Operator::Aces,
+            Operator::Aces => Operator::Reinhard,
+        };
+    }
+
+    // A 0xRRGGBB sRGB pixel for `radiance`, each channel through the curve
+    // on its own so overbright colors wash out towards white. This is the
+    // only place linear light gets gamma encoded.
+    pub fn map(&self, radiance: Vec3) -> u32 {
+        let scale = self.exposure.exp2();
+        let channel = |value: f32| encode(self.operator.apply((value * scale).max(0.0))) as u32;
+        (channel(radiance.x) << 16) | (channel(radiance.y) << 8) | channel(radiance.z)
+    }
+
+    pub fn label(&self) -> String {
+        format!("{} {:+.1} EV", self.operator.name(), self.exposure)
+    }
+}
@@ -0,0 +1,155 @@
+use nalgebra_glm::Vec3;
+use crate::scene::{Scene, SPHERE_RADIUS};
+
+// Frames a notification stays on screen after its event starts.
+const NOTIFICATION_FRAMES: u32 = 300;
+// Closest the camera is put to the body in front, in its radii.
+const MIN_VIEW_RADII: f32 = 3.0;
+// Room left around the observing body when the camera is put near it.
+const OBSERVER_CLEARANCE_RADII: f32 = 1.5;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EventKind {
+    // A smaller body crossing a star's disk.
+    Transit,
+    // A body covering a star.
+    Eclipse,
+    // A body hiding anything that isn't a star.
+    Occultation,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Observer {
+    Camera,
+    Body(usize),
+}
+
+// `front` overlapping `back` on the sky of `observer`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Event {
+    pub kind: EventKind,
+    pub front: usize,
+    pub back: usize,
+    pub observer: Observer,
+}
+
+impl Event {
+    pub fn describe(&self, scene: &Scene) -> String {
+        let (front, back) = (&scene.bodies[self.front].name, &scene.bodies[self.back].name);
+        let what = match self.kind {
+            EventKind::Transit => format!("Tránsito de {} frente a {}", front, back),
+            EventKind::Eclipse => format!("Eclipse de {} por {}", back, front),
+            EventKind::Occultation => format!("Ocultación de {} por {}", back, front),
+        };
+        match self.observer {
+            Observer::Camera => format!("{} desde la cámara", what),
+            Observer::Body(observer) => format!("{} visto desde {}", what, scene.bodies[observer].name),
+        }
+    }
+}
+
+// Watches for bodies passing in front of each other, seen from the camera
+// and from every planet and moon. From a body only the sun counts as the
+// one behind, so each planet reports its own transits and eclipses rather
+// than every time a neighbour hides a star-lit dot. An event fires once
+// when it starts and shows a notification for a while.
+pub struct TransitWatch {
+    active: Vec<Event>,
+    notification: Option<(Event, u32)>,
+}
+
+impl TransitWatch {
+    pub fn new() -> Self {
+        TransitWatch { active: Vec::new(), notification: None }
+    }
+
+    // `positions` are the world positions the bodies are drawn at.
+    pub fn update(&mut self, scene: &Scene, positions: &[Vec3], eye: Vec3) {
+        let mut events = overlaps(scene, positions, eye, Observer::Camera, |_| true);
+        for observer in (0..scene.bodies.len()).filter(|&body_id| !is_star(scene, body_id)) {
+            events.extend(overlaps(scene, positions, positions[observer], Observer::Body(observer), |back| is_star(scene, back)));
+        }
+
+        if let Some(started) = events.iter().rev().find(|event| !self.active.contains(event)) {
+            self.notification = Some((*started, NOTIFICATION_FRAMES));
+        }
+        self.active = events;
+        if let Some((_, frames_left)) = &mut self.notification {
+            *frames_left = frames_left.saturating_sub(1);
+        }
+        self.notification = self.notification.filter(|&(_, frames_left)| frames_left > 0);
+    }
+
+    pub fn notification(&self) -> Option<&Event> {
+        self.notification.as_ref().map(|(event, _)| event)
+    }
+
+    // Eye and center for watching the notified event: on the observer's side
+    // of the body in front, looking through it at the one behind.
+    pub fn best_view(&self, scene: &Scene, positions: &[Vec3], eye: Vec3) -> Option<(Vec3, Vec3)> {
+        let event = self.notification()?;
+        let radius = |body_id: usize| scene.bodies[body_id].scale * SPHERE_RADIUS;
+        let (observer, observer_radius) = match event.observer {
+            Observer::Camera => (eye, 0.0),
+            Observer::Body(body_id) => (positions[body_id], radius(body_id)),
+        };
+        let front = positions[event.front];
+        let offset = observer - front;
+        let distance = offset.magnitude();
+        let direction = offset.try_normalize(1.0e-6)?;
+        let from_front = (distance * 0.5).min(distance - observer_radius * OBSERVER_CLEARANCE_RADII).max(radius(event.front) * MIN_VIEW_RADII);
+        Some((front + direction * from_front, positions[event.back]))
+    }
+}
+
+fn is_star(scene: &Scene, body_id: usize) -> bool {
+    scene.bodies[body_id].orbit.semi_major_axis == 0.0
+}
+
+// Every pair of bodies whose disks overlap as seen from `from`, the nearer
+// one in front, keeping only backs that `wanted_back` accepts.
+fn overlaps(scene: &Scene, positions: &[Vec3], from: Vec3, observer: Observer, wanted_back: impl Fn(usize) -> bool) -> Vec<Event> {
+    // Direction, distance and angular radius of each body, or None for the
+    // observer itself and anything it is inside of.
+    let sky: Vec<Option<(Vec3, f32, f32)>> = positions
+        .iter()
+        .enumerate()
+        .map(|(body_id, &position)| {
+            if observer == Observer::Body(body_id) {
+                return None;
+            }
+            let offset = position - from;
+            let distance = offset.magnitude();
+            let radius = scene.bodies[body_id].scale * SPHERE_RADIUS;
+            (distance > radius).then(|| (offset / distance, distance, (radius / distance).asin()))
+        })
+        .collect();
+
+    let mut events = Vec::new();
+    for (front, front_sky) in sky.iter().enumerate() {
+        let Some((front_direction, front_distance, front_size)) = *front_sky else {
+            continue;
+        };
+        for (back, back_sky) in sky.iter().enumerate() {
+            let Some((back_direction, back_distance, back_size)) = *back_sky else {
+                continue;
+            };
+            if back == front || back_distance <= front_distance || !wanted_back(back) {
+                continue;
+            }
+            let separation = front_direction.dot(&back_direction).clamp(-1.0, 1.0).acos();
+            if separation >= front_size + back_size {
+                continue;
+            }
+            let kind = if !is_star(scene, back) {
+                EventKind::Occultation
+            } else if front_size < back_size {
+                EventKind::Transit
+            } else {
+                EventKind::Eclipse
+            };
+            events.push(Event { kind, front, back, observer });
+        }
+    }
+    events
+}
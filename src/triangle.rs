@@ -1,51 +1,59 @@
-use nalgebra_glm::{Vec3, dot};
+use nalgebra_glm::Vec3;
 use crate::fragment::Fragment;
 use crate::vertex::Vertex;
-use crate::color::Color;
 
-pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
+pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, width: usize, height: usize) -> Vec<Fragment> {
   let mut fragments = Vec::new();
   let (a, b, c) = (v1.transformed_position, v2.transformed_position, v3.transformed_position);
 
+  // Only scan the part of the bounding box that is on screen; triangles
+  // right in front of the camera can span millions of pixels otherwise.
   let (min_x, min_y, max_x, max_y) = calculate_bounding_box(&a, &b, &c);
-
-  let light_dir = Vec3::new(0.0, 0.0, 1.0);
+  let (min_x, min_y) = (min_x.max(0), min_y.max(0));
+  let (max_x, max_y) = (max_x.min(width as i32 - 1), max_y.min(height as i32 - 1));
 
   let triangle_area = edge_function(&a, &b, &c);
 
+  // Attributes vary linearly across the triangle in 3D, not on screen, so
+  // they are weighted by 1/w and renormalized. Depth is already linear on
+  // screen after the divide.
+  let inverse_w = (1.0 / v1.clip_position.w, 1.0 / v2.clip_position.w, 1.0 / v3.clip_position.w);
+
   for y in min_y..=max_y {
     for x in min_x..=max_x {
       let point = Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.0);
 
       let (w1, w2, w3) = barycentric_coordinates(&point, &a, &b, &c, triangle_area);
 
-      if w1 >= 0.0 && w1 <= 1.0 && 
-         w2 >= 0.0 && w2 <= 1.0 &&
-         w3 >= 0.0 && w3 <= 1.0 {
+      if (0.0..=1.0).contains(&w1) &&
+         (0.0..=1.0).contains(&w2) &&
+         (0.0..=1.0).contains(&w3) {
 
-        let normal = v1.transformed_normal * w1 + v2.transformed_normal * w2 + v3.transformed_normal * w3;
-        let normal = normal.normalize();
+        let (p1, p2, p3) = (w1 * inverse_w.0, w2 * inverse_w.1, w3 * inverse_w.2);
+        let sum = p1 + p2 + p3;
+        let (p1, p2, p3) = (p1 / sum, p2 / sum, p3 / sum);
 
-        let intensity = dot(&normal, &light_dir).max(0.0);
+        let normal = v1.transformed_normal * p1 + v2.transformed_normal * p2 + v3.transformed_normal * p3;
+        let normal = normal.normalize();
 
-        let base_color = Color::new(100, 100, 100);
-        let lit_color = base_color * intensity;
+        let color = v1.color * p1 + v2.color * p2 + v3.color * p3;
 
         let depth = a.z * w1 + b.z * w2 + c.z * w3;
 
-        let vertex_position = v1.position * w1 + v2.position * w2 + v3.position * w3;
-
-        fragments.push(
-            Fragment::new(
-                x as f32,
-                y as f32,
-                lit_color,
-                depth,
-                normal,
-                intensity,
-                vertex_position,
-            )
+        let vertex_position = v1.position * p1 + v2.position * p2 + v3.position * p3;
+
+        let mut fragment = Fragment::new(
+            x as f32,
+            y as f32,
+            color,
+            depth,
+            normal,
+            1.0,
+            vertex_position,
         );
+        fragment.world_position = v1.world_position * p1 + v2.world_position * p2 + v3.world_position * p3;
+        fragment.tex_coords = v1.tex_coords * p1 + v2.tex_coords * p2 + v3.tex_coords * p3;
+        fragments.push(fragment);
       }
     }
   }
@@ -53,6 +61,13 @@ pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
   fragments
 }
 
+// Screen-space winding test. Meshes are counter-clockwise seen from outside;
+// the viewport flips y, so on screen their front faces turn clockwise.
+pub fn is_front_facing(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> bool {
+  let (a, b, c) = (v1.transformed_position, v2.transformed_position, v3.transformed_position);
+  edge_function(&a, &b, &c) > 0.0
+}
+
 fn calculate_bounding_box(v1: &Vec3, v2: &Vec3, v3: &Vec3) -> (i32, i32, i32, i32) {
     let min_x = v1.x.min(v2.x).min(v3.x).floor() as i32;
     let min_y = v1.y.min(v2.y).min(v3.y).floor() as i32;
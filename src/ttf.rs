@@ -0,0 +1,604 @@
+use std::fs;
+
+// Just enough of TrueType to draw HUD text: the character map, horizontal
+// metrics and quadratic outlines (simple and composite glyphs). Hinting,
+// kerning and CFF outlines are not supported.
+pub struct Font {
+    data: Vec<u8>,
+    units_per_em: f32,
+    glyph_count: u16,
+    long_offsets: bool,
+    long_metrics: u16,
+    cmap: usize,
+    loca: usize,
+    glyf: usize,
+    hmtx: usize,
+}
+
+// Anti-aliased coverage of one glyph, 0..=255 per pixel. `left` and `top`
+// place the bitmap relative to the pen on the baseline, `top` counting up.
+pub struct GlyphBitmap {
+    pub width: usize,
+    pub height: usize,
+    pub left: i32,
+    pub top: i32,
+    pub advance: usize,
+    pub coverage: Vec<u8>,
+}
+
+// Sub-scanlines per pixel row; coverage along each of them is exact.
+const SUBSAMPLES: usize = 8;
+// Composite glyphs may nest; real fonts stay well below this.
+const MAX_NESTING: usize = 8;
+
+impl Font {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let data = fs::read(path).map_err(|error| format!("cannot read font `{}`: {}", path, error))?;
+        Font::parse(data).map_err(|message| format!("font `{}`: {}", path, message))
+    }
+
+    pub fn parse(data: Vec<u8>) -> Result<Self, String> {
+        let table = |tag: &[u8; 4]| -> Result<usize, String> {
+            let count = read_u16(&data, 4).ok_or("truncated header")? as usize;
+            (0..count)
+                .map(|index| 12 + index * 16)
+                .find(|&record| data.get(record..record + 4) == Some(tag))
+                .and_then(|record| read_u32(&data, record + 8))
+                .map(|offset| offset as usize)
+                .ok_or_else(|| format!("missing `{}` table", String::from_utf8_lossy(tag)))
+        };
+        match read_u32(&data, 0) {
+            Some(0x0001_0000) | Some(0x7472_7565) => {}
+            Some(0x4F54_544F) => return Err("CFF outlines (OpenType .otf) are not supported".to_string()),
+            _ => return Err("not a TrueType font".to_string()),
+        }
+
+        let head = table(b"head")?;
+        let maxp = table(b"maxp")?;
+        let hhea = table(b"hhea")?;
+        let truncated = || "truncated tables".to_string();
+        let font = Font {
+            units_per_em: read_u16(&data, head + 18).ok_or_else(truncated)?.max(1) as f32,
+            long_offsets: read_u16(&data, head + 50).ok_or_else(truncated)? == 1,
+            glyph_count: read_u16(&data, maxp + 4).ok_or_else(truncated)?,
+            long_metrics: read_u16(&data, hhea + 34).ok_or_else(truncated)?.max(1),
+            cmap: Font::pick_cmap(&data, table(b"cmap")?).ok_or("no Unicode character map")?,
+            loca: table(b"loca")?,
+            glyf: table(b"glyf")?,
+            hmtx: table(b"hmtx")?,
+            data,
+        };
+        Ok(font)
+    }
+
+    // The Unicode subtable in a format we read: full Unicode (format 12)
+    // over the basic plane (format 4).
+    fn pick_cmap(data: &[u8], cmap: usize) -> Option<usize> {
+        let count = read_u16(data, cmap + 2)? as usize;
+        let mut best: Option<(usize, u16)> = None;
+        for index in 0..count {
+            let record = cmap + 4 + index * 8;
+            let platform = read_u16(data, record)?;
+            let encoding = read_u16(data, record + 2)?;
+            let subtable = cmap + read_u32(data, record + 4)? as usize;
+            let unicode = platform == 0 || (platform == 3 && (encoding == 1 || encoding == 10));
+            let format = read_u16(data, subtable)?;
+            if unicode && (format == 4 || format == 12) && best.is_none_or(|(_, best_format)| format > best_format) {
+                best = Some((subtable, format));
+            }
+        }
+        best.map(|(subtable, _)| subtable)
+    }
+
+    // Glyph for a character; 0, the font's own "missing" glyph, if none.
+    pub fn glyph_index(&self, c: char) -> u16 {
+        self.lookup(c as u32).filter(|&glyph| glyph < self.glyph_count).unwrap_or(0)
+    }
+
+    fn lookup(&self, code: u32) -> Option<u16> {
+        let data = &self.data;
+        let subtable = self.cmap;
+        match read_u16(data, subtable)? {
+            4 => {
+                let code = u16::try_from(code).ok()?;
+                let segments = read_u16(data, subtable + 6)? as usize / 2;
+                let ends = subtable + 14;
+                let starts = ends + segments * 2 + 2;
+                let deltas = starts + segments * 2;
+                let range_offsets = deltas + segments * 2;
+                let segment = (0..segments).find(|&segment| read_u16(data, ends + segment * 2).is_some_and(|end| end >= code))?;
+                let start = read_u16(data, starts + segment * 2)?;
+                if start > code {
+                    return None;
+                }
+                let delta = read_u16(data, deltas + segment * 2)?;
+                let range_offset_at = range_offsets + segment * 2;
+                let range_offset = read_u16(data, range_offset_at)? as usize;
+                if range_offset == 0 {
+                    return Some(code.wrapping_add(delta));
+                }
+                let glyph = read_u16(data, range_offset_at + range_offset + (code - start) as usize * 2)?;
+                (glyph != 0).then(|| glyph.wrapping_add(delta))
+            }
+            12 => {
+                let groups = read_u32(data, subtable + 12)? as usize;
+                (0..groups).find_map(|group| {
+                    let record = subtable + 16 + group * 12;
+                    let start = read_u32(data, record)?;
+                    let end = read_u32(data, record + 4)?;
+                    let first_glyph = read_u32(data, record + 8)?;
+                    (start..=end).contains(&code).then(|| (first_glyph + code - start) as u16)
+                })
+            }
+            _ => None,
+        }
+    }
+
+    // Height of the capital H as a fraction of the em, to size the font so
+    // its letters stand as tall as some other font's.
+    pub fn cap_height(&self) -> f32 {
+        let height = self.glyph_data(self.glyph_index('H')).and_then(|(start, _)| read_u16(&self.data, start + 8)).map_or(0, |y_max| y_max as i16);
+        if height > 0 {
+            height as f32 / self.units_per_em
+        } else {
+            0.7
+        }
+    }
+
+    fn advance_units(&self, glyph: u16) -> f32 {
+        let metric = glyph.min(self.long_metrics - 1) as usize;
+        read_u16(&self.data, self.hmtx + metric * 4).unwrap_or(0) as f32
+    }
+
+    // Byte range of a glyph's outline; None for an empty glyph like a space.
+    fn glyph_data(&self, glyph: u16) -> Option<(usize, usize)> {
+        let (start, end) = if self.long_offsets {
+            let at = self.loca + glyph as usize * 4;
+            (read_u32(&self.data, at)? as usize, read_u32(&self.data, at + 4)? as usize)
+        } else {
+            let at = self.loca + glyph as usize * 2;
+            (read_u16(&self.data, at)? as usize * 2, read_u16(&self.data, at + 2)? as usize * 2)
+        };
+        (end > start).then_some((self.glyf + start, self.glyf + end))
+    }
+
+    // Closed contours in font units, y up, as (x, y, on_curve) points.
+    fn contours(&self, glyph: u16, depth: usize, contours: &mut Vec<Vec<(f32, f32, bool)>>) -> Option<()> {
+        let Some((start, end)) = self.glyph_data(glyph) else {
+            return Some(());
+        };
+        let data = self.data.get(start..end)?;
+        let contour_count = read_u16(data, 0)? as i16;
+        if contour_count >= 0 {
+            Font::simple_contours(data, contour_count as usize, contours)
+        } else if depth < MAX_NESTING {
+            self.composite_contours(data, depth, contours)
+        } else {
+            None
+        }
+    }
+
+    fn simple_contours(data: &[u8], contour_count: usize, contours: &mut Vec<Vec<(f32, f32, bool)>>) -> Option<()> {
+        const ON_CURVE: u8 = 0x01;
+        const X_SHORT: u8 = 0x02;
+        const Y_SHORT: u8 = 0x04;
+        const REPEAT: u8 = 0x08;
+        const X_SAME_OR_POSITIVE: u8 = 0x10;
+        const Y_SAME_OR_POSITIVE: u8 = 0x20;
+
+        let ends: Vec<usize> = (0..contour_count).map(|contour| read_u16(data, 10 + contour * 2).map(|end| end as usize)).collect::<Option<_>>()?;
+        let point_count = ends.last().map_or(0, |end| end + 1);
+        let instructions = 10 + contour_count * 2;
+        let mut at = instructions + 2 + read_u16(data, instructions)? as usize;
+
+        let mut flags = Vec::with_capacity(point_count);
+        while flags.len() < point_count {
+            let flag = *data.get(at)?;
+            at += 1;
+            let mut repeats = 1;
+            if flag & REPEAT != 0 {
+                repeats += *data.get(at)? as usize;
+                at += 1;
+            }
+            flags.extend(std::iter::repeat_n(flag, repeats));
+        }
+        flags.truncate(point_count);
+
+        let mut read_axis = |short: u8, same_or_positive: u8| -> Option<Vec<f32>> {
+            let mut value = 0i32;
+            let mut values = Vec::with_capacity(point_count);
+            for &flag in &flags {
+                if flag & short != 0 {
+                    let step = *data.get(at)? as i32;
+                    at += 1;
+                    value += if flag & same_or_positive != 0 { step } else { -step };
+                } else if flag & same_or_positive == 0 {
+                    value += read_u16(data, at)? as i16 as i32;
+                    at += 2;
+                }
+                values.push(value as f32);
+            }
+            Some(values)
+        };
+        let xs = read_axis(X_SHORT, X_SAME_OR_POSITIVE)?;
+        let ys = read_axis(Y_SHORT, Y_SAME_OR_POSITIVE)?;
+
+        let mut first = 0;
+        for end in ends {
+            if end < first || end >= point_count {
+                return None;
+            }
+            contours.push((first..=end).map(|point| (xs[point], ys[point], flags[point] & ON_CURVE != 0)).collect());
+            first = end + 1;
+        }
+        Some(())
+    }
+
+    fn composite_contours(&self, data: &[u8], depth: usize, contours: &mut Vec<Vec<(f32, f32, bool)>>) -> Option<()> {
+        const WORD_ARGUMENTS: u16 = 0x0001;
+        const XY_OFFSETS: u16 = 0x0002;
+        const SCALE: u16 = 0x0008;
+        const MORE_COMPONENTS: u16 = 0x0020;
+        const XY_SCALE: u16 = 0x0040;
+        const TWO_BY_TWO: u16 = 0x0080;
+
+        let f2dot14 = |at: usize| read_u16(data, at).map(|value| value as i16 as f32 / 16384.0);
+        let mut at = 10;
+        loop {
+            let flags = read_u16(data, at)?;
+            let component = read_u16(data, at + 2)?;
+            at += 4;
+            let (dx, dy) = if flags & WORD_ARGUMENTS != 0 {
+                at += 4;
+                (read_u16(data, at - 4)? as i16 as f32, read_u16(data, at - 2)? as i16 as f32)
+            } else {
+                at += 2;
+                (*data.get(at - 2)? as i8 as f32, *data.get(at - 1)? as i8 as f32)
+            };
+            // Components placed by matching points are rare; they are drawn
+            // unmoved instead.
+            let (dx, dy) = if flags & XY_OFFSETS != 0 { (dx, dy) } else { (0.0, 0.0) };
+            let [a, b, c, d] = if flags & SCALE != 0 {
+                at += 2;
+                let scale = f2dot14(at - 2)?;
+                [scale, 0.0, 0.0, scale]
+            } else if flags & XY_SCALE != 0 {
+                at += 4;
+                [f2dot14(at - 4)?, 0.0, 0.0, f2dot14(at - 2)?]
+            } else if flags & TWO_BY_TWO != 0 {
+                at += 8;
+                [f2dot14(at - 8)?, f2dot14(at - 6)?, f2dot14(at - 4)?, f2dot14(at - 2)?]
+            } else {
+                [1.0, 0.0, 0.0, 1.0]
+            };
+
+            let first = contours.len();
+            self.contours(component, depth + 1, contours)?;
+            for point in contours[first..].iter_mut().flatten() {
+                let (x, y, on_curve) = *point;
+                *point = (a * x + c * y + dx, b * x + d * y + dy, on_curve);
+            }
+            if flags & MORE_COMPONENTS == 0 {
+                return Some(());
+            }
+        }
+    }
+
+    // Draws a glyph at `pixels_per_em`. A glyph whose outline can't be read
+    // comes out blank but keeps its advance.
+    pub fn rasterize(&self, glyph: u16, pixels_per_em: f32) -> GlyphBitmap {
+        let scale = pixels_per_em / self.units_per_em;
+        let advance = (self.advance_units(glyph) * scale).round() as usize;
+        let blank = GlyphBitmap { width: 0, height: 0, left: 0, top: 0, advance, coverage: Vec::new() };
+
+        let mut contours = Vec::new();
+        if self.contours(glyph, 0, &mut contours).is_none() {
+            return blank;
+        }
+        // Into pixels, y down from the baseline.
+        let edges: Vec<((f32, f32), (f32, f32))> = contours
+            .iter()
+            .flat_map(|contour| flatten(&contour.iter().map(|&(x, y, on_curve)| (x * scale, -y * scale, on_curve)).collect::<Vec<_>>()))
+            .collect();
+        if edges.is_empty() {
+            return blank;
+        }
+
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (f32::INFINITY, f32::INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for &((x, y), _) in &edges {
+            (min_x, min_y, max_x, max_y) = (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y));
+        }
+        let (left, top) = (min_x.floor(), min_y.floor());
+        let width = (max_x.ceil() - left) as usize + 1;
+        let height = (max_y.ceil() - top) as usize + 1;
+
+        let mut coverage = vec![0.0f32; width * height];
+        let mut crossings: Vec<(f32, i32)> = Vec::new();
+        for row in 0..height {
+            let line = &mut coverage[row * width..(row + 1) * width];
+            for sample in 0..SUBSAMPLES {
+                let y = top + row as f32 + (sample as f32 + 0.5) / SUBSAMPLES as f32;
+                crossings.clear();
+                for &((x0, y0), (x1, y1)) in &edges {
+                    let (upper, lower, winding) = if y0 < y1 { ((x0, y0), (x1, y1), 1) } else { ((x1, y1), (x0, y0), -1) };
+                    if y >= upper.1 && y < lower.1 {
+                        let t = (y - upper.1) / (lower.1 - upper.1);
+                        crossings.push((upper.0 + (lower.0 - upper.0) * t - left, winding));
+                    }
+                }
+                crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+                // Non-zero fill: spans where the contours wind around.
+                let mut winding = 0;
+                for pair in crossings.windows(2) {
+                    winding += pair[0].1;
+                    if winding != 0 {
+                        add_span(line, pair[0].0, pair[1].0, 1.0 / SUBSAMPLES as f32);
+                    }
+                }
+            }
+        }
+
+        GlyphBitmap {
+            width,
+            height,
+            left: left as i32,
+            top: -top as i32,
+            advance,
+            coverage: coverage.iter().map(|&amount| (amount.min(1.0) * 255.0).round() as u8).collect(),
+        }
+    }
+}
+
+// Adds `weight` times the overlap of [from, to) with each pixel of the row.
+fn add_span(line: &mut [f32], from: f32, to: f32, weight: f32) {
+    let from = from.max(0.0);
+    let to = to.min(line.len() as f32);
+    if to <= from {
+        return;
+    }
+    let (first, last) = (from as usize, (to.ceil() as usize).min(line.len()));
+    for (column, cell) in line.iter_mut().enumerate().take(last).skip(first) {
+        let overlap = (to.min(column as f32 + 1.0) - from.max(column as f32)).max(0.0);
+        *cell += overlap * weight;
+    }
+}
+
+// Line segments along a closed contour of on- and off-curve points, each
+// quadratic curve split finely enough to look smooth at its size.
+fn flatten(points: &[(f32, f32, bool)]) -> Vec<((f32, f32), (f32, f32))> {
+    let mut segments = Vec::new();
+    if points.len() < 2 {
+        return segments;
+    }
+    let midpoint = |a: (f32, f32, bool), b: (f32, f32, bool)| ((a.0 + b.0) * 0.5, (a.1 + b.1) * 0.5);
+    // Start on the curve: the first on-curve point, or between two control
+    // points when there is none.
+    let start_index = points.iter().position(|point| point.2);
+    let start = start_index.map_or_else(|| midpoint(points[0], points[1]), |index| (points[index].0, points[index].1));
+    let offset = start_index.unwrap_or(0);
+
+    let mut from = start;
+    let mut control: Option<(f32, f32)> = None;
+    let curve_to = |segments: &mut Vec<_>, from: (f32, f32), control: (f32, f32), to: (f32, f32)| {
+        let bend = ((from.0 - 2.0 * control.0 + to.0).powi(2) + (from.1 - 2.0 * control.1 + to.1).powi(2)).sqrt();
+        let steps = (bend.sqrt() * 2.0).ceil().clamp(1.0, 16.0) as usize;
+        let mut previous = from;
+        for step in 1..=steps {
+            let t = step as f32 / steps as f32;
+            let u = 1.0 - t;
+            let point = (u * u * from.0 + 2.0 * u * t * control.0 + t * t * to.0, u * u * from.1 + 2.0 * u * t * control.1 + t * t * to.1);
+            segments.push((previous, point));
+            previous = point;
+        }
+    };
+
+    for step in 1..=points.len() {
+        let point = points[(offset + step) % points.len()];
+        let position = (point.0, point.1);
+        match (point.2, control) {
+            (true, None) => {
+                segments.push((from, position));
+                from = position;
+            }
+            (true, Some(previous_control)) => {
+                curve_to(&mut segments, from, previous_control, position);
+                from = position;
+                control = None;
+            }
+            (false, None) => control = Some(position),
+            (false, Some(previous_control)) => {
+                // Two control points in a row imply an on-curve point
+                // halfway between them.
+                let implied = ((previous_control.0 + position.0) * 0.5, (previous_control.1 + position.1) * 0.5);
+                curve_to(&mut segments, from, previous_control, implied);
+                from = implied;
+                control = Some(position);
+            }
+        }
+    }
+    // Close the contour back to where it started.
+    match control {
+        Some(last_control) => curve_to(&mut segments, from, last_control, start),
+        None if from != start => segments.push((from, start)),
+        None => {}
+    }
+    segments
+}
+
+fn read_u16(data: &[u8], at: usize) -> Option<u16> {
+    data.get(at..at + 2).map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    data.get(at..at + 4).map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u16s(values: &[u16]) -> Vec<u8> {
+        values.iter().flat_map(|value| value.to_be_bytes()).collect()
+    }
+
+    // A 100-unit square as a simple glyph, every point stored a different
+    // way: unchanged, short positive, short positive in y, and a full word.
+    fn square() -> Vec<u8> {
+        let mut glyph = u16s(&[1, 0, 0, 100, 100, 3, 0]);
+        glyph.extend([0x31, 0x33, 0x35, 0x21]);
+        glyph.extend([100, 0xFF, 0x9C]);
+        glyph.extend([100]);
+        glyph
+    }
+
+    // Two squares: one moved by word offsets, one halved and moved by byte
+    // offsets.
+    fn composite() -> Vec<u8> {
+        let mut glyph = u16s(&[0xFFFF, 0, 0, 300, 100]);
+        glyph.extend(u16s(&[0x0023, 1, 200, (-50i16) as u16]));
+        glyph.extend(u16s(&[0x000A, 1]));
+        glyph.extend([10, 0]);
+        glyph.extend(u16s(&[0x2000]));
+        glyph
+    }
+
+    // cmap format 4: 'A'..='C' by delta, 'a'..='b' through the glyph array
+    // ('b' maps to nothing) and the closing 0xFFFF segment.
+    fn format_4() -> Vec<u8> {
+        let header = [4, 0, 0, 6, 0, 0, 0];
+        let (ends, starts) = ([67, 98, 0xFFFF], [65, 97, 0xFFFF]);
+        let (deltas, range_offsets) = ([1u16.wrapping_sub(65), 0, 1], [0, 4, 0]);
+        u16s(&[&header[..], &ends, &[0], &starts, &deltas, &range_offsets, &[3, 0]].concat())
+    }
+
+    // cmap format 12: one group above the basic plane and one in it.
+    fn format_12() -> Vec<u8> {
+        let mut subtable = u16s(&[12, 0, 0, 0, 0, 0, 0, 2]);
+        for value in [0x1F600u32, 0x1F601, 2, 65, 65, 1] {
+            subtable.extend(value.to_be_bytes());
+        }
+        subtable
+    }
+
+    // A font with glyphs 0 (empty), 1 (square), 2 (composite) and 3 (empty)
+    // and the given (platform, encoding, subtable) character maps.
+    fn font_data(maps: &[(u16, u16, Vec<u8>)]) -> Vec<u8> {
+        let mut head = vec![0; 54];
+        head[18..20].copy_from_slice(&100u16.to_be_bytes());
+        head[50..52].copy_from_slice(&1u16.to_be_bytes());
+        let maxp = u16s(&[0, 0x5000, 4]);
+        let mut hhea = vec![0; 36];
+        hhea[34..36].copy_from_slice(&2u16.to_be_bytes());
+        let hmtx = u16s(&[50, 0, 120, 0]);
+
+        let mut cmap = u16s(&[0, maps.len() as u16]);
+        let mut offset = 4 + maps.len() * 8;
+        for (platform, encoding, subtable) in maps {
+            cmap.extend(u16s(&[*platform, *encoding]));
+            cmap.extend((offset as u32).to_be_bytes());
+            offset += subtable.len();
+        }
+        for (_, _, subtable) in maps {
+            cmap.extend(subtable);
+        }
+
+        let (square, composite) = (square(), composite());
+        let mut loca = Vec::new();
+        for end in [0, 0, square.len(), square.len() + composite.len(), square.len() + composite.len()] {
+            loca.extend((end as u32).to_be_bytes());
+        }
+        let glyf = [square, composite].concat();
+
+        let tables: [(&[u8; 4], Vec<u8>); 7] =
+            [(b"cmap", cmap), (b"glyf", glyf), (b"head", head), (b"hhea", hhea), (b"hmtx", hmtx), (b"loca", loca), (b"maxp", maxp)];
+        let mut data = u16s(&[1, 0, tables.len() as u16, 0, 0, 0]);
+        let mut offset = data.len() + tables.len() * 16;
+        let mut body: Vec<u8> = Vec::new();
+        for (tag, table) in &tables {
+            data.extend(*tag);
+            data.extend([0; 4]);
+            data.extend((offset as u32).to_be_bytes());
+            data.extend((table.len() as u32).to_be_bytes());
+            offset += table.len();
+            body.extend(table);
+        }
+        data.extend(body);
+        data
+    }
+
+    fn font(maps: &[(u16, u16, Vec<u8>)]) -> Font {
+        Font::parse(font_data(maps)).unwrap()
+    }
+
+    fn contours(font: &Font, glyph: u16) -> Vec<Vec<(f32, f32, bool)>> {
+        let mut contours = Vec::new();
+        font.contours(glyph, 0, &mut contours).unwrap();
+        contours
+    }
+
+    const SQUARE: [(f32, f32, bool); 4] = [(0.0, 0.0, true), (100.0, 0.0, true), (100.0, 100.0, true), (0.0, 100.0, true)];
+
+    #[test]
+    fn format_4_maps_by_delta_and_by_glyph_array() {
+        let font = font(&[(3, 1, format_4())]);
+        assert_eq!(['A', 'B', 'C', 'D'].map(|c| font.glyph_index(c)), [1, 2, 3, 0]);
+        assert_eq!(font.glyph_index('a'), 3);
+        assert_eq!(font.glyph_index('b'), 0);
+        assert_eq!(font.glyph_index('@'), 0);
+        assert_eq!(font.glyph_index('😀'), 0);
+    }
+
+    #[test]
+    fn format_12_reaches_past_the_basic_plane() {
+        let font = font(&[(3, 10, format_12())]);
+        assert_eq!(font.glyph_index('A'), 1);
+        assert_eq!(font.glyph_index('😀'), 2);
+        assert_eq!(font.glyph_index('😁'), 3);
+        assert_eq!(font.glyph_index('😂'), 0);
+    }
+
+    #[test]
+    fn prefers_format_12_and_skips_non_unicode_maps() {
+        assert_eq!(font(&[(3, 1, format_4()), (0, 4, format_12())]).glyph_index('😀'), 2);
+        assert_eq!(font(&[(1, 0, format_12()), (3, 1, format_4())]).glyph_index('😀'), 0);
+        assert_eq!(Font::parse(font_data(&[(1, 0, format_4())])).err().unwrap(), "no Unicode character map");
+    }
+
+    #[test]
+    fn rejects_other_formats() {
+        assert_eq!(Font::parse(b"OTTO\0\0\0\0".to_vec()).err().unwrap(), "CFF outlines (OpenType .otf) are not supported");
+        assert_eq!(Font::parse(b"GIF89a".to_vec()).err().unwrap(), "not a TrueType font");
+        assert_eq!(Font::parse(u16s(&[1, 0, 0])).err().unwrap(), "missing `head` table");
+    }
+
+    #[test]
+    fn parses_simple_glyph() {
+        let font = font(&[(3, 1, format_4())]);
+        assert_eq!(contours(&font, 1), [SQUARE.to_vec()]);
+        assert!(contours(&font, 0).is_empty());
+        assert!(contours(&font, 3).is_empty());
+    }
+
+    #[test]
+    fn parses_composite_glyph() {
+        let font = font(&[(3, 1, format_4())]);
+        let moved = SQUARE.map(|(x, y, on_curve)| (x + 200.0, y - 50.0, on_curve));
+        let halved = SQUARE.map(|(x, y, on_curve)| (x * 0.5 + 10.0, y * 0.5, on_curve));
+        assert_eq!(contours(&font, 2), [moved.to_vec(), halved.to_vec()]);
+    }
+
+    #[test]
+    fn rasterizes_square_as_solid_block() {
+        let font = font(&[(3, 1, format_4())]);
+        let bitmap = font.rasterize(1, 10.0);
+        assert_eq!((bitmap.left, bitmap.top, bitmap.advance), (0, 10, 12));
+        assert_eq!((bitmap.width, bitmap.height), (11, 11));
+        for row in 0..10 {
+            assert!(bitmap.coverage[row * bitmap.width..row * bitmap.width + 10].iter().all(|&value| value == 255));
+            assert_eq!(bitmap.coverage[row * bitmap.width + 10], 0);
+        }
+        // Glyphs past the last long metric share its advance.
+        assert_eq!(font.rasterize(3, 10.0).advance, 12);
+        assert_eq!(font.rasterize(0, 10.0).advance, 5);
+    }
+}
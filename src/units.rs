@@ -0,0 +1,186 @@
+use std::f32::consts::PI;
+use crate::sim::STEPS_PER_SECOND;
+
+pub const KM_PER_AU: f32 = 149_597_870.7;
+
+// What a scene value measures. Each kind has its own mapping to scene
+// units, because real orbits and real planet sizes can't share one scale.
+#[derive(Clone, Copy)]
+pub enum Quantity {
+    OrbitDistance,
+    BodySize,
+    Duration,
+    Angle,
+}
+
+impl Quantity {
+    pub fn unit_names(self) -> &'static str {
+        match self {
+            Quantity::OrbitDistance | Quantity::BodySize => "AU, km",
+            Quantity::Duration => "d, h, yr",
+            Quantity::Angle => "deg, rad",
+        }
+    }
+}
+
+pub struct UnitScale {
+    // Scene units per astronomical unit, used for orbital distances.
+    pub distance_scale: f32,
+    // Scene units per kilometre, used for body radii.
+    pub size_scale: f32,
+    // Sim seconds per day, used for periods.
+    pub day_length: f32,
+}
+
+impl Default for UnitScale {
+    fn default() -> Self {
+        UnitScale {
+            distance_scale: 10.0,
+            size_scale: 0.00007,
+            day_length: 1.0 / STEPS_PER_SECOND,
+        }
+    }
+}
+
+impl UnitScale {
+    // Converts a value written as "<number> <unit>" into scene units. Bare
+    // numbers are returned untouched so existing scene files keep working.
+    pub fn parse(&self, text: &str, quantity: Quantity) -> Result<f32, String> {
+        let text = text.trim();
+        let split = text
+            .find(|c: char| c.is_alphabetic() || c == '°')
+            .unwrap_or(text.len());
+        let (number, unit) = text.split_at(split);
+
+        let value: f32 = number
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid number in `{}`", text))?;
+        let written = unit.trim();
+        if written.is_empty() {
+            return Ok(value);
+        }
+
+        let unknown_unit = || format!("unknown unit `{}` (expected {})", written, quantity.unit_names());
+        let unit = written.to_lowercase();
+
+        match quantity {
+            Quantity::OrbitDistance | Quantity::BodySize => {
+                let km = match unit.as_str() {
+                    "au" => value * KM_PER_AU,
+                    "km" => value,
+                    _ => return Err(unknown_unit()),
+                };
+                Ok(match quantity {
+                    Quantity::OrbitDistance => km / KM_PER_AU * self.distance_scale,
+                    _ => km * self.size_scale,
+                })
+            }
+            Quantity::Duration => {
+                let days = match unit.as_str() {
+                    "d" | "day" | "days" => value,
+                    "h" | "hour" | "hours" => value / 24.0,
+                    "yr" | "year" | "years" => value * 365.25,
+                    _ => return Err(unknown_unit()),
+                };
+                Ok(days * self.day_length)
+            }
+            Quantity::Angle => match unit.as_str() {
+                "deg" | "degree" | "degrees" | "°" => Ok(value.to_radians()),
+                "rad" | "radian" | "radians" => Ok(value),
+                _ => Err(unknown_unit()),
+            },
+        }
+    }
+}
+
+// Angular speed in radians per sim step for a full turn every `period`
+// seconds.
+pub fn speed_from_period(period: f32) -> f32 {
+    2.0 * PI / (period * STEPS_PER_SECOND)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orbit::Orbit;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() <= 1e-5 * a.abs().max(b.abs()).max(1.0)
+    }
+
+    #[test]
+    fn bare_numbers_pass_through() {
+        let scale = UnitScale::default();
+        for quantity in [Quantity::OrbitDistance, Quantity::BodySize, Quantity::Duration, Quantity::Angle] {
+            assert_eq!(scale.parse(" 2.5 ", quantity), Ok(2.5));
+        }
+    }
+
+    #[test]
+    fn distances_round_trip_between_au_and_km() {
+        let scale = UnitScale { distance_scale: 10.0, size_scale: 0.001, day_length: 1.0 };
+        assert!(close(scale.parse("1 AU", Quantity::OrbitDistance).unwrap(), 10.0));
+        assert!(close(scale.parse("149597870.7 km", Quantity::OrbitDistance).unwrap(), 10.0));
+        assert!(close(scale.parse("0.5au", Quantity::OrbitDistance).unwrap(), 5.0));
+
+        assert!(close(scale.parse("6371 km", Quantity::BodySize).unwrap(), 6.371));
+        let from_au = scale.parse("1 AU", Quantity::BodySize).unwrap();
+        assert!(close(from_au, scale.parse("149597870.7 km", Quantity::BodySize).unwrap()));
+    }
+
+    #[test]
+    fn durations_agree_across_units() {
+        let scale = UnitScale { day_length: 0.25, ..UnitScale::default() };
+        let days = |text| scale.parse(text, Quantity::Duration).unwrap() / scale.day_length;
+        assert!(close(days("1 d"), 1.0));
+        assert!(close(days("48 h"), 2.0));
+        assert!(close(days("1 yr"), days("365.25 days")));
+        assert!(close(days("2 years"), days("17532 hours")));
+    }
+
+    #[test]
+    fn units_ignore_case_and_number() {
+        let scale = UnitScale::default();
+        let pairs = [
+            ("1 au", "1 AU", Quantity::OrbitDistance),
+            ("5 KM", "5 km", Quantity::BodySize),
+            ("1 Day", "1 d", Quantity::Duration),
+            ("1 hour", "1 H", Quantity::Duration),
+            ("1 YEAR", "1 yr", Quantity::Duration),
+            ("1 degree", "1 deg", Quantity::Angle),
+            ("2 Degrees", "2 DEG", Quantity::Angle),
+            ("1 radian", "1 rad", Quantity::Angle),
+            ("2 radians", "2 Rad", Quantity::Angle),
+        ];
+        for (a, b, quantity) in pairs {
+            assert_eq!(scale.parse(a, quantity), scale.parse(b, quantity), "{} vs {}", a, b);
+            assert!(scale.parse(a, quantity).is_ok());
+        }
+    }
+
+    #[test]
+    fn angles_round_trip_between_degrees_and_radians() {
+        let scale = UnitScale::default();
+        assert!(close(scale.parse("180 deg", Quantity::Angle).unwrap(), PI));
+        assert!(close(scale.parse("-90°", Quantity::Angle).unwrap(), -PI / 2.0));
+        assert_eq!(scale.parse("1.5 rad", Quantity::Angle), Ok(1.5));
+    }
+
+    #[test]
+    fn rejects_bad_numbers_and_units() {
+        let scale = UnitScale::default();
+        assert_eq!(scale.parse("far AU", Quantity::OrbitDistance), Err("invalid number in `far AU`".to_string()));
+        assert_eq!(scale.parse("3 pc", Quantity::OrbitDistance), Err("unknown unit `pc` (expected AU, km)".to_string()));
+        assert_eq!(scale.parse("3 min", Quantity::Duration), Err("unknown unit `min` (expected d, h, yr)".to_string()));
+        assert_eq!(scale.parse("3 AU", Quantity::Angle), Err("unknown unit `AU` (expected deg, rad)".to_string()));
+    }
+
+    #[test]
+    fn speed_from_period_round_trips_through_orbit_period() {
+        for period in [0.5, 20.0, 365.25] {
+            let orbit = Orbit::circular(1.0, speed_from_period(period));
+            assert!(close(orbit.period().unwrap(), period));
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use std::f32::consts::PI;
+
+// How a variable star's brightness changes over one period.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LightCurve {
+    // 1 ± amplitude, smoothly, like a pulsating star.
+    Sinusoid { amplitude: f32 },
+    // Brightness at evenly spaced points of the period, joined by straight
+    // lines and wrapping around from the last back to the first.
+    Table(Vec<f32>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Variability {
+    // In sim seconds.
+    pub period: f32,
+    pub curve: LightCurve,
+}
+
+impl Variability {
+    // Luminosity at `time` relative to the star's average.
+    pub fn luminosity(&self, time: f32) -> f32 {
+        let phase = (time / self.period).rem_euclid(1.0);
+        match &self.curve {
+            LightCurve::Sinusoid { amplitude } => 1.0 + amplitude * (phase * 2.0 * PI).sin(),
+            LightCurve::Table(samples) => {
+                let scaled = phase * samples.len() as f32;
+                let index = (scaled as usize).min(samples.len() - 1);
+                let next = samples[(index + 1) % samples.len()];
+                samples[index] + (next - samples[index]) * (scaled - index as f32)
+            }
+        }
+    }
+}
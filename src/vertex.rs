@@ -1,4 +1,4 @@
-use nalgebra_glm::{Vec2, Vec3};
+use nalgebra_glm::{Vec2, Vec3, Vec4};
 use crate::color::Color;
 
 #[derive(Clone, Debug)]
@@ -7,8 +7,12 @@ pub struct Vertex {
   pub normal: Vec3,
   pub tex_coords: Vec2,
   pub color: Color,
+  // Before the perspective divide, for clipping.
+  pub clip_position: Vec4,
   pub transformed_position: Vec3,
   pub transformed_normal: Vec3,
+  // After the model matrix, for lighting.
+  pub world_position: Vec3,
 }
 
 impl Vertex {
@@ -18,8 +22,10 @@ impl Vertex {
       normal,
       tex_coords,
       color: Color::black(),
+      clip_position: Vec4::new(position.x, position.y, position.z, 1.0),
       transformed_position: position,
       transformed_normal: normal,
+      world_position: position,
     }
   }
 
@@ -29,8 +35,10 @@ impl Vertex {
       normal: Vec3::new(0.0, 0.0, 0.0),
       tex_coords: Vec2::new(0.0, 0.0),
       color,
+      clip_position: Vec4::new(position.x, position.y, position.z, 1.0),
       transformed_position: Vec3::new(0.0, 0.0, 0.0),
       transformed_normal: Vec3::new(0.0, 0.0, 0.0),
+      world_position: position,
     }
   }
 
@@ -47,8 +55,10 @@ impl Default for Vertex {
       normal: Vec3::new(0.0, 1.0, 0.0),
       tex_coords: Vec2::new(0.0, 0.0),
       color: Color::black(),
+      clip_position: Vec4::new(0.0, 0.0, 0.0, 1.0),
       transformed_position: Vec3::new(0.0, 0.0, 0.0),
       transformed_normal: Vec3::new(0.0, 1.0, 0.0),
+      world_position: Vec3::new(0.0, 0.0, 0.0),
     }
   }
 }
\ No newline at end of file